@@ -0,0 +1,1149 @@
+//! The `WindowingBackend` trait and the free functions that forward to the
+//! default backend.
+
+use crate::{
+    ActiveWindowSnapshot, BackendAlreadySet, BackendKind, IconData, MonitorInfo, PhysicalWindowInfo, Unsupported,
+    Window, WindowDetails, WindowEvents, WindowInfo, WindowProperties, WindowQuery, WindowResult, WindowState,
+    WindowStateFlag, WindowSystem, Windows,
+};
+use std::{collections::HashSet, sync::OnceLock};
+
+/// Everything the crate can do with windows, as one object. The platform's
+/// `WindowSystem` implements it and serves the free functions; other
+/// implementations (test doubles, alternative compositor backends) can stand
+/// in wherever a `&dyn WindowingBackend` is accepted.
+///
+/// Every operation but `kind` has a default: either `Unsupported`, or (as its
+/// documentation says) one built from other operations. An implementation
+/// only needs to provide what it supports.
+///
+/// Something that is normally absent (no focused window, a process without
+/// windows, a window without a title) is `Ok(None)` or an empty `Vec`, never
+/// an error; errors are reserved for calls that failed.
+pub trait WindowingBackend: Send + Sync {
+    /// Which backend this is, as reported by `detect_backend`.
+    fn kind(&self) -> BackendKind;
+
+    /// All top-level windows but desktop pseudo-windows: the root window and
+    /// the desktop a file manager or the shell draws icons and wallpaper on.
+    fn list_windows(&self) -> crate::Result<Vec<Window>> {
+        Err(Unsupported::new("list_windows", self.kind().name()).into())
+    }
+
+    /// `list_windows` with the desktop pseudo-windows it leaves out, the root
+    /// window first. Defaults to `list_windows` plus `get_root_window` where
+    /// the backend has one.
+    fn list_windows_including_desktop(&self) -> crate::Result<Vec<Window>> {
+        let mut windows = self.list_windows()?;
+        if let Some(root) = unless_unsupported(self.get_root_window())? {
+            windows.insert(0, root);
+        }
+        Ok(windows)
+    }
+
+    /// `list_windows` as an iterator of `WindowHandle`s, whose data is only
+    /// fetched when a method asks for it. `windows_in` does the same for a
+    /// `dyn WindowingBackend`.
+    fn windows(&self) -> crate::Result<Windows<'_>>
+    where
+        Self: Sized,
+    {
+        Ok(Windows::new(self, self.list_windows()?))
+    }
+
+    /// Whether a window with this handle exists now. By default, whether
+    /// `list_windows` has it, so windows it leaves out count as missing.
+    fn window_exists(&self, window: Window) -> crate::Result<bool> {
+        Ok(self.list_windows()?.contains(&window))
+    }
+
+    /// Number of open windows. Defaults to the length of `list_windows`; a backend
+    /// with a cheaper query may count windows it leaves out, like X11's desktop.
+    fn window_count(&self) -> crate::Result<usize> {
+        Ok(self.list_windows()?.len())
+    }
+
+    /// A window's position and size, in the backend's native units.
+    fn get_window_info(&self, _window: Window) -> crate::Result<WindowInfo> {
+        Err(Unsupported::new("get_window_info", self.kind().name()).into())
+    }
+
+    /// `get_window_info` for many windows, in input order. A window that fails
+    /// (e.g. closed mid-batch) gets its own error; the outer error is for the
+    /// batch as a whole. Defaults to one `get_window_info` call per window.
+    fn get_window_infos(&self, windows: &[Window]) -> crate::Result<Vec<WindowResult<WindowInfo>>> {
+        Ok(windows.iter().map(|&window| self.get_window_info(window)).collect())
+    }
+
+    /// Geometry, title, pid, state flags and whether the window is on the
+    /// current workspace, in one call. Defaults to `gather_window_details`.
+    fn get_window_details(&self, window: Window) -> crate::Result<WindowDetails> {
+        gather_window_details(self, window)
+    }
+
+    /// The pid, app id, title and workspace of many windows, in input order,
+    /// with per-window errors as in `get_window_infos`. Parts the backend
+    /// doesn't support are `None`. Defaults to `gather_window_properties`.
+    fn get_window_properties(&self, windows: &[Window]) -> crate::Result<Vec<WindowResult<WindowProperties>>> {
+        Ok(windows.iter().map(|&window| gather_window_properties(self, window)).collect())
+    }
+
+    /// `get_window_info`, retrying transient failures. Defaults to a single
+    /// `get_window_info`, for backends that don't report such failures.
+    fn get_window_info_with_retry(&self, window: Window, _retries: u32) -> crate::Result<WindowInfo> {
+        self.get_window_info(window)
+    }
+
+    /// A window's frame in physical pixels.
+    fn get_window_bounds(&self, _window: Window) -> crate::Result<PhysicalWindowInfo> {
+        Err(Unsupported::new("get_window_bounds", self.kind().name()).into())
+    }
+
+    /// The process's main window, or its first window if none looks main.
+    /// `None` if it has no window (yet), or no such process exists.
+    fn find_window_by_pid(&self, _target_pid: u32) -> crate::Result<Option<Window>> {
+        Err(Unsupported::new("find_window_by_pid", self.kind().name()).into())
+    }
+
+    /// All of the process's windows; empty if it has none.
+    fn find_windows_by_pid(&self, _target_pid: u32) -> crate::Result<Vec<Window>> {
+        Err(Unsupported::new("find_windows_by_pid", self.kind().name()).into())
+    }
+
+    /// The windows of any of these processes, each with its pid, from a single
+    /// pass over the window list. Defaults to `list_windows` and
+    /// `get_window_pid`.
+    fn find_windows_by_pids(&self, pids: &[u32]) -> crate::Result<Vec<(u32, Window)>> {
+        collect_windows_by_pids(self, pids)
+    }
+
+    /// Whether the process has any top-level window, stopping at the first one
+    /// found. Defaults to `find_window_by_pid`, which collects no list.
+    fn pid_has_window(&self, target_pid: u32) -> crate::Result<bool> {
+        self.find_window_by_pid(target_pid).map(|window| window.is_some())
+    }
+
+    /// The process's windows that pass `is_main_window`. Defaults to
+    /// `find_windows_by_pid` and `is_main_window`.
+    fn find_visible_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<Window>> {
+        let mut visible = Vec::new();
+        for window in self.find_windows_by_pid(target_pid)? {
+            // A window may close between listing and querying it
+            if self.is_main_window(window).unwrap_or(false) {
+                visible.push(window);
+            }
+        }
+        Ok(visible)
+    }
+
+    /// Windows whose app id matches, ignoring ASCII case (X11 class names are
+    /// only an approximation of Wayland app ids). Defaults to `list_windows`
+    /// and `get_window_app_id`.
+    fn find_windows_by_app_id(&self, app_id: &str) -> crate::Result<Vec<Window>> {
+        let mut found = Vec::new();
+        for window in self.list_windows()? {
+            // A window may close between listing and querying it
+            if self.get_window_app_id(window)
+                .ok()
+                .flatten()
+                .is_some_and(|id| id.eq_ignore_ascii_case(app_id))
+            {
+                found.push(window);
+            }
+        }
+        Ok(found)
+    }
+
+    /// The (instance, class) of the process's main window, `None` if it has no window.
+    fn get_class_for_pid(&self, _target_pid: u32) -> crate::Result<Option<(String, String)>> {
+        Err(Unsupported::new("get_class_for_pid", self.kind().name()).into())
+    }
+
+    /// Whether a window looks like its application's primary window. Defaults
+    /// to having a non-empty title, enough where `list_windows` only has
+    /// toplevels.
+    fn is_main_window(&self, window: Window) -> crate::Result<bool> {
+        Ok(self.get_window_title(window)?.is_some_and(|title| !title.is_empty()))
+    }
+
+    /// The focused window, `None` while nothing is focused.
+    fn get_active_window(&self) -> crate::Result<Option<Window>> {
+        Err(Unsupported::new("get_active_window", self.kind().name()).into())
+    }
+
+    /// The focused window with its pid, title, app id and geometry, read
+    /// together so they all describe the same window. Defaults to
+    /// `snapshot_active_window`.
+    fn active_window_snapshot(&self) -> crate::Result<Option<ActiveWindowSnapshot>> {
+        snapshot_active_window(self)
+    }
+
+    /// The focused window's process, `None` while nothing is focused or the
+    /// focused window doesn't report a process.
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        Err(Unsupported::new("get_active_window_pid", self.kind().name()).into())
+    }
+
+    /// The process owning a window, `None` if the window doesn't report one.
+    fn get_window_pid(&self, _window: Window) -> crate::Result<Option<u32>> {
+        Err(Unsupported::new("get_window_pid", self.kind().name()).into())
+    }
+
+    /// A window's title, `None` if it has none.
+    fn get_window_title(&self, _window: Window) -> crate::Result<Option<String>> {
+        Err(Unsupported::new("get_window_title", self.kind().name()).into())
+    }
+
+    /// A window's application id (WM_CLASS class, Wayland app_id).
+    fn get_window_app_id(&self, _window: Window) -> crate::Result<Option<String>> {
+        Err(Unsupported::new("get_window_app_id", self.kind().name()).into())
+    }
+
+    /// A window's icon as RGBA.
+    fn get_window_icon(&self, _window: Window) -> crate::Result<Option<IconData>> {
+        Err(Unsupported::new("get_window_icon", self.kind().name()).into())
+    }
+
+    /// Take a window off screen (and so off the taskbar and the window
+    /// switcher) until `show_window`.
+    fn hide_window(&self, _window: Window) -> crate::Result<()> {
+        Err(Unsupported::new("hide_window", self.kind().name()).into())
+    }
+
+    /// Put a window taken off screen by `hide_window` back, without focusing it.
+    /// The free `show_window` also undoes what `hide_window_with` applied.
+    fn show_window(&self, _window: Window) -> crate::Result<()> {
+        Err(Unsupported::new("show_window", self.kind().name()).into())
+    }
+
+    /// Leave a window off the taskbar (and pager), or put it back, without hiding it.
+    fn set_skip_taskbar(&self, _window: Window, _skip: bool) -> crate::Result<()> {
+        Err(Unsupported::new("set_skip_taskbar", self.kind().name()).into())
+    }
+
+    /// Leave a window out of the Alt-Tab window switcher, or put it back,
+    /// without hiding it.
+    fn set_skip_switcher(&self, _window: Window, _skip: bool) -> crate::Result<()> {
+        Err(Unsupported::new("set_skip_switcher", self.kind().name()).into())
+    }
+
+    /// Minimize a window.
+    fn minimize_window(&self, _window: Window) -> crate::Result<()> {
+        Err(Unsupported::new("minimize_window", self.kind().name()).into())
+    }
+
+    /// Maximize a window.
+    fn maximize_window(&self, _window: Window) -> crate::Result<()> {
+        Err(Unsupported::new("maximize_window", self.kind().name()).into())
+    }
+
+    /// Maximize a window, or restore it if already maximized on both axes.
+    /// Defaults to `window_has_state` and `set_window_state`.
+    fn toggle_maximize(&self, window: Window) -> crate::Result<()> {
+        if self.window_has_state(window, WindowState::Maximized)? {
+            self.set_window_state(window, WindowStateFlag::MaximizedVert, false)?;
+            self.set_window_state(window, WindowStateFlag::MaximizedHorz, false)
+        } else {
+            self.maximize_window(window)
+        }
+    }
+
+    /// Ask a window to close.
+    fn close_window(&self, _window: Window) -> crate::Result<()> {
+        Err(Unsupported::new("close_window", self.kind().name()).into())
+    }
+
+    /// Move a window's top-left corner.
+    fn move_window(&self, _window: Window, _x: i32, _y: i32) -> crate::Result<()> {
+        Err(Unsupported::new("move_window", self.kind().name()).into())
+    }
+
+    /// Resize a window.
+    fn resize_window(&self, _window: Window, _width: u32, _height: u32) -> crate::Result<()> {
+        Err(Unsupported::new("resize_window", self.kind().name()).into())
+    }
+
+    /// Move and resize a window. Defaults to `move_window`, then `resize_window`.
+    fn set_window_rect(
+        &self,
+        window: Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> crate::Result<()> {
+        self.move_window(window, x, y)?;
+        self.resize_window(window, width, height)
+    }
+
+    /// Give a window input focus.
+    fn focus_window(&self, _window: Window) -> crate::Result<()> {
+        Err(Unsupported::new("focus_window", self.kind().name()).into())
+    }
+
+    /// Whether a window accepts input focus.
+    fn can_focus(&self, _window: Window) -> crate::Result<bool> {
+        Err(Unsupported::new("can_focus", self.kind().name()).into())
+    }
+
+    /// Activate a window, optionally refreshing its user time first.
+    fn activate_window(&self, _window: Window, _update_user_time: bool) -> crate::Result<()> {
+        Err(Unsupported::new("activate_window", self.kind().name()).into())
+    }
+
+    /// Set a window's last user interaction time.
+    fn set_user_time(&self, _window: Window, _timestamp: u32) -> crate::Result<()> {
+        Err(Unsupported::new("set_user_time", self.kind().name()).into())
+    }
+
+    /// The workspace a window is on, `None` if it is on all of them.
+    fn get_window_workspace(&self, _window: Window) -> crate::Result<Option<u32>> {
+        Err(Unsupported::new("get_window_workspace", self.kind().name()).into())
+    }
+
+    /// Move a window to another workspace.
+    fn move_window_to_workspace(&self, _window: Window, _workspace: u32) -> crate::Result<()> {
+        Err(Unsupported::new("move_window_to_workspace", self.kind().name()).into())
+    }
+
+    /// The active workspace, `None` if the window manager doesn't report one.
+    fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+        Err(Unsupported::new("get_current_workspace", self.kind().name()).into())
+    }
+
+    /// Switch to another workspace.
+    fn switch_workspace(&self, _workspace: u32) -> crate::Result<()> {
+        Err(Unsupported::new("switch_workspace", self.kind().name()).into())
+    }
+
+    /// A blocking stream of window events.
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        Err(Unsupported::new("subscribe_window_events", self.kind().name()).into())
+    }
+
+    /// Every monitor, with its name and scale factor.
+    fn list_monitors(&self) -> crate::Result<Vec<MonitorInfo>> {
+        Err(Unsupported::new("list_monitors", self.kind().name()).into())
+    }
+
+    /// The monitor holding most of a window, `None` if it is on none.
+    fn get_window_monitor(&self, _window: Window) -> crate::Result<Option<MonitorInfo>> {
+        Err(Unsupported::new("get_window_monitor", self.kind().name()).into())
+    }
+
+    /// Every monitor a window overlaps, the one holding most of it first
+    /// (so the first is `get_window_monitor`); empty if it is on none.
+    fn get_window_monitors(&self, _window: Window) -> crate::Result<Vec<MonitorInfo>> {
+        Err(Unsupported::new("get_window_monitors", self.kind().name()).into())
+    }
+
+    /// The work area of the monitor holding most of a window: the monitor less
+    /// the space panels, docks and taskbars reserve. `None` if it is on none.
+    fn get_window_work_area(&self, _window: Window) -> crate::Result<Option<WindowInfo>> {
+        Err(Unsupported::new("get_window_work_area", self.kind().name()).into())
+    }
+
+    /// Move a window's top-left corner to `(x, y)`, pulled back as needed so the
+    /// whole frame stays inside the work area of the monitor it is on now. A
+    /// frame larger than the work area keeps its top-left corner in it; a
+    /// window on no monitor is moved as given.
+    fn move_window_clamped(&self, window: Window, x: i32, y: i32) -> crate::Result<()> {
+        let info = self.get_window_info(window)?;
+        let Some(area) = self.get_window_work_area(window)? else {
+            return self.move_window(window, x, y);
+        };
+        let clamp = |value: i32, start: i32, end: i32, length: u32| {
+            let last = (end as i64 - length as i64).max(start as i64);
+            (value as i64).clamp(start as i64, last) as i32
+        };
+        let x = clamp(x, area.pos.0, area.right(), info.size.0);
+        let y = clamp(y, area.pos.1, area.bottom(), info.size.1);
+        self.move_window(window, x, y)
+    }
+
+    /// The bounding rectangle of all monitors.
+    fn get_virtual_screen(&self) -> crate::Result<WindowInfo> {
+        Err(Unsupported::new("get_virtual_screen", self.kind().name()).into())
+    }
+
+    /// The root/desktop window.
+    fn get_root_window(&self) -> crate::Result<Window> {
+        Err(Unsupported::new("get_root_window", self.kind().name()).into())
+    }
+
+    /// The pointer's position in screen coordinates and the top-level window
+    /// under it, read together. `None` over the bare desktop.
+    fn pointer_state(&self) -> crate::Result<(i32, i32, Option<Window>)> {
+        Err(Unsupported::new("pointer_state", self.kind().name()).into())
+    }
+
+    /// Whether a window is in a `WindowState`.
+    fn window_has_state(&self, _window: Window, _state: WindowState) -> crate::Result<bool> {
+        Err(Unsupported::new("window_has_state", self.kind().name()).into())
+    }
+
+    /// Every `WindowStateFlag` set on a window.
+    fn get_window_states(&self, _window: Window) -> crate::Result<Vec<WindowStateFlag>> {
+        Err(Unsupported::new("get_window_states", self.kind().name()).into())
+    }
+
+    /// Add or remove a `WindowStateFlag`.
+    fn set_window_state(&self, _window: Window, _flag: WindowStateFlag, _enabled: bool) -> crate::Result<()> {
+        Err(Unsupported::new("set_window_state", self.kind().name()).into())
+    }
+
+    /// Whether a window shows on all workspaces.
+    fn is_sticky(&self, _window: Window) -> crate::Result<bool> {
+        Err(Unsupported::new("is_sticky", self.kind().name()).into())
+    }
+
+    /// Show a window on all workspaces, or only its own.
+    fn set_sticky(&self, _window: Window, _sticky: bool) -> crate::Result<()> {
+        Err(Unsupported::new("set_sticky", self.kind().name()).into())
+    }
+
+    /// Whether a window is kept above other windows.
+    fn is_always_on_top(&self, _window: Window) -> crate::Result<bool> {
+        Err(Unsupported::new("is_always_on_top", self.kind().name()).into())
+    }
+
+    /// Keep a window above other windows, or let it be stacked normally.
+    fn set_always_on_top(&self, _window: Window, _on_top: bool) -> crate::Result<()> {
+        Err(Unsupported::new("set_always_on_top", self.kind().name()).into())
+    }
+
+    /// Whether a window is kept below other windows.
+    fn is_always_below(&self, _window: Window) -> crate::Result<bool> {
+        Err(Unsupported::new("is_always_below", self.kind().name()).into())
+    }
+
+    /// Keep a window below other windows (a desktop widget, say), or let it be
+    /// stacked normally.
+    fn set_always_below(&self, _window: Window, _below: bool) -> crate::Result<()> {
+        Err(Unsupported::new("set_always_below", self.kind().name()).into())
+    }
+
+    /// Let mouse input pass through a window to whatever is beneath it, or
+    /// have it take input again.
+    fn set_click_through(&self, _window: Window, _click_through: bool) -> crate::Result<()> {
+        Err(Unsupported::new("set_click_through", self.kind().name()).into())
+    }
+}
+
+/// `get_window_details` assembled from one call per part. Parts the backend
+/// doesn't support are left empty rather than failing the whole call, and a
+/// window is taken to be on the active desktop unless both its workspace and
+/// the current one are known and differ.
+pub(crate) fn gather_window_details<B: WindowingBackend + ?Sized>(
+    backend: &B,
+    window: Window,
+) -> crate::Result<WindowDetails> {
+    let info = backend.get_window_info(window)?;
+    let title = unless_unsupported(backend.get_window_title(window))?.flatten();
+    let pid = unless_unsupported(backend.get_window_pid(window))?.flatten();
+    let states = unless_unsupported(backend.get_window_states(window))?.unwrap_or_default();
+    let on_active_desktop = match unless_unsupported(backend.get_window_workspace(window))?.flatten() {
+        Some(workspace) => unless_unsupported(backend.get_current_workspace())?
+            .flatten()
+            .is_none_or(|current| current == workspace),
+        None => true,
+    };
+    Ok(WindowDetails { info, title, pid, states, on_active_desktop })
+}
+
+/// `get_window_properties` for one window from one call per property, with
+/// the ones the backend doesn't support left `None`.
+pub(crate) fn gather_window_properties<B: WindowingBackend + ?Sized>(
+    backend: &B,
+    window: Window,
+) -> crate::Result<WindowProperties> {
+    Ok(WindowProperties {
+        pid: unless_unsupported(backend.get_window_pid(window))?.flatten(),
+        app_id: unless_unsupported(backend.get_window_app_id(window))?.flatten(),
+        title: unless_unsupported(backend.get_window_title(window))?.flatten(),
+        workspace: unless_unsupported(backend.get_window_workspace(window))?.flatten(),
+    })
+}
+
+/// `active_window_snapshot` from `get_active_window`, `get_window_details`
+/// and `get_window_app_id`. If focus moves while the window is read (or the
+/// window closes), the new active window is read once more; a second change
+/// is accepted, since that snapshot's window was active when it was taken.
+pub(crate) fn snapshot_active_window<B: WindowingBackend + ?Sized>(
+    backend: &B,
+) -> crate::Result<Option<ActiveWindowSnapshot>> {
+    let mut retried = false;
+    loop {
+        let Some(window) = backend.get_active_window()? else {
+            return Ok(None);
+        };
+        let details = match backend.get_window_details(window) {
+            Ok(details) => details,
+            Err(crate::Error::WindowGone(_) | crate::Error::WindowNotFound(_)) if !retried => {
+                retried = true;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        let app_id = match unless_unsupported(backend.get_window_app_id(window)) {
+            Ok(app_id) => app_id.flatten(),
+            Err(crate::Error::WindowGone(_) | crate::Error::WindowNotFound(_)) if !retried => {
+                retried = true;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        if retried || backend.get_active_window()? == Some(window) {
+            return Ok(Some(ActiveWindowSnapshot {
+                window,
+                pid: details.pid,
+                title: details.title,
+                app_id,
+                process_name: details.pid.map(crate::get_process_name).transpose()?.flatten(),
+                info: details.info,
+            }));
+        }
+        retried = true;
+    }
+}
+
+/// `find_windows_by_pids` from `list_windows` and one `get_window_pid` per
+/// window. Windows that close during the pass are left out.
+pub(crate) fn collect_windows_by_pids<B: WindowingBackend + ?Sized>(
+    backend: &B,
+    pids: &[u32],
+) -> crate::Result<Vec<(u32, Window)>> {
+    if pids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pids: HashSet<u32> = pids.iter().copied().collect();
+    let mut found = Vec::new();
+    for window in backend.list_windows()? {
+        match backend.get_window_pid(window) {
+            Ok(Some(pid)) if pids.contains(&pid) => found.push((pid, window)),
+            Ok(_) | Err(crate::Error::WindowGone(_) | crate::Error::WindowNotFound(_)) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(found)
+}
+
+/// `Ok(None)` where the operation is unsupported
+pub(crate) fn unless_unsupported<T>(result: crate::Result<T>) -> crate::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(crate::Error::Unsupported(_)) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// The backend serving the free functions: the one passed to
+/// `set_global_backend`, or else a `WindowSystem` shared by the whole process,
+/// so its connection is reused across calls. On X11 that is one connection per
+/// calling thread, reconnected once if the X server went away.
+pub fn default_backend() -> &'static dyn WindowingBackend {
+    match GLOBAL.get() {
+        Some(backend) => backend.as_ref(),
+        None => shared(),
+    }
+}
+
+static GLOBAL: OnceLock<Box<dyn WindowingBackend>> = OnceLock::new();
+
+/// Serve the free functions (and `default_backend`) from `backend` instead of
+/// the platform's `WindowSystem`, e.g. a compositor backend from another
+/// crate. It can be set once per process: a later call fails with
+/// `BackendAlreadySet` and drops its backend. Set it before other threads
+/// start making calls, as calls made earlier went to the platform backend.
+pub fn set_global_backend(backend: Box<dyn WindowingBackend>) -> Result<(), BackendAlreadySet> {
+    GLOBAL.set(backend).map_err(|_| BackendAlreadySet)
+}
+
+/// The process-wide `WindowSystem`, created on first use. Creating it doesn't
+/// connect; the connection is opened by the first call that needs one. On
+/// Linux each thread gets its own X connection.
+pub(crate) fn shared() -> &'static WindowSystem {
+    static SHARED: OnceLock<WindowSystem> = OnceLock::new();
+    #[cfg(target_os = "linux")]
+    let create = WindowSystem::per_thread;
+    #[cfg(not(target_os = "linux"))]
+    let create = WindowSystem::default;
+    SHARED.get_or_init(create)
+}
+
+// `WindowSystem` is shared between threads (by `shared`, and by callers holding
+// an `Arc<WindowSystem>`), so it has to stay `Send + Sync` on every platform.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<WindowSystem>();
+};
+
+/// The backend serving these calls (override with `WINDOWING_BACKEND=<name>` on Linux)
+pub fn detect_backend() -> BackendKind {
+    default_backend().kind()
+}
+
+pub fn list_windows() -> crate::Result<Vec<Window>> {
+    default_backend().list_windows()
+}
+
+pub fn list_windows_including_desktop() -> crate::Result<Vec<Window>> {
+    default_backend().list_windows_including_desktop()
+}
+
+pub fn window_exists(window: Window) -> crate::Result<bool> {
+    default_backend().window_exists(window)
+}
+
+pub fn window_count() -> crate::Result<usize> {
+    default_backend().window_count()
+}
+
+pub fn get_window_info(window: Window) -> crate::Result<WindowInfo> {
+    default_backend().get_window_info(window)
+}
+
+pub fn get_window_infos(windows: &[Window]) -> crate::Result<Vec<WindowResult<WindowInfo>>> {
+    default_backend().get_window_infos(windows)
+}
+
+pub fn get_window_details(window: Window) -> crate::Result<WindowDetails> {
+    default_backend().get_window_details(window)
+}
+
+pub fn get_window_properties(windows: &[Window]) -> crate::Result<Vec<WindowResult<WindowProperties>>> {
+    default_backend().get_window_properties(windows)
+}
+
+pub fn get_window_info_with_retry(window: Window, retries: u32) -> crate::Result<WindowInfo> {
+    default_backend().get_window_info_with_retry(window, retries)
+}
+
+/// `get_window_info` for a raw id, validated with `Window::from_id`
+pub fn get_window_info_by_id(id: u64) -> crate::Result<WindowInfo> {
+    get_window_info(Window::from_id(id)?)
+}
+
+pub fn get_window_bounds(window: Window) -> crate::Result<PhysicalWindowInfo> {
+    default_backend().get_window_bounds(window)
+}
+
+/// `pid`, or `InvalidPid` for one no process can have
+pub(crate) fn check_pid(pid: u32) -> crate::Result<u32> {
+    if pid == 0 {
+        return Err(crate::Error::InvalidPid(pid));
+    }
+    Ok(pid)
+}
+
+pub fn find_window_by_pid(target_pid: u32) -> crate::Result<Option<Window>> {
+    find_window_by_pid_in(default_backend(), target_pid)
+}
+
+/// `find_window_by_pid` on `backend`: the process's first main window, or its
+/// first window if none looks main
+pub(crate) fn find_window_by_pid_in(
+    backend: &dyn WindowingBackend,
+    target_pid: u32,
+) -> crate::Result<Option<Window>> {
+    let query = WindowQuery::new().pid(target_pid);
+    if let Some(window) = query.clone().visible(true).first_in(backend)? {
+        return Ok(Some(window));
+    }
+    query.first_in(backend)
+}
+
+/// `find_window_by_pid`, but a process that isn't running is
+/// `ProcessNotFound` rather than `Ok(None)`, which is left for a running
+/// process without windows.
+pub fn find_window_by_pid_strict(target_pid: u32) -> crate::Result<Option<Window>> {
+    find_window_by_pid_strict_in(default_backend(), target_pid)
+}
+
+/// `find_window_by_pid_strict` on `backend`
+pub(crate) fn find_window_by_pid_strict_in(
+    backend: &dyn WindowingBackend,
+    target_pid: u32,
+) -> crate::Result<Option<Window>> {
+    let window = find_window_by_pid_in(backend, target_pid)?;
+    // Checked after the search, so a process exiting during it reads as gone
+    if window.is_none() && !crate::process_exists(target_pid)? {
+        return Err(crate::Error::ProcessNotFound(target_pid));
+    }
+    Ok(window)
+}
+
+pub fn find_windows_by_pid(target_pid: u32) -> crate::Result<Vec<Window>> {
+    WindowQuery::new().pid(target_pid).all()
+}
+
+pub fn find_windows_by_pids(pids: &[u32]) -> crate::Result<Vec<(u32, Window)>> {
+    for &pid in pids {
+        check_pid(pid)?;
+    }
+    default_backend().find_windows_by_pids(pids)
+}
+
+pub fn pid_has_window(target_pid: u32) -> crate::Result<bool> {
+    default_backend().pid_has_window(check_pid(target_pid)?)
+}
+
+pub fn find_visible_windows_by_pid(target_pid: u32) -> crate::Result<Vec<Window>> {
+    WindowQuery::new().pid(target_pid).visible(true).all()
+}
+
+pub fn find_windows_by_app_id(app_id: &str) -> crate::Result<Vec<Window>> {
+    WindowQuery::new().app_id(app_id).all()
+}
+
+pub fn get_class_for_pid(target_pid: u32) -> crate::Result<Option<(String, String)>> {
+    default_backend().get_class_for_pid(check_pid(target_pid)?)
+}
+
+pub fn is_main_window(window: Window) -> crate::Result<bool> {
+    default_backend().is_main_window(window)
+}
+
+pub fn get_active_window() -> crate::Result<Option<Window>> {
+    default_backend().get_active_window()
+}
+
+pub fn active_window_snapshot() -> crate::Result<Option<ActiveWindowSnapshot>> {
+    default_backend().active_window_snapshot()
+}
+
+pub fn get_active_window_pid() -> crate::Result<Option<u32>> {
+    default_backend().get_active_window_pid()
+}
+
+pub fn get_window_pid(window: Window) -> crate::Result<Option<u32>> {
+    default_backend().get_window_pid(window)
+}
+
+/// `get_window_pid` for a raw id, validated with `Window::from_id`
+pub fn get_window_pid_by_id(id: u64) -> crate::Result<Option<u32>> {
+    get_window_pid(Window::from_id(id)?)
+}
+
+pub fn get_window_title(window: Window) -> crate::Result<Option<String>> {
+    default_backend().get_window_title(window)
+}
+
+/// `get_window_title` for a raw id, validated with `Window::from_id`
+pub fn get_window_title_by_id(id: u64) -> crate::Result<Option<String>> {
+    get_window_title(Window::from_id(id)?)
+}
+
+pub fn get_window_app_id(window: Window) -> crate::Result<Option<String>> {
+    default_backend().get_window_app_id(window)
+}
+
+pub fn get_window_icon(window: Window) -> crate::Result<Option<IconData>> {
+    default_backend().get_window_icon(window)
+}
+
+pub fn hide_window(window: Window) -> crate::Result<()> {
+    default_backend().hide_window(window)
+}
+
+pub fn set_skip_switcher(window: Window, skip: bool) -> crate::Result<()> {
+    default_backend().set_skip_switcher(window, skip)
+}
+
+pub fn set_skip_taskbar(window: Window, skip: bool) -> crate::Result<()> {
+    default_backend().set_skip_taskbar(window, skip)
+}
+
+pub fn minimize_window(window: Window) -> crate::Result<()> {
+    default_backend().minimize_window(window)
+}
+
+pub fn maximize_window(window: Window) -> crate::Result<()> {
+    default_backend().maximize_window(window)
+}
+
+pub fn toggle_maximize(window: Window) -> crate::Result<()> {
+    default_backend().toggle_maximize(window)
+}
+
+pub fn close_window(window: Window) -> crate::Result<()> {
+    default_backend().close_window(window)
+}
+
+pub fn move_window(window: Window, x: i32, y: i32) -> crate::Result<()> {
+    default_backend().move_window(window, x, y)
+}
+
+pub fn move_window_clamped(window: Window, x: i32, y: i32) -> crate::Result<()> {
+    default_backend().move_window_clamped(window, x, y)
+}
+
+pub fn resize_window(window: Window, width: u32, height: u32) -> crate::Result<()> {
+    default_backend().resize_window(window, width, height)
+}
+
+pub fn set_window_rect(window: Window, x: i32, y: i32, width: u32, height: u32) -> crate::Result<()> {
+    default_backend().set_window_rect(window, x, y, width, height)
+}
+
+pub fn focus_window(window: Window) -> crate::Result<()> {
+    default_backend().focus_window(window)
+}
+
+pub fn can_focus(window: Window) -> crate::Result<bool> {
+    default_backend().can_focus(window)
+}
+
+pub fn activate_window(window: Window, update_user_time: bool) -> crate::Result<()> {
+    default_backend().activate_window(window, update_user_time)
+}
+
+pub fn set_user_time(window: Window, timestamp: u32) -> crate::Result<()> {
+    default_backend().set_user_time(window, timestamp)
+}
+
+pub fn get_window_workspace(window: Window) -> crate::Result<Option<u32>> {
+    default_backend().get_window_workspace(window)
+}
+
+pub fn move_window_to_workspace(window: Window, workspace: u32) -> crate::Result<()> {
+    default_backend().move_window_to_workspace(window, workspace)
+}
+
+pub fn get_current_workspace() -> crate::Result<Option<u32>> {
+    default_backend().get_current_workspace()
+}
+
+pub fn switch_workspace(workspace: u32) -> crate::Result<()> {
+    default_backend().switch_workspace(workspace)
+}
+
+pub fn subscribe_window_events() -> crate::Result<WindowEvents> {
+    default_backend().subscribe_window_events()
+}
+
+pub fn get_window_monitor(window: Window) -> crate::Result<Option<MonitorInfo>> {
+    default_backend().get_window_monitor(window)
+}
+
+pub fn get_window_monitors(window: Window) -> crate::Result<Vec<MonitorInfo>> {
+    default_backend().get_window_monitors(window)
+}
+
+pub fn get_window_work_area(window: Window) -> crate::Result<Option<WindowInfo>> {
+    default_backend().get_window_work_area(window)
+}
+
+pub fn get_virtual_screen() -> crate::Result<WindowInfo> {
+    default_backend().get_virtual_screen()
+}
+
+pub fn get_root_window() -> crate::Result<Window> {
+    default_backend().get_root_window()
+}
+
+pub fn pointer_state() -> crate::Result<(i32, i32, Option<Window>)> {
+    default_backend().pointer_state()
+}
+
+pub fn window_has_state(window: Window, state: WindowState) -> crate::Result<bool> {
+    default_backend().window_has_state(window, state)
+}
+
+pub fn get_window_states(window: Window) -> crate::Result<Vec<WindowStateFlag>> {
+    default_backend().get_window_states(window)
+}
+
+pub fn set_window_state(window: Window, flag: WindowStateFlag, enabled: bool) -> crate::Result<()> {
+    default_backend().set_window_state(window, flag, enabled)
+}
+
+pub fn is_sticky(window: Window) -> crate::Result<bool> {
+    default_backend().is_sticky(window)
+}
+
+pub fn set_sticky(window: Window, sticky: bool) -> crate::Result<()> {
+    default_backend().set_sticky(window, sticky)
+}
+
+pub fn is_always_on_top(window: Window) -> crate::Result<bool> {
+    default_backend().is_always_on_top(window)
+}
+
+pub fn set_always_on_top(window: Window, on_top: bool) -> crate::Result<()> {
+    default_backend().set_always_on_top(window, on_top)
+}
+
+pub fn is_always_below(window: Window) -> crate::Result<bool> {
+    default_backend().is_always_below(window)
+}
+
+pub fn set_always_below(window: Window, below: bool) -> crate::Result<()> {
+    default_backend().set_always_below(window, below)
+}
+
+pub fn set_click_through(window: Window, click_through: bool) -> crate::Result<()> {
+    default_backend().set_click_through(window, click_through)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBackend, MockFailure, MockWindow};
+    use std::sync::Mutex;
+
+    fn window(id: u64) -> Window {
+        Window::from_id(id).unwrap()
+    }
+
+    type Change = Box<dyn FnOnce(&MockBackend) + Send>;
+
+    /// A mock whose desktop changes while a window is being read: each
+    /// `get_window_details` call first applies the next queued change
+    struct Racing {
+        mock: MockBackend,
+        changes: Mutex<Vec<Change>>,
+    }
+
+    impl Racing {
+        fn new(mock: MockBackend, mut changes: Vec<Change>) -> Self {
+            changes.reverse();
+            Self { mock, changes: Mutex::new(changes) }
+        }
+    }
+
+    impl WindowingBackend for Racing {
+        fn kind(&self) -> BackendKind {
+            self.mock.kind()
+        }
+
+        fn get_active_window(&self) -> crate::Result<Option<Window>> {
+            self.mock.get_active_window()
+        }
+
+        fn get_window_details(&self, window: Window) -> crate::Result<WindowDetails> {
+            let change = self.changes.lock().unwrap().pop();
+            if let Some(change) = change {
+                change(&self.mock);
+            }
+            self.mock.get_window_details(window)
+        }
+
+        fn get_window_app_id(&self, window: Window) -> crate::Result<Option<String>> {
+            self.mock.get_window_app_id(window)
+        }
+    }
+
+    fn two_windows() -> MockBackend {
+        MockBackend::new()
+            .with_window(MockWindow::new(window(1)).with_title("Editor").with_app_id("editor"))
+            .with_window(MockWindow::new(window(2)).with_title("Terminal").with_app_id("terminal"))
+            .with_active(window(1))
+    }
+
+    #[test]
+    fn snapshot_active_window_reads_the_focused_window() {
+        let snapshot = snapshot_active_window(&two_windows()).unwrap().unwrap();
+        assert_eq!(snapshot.window, window(1));
+        assert_eq!(snapshot.title.as_deref(), Some("Editor"));
+        assert_eq!(snapshot.app_id.as_deref(), Some("editor"));
+        assert_eq!(snapshot.process_name, None);
+    }
+
+    #[test]
+    fn snapshot_active_window_is_none_with_nothing_focused() {
+        let backend = MockBackend::new().with_window(MockWindow::new(window(1)).with_title("Editor"));
+        assert!(snapshot_active_window(&backend).unwrap().is_none());
+    }
+
+    #[test]
+    fn snapshot_active_window_rereads_when_focus_moves() {
+        let backend = Racing::new(two_windows(), vec![Box::new(|mock| mock.focus_window(window(2)).unwrap())]);
+
+        let snapshot = snapshot_active_window(&backend).unwrap().unwrap();
+        assert_eq!(snapshot.window, window(2));
+        assert_eq!(snapshot.title.as_deref(), Some("Terminal"));
+    }
+
+    #[test]
+    fn snapshot_active_window_rereads_when_the_window_closes() {
+        let backend = Racing::new(
+            two_windows(),
+            vec![Box::new(|mock| {
+                mock.remove_window(window(1));
+                mock.focus_window(window(2)).unwrap();
+            })],
+        );
+
+        let snapshot = snapshot_active_window(&backend).unwrap().unwrap();
+        assert_eq!(snapshot.window, window(2));
+        assert_eq!(snapshot.app_id.as_deref(), Some("terminal"));
+    }
+
+    #[test]
+    fn snapshot_active_window_accepts_a_second_focus_change() {
+        let backend = Racing::new(
+            two_windows(),
+            vec![
+                Box::new(|mock| mock.focus_window(window(2)).unwrap()),
+                Box::new(|mock| mock.focus_window(window(1)).unwrap()),
+            ],
+        );
+
+        // The retry read window 2, which was active when it was read
+        let snapshot = snapshot_active_window(&backend).unwrap().unwrap();
+        assert_eq!(snapshot.window, window(2));
+    }
+
+    #[test]
+    fn snapshot_active_window_is_none_when_the_window_closes_with_nothing_focused_after() {
+        let backend = Racing::new(two_windows(), vec![Box::new(|mock| mock.remove_window(window(1)))]);
+        assert!(snapshot_active_window(&backend).unwrap().is_none());
+    }
+
+    /// A 1920x1080 monitor with a 40-pixel panel along the top, and a second
+    /// one to its right with nothing reserved
+    fn desktop() -> MockBackend {
+        let monitor = |name: &str, x: i32| MonitorInfo {
+            name: name.into(),
+            bounds: WindowInfo { pos: (x, 0), size: (1920, 1080) },
+            scale_factor: 1.0,
+        };
+        MockBackend::new()
+            .with_monitor_work_area(monitor("DP-1", 0), WindowInfo { pos: (0, 40), size: (1920, 1040) })
+            .with_monitor(monitor("DP-2", 1920))
+    }
+
+    fn position(backend: &MockBackend, window: Window) -> (i32, i32) {
+        backend.window(window).unwrap().geometry().pos
+    }
+
+    #[test]
+    fn move_window_clamped_keeps_the_frame_in_the_work_area() {
+        let backend = desktop().with_window(MockWindow::new(window(1)).with_geometry(100, 100, 800, 600));
+
+        // Past the bottom-right corner and under the panel
+        backend.move_window_clamped(window(1), 1500, 900).unwrap();
+        assert_eq!(position(&backend, window(1)), (1120, 480));
+        backend.move_window_clamped(window(1), -50, 0).unwrap();
+        assert_eq!(position(&backend, window(1)), (0, 40));
+        // Already inside: moved as given
+        backend.move_window_clamped(window(1), 300, 200).unwrap();
+        assert_eq!(position(&backend, window(1)), (300, 200));
+    }
+
+    #[test]
+    fn move_window_clamped_uses_the_monitor_the_window_is_on_now() {
+        let backend = desktop().with_window(MockWindow::new(window(1)).with_geometry(2000, 100, 800, 600));
+
+        // DP-2 reserves nothing, so y = 0 is allowed; x is kept on DP-2
+        backend.move_window_clamped(window(1), 100, 0).unwrap();
+        assert_eq!(position(&backend, window(1)), (1920, 0));
+    }
+
+    #[test]
+    fn move_window_clamped_keeps_the_corner_of_an_oversized_frame_in_the_work_area() {
+        let backend = desktop().with_window(MockWindow::new(window(1)).with_geometry(0, 40, 2500, 1200));
+
+        backend.move_window_clamped(window(1), 300, 300).unwrap();
+        assert_eq!(position(&backend, window(1)), (0, 40));
+    }
+
+    #[test]
+    fn move_window_clamped_moves_a_window_on_no_monitor_as_given() {
+        let backend = desktop().with_window(MockWindow::new(window(1)).with_geometry(-5000, -5000, 800, 600));
+
+        backend.move_window_clamped(window(1), -4000, 9000).unwrap();
+        assert_eq!(position(&backend, window(1)), (-4000, 9000));
+    }
+
+    #[test]
+    fn nothing_focused_is_none_not_an_error() {
+        let backend = MockBackend::new().with_window(MockWindow::new(window(1)).with_pid(10).with_title("Editor"));
+
+        assert_eq!(backend.get_active_window().unwrap(), None);
+        assert_eq!(backend.get_active_window_pid().unwrap(), None);
+        assert_eq!(backend.active_window_snapshot().unwrap(), None);
+    }
+
+    #[test]
+    fn a_process_without_windows_is_none_or_empty_not_an_error() {
+        // This test process is running but has no windows; another one has
+        let backend = MockBackend::new().with_window(MockWindow::new(window(1)).with_pid(10).with_title("Editor"));
+        let windowless = std::process::id();
+
+        assert_eq!(backend.find_window_by_pid(windowless).unwrap(), None);
+        assert!(backend.find_windows_by_pid(windowless).unwrap().is_empty());
+        assert!(backend.find_visible_windows_by_pid(windowless).unwrap().is_empty());
+        assert!(backend.find_windows_by_pids(&[windowless]).unwrap().is_empty());
+        assert!(!backend.pid_has_window(windowless).unwrap());
+        assert_eq!(backend.get_class_for_pid(windowless).unwrap(), None);
+        assert_eq!(WindowQuery::new().pid(windowless).first_in(&backend).unwrap(), None);
+    }
+
+    #[test]
+    fn pid_has_window_asks_for_one_window_only() {
+        let backend = MockBackend::new()
+            .with_window(MockWindow::new(window(1)).with_pid(10).with_title("Editor"))
+            .with_failure("find_windows_by_pid", MockFailure::Unsupported);
+
+        assert!(backend.pid_has_window(10).unwrap());
+        assert!(!backend.pid_has_window(20).unwrap());
+    }
+
+    #[test]
+    fn find_window_by_pid_prefers_a_main_window_and_falls_back_to_the_first() {
+        let tooltip =
+            MockWindow::new(window(1)).with_pid(10).with_title("Tip").with_state(WindowStateFlag::SkipTaskbar);
+        let backend = MockBackend::new()
+            .with_window(tooltip.clone())
+            .with_window(MockWindow::new(window(2)).with_pid(10).with_title("Editor"));
+
+        assert_eq!(find_window_by_pid_in(&backend, 10).unwrap(), Some(window(2)));
+        let backend = MockBackend::new().with_window(tooltip);
+        assert_eq!(find_window_by_pid_in(&backend, 10).unwrap(), Some(window(1)));
+        assert_eq!(find_window_by_pid_in(&backend, 20).unwrap(), None);
+        assert!(matches!(find_window_by_pid_in(&backend, 0), Err(crate::Error::InvalidPid(0))));
+    }
+
+    /// The pid of a process that has exited and been reaped
+    fn exited_pid() -> u32 {
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .arg("--list")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn find_window_by_pid_strict_reports_a_process_that_just_exited() {
+        let backend = MockBackend::new().with_window(MockWindow::new(window(1)).with_pid(10).with_title("Editor"));
+        let pid = exited_pid();
+
+        assert!(!crate::process_exists(pid).unwrap());
+        let strict = find_window_by_pid_strict_in(&backend, pid);
+        assert!(matches!(strict, Err(crate::Error::ProcessNotFound(gone)) if gone == pid));
+        // The lenient search can't tell it from a running process without windows
+        assert_eq!(backend.find_window_by_pid(pid).unwrap(), None);
+    }
+
+    #[test]
+    fn find_window_by_pid_strict_is_none_for_a_running_process_without_windows() {
+        let backend = MockBackend::new().with_window(MockWindow::new(window(1)).with_pid(10).with_title("Editor"));
+
+        assert!(crate::process_exists(std::process::id()).unwrap());
+        assert_eq!(find_window_by_pid_strict_in(&backend, std::process::id()).unwrap(), None);
+        assert!(matches!(find_window_by_pid_strict_in(&backend, 0), Err(crate::Error::InvalidPid(0))));
+    }
+
+    #[test]
+    fn find_window_by_pid_strict_finds_the_window_without_checking_the_process() {
+        // pid 10 needn't be running: a window owned by it is enough
+        let backend = MockBackend::new().with_window(MockWindow::new(window(1)).with_pid(10).with_title("Editor"));
+
+        assert_eq!(find_window_by_pid_strict_in(&backend, 10).unwrap(), Some(window(1)));
+    }
+}
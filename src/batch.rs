@@ -0,0 +1,48 @@
+//! Applying one operation to many windows without stopping at the first failure.
+
+use crate::Window;
+
+/// What `best_effort` did: the windows the operation succeeded on, with its
+/// result, and those it failed on, with the error, each in input order.
+#[derive(Debug)]
+pub struct BatchReport<T = ()> {
+    pub succeeded: Vec<(Window, T)>,
+    pub failed: Vec<(Window, crate::Error)>,
+}
+
+impl<T> BatchReport<T> {
+    /// Whether the operation succeeded on every window.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// The windows the operation failed on.
+    pub fn failed_windows(&self) -> impl Iterator<Item = Window> + '_ {
+        self.failed.iter().map(|&(window, _)| window)
+    }
+}
+
+/// Run `operation` on every window, collecting per-window failures (a window
+/// that closed, refused a move, lacks a property) instead of stopping at the
+/// first, e.g. to apply a layout to as many windows as will take it:
+/// `best_effort(&windows, |window| move_window(window, 0, 0))`.
+///
+/// Failures that no other window would escape end the batch with that error:
+/// losing the connection (`ConnectionFailed`, `NoDisplay`) or the window
+/// system not answering (`Timeout`).
+pub fn best_effort<T>(
+    windows: &[Window],
+    mut operation: impl FnMut(Window) -> crate::Result<T>,
+) -> crate::Result<BatchReport<T>> {
+    let mut report = BatchReport { succeeded: Vec::new(), failed: Vec::new() };
+    for &window in windows {
+        match operation(window) {
+            Ok(value) => report.succeeded.push((window, value)),
+            Err(error @ (crate::Error::ConnectionFailed(_) | crate::Error::NoDisplay { .. } | crate::Error::Timeout)) => {
+                return Err(error);
+            }
+            Err(error) => report.failed.push((window, error)),
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,199 @@
+//! The crate's error type.
+//!
+//! Up to 0.1 every call returned `Box<dyn std::error::Error>`, and callers told
+//! failures apart by downcasting or by matching message text. Since 0.2 calls return
+//! `windowing::Result<T>`, whose `Error` says what went wrong in its variant.
+//! To migrate, match on the variant where the message used to be inspected:
+//! `Error::WindowGone(window)` for "Window … does not exist",
+//! `Error::Unsupported(_)` where `Unsupported` was downcast to, and so on.
+//! `Error` is `Send + Sync + 'static`, so `?` still converts it into a
+//! `Box<dyn std::error::Error>` (or `anyhow::Error`) in calling code.
+//!
+//! 0.2 also drops the `Option` that `get_window_info`, `get_window_info_by_id`
+//! and `get_window_info_with_retry` returned on Windows only: a closed or
+//! invalid handle is now `Error::WindowGone` there as on other platforms.
+
+use crate::{FocusRefused, InvalidWindowId, PermissionDenied, Unsupported, Window};
+use std::fmt;
+
+/// What went wrong in a call to the crate.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The window system couldn't be reached, or the connection to it was lost
+    /// (no `DISPLAY`, compositor socket missing, X server restarted).
+    ConnectionFailed(Box<dyn std::error::Error + Send + Sync>),
+    /// There is no display to connect to: neither `DISPLAY` nor
+    /// `WAYLAND_DISPLAY` is set, or nothing listens on the one tried
+    /// (`display`). Typical of containers, CI and text consoles; see
+    /// `is_display_available` to check up front.
+    NoDisplay { display: Option<String> },
+    /// The backend has no window with this handle. Compositor backends look
+    /// windows up by id and can't tell a closed window from a bad id, so they
+    /// report closed windows this way too.
+    WindowNotFound(Window),
+    /// The window existed but has been destroyed.
+    WindowGone(Window),
+    /// A window lacks a property or attribute the operation needs.
+    PropertyMissing { window: Window, property: &'static str },
+    Unsupported(Unsupported),
+    PermissionDenied(PermissionDenied),
+    FocusRefused(FocusRefused),
+    InvalidWindowId(InvalidWindowId),
+    /// A pid no process can have (0).
+    InvalidPid(u32),
+    /// No process with this pid is running (it exited, or never existed).
+    ProcessNotFound(u32),
+    /// The window system didn't answer within the request timeout (see
+    /// `WindowSystem::with_request_timeout`; `wayland-wlr` waits 5 seconds).
+    Timeout,
+    /// A request to the window system failed in a way the variants above
+    /// don't cover, with where it happened: the crate `operation`, the
+    /// `window` if there was one, and the X `request` or property, or the
+    /// Win32 function, that failed.
+    Request {
+        operation: &'static str,
+        window: Option<Window>,
+        request: &'static str,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Any other failure, as reported by the platform.
+    Platform(Box<dyn std::error::Error + Send + Sync>),
+}
+
+// Errors are sent across threads (event subscriptions, `Arc`-shared trackers)
+// and boxed into `Box<dyn Error + Send + Sync>` by callers.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+    assert_send_sync::<Error>();
+};
+
+/// `std::result::Result` with the crate's `Error`.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// Wrap a failure to reach the window system.
+    pub(crate) fn connection(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Error::ConnectionFailed(source.into())
+    }
+
+    /// Say where a `Platform` error came from, making it a `Request` error.
+    /// The other variants already say what went wrong and pass through.
+    #[cfg_attr(not(any(all(target_os = "linux", feature = "x11"), target_os = "windows")), allow(dead_code))]
+    pub(crate) fn context(self, operation: &'static str, window: Option<Window>, request: &'static str) -> Self {
+        match self {
+            Error::Platform(source) => Error::Request { operation, window, request, source },
+            error => error,
+        }
+    }
+}
+
+/// `Error::context` for any result whose error converts into `Error`
+#[cfg_attr(not(any(all(target_os = "linux", feature = "x11"), target_os = "windows")), allow(dead_code))]
+pub(crate) trait Context<T> {
+    fn context(self, operation: &'static str, window: Option<Window>, request: &'static str) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn context(self, operation: &'static str, window: Option<Window>, request: &'static str) -> Result<T> {
+        self.map_err(|error| error.into().context(operation, window, request))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ConnectionFailed(source) => write!(f, "Could not reach the window system: {source}"),
+            Error::NoDisplay { display: None } => {
+                write!(f, "No display is available (DISPLAY and WAYLAND_DISPLAY are unset)")
+            }
+            Error::NoDisplay { display: Some(display) } => write!(f, "No display is available at {display}"),
+            Error::WindowNotFound(window) => write!(f, "No window {window} is known to the backend"),
+            Error::WindowGone(window) => write!(f, "Window {window} does not exist"),
+            Error::PropertyMissing { window, property } => write!(f, "Window {window} has no {property}"),
+            Error::Unsupported(error) => error.fmt(f),
+            Error::PermissionDenied(error) => error.fmt(f),
+            Error::FocusRefused(error) => error.fmt(f),
+            Error::InvalidWindowId(error) => error.fmt(f),
+            Error::InvalidPid(pid) => write!(f, "{pid} is not a valid process id"),
+            Error::ProcessNotFound(pid) => write!(f, "No process {pid} is running"),
+            Error::Timeout => write!(f, "The window system did not answer in time"),
+            Error::Request { operation, window: Some(window), request, source } => {
+                write!(f, "{operation} failed on window {window} ({request}): {source}")
+            }
+            Error::Request { operation, window: None, request, source } => {
+                write!(f, "{operation} failed ({request}): {source}")
+            }
+            Error::Platform(source) => source.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::ConnectionFailed(source) | Error::Request { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<Unsupported> for Error {
+    fn from(error: Unsupported) -> Self {
+        Error::Unsupported(error)
+    }
+}
+
+impl From<PermissionDenied> for Error {
+    fn from(error: PermissionDenied) -> Self {
+        Error::PermissionDenied(error)
+    }
+}
+
+impl From<FocusRefused> for Error {
+    fn from(error: FocusRefused) -> Self {
+        Error::FocusRefused(error)
+    }
+}
+
+impl From<InvalidWindowId> for Error {
+    fn from(error: InvalidWindowId) -> Self {
+        Error::InvalidWindowId(error)
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Platform(message.into())
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Platform(message.into())
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(error: std::str::Utf8Error) -> Self {
+        Error::Platform(Box::new(error))
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        Error::Platform(Box::new(error))
+    }
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(error: std::num::ParseIntError) -> Self {
+        Error::Platform(Box::new(error))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Platform(Box::new(error))
+    }
+}
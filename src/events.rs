@@ -0,0 +1,51 @@
+//! Window lifecycle/focus events shared by every backend that can watch them.
+
+/// Something that happened to a top-level window.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WindowEvent {
+    /// A new top-level window appeared.
+    Opened(crate::Window),
+    /// A top-level window went away.
+    Closed(crate::Window),
+    /// The active window changed; `None` when nothing has focus.
+    Focused(Option<crate::Window>),
+    /// A window's title changed.
+    TitleChanged(crate::Window),
+    /// A window was moved to another workspace (zero-based index, when known).
+    WorkspaceChanged(crate::Window, Option<u32>),
+}
+
+/// Blocking iterator over [`WindowEvent`]s, returned by `subscribe_window_events`.
+///
+/// Each call to `next` waits for the next event; an `Err` item means the
+/// underlying connection failed and the stream should be dropped.
+pub struct WindowEvents {
+    source: Box<dyn Iterator<Item = crate::Result<WindowEvent>> + Send>,
+}
+
+impl WindowEvents {
+    #[cfg_attr(
+        not(all(
+            target_os = "linux",
+            any(feature = "x11", feature = "wayland-wlr", feature = "hyprland", feature = "sway-ipc")
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn new(
+        source: impl Iterator<Item = crate::Result<WindowEvent>> + Send + 'static,
+    ) -> Self {
+        Self {
+            source: Box::new(source),
+        }
+    }
+}
+
+impl Iterator for WindowEvents {
+    type Item = crate::Result<WindowEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.source.next()
+    }
+}
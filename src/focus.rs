@@ -0,0 +1,62 @@
+//! Most-recently-used focus order, built on `subscribe_window_events`.
+
+use crate::{Window, WindowEvent, WindowState};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Tracks which windows had focus, most recent first, by following the
+/// `Focused` and `Closed` events of `subscribe_window_events`.
+///
+/// The history starts with the window active when tracking began. The
+/// watching thread blocks on the event stream, so after the tracker is dropped
+/// it lingers until the next window event arrives.
+pub struct FocusTracker {
+    recent: Arc<Mutex<Vec<Window>>>,
+}
+
+impl FocusTracker {
+    /// Start watching focus changes in a background thread.
+    pub fn start() -> crate::Result<Self> {
+        let events = crate::subscribe_window_events()?;
+        let initial = crate::get_active_window().ok().flatten();
+        let recent = Arc::new(Mutex::new(initial.into_iter().collect::<Vec<_>>()));
+        let history = Arc::downgrade(&recent);
+        thread::spawn(move || {
+            for event in events {
+                let Ok(event) = event else {
+                    break;
+                };
+                let Some(history) = history.upgrade() else {
+                    break;
+                };
+                let mut history = history.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                match event {
+                    WindowEvent::Focused(Some(window)) => {
+                        history.retain(|&seen| seen != window);
+                        history.insert(0, window);
+                    }
+                    WindowEvent::Closed(window) => history.retain(|&seen| seen != window),
+                    _ => {}
+                }
+            }
+        });
+        Ok(Self { recent })
+    }
+
+    /// Windows that had focus since tracking started, most recent first.
+    /// Windows that have since closed are left out, including on backends that
+    /// don't report `Closed` events.
+    pub fn recent_windows(&self) -> Vec<Window> {
+        let snapshot = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let (open, closed): (Vec<Window>, Vec<Window>) = snapshot
+            .into_iter()
+            .partition(|&window| !crate::window_has_state(window, WindowState::Closed).unwrap_or(false));
+        if !closed.is_empty() {
+            let mut history = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            history.retain(|window| !closed.contains(window));
+        }
+        open
+    }
+}
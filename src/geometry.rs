@@ -0,0 +1,386 @@
+//! Conversions between logical coordinates and monitor pixels, and rectangle arithmetic.
+
+use crate::{MonitorInfo, PhysicalWindowInfo, Window, WindowInfo, WindowingBackend};
+
+/// Rectangle arithmetic, done in `i64` so frames reaching past `i32::MAX`
+/// (a position near the limit plus a large size) don't wrap. A rectangle
+/// covers `pos` up to but not including `right()` and `bottom()`, so a
+/// zero-sized one covers no point and meets nothing.
+impl WindowInfo {
+    /// The first column past the right edge, `x + width`, saturating at `i32::MAX`.
+    pub fn right(&self) -> i32 {
+        self.pos.0.saturating_add_unsigned(self.size.0)
+    }
+
+    /// The first row past the bottom edge, `y + height`, saturating at `i32::MAX`.
+    pub fn bottom(&self) -> i32 {
+        self.pos.1.saturating_add_unsigned(self.size.1)
+    }
+
+    /// Width times height; cannot overflow.
+    pub fn area(&self) -> u64 {
+        self.size.0 as u64 * self.size.1 as u64
+    }
+
+    /// The middle point, rounded toward the top left; saturates at `i32::MAX`.
+    pub fn center(&self) -> (i32, i32) {
+        let middle = |start: i32, len: u32| (start as i64 + len as i64 / 2).min(i32::MAX as i64) as i32;
+        (middle(self.pos.0, self.size.0), middle(self.pos.1, self.size.1))
+    }
+
+    /// Whether `(x, y)` is inside: on the left or top edge counts, on
+    /// `right()` or `bottom()` doesn't.
+    pub fn contains_point(&self, (x, y): (i32, i32)) -> bool {
+        let inside = |value: i32, start: i32, len: u32| {
+            value >= start && (value as i64) < start as i64 + len as i64
+        };
+        inside(x, self.pos.0, self.size.0) && inside(y, self.pos.1, self.size.1)
+    }
+
+    /// The area the two rectangles share, or `None` if they share none:
+    /// disjoint, only touching along an edge, or either is zero-sized.
+    pub fn intersection(&self, other: &WindowInfo) -> Option<WindowInfo> {
+        let span = |start_a: i32, len_a: u32, start_b: i32, len_b: u32| {
+            let start = start_a.max(start_b);
+            let end = (start_a as i64 + len_a as i64).min(start_b as i64 + len_b as i64);
+            // The shared span is no longer than either, so it fits in u32
+            (end > start as i64).then(|| (start, (end - start as i64) as u32))
+        };
+        let (x, width) = span(self.pos.0, self.size.0, other.pos.0, other.size.0)?;
+        let (y, height) = span(self.pos.1, self.size.1, other.pos.1, other.size.1)?;
+        Some(WindowInfo { pos: (x, y), size: (width, height) })
+    }
+
+    /// Whether the two rectangles share any area; touching edges don't count.
+    pub fn intersects(&self, other: &WindowInfo) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The smallest rectangle covering both. A zero-sized rectangle covers
+    /// nothing, so it is left out (`self` if both are); a width or height
+    /// past `u32::MAX` saturates.
+    pub fn union(&self, other: &WindowInfo) -> WindowInfo {
+        if other.area() == 0 {
+            return *self;
+        }
+        if self.area() == 0 {
+            return *other;
+        }
+        let (left, top) = (self.pos.0.min(other.pos.0), self.pos.1.min(other.pos.1));
+        let end = |start: i32, len: u32| start as i64 + len as i64;
+        let right = end(self.pos.0, self.size.0).max(end(other.pos.0, other.size.0));
+        let bottom = end(self.pos.1, self.size.1).max(end(other.pos.1, other.size.1));
+        let extent = |start: i32, end: i64| u32::try_from(end - start as i64).unwrap_or(u32::MAX);
+        WindowInfo { pos: (left, top), size: (extent(left, right), extent(top, bottom)) }
+    }
+}
+
+/// Area shared by two rectangles.
+fn overlap(a: &WindowInfo, b: &WindowInfo) -> u64 {
+    a.intersection(b).map_or(0, |shared| shared.area())
+}
+
+/// The monitor holding most of `info`, if it is on any.
+pub(crate) fn monitor_of<'a>(info: &WindowInfo, monitors: &'a [MonitorInfo]) -> Option<&'a MonitorInfo> {
+    monitors
+        .iter()
+        .rev()
+        .max_by_key(|monitor| overlap(info, &monitor.bounds))
+        .filter(|monitor| overlap(info, &monitor.bounds) > 0)
+}
+
+/// Every monitor `info` overlaps, the one holding most of it first; monitors
+/// with equal overlap keep their order in `monitors`.
+pub(crate) fn monitors_of(info: &WindowInfo, monitors: &[MonitorInfo]) -> Vec<MonitorInfo> {
+    let mut touched: Vec<(u64, &MonitorInfo)> = monitors
+        .iter()
+        .map(|monitor| (overlap(info, &monitor.bounds), monitor))
+        .filter(|&(area, _)| area > 0)
+        .collect();
+    touched.sort_by_key(|&(area, _)| std::cmp::Reverse(area));
+    touched.into_iter().map(|(_, monitor)| monitor.clone()).collect()
+}
+
+/// Scale a logical rectangle to pixels of the monitor holding most of it, the
+/// one whose scale the window server renders it at. A window off every
+/// monitor uses the first monitor's scale, or 1 without monitors. The
+/// position is the monitor's logical origin plus the window's offset on it
+/// in pixels, since mixed-scale layouts have no global pixel space.
+pub(crate) fn to_physical(info: WindowInfo, monitors: &[MonitorInfo]) -> PhysicalWindowInfo {
+    let (origin, scale) = monitor_of(&info, monitors)
+        .or(monitors.first())
+        .map_or(((0, 0), 1.0), |monitor| (monitor.bounds.pos, monitor.scale_factor));
+    let offset = |value: i32, origin: i32| origin + ((value - origin) as f64 * scale).round() as i32;
+    PhysicalWindowInfo {
+        pos: (offset(info.pos.0, origin.0), offset(info.pos.1, origin.1)),
+        size: (
+            (info.size.0 as f64 * scale).round() as u32,
+            (info.size.1 as f64 * scale).round() as u32,
+        ),
+    }
+}
+
+/// The smallest rectangle containing every window, in `WindowInfo` units,
+/// with the geometry read in one `get_window_infos` batch. Fails for an empty
+/// slice, and if any window's geometry can't be read (e.g. it closed).
+pub fn bounding_box(windows: &[Window]) -> crate::Result<WindowInfo> {
+    bounding_box_in(crate::default_backend(), windows)
+}
+
+/// `bounding_box` on `backend`
+pub(crate) fn bounding_box_in(backend: &dyn WindowingBackend, windows: &[Window]) -> crate::Result<WindowInfo> {
+    let mut bounds: Option<(i64, i64, i64, i64)> = None;
+    for info in backend.get_window_infos(windows)? {
+        let info = info?;
+        // Edges in i64, so a position near i32::MAX plus its size can't overflow
+        let (left, top) = (info.pos.0 as i64, info.pos.1 as i64);
+        let (right, bottom) = (left + info.size.0 as i64, top + info.size.1 as i64);
+        bounds = Some(match bounds {
+            None => (left, top, right, bottom),
+            Some((l, t, r, b)) => (l.min(left), t.min(top), r.max(right), b.max(bottom)),
+        });
+    }
+    let (left, top, right, bottom) = bounds.ok_or("bounding_box needs at least one window")?;
+    // The union of windows at opposite ends of the coordinate space can be
+    // wider than u32; saturate rather than wrap
+    let extent = |start: i64, end: i64| u32::try_from(end - start).unwrap_or(u32::MAX);
+    Ok(WindowInfo {
+        pos: (left as i32, top as i32),
+        size: (extent(left, right), extent(top, bottom)),
+    })
+}
+
+/// `window`'s frame with its position measured from `reference`'s top-left
+/// corner instead of the screen's, e.g. `(reference width, 0)` for a palette
+/// docked to the right of `reference`. Both frames are read in one
+/// `get_window_infos` batch, so they are in the same units.
+pub fn get_relative_bounds(window: Window, reference: Window) -> crate::Result<WindowInfo> {
+    relative_bounds_in(crate::default_backend(), window, reference)
+}
+
+/// `get_relative_bounds` on `backend`
+pub(crate) fn relative_bounds_in(
+    backend: &dyn WindowingBackend,
+    window: Window,
+    reference: Window,
+) -> crate::Result<WindowInfo> {
+    let infos: [_; 2] = backend.get_window_infos(&[window, reference])?
+        .try_into()
+        .map_err(|_| "get_window_infos returned the wrong number of results")?;
+    let [info, reference] = infos;
+    let (info, reference) = (info?, reference?);
+    Ok(WindowInfo {
+        pos: (info.pos.0.saturating_sub(reference.pos.0), info.pos.1.saturating_sub(reference.pos.1)),
+        size: info.size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBackend, MockWindow};
+
+    fn window(id: u64) -> Window {
+        Window::from_id(id).unwrap()
+    }
+
+    fn monitor(name: &str, x: i32, y: i32, width: u32, height: u32) -> MonitorInfo {
+        MonitorInfo { name: name.into(), bounds: WindowInfo { pos: (x, y), size: (width, height) }, scale_factor: 1.0 }
+    }
+
+    /// Two 1920x1080 monitors side by side, the second one offset down by 200
+    fn dual_head() -> MockBackend {
+        MockBackend::new()
+            .with_monitor(monitor("DP-1", 0, 0, 1920, 1080))
+            .with_monitor(monitor("HDMI-1", 1920, 200, 1920, 1080))
+    }
+
+    #[test]
+    fn bounding_box_spans_every_window() {
+        let backend = dual_head()
+            .with_window(MockWindow::new(window(1)).with_geometry(-100, 50, 300, 200))
+            .with_window(MockWindow::new(window(2)).with_geometry(1900, 400, 100, 100))
+            .with_window(MockWindow::new(window(3)).with_geometry(500, -20, 10, 10));
+
+        let bounds = bounding_box_in(&backend, &[window(1), window(2), window(3)]).unwrap();
+        assert_eq!(bounds, WindowInfo { pos: (-100, -20), size: (2100, 520) });
+        let single = bounding_box_in(&backend, &[window(2)]).unwrap();
+        assert_eq!(single, WindowInfo { pos: (1900, 400), size: (100, 100) });
+    }
+
+    #[test]
+    fn bounding_box_saturates_instead_of_wrapping() {
+        let backend = MockBackend::new()
+            .with_window(MockWindow::new(window(1)).with_geometry(i32::MIN, 0, 10, 10))
+            .with_window(MockWindow::new(window(2)).with_geometry(i32::MAX - 5, 0, u32::MAX, 10));
+
+        let bounds = bounding_box_in(&backend, &[window(1), window(2)]).unwrap();
+        assert_eq!(bounds, WindowInfo { pos: (i32::MIN, 0), size: (u32::MAX, 10) });
+    }
+
+    #[test]
+    fn bounding_box_fails_without_windows_or_with_a_closed_one() {
+        let backend = MockBackend::new().with_window(MockWindow::new(window(1)));
+
+        assert!(bounding_box_in(&backend, &[]).is_err());
+        assert!(matches!(bounding_box_in(&backend, &[window(1), window(2)]), Err(crate::Error::WindowGone(_))));
+    }
+
+    #[test]
+    fn window_spanning_two_monitors_belongs_to_the_one_holding_most_of_it() {
+        // 200 columns on DP-1, 600 on HDMI-1
+        let backend = dual_head().with_window(MockWindow::new(window(1)).with_geometry(1720, 300, 800, 600));
+        let names = |monitors: Vec<MonitorInfo>| monitors.into_iter().map(|monitor| monitor.name).collect::<Vec<_>>();
+
+        let primary = backend.get_window_monitor(window(1)).unwrap().unwrap();
+        assert_eq!(primary.name, "HDMI-1");
+        assert_eq!(names(backend.get_window_monitors(window(1)).unwrap()), ["HDMI-1", "DP-1"]);
+    }
+
+    #[test]
+    fn monitor_lookup_uses_the_frame_position_on_the_whole_screen() {
+        // Above HDMI-1's top edge, so only on DP-1 even though it is right of x = 1920
+        let backend = dual_head().with_window(MockWindow::new(window(1)).with_geometry(1800, 0, 400, 150));
+
+        let names: Vec<_> = backend.get_window_monitors(window(1)).unwrap().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, ["DP-1"]);
+    }
+
+    #[test]
+    fn window_off_every_monitor_has_none() {
+        let backend = dual_head()
+            .with_window(MockWindow::new(window(1)).with_geometry(-900, 0, 800, 600))
+            // Only touching DP-1's right edge and HDMI-1's top edge
+            .with_window(MockWindow::new(window(2)).with_geometry(1920, 0, 100, 200));
+
+        for window in [window(1), window(2)] {
+            assert_eq!(backend.get_window_monitor(window).unwrap(), None);
+            assert!(backend.get_window_monitors(window).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn monitors_holding_equal_parts_keep_their_listed_order() {
+        let backend = dual_head().with_window(MockWindow::new(window(1)).with_geometry(1820, 300, 200, 100));
+
+        assert_eq!(backend.get_window_monitor(window(1)).unwrap().unwrap().name, "DP-1");
+        let names: Vec<_> = backend.get_window_monitors(window(1)).unwrap().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, ["DP-1", "HDMI-1"]);
+    }
+
+    fn scaled(monitor: MonitorInfo, scale_factor: f64) -> MonitorInfo {
+        MonitorInfo { scale_factor, ..monitor }
+    }
+
+    #[test]
+    fn physical_frame_on_a_single_2x_monitor_doubles() {
+        let monitors = [scaled(monitor("Built-in", 0, 0, 1440, 900), 2.0)];
+
+        let physical = to_physical(WindowInfo { pos: (100, 38), size: (800, 600) }, &monitors);
+        assert_eq!(physical, PhysicalWindowInfo { pos: (200, 76), size: (1600, 1200) });
+    }
+
+    #[test]
+    fn physical_frame_uses_the_scale_of_the_monitor_holding_most_of_it() {
+        // A 2x laptop panel with a 1x external display to its right
+        let monitors = [scaled(monitor("Built-in", 0, 0, 1440, 900), 2.0), monitor("External", 1440, 0, 1920, 1080)];
+
+        // 100 columns on the panel, 700 on the external display
+        let spanning = to_physical(WindowInfo { pos: (1340, 100), size: (800, 600) }, &monitors);
+        assert_eq!(spanning, PhysicalWindowInfo { pos: (1340, 100), size: (800, 600) });
+        // Offsets are scaled from the holding monitor's origin
+        let on_panel = to_physical(WindowInfo { pos: (40, 100), size: (800, 600) }, &monitors);
+        assert_eq!(on_panel, PhysicalWindowInfo { pos: (80, 200), size: (1600, 1200) });
+        let external = [scaled(monitors[1].clone(), 1.5)];
+        let fractional = to_physical(WindowInfo { pos: (1540, 10), size: (101, 33) }, &external);
+        assert_eq!(fractional, PhysicalWindowInfo { pos: (1590, 15), size: (152, 50) });
+    }
+
+    #[test]
+    fn physical_frame_off_every_monitor_uses_the_first_scale() {
+        let monitors = [scaled(monitor("Built-in", 0, 0, 1440, 900), 2.0)];
+
+        let physical = to_physical(WindowInfo { pos: (-500, 0), size: (100, 100) }, &monitors);
+        assert_eq!(physical, PhysicalWindowInfo { pos: (-1000, 0), size: (200, 200) });
+        assert_eq!(to_physical(rect(5, 5, 10, 10), &[]), PhysicalWindowInfo { pos: (5, 5), size: (10, 10) });
+    }
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> WindowInfo {
+        WindowInfo { pos: (x, y), size: (width, height) }
+    }
+
+    #[test]
+    fn right_and_bottom_are_one_past_the_edges() {
+        assert_eq!((rect(10, 20, 30, 40).right(), rect(10, 20, 30, 40).bottom()), (40, 60));
+        assert_eq!((rect(-50, -60, 20, 10).right(), rect(-50, -60, 20, 10).bottom()), (-30, -50));
+        assert_eq!((rect(5, 7, 0, 0).right(), rect(5, 7, 0, 0).bottom()), (5, 7));
+        assert_eq!(rect(i32::MAX - 1, 0, 10, 0).right(), i32::MAX);
+        assert_eq!(rect(0, i32::MIN, 0, u32::MAX).bottom(), i32::MAX);
+    }
+
+    #[test]
+    fn center_rounds_toward_the_top_left() {
+        assert_eq!(rect(0, 0, 100, 50).center(), (50, 25));
+        assert_eq!(rect(-10, -10, 5, 3).center(), (-8, -9));
+        assert_eq!(rect(3, 4, 0, 0).center(), (3, 4));
+        assert_eq!(rect(i32::MAX - 1, 0, u32::MAX, 0).center(), (i32::MAX, 0));
+    }
+
+    #[test]
+    fn contains_point_includes_the_top_left_edges_only() {
+        let frame = rect(-100, -50, 200, 100);
+        assert!(frame.contains_point((-100, -50)));
+        assert!(frame.contains_point((0, 0)));
+        assert!(frame.contains_point((99, 49)));
+        assert!(!frame.contains_point((100, 0)));
+        assert!(!frame.contains_point((0, 50)));
+        assert!(!frame.contains_point((-101, 0)));
+        assert!(!rect(5, 5, 0, 0).contains_point((5, 5)));
+        assert!(!rect(5, 5, 10, 0).contains_point((5, 5)));
+    }
+
+    #[test]
+    fn intersection_is_the_shared_area() {
+        assert_eq!(rect(0, 0, 100, 100).intersection(&rect(50, -20, 100, 60)), Some(rect(50, 0, 50, 40)));
+        assert_eq!(rect(-30, -30, 20, 20).intersection(&rect(-40, -25, 100, 5)), Some(rect(-30, -25, 20, 5)));
+        // One inside the other
+        assert_eq!(rect(0, 0, 100, 100).intersection(&rect(10, 10, 5, 5)), Some(rect(10, 10, 5, 5)));
+        // Frames reaching past i32::MAX don't wrap
+        let far = rect(i32::MAX - 10, 0, u32::MAX, 10);
+        assert_eq!(far.intersection(&rect(i32::MAX - 5, 0, 100, 10)), Some(rect(i32::MAX - 5, 0, 100, 10)));
+    }
+
+    #[test]
+    fn touching_disjoint_or_zero_sized_rectangles_do_not_intersect() {
+        let frame = rect(0, 0, 100, 100);
+        for other in [rect(100, 0, 50, 50), rect(0, 100, 50, 50), rect(-50, -50, 50, 50), rect(300, 300, 10, 10)] {
+            assert_eq!(frame.intersection(&other), None, "{other:?}");
+            assert!(!frame.intersects(&other), "{other:?}");
+        }
+        assert!(!frame.intersects(&rect(50, 50, 0, 0)));
+        assert!(!frame.intersects(&rect(50, 50, 10, 0)));
+        assert!(!rect(50, 50, 0, 0).intersects(&frame));
+        assert!(frame.intersects(&rect(-50, -50, 51, 51)));
+    }
+
+    #[test]
+    fn union_covers_both() {
+        assert_eq!(rect(0, 0, 10, 10).union(&rect(20, 30, 10, 10)), rect(0, 0, 30, 40));
+        assert_eq!(rect(-20, 5, 10, 10).union(&rect(0, -15, 5, 5)), rect(-20, -15, 25, 30));
+        assert_eq!(rect(0, 0, 100, 100).union(&rect(10, 10, 5, 5)), rect(0, 0, 100, 100));
+        assert_eq!(rect(10, 10, 5, 5).union(&rect(0, 0, 100, 100)), rect(0, 0, 100, 100));
+    }
+
+    #[test]
+    fn union_leaves_out_zero_sized_rectangles() {
+        assert_eq!(rect(0, 0, 10, 10).union(&rect(-500, -500, 0, 0)), rect(0, 0, 10, 10));
+        assert_eq!(rect(-500, -500, 0, 20).union(&rect(0, 0, 10, 10)), rect(0, 0, 10, 10));
+        assert_eq!(rect(1, 2, 0, 0).union(&rect(3, 4, 0, 0)), rect(1, 2, 0, 0));
+    }
+
+    #[test]
+    fn union_saturates_instead_of_wrapping() {
+        let union = rect(i32::MIN, 0, 10, 10).union(&rect(i32::MAX - 5, 0, u32::MAX, 10));
+        assert_eq!(union, rect(i32::MIN, 0, u32::MAX, 10));
+    }
+}
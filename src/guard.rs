@@ -0,0 +1,45 @@
+//! Guards that change a window property for a scope and put it back on drop.
+
+use crate::Window;
+
+/// Keeps a window always-on-top until dropped, then restores whatever it was
+/// before. Returned by `always_on_top_scoped`.
+///
+/// Restoring is best effort: errors on drop (e.g. the window has closed) are
+/// ignored.
+#[must_use = "the window is restored as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct AlwaysOnTopGuard {
+    window: Window,
+    previous: bool,
+}
+
+impl AlwaysOnTopGuard {
+    /// The window being kept on top.
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// Whether the window was already on top when the guard was created.
+    pub fn was_on_top(&self) -> bool {
+        self.previous
+    }
+}
+
+impl Drop for AlwaysOnTopGuard {
+    fn drop(&mut self) {
+        if !self.previous {
+            let _ = crate::set_always_on_top(self.window, false);
+        }
+    }
+}
+
+/// Keep `window` above other windows while the returned guard is alive. The
+/// prior state is read first, so a window that was already on top stays on top.
+pub fn always_on_top_scoped(window: Window) -> crate::Result<AlwaysOnTopGuard> {
+    let previous = crate::is_always_on_top(window)?;
+    if !previous {
+        crate::set_always_on_top(window, true)?;
+    }
+    Ok(AlwaysOnTopGuard { window, previous })
+}
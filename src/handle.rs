@@ -0,0 +1,617 @@
+//! Windows paired with the backend they belong to, for method-call syntax.
+
+use crate::{
+    IconData, MonitorInfo, PhysicalWindowInfo, Window, WindowDetails, WindowInfo, WindowState, WindowStateFlag,
+    WindowingBackend,
+};
+
+/// A `Window` together with the backend that serves it, so operations read as
+/// methods: `handle.title()?` rather than `get_window_title(window)?`.
+///
+/// `Window::handle` pairs a window with the default backend (the one behind
+/// the free functions) and `Window::handle_in` with any other, such as a
+/// `WindowSystem` or a `MockBackend`. The enumeration functions here return
+/// handles directly; `window` is the plain `Window` again, for the free
+/// functions, storage or another process.
+///
+/// ```no_run
+/// use windowing::WindowHandle;
+///
+/// for handle in WindowHandle::list()? {
+///     if handle.title()?.is_some_and(|title| title.contains("Inbox")) {
+///         handle.move_to(0, 0)?;
+///         handle.focus()?;
+///     }
+/// }
+/// # Ok::<(), windowing::Error>(())
+/// ```
+#[derive(Copy, Clone)]
+pub struct WindowHandle<'a> {
+    window: Window,
+    backend: &'a dyn WindowingBackend,
+}
+
+impl std::fmt::Debug for WindowHandle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WindowHandle")
+            .field("window", &self.window)
+            .field("backend", &self.backend.kind())
+            .finish()
+    }
+}
+
+/// Handles compare by window; two handles to one window via different
+/// backends are equal.
+impl PartialEq for WindowHandle<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.window == other.window
+    }
+}
+
+impl Eq for WindowHandle<'_> {}
+
+/// Handles order by window, as `Window` does.
+impl PartialOrd for WindowHandle<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for WindowHandle<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.window.cmp(&other.window)
+    }
+}
+
+impl std::hash::Hash for WindowHandle<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.window.hash(state);
+    }
+}
+
+impl std::fmt::Display for WindowHandle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.window.fmt(f)
+    }
+}
+
+impl From<WindowHandle<'_>> for Window {
+    fn from(handle: WindowHandle<'_>) -> Self {
+        handle.window
+    }
+}
+
+impl Window {
+    /// This window with the default backend, for method-call syntax.
+    ///
+    /// ```no_run
+    /// use windowing::get_active_window;
+    ///
+    /// if let Some(window) = get_active_window()? {
+    ///     let handle = window.handle();
+    ///     println!("{:?} at {:?}", handle.title()?, handle.info()?.pos);
+    /// }
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn handle(self) -> WindowHandle<'static> {
+        WindowHandle { window: self, backend: crate::default_backend() }
+    }
+
+    /// This window with `backend`, for method-call syntax.
+    ///
+    /// ```no_run
+    /// use windowing::{WindowSystem, get_active_window};
+    ///
+    /// let system = WindowSystem::new()?;
+    /// if let Some(window) = get_active_window()? {
+    ///     window.handle_in(&system).minimize()?;
+    /// }
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn handle_in(self, backend: &dyn WindowingBackend) -> WindowHandle<'_> {
+        WindowHandle { window: self, backend }
+    }
+}
+
+/// Top-level windows as `WindowHandle`s, from `windows` or
+/// `WindowingBackend::windows`. The window list is read up front (only the
+/// handles, so it is cheap); titles, pids and geometry are fetched per window
+/// by the handle methods, so `filter(..).take(1)` stops asking once it has a
+/// match. Windows that close while iterating are still yielded, and their
+/// methods fail with `WindowGone`.
+#[derive(Clone)]
+pub struct Windows<'a> {
+    backend: &'a dyn WindowingBackend,
+    windows: std::vec::IntoIter<Window>,
+}
+
+impl std::fmt::Debug for Windows<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Windows")
+            .field("backend", &self.backend.kind())
+            .field("remaining", &self.windows.as_slice())
+            .finish()
+    }
+}
+
+impl<'a> Windows<'a> {
+    pub(crate) fn new(backend: &'a dyn WindowingBackend, windows: Vec<Window>) -> Self {
+        Self { backend, windows: windows.into_iter() }
+    }
+}
+
+impl<'a> Iterator for Windows<'a> {
+    type Item = WindowHandle<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.windows.next().map(|window| window.handle_in(self.backend))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.windows.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Windows<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.windows.next_back().map(|window| window.handle_in(self.backend))
+    }
+}
+
+impl ExactSizeIterator for Windows<'_> {}
+
+impl std::iter::FusedIterator for Windows<'_> {}
+
+/// The top-level windows of the default backend, as lazily queried handles.
+///
+/// ```no_run
+/// let pid = std::process::id();
+/// if let Some(own) = windowing::windows()?.find(|w| w.pid().ok().flatten() == Some(pid)) {
+///     own.set_always_on_top(true)?;
+/// }
+/// # Ok::<(), windowing::Error>(())
+/// ```
+pub fn windows() -> crate::Result<Windows<'static>> {
+    windows_in(crate::default_backend())
+}
+
+/// `windows`, through `backend`; for a backend behind `dyn`, where
+/// `WindowingBackend::windows` isn't available.
+pub fn windows_in(backend: &dyn WindowingBackend) -> crate::Result<Windows<'_>> {
+    Ok(Windows::new(backend, backend.list_windows()?))
+}
+
+impl WindowHandle<'static> {
+    /// `list_windows`, as handles.
+    pub fn list() -> crate::Result<Vec<Self>> {
+        Self::list_in(crate::default_backend())
+    }
+
+    /// `get_active_window`, as a handle.
+    ///
+    /// ```no_run
+    /// use windowing::WindowHandle;
+    ///
+    /// if let Some(active) = WindowHandle::active()? {
+    ///     active.set_rect(0, 0, 1280, 720)?;
+    /// }
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn active() -> crate::Result<Option<Self>> {
+        Self::active_in(crate::default_backend())
+    }
+
+    /// `find_windows_by_pid`, as handles.
+    ///
+    /// ```no_run
+    /// use windowing::WindowHandle;
+    ///
+    /// for handle in WindowHandle::find_by_pid(std::process::id())? {
+    ///     handle.hide()?;
+    /// }
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn find_by_pid(pid: u32) -> crate::Result<Vec<Self>> {
+        Self::find_by_pid_in(crate::default_backend(), pid)
+    }
+
+    /// `find_windows_by_app_id`, as handles.
+    pub fn find_by_app_id(app_id: &str) -> crate::Result<Vec<Self>> {
+        Self::find_by_app_id_in(crate::default_backend(), app_id)
+    }
+}
+
+impl<'a> WindowHandle<'a> {
+    fn wrap(backend: &'a dyn WindowingBackend, windows: Vec<Window>) -> Vec<Self> {
+        windows.into_iter().map(|window| window.handle_in(backend)).collect()
+    }
+
+    /// `list`, through `backend`.
+    pub fn list_in(backend: &'a dyn WindowingBackend) -> crate::Result<Vec<Self>> {
+        Ok(Self::wrap(backend, backend.list_windows()?))
+    }
+
+    /// `active`, through `backend`.
+    pub fn active_in(backend: &'a dyn WindowingBackend) -> crate::Result<Option<Self>> {
+        Ok(backend.get_active_window()?.map(|window| window.handle_in(backend)))
+    }
+
+    /// `find_by_pid`, through `backend`.
+    pub fn find_by_pid_in(backend: &'a dyn WindowingBackend, pid: u32) -> crate::Result<Vec<Self>> {
+        crate::backend::check_pid(pid)?;
+        Ok(Self::wrap(backend, backend.find_windows_by_pid(pid)?))
+    }
+
+    /// `find_by_app_id`, through `backend`.
+    pub fn find_by_app_id_in(backend: &'a dyn WindowingBackend, app_id: &str) -> crate::Result<Vec<Self>> {
+        Ok(Self::wrap(backend, backend.find_windows_by_app_id(app_id)?))
+    }
+
+    /// The plain window handle.
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// The backend the methods go through.
+    pub fn backend(&self) -> &'a dyn WindowingBackend {
+        self.backend
+    }
+
+    /// `get_window_info`: the frame in the backend's logical units.
+    ///
+    /// ```no_run
+    /// # let handle = windowing::WindowHandle::active()?.unwrap();
+    /// let frame = handle.info()?;
+    /// handle.move_to(frame.pos.0 + 50, frame.pos.1)?;
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn info(&self) -> crate::Result<WindowInfo> {
+        self.backend.get_window_info(self.window)
+    }
+
+    pub fn details(&self) -> crate::Result<WindowDetails> {
+        self.backend.get_window_details(self.window)
+    }
+
+    pub fn bounds(&self) -> crate::Result<PhysicalWindowInfo> {
+        self.backend.get_window_bounds(self.window)
+    }
+
+    /// `get_window_title`.
+    ///
+    /// ```no_run
+    /// # let handle = windowing::WindowHandle::active()?.unwrap();
+    /// if handle.title()?.is_some_and(|title| title.ends_with("— Private Browsing")) {
+    ///     handle.minimize()?;
+    /// }
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn title(&self) -> crate::Result<Option<String>> {
+        self.backend.get_window_title(self.window)
+    }
+
+    pub fn app_id(&self) -> crate::Result<Option<String>> {
+        self.backend.get_window_app_id(self.window)
+    }
+
+    /// `get_window_pid`.
+    ///
+    /// ```no_run
+    /// # let handle = windowing::WindowHandle::active()?.unwrap();
+    /// let name = match handle.pid()? {
+    ///     Some(pid) => windowing::get_process_name(pid)?,
+    ///     None => None,
+    /// };
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn pid(&self) -> crate::Result<Option<u32>> {
+        self.backend.get_window_pid(self.window)
+    }
+
+    pub fn icon(&self) -> crate::Result<Option<IconData>> {
+        self.backend.get_window_icon(self.window)
+    }
+
+    pub fn monitor(&self) -> crate::Result<Option<MonitorInfo>> {
+        self.backend.get_window_monitor(self.window)
+    }
+
+    pub fn monitors(&self) -> crate::Result<Vec<MonitorInfo>> {
+        self.backend.get_window_monitors(self.window)
+    }
+
+    pub fn workspace(&self) -> crate::Result<Option<u32>> {
+        self.backend.get_window_workspace(self.window)
+    }
+
+    pub fn is_main(&self) -> crate::Result<bool> {
+        self.backend.is_main_window(self.window)
+    }
+
+    pub fn can_focus(&self) -> crate::Result<bool> {
+        self.backend.can_focus(self.window)
+    }
+
+    pub fn has_state(&self, state: WindowState) -> crate::Result<bool> {
+        self.backend.window_has_state(self.window, state)
+    }
+
+    pub fn states(&self) -> crate::Result<Vec<WindowStateFlag>> {
+        self.backend.get_window_states(self.window)
+    }
+
+    pub fn set_state(&self, flag: WindowStateFlag, enabled: bool) -> crate::Result<()> {
+        self.backend.set_window_state(self.window, flag, enabled)
+    }
+
+    /// `move_window`: put the frame's top-left corner at `(x, y)`.
+    ///
+    /// ```no_run
+    /// use windowing::WindowHandle;
+    ///
+    /// for (index, handle) in WindowHandle::list()?.into_iter().enumerate() {
+    ///     handle.move_to(40 * index as i32, 40 * index as i32)?;
+    /// }
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn move_to(&self, x: i32, y: i32) -> crate::Result<()> {
+        self.backend.move_window(self.window, x, y)
+    }
+
+    pub fn resize(&self, width: u32, height: u32) -> crate::Result<()> {
+        self.backend.resize_window(self.window, width, height)
+    }
+
+    pub fn set_rect(&self, x: i32, y: i32, width: u32, height: u32) -> crate::Result<()> {
+        self.backend.set_window_rect(self.window, x, y, width, height)
+    }
+
+    pub fn focus(&self) -> crate::Result<()> {
+        self.backend.focus_window(self.window)
+    }
+
+    pub fn activate(&self, update_user_time: bool) -> crate::Result<()> {
+        self.backend.activate_window(self.window, update_user_time)
+    }
+
+    /// `hide_window`; `show` undoes it.
+    ///
+    /// ```no_run
+    /// # let handle = windowing::WindowHandle::active()?.unwrap();
+    /// handle.hide()?;
+    /// std::thread::sleep(std::time::Duration::from_secs(1));
+    /// handle.show()?;
+    /// # Ok::<(), windowing::Error>(())
+    /// ```
+    pub fn hide(&self) -> crate::Result<()> {
+        self.backend.hide_window(self.window)
+    }
+
+    pub fn show(&self) -> crate::Result<()> {
+        self.backend.show_window(self.window)
+    }
+
+    pub fn minimize(&self) -> crate::Result<()> {
+        self.backend.minimize_window(self.window)
+    }
+
+    pub fn maximize(&self) -> crate::Result<()> {
+        self.backend.maximize_window(self.window)
+    }
+
+    pub fn toggle_maximize(&self) -> crate::Result<()> {
+        self.backend.toggle_maximize(self.window)
+    }
+
+    pub fn close(&self) -> crate::Result<()> {
+        self.backend.close_window(self.window)
+    }
+
+    pub fn move_to_workspace(&self, workspace: u32) -> crate::Result<()> {
+        self.backend.move_window_to_workspace(self.window, workspace)
+    }
+
+    pub fn set_skip_taskbar(&self, skip: bool) -> crate::Result<()> {
+        self.backend.set_skip_taskbar(self.window, skip)
+    }
+
+    pub fn is_sticky(&self) -> crate::Result<bool> {
+        self.backend.is_sticky(self.window)
+    }
+
+    pub fn set_sticky(&self, sticky: bool) -> crate::Result<()> {
+        self.backend.set_sticky(self.window, sticky)
+    }
+
+    pub fn is_always_on_top(&self) -> crate::Result<bool> {
+        self.backend.is_always_on_top(self.window)
+    }
+
+    pub fn set_always_on_top(&self, on_top: bool) -> crate::Result<()> {
+        self.backend.set_always_on_top(self.window, on_top)
+    }
+
+    pub fn is_always_below(&self) -> crate::Result<bool> {
+        self.backend.is_always_below(self.window)
+    }
+
+    pub fn set_always_below(&self, below: bool) -> crate::Result<()> {
+        self.backend.set_always_below(self.window, below)
+    }
+
+    pub fn set_click_through(&self, click_through: bool) -> crate::Result<()> {
+        self.backend.set_click_through(self.window, click_through)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBackend, MockFailure, MockWindow};
+
+    fn window(id: u64) -> Window {
+        Window::from_id(id).unwrap()
+    }
+
+    /// An editor and two terminals of one process, the editor focused
+    fn desktop() -> MockBackend {
+        MockBackend::new()
+            .with_monitor(MonitorInfo {
+                name: "DP-1".into(),
+                bounds: WindowInfo { pos: (0, 0), size: (1920, 1080) },
+                scale_factor: 1.0,
+            })
+            .with_window(
+                MockWindow::new(window(1))
+                    .with_pid(10)
+                    .with_title("notes.md - Editor")
+                    .with_app_id("org.example.Editor")
+                    .with_geometry(100, 50, 800, 600)
+                    .with_workspace(0),
+            )
+            .with_window(MockWindow::new(window(2)).with_pid(20).with_title("bash").with_app_id("org.example.Term"))
+            .with_window(MockWindow::new(window(3)).with_pid(20).with_title("top").with_app_id("org.example.Term"))
+            .with_active(window(1))
+    }
+
+    #[test]
+    fn getters_read_through_the_backend() {
+        let backend = desktop();
+        let editor = window(1).handle_in(&backend);
+
+        assert_eq!(editor.window(), window(1));
+        assert_eq!(editor.backend().kind(), backend.kind());
+        assert_eq!(editor.info().unwrap(), WindowInfo { pos: (100, 50), size: (800, 600) });
+        assert_eq!(editor.title().unwrap().as_deref(), Some("notes.md - Editor"));
+        assert_eq!(editor.app_id().unwrap().as_deref(), Some("org.example.Editor"));
+        assert_eq!(editor.pid().unwrap(), Some(10));
+        assert_eq!(editor.workspace().unwrap(), Some(0));
+        assert_eq!(editor.icon().unwrap(), None);
+        assert_eq!(editor.monitor().unwrap().map(|monitor| monitor.name), Some("DP-1".into()));
+        assert_eq!(editor.monitors().unwrap().len(), 1);
+        assert!(editor.is_main().unwrap());
+        assert!(editor.can_focus().unwrap());
+        assert!(editor.has_state(WindowState::Focused).unwrap());
+        let details = editor.details().unwrap();
+        assert_eq!((details.pid, details.title.as_deref()), (Some(10), Some("notes.md - Editor")));
+    }
+
+    #[test]
+    fn geometry_methods_change_the_window() {
+        let backend = desktop();
+        let editor = window(1).handle_in(&backend);
+
+        editor.move_to(-20, 30).unwrap();
+        assert_eq!(editor.info().unwrap().pos, (-20, 30));
+        editor.resize(640, 480).unwrap();
+        assert_eq!(editor.info().unwrap(), WindowInfo { pos: (-20, 30), size: (640, 480) });
+        editor.set_rect(0, 0, 1920, 1080).unwrap();
+        assert_eq!(backend.window(window(1)).unwrap().geometry(), WindowInfo { pos: (0, 0), size: (1920, 1080) });
+    }
+
+    #[test]
+    fn state_methods_change_the_window() {
+        let backend = desktop();
+        let term = window(2).handle_in(&backend);
+
+        term.hide().unwrap();
+        assert!(!term.has_state(WindowState::Visible).unwrap());
+        term.show().unwrap();
+        assert!(term.has_state(WindowState::Visible).unwrap());
+        term.minimize().unwrap();
+        assert!(term.has_state(WindowState::Minimized).unwrap());
+        term.maximize().unwrap();
+        assert!(term.has_state(WindowState::Maximized).unwrap());
+        term.toggle_maximize().unwrap();
+        assert!(!term.has_state(WindowState::Maximized).unwrap());
+        term.set_state(WindowStateFlag::Fullscreen, true).unwrap();
+        assert!(term.states().unwrap().contains(&WindowStateFlag::Fullscreen));
+
+        term.set_sticky(true).unwrap();
+        term.set_always_on_top(true).unwrap();
+        assert!(term.is_sticky().unwrap() && term.is_always_on_top().unwrap() && !term.is_always_below().unwrap());
+        term.set_always_on_top(false).unwrap();
+        term.set_always_below(true).unwrap();
+        assert!(!term.is_always_on_top().unwrap() && term.is_always_below().unwrap());
+        term.set_skip_taskbar(true).unwrap();
+        assert!(!term.is_main().unwrap());
+        term.move_to_workspace(3).unwrap();
+        assert_eq!(term.workspace().unwrap(), Some(3));
+    }
+
+    #[test]
+    fn focus_activate_and_close() {
+        let backend = desktop();
+        let (bash, top) = (window(2).handle_in(&backend), window(3).handle_in(&backend));
+
+        bash.focus().unwrap();
+        assert_eq!(WindowHandle::active_in(&backend).unwrap(), Some(bash));
+        top.activate(true).unwrap();
+        assert!(top.has_state(WindowState::Focused).unwrap());
+        top.close().unwrap();
+        assert!(backend.window(window(3)).is_none());
+        assert_eq!(WindowHandle::active_in(&backend).unwrap(), None);
+    }
+
+    #[test]
+    fn methods_on_a_closed_window_fail_with_window_gone() {
+        let backend = desktop();
+        let term = window(2).handle_in(&backend);
+        backend.remove_window(window(2));
+
+        assert!(matches!(term.info(), Err(crate::Error::WindowGone(gone)) if gone == window(2)));
+        assert!(matches!(term.title(), Err(crate::Error::WindowGone(_))));
+        assert!(matches!(term.move_to(0, 0), Err(crate::Error::WindowGone(_))));
+        assert!(matches!(term.hide(), Err(crate::Error::WindowGone(_))));
+        assert!(term.has_state(WindowState::Closed).unwrap());
+    }
+
+    #[test]
+    fn backend_failures_reach_the_caller() {
+        let backend = desktop().with_failure("move_window", MockFailure::PermissionDenied);
+        let editor = window(1).handle_in(&backend);
+
+        assert!(matches!(editor.move_to(0, 0), Err(crate::Error::PermissionDenied(_))));
+        assert_eq!(editor.info().unwrap().pos, (100, 50));
+    }
+
+    #[test]
+    fn enumeration_returns_handles_in_list_order() {
+        let backend = desktop();
+        let ids = |handles: Vec<WindowHandle<'_>>| handles.into_iter().map(Window::from).collect::<Vec<_>>();
+
+        assert_eq!(ids(WindowHandle::list_in(&backend).unwrap()), [window(1), window(2), window(3)]);
+        assert_eq!(ids(WindowHandle::find_by_pid_in(&backend, 20).unwrap()), [window(2), window(3)]);
+        assert_eq!(ids(WindowHandle::find_by_app_id_in(&backend, "org.example.Term").unwrap()), [window(2), window(3)]);
+        assert!(WindowHandle::find_by_pid_in(&backend, 99).unwrap().is_empty());
+        assert!(matches!(WindowHandle::find_by_pid_in(&backend, 0), Err(crate::Error::InvalidPid(0))));
+        assert_eq!(WindowHandle::active_in(&backend).unwrap().map(Window::from), Some(window(1)));
+    }
+
+    #[test]
+    fn windows_iterates_handles_bound_to_the_backend() {
+        let backend = desktop();
+
+        let mut all = windows_in(&backend).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all.next_back().map(Window::from), Some(window(3)));
+        assert_eq!(all.next().unwrap().title().unwrap().as_deref(), Some("notes.md - Editor"));
+        assert_eq!(all.len(), 1);
+
+        let top = backend.windows().unwrap().find(|handle| handle.title().unwrap().as_deref() == Some("top"));
+        top.unwrap().close().unwrap();
+        assert_eq!(windows_in(&backend).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn handles_compare_display_and_convert_as_their_window() {
+        let backend = desktop();
+        let other = MockBackend::new();
+
+        assert_eq!(window(2).handle_in(&backend), window(2).handle_in(&other));
+        assert_ne!(window(2).handle_in(&backend), window(3).handle_in(&backend));
+        assert!(window(2).handle_in(&backend) < window(3).handle_in(&backend));
+        assert_eq!(window(2).handle_in(&backend).to_string(), window(2).to_string());
+        assert_eq!(Window::from(window(2).handle_in(&backend)), window(2));
+    }
+}
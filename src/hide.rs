@@ -0,0 +1,96 @@
+//! Hiding windows in a chosen way and undoing exactly that.
+
+use crate::{Window, WindowingBackend};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Which ways `hide_window_with` hides a window. The default, `unmap` alone,
+/// is what `hide_window` does: the window leaves the screen, and with it the
+/// taskbar and window switcher, on every platform.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HideOptions {
+    /// Leave the window off the taskbar and pager (`set_skip_taskbar`).
+    pub skip_taskbar: bool,
+    /// Leave the window out of Alt-Tab (`set_skip_switcher`). On Windows this
+    /// is the same style as `skip_taskbar`.
+    pub skip_switcher: bool,
+    /// Take the window off screen (`hide_window`).
+    pub unmap: bool,
+}
+
+impl Default for HideOptions {
+    fn default() -> Self {
+        Self { skip_taskbar: false, skip_switcher: false, unmap: true }
+    }
+}
+
+/// What `hide_window_with` applied to each window it hid, for `show_window`
+static HIDDEN: Mutex<BTreeMap<Window, HideOptions>> = Mutex::new(BTreeMap::new());
+
+const NOTHING: HideOptions = HideOptions { skip_taskbar: false, skip_switcher: false, unmap: false };
+
+fn hidden() -> std::sync::MutexGuard<'static, BTreeMap<Window, HideOptions>> {
+    HIDDEN.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Hide `window` in the ways `options` selects, remembering what was applied
+/// for `show_window`. If a step fails, the steps before it stay applied (and
+/// remembered) and the error is returned.
+pub fn hide_window_with(window: Window, options: HideOptions) -> crate::Result<()> {
+    hide_window_with_in(crate::default_backend(), window, options)
+}
+
+/// `hide_window_with`, through `backend`.
+pub fn hide_window_with_in<B: WindowingBackend + ?Sized>(
+    backend: &B,
+    window: Window,
+    options: HideOptions,
+) -> crate::Result<()> {
+    let mut applied = NOTHING;
+    let result = (|| {
+        if options.skip_taskbar {
+            backend.set_skip_taskbar(window, true)?;
+            applied.skip_taskbar = true;
+        }
+        if options.skip_switcher {
+            backend.set_skip_switcher(window, true)?;
+            applied.skip_switcher = true;
+        }
+        if options.unmap {
+            backend.hide_window(window)?;
+            applied.unmap = true;
+        }
+        Ok(())
+    })();
+    // Hiding twice keeps what either call applied, so one `show_window` undoes both
+    let mut hidden = hidden();
+    let recorded = hidden.entry(window).or_insert(NOTHING);
+    recorded.skip_taskbar |= applied.skip_taskbar;
+    recorded.skip_switcher |= applied.skip_switcher;
+    recorded.unmap |= applied.unmap;
+    if *recorded == NOTHING {
+        hidden.remove(&window);
+    }
+    result
+}
+
+/// Undo how `window` was hidden: what `hide_window_with` applied to it, or,
+/// for a window it didn't hide, what `hide_window` does.
+pub fn show_window(window: Window) -> crate::Result<()> {
+    show_window_in(crate::default_backend(), window)
+}
+
+/// `show_window`, through `backend`.
+pub fn show_window_in<B: WindowingBackend + ?Sized>(backend: &B, window: Window) -> crate::Result<()> {
+    let applied = hidden().remove(&window).unwrap_or_default();
+    if applied.unmap {
+        backend.show_window(window)?;
+    }
+    if applied.skip_switcher {
+        backend.set_skip_switcher(window, false)?;
+    }
+    if applied.skip_taskbar {
+        backend.set_skip_taskbar(window, false)?;
+    }
+    Ok(())
+}
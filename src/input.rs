@@ -0,0 +1,87 @@
+//! Portable input types shared by the click/key APIs.
+//!
+//! Both enums are `#[non_exhaustive]` so new buttons/modifiers can be added
+//! without a breaking release.
+
+/// A mouse button, independent of the platform numbering.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    /// First extra button (usually "back").
+    X1,
+    /// Second extra button (usually "forward").
+    X2,
+}
+
+/// A keyboard modifier, independent of the platform key codes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+    /// The "Windows"/"Command"/"Meta" key.
+    Super,
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+impl MouseButton {
+    /// The core X11 button number (1 = left, 2 = middle, 3 = right, 8/9 = extra buttons).
+    pub fn x11_button(self) -> x11rb::protocol::xproto::Button {
+        match self {
+            MouseButton::Left => 1,
+            MouseButton::Middle => 2,
+            MouseButton::Right => 3,
+            MouseButton::X1 => 8,
+            MouseButton::X2 => 9,
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+impl Modifier {
+    /// The X11 modifier mask bit. Alt and Super use the conventional Mod1/Mod4 mapping.
+    pub fn x11_modmask(self) -> x11rb::protocol::xproto::ModMask {
+        use x11rb::protocol::xproto::ModMask;
+        match self {
+            Modifier::Ctrl => ModMask::CONTROL,
+            Modifier::Alt => ModMask::M1,
+            Modifier::Shift => ModMask::SHIFT,
+            Modifier::Super => ModMask::M4,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl MouseButton {
+    /// The Win32 virtual-key code for the button (`VK_LBUTTON`, `VK_MBUTTON`, ...).
+    pub fn virtual_key(self) -> windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            VK_LBUTTON, VK_MBUTTON, VK_RBUTTON, VK_XBUTTON1, VK_XBUTTON2,
+        };
+        match self {
+            MouseButton::Left => VK_LBUTTON,
+            MouseButton::Middle => VK_MBUTTON,
+            MouseButton::Right => VK_RBUTTON,
+            MouseButton::X1 => VK_XBUTTON1,
+            MouseButton::X2 => VK_XBUTTON2,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Modifier {
+    /// The Win32 virtual-key code for the modifier. Super maps to the left Windows key.
+    pub fn virtual_key(self) -> windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{VK_CONTROL, VK_LWIN, VK_MENU, VK_SHIFT};
+        match self {
+            Modifier::Ctrl => VK_CONTROL,
+            Modifier::Alt => VK_MENU,
+            Modifier::Shift => VK_SHIFT,
+            Modifier::Super => VK_LWIN,
+        }
+    }
+}
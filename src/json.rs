@@ -0,0 +1,367 @@
+//! Small JSON reader for the compositor IPC backends.
+//!
+//! The IPC payloads are modest and we only ever look up a handful of fields,
+//! so a simple tree with linear object lookup is plenty.
+
+/// Arrays and objects nested deeper than this are rejected rather than
+/// recursed into, so a hostile payload can't overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+static NULL: Value = Value::Null;
+
+impl Value {
+    /// Look up an object member; anything missing (or not an object) reads as `Null`.
+    pub fn get(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value)
+                .unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) if n.fract() == 0.0 => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Value] {
+        match self {
+            Value::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+/// Parse a complete JSON document.
+pub(crate) fn parse(input: &str) -> crate::Result<Value> {
+    let mut parser = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Arrays and objects open around `pos`
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> crate::Error {
+        format!("Invalid JSON at byte {}: {what}", self.pos).into()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.bytes.get(self.pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> crate::Result<()> {
+        if self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{literal}`")))
+        }
+    }
+
+    fn value(&mut self) -> crate::Result<Value> {
+        self.whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'n') => self.expect("null").map(|_| Value::Null),
+            Some(b't') => self.expect("true").map(|_| Value::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> crate::Result<Value>) -> crate::Result<Value> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn number(&mut self) -> crate::Result<Value> {
+        let start = self.pos;
+        while matches!(
+            self.bytes.get(self.pos),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])?
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| self.error("malformed number"))
+    }
+
+    fn hex4(&mut self) -> crate::Result<u32> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error("truncated escape"))?;
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(self.error("malformed escape"));
+        }
+        let value = u32::from_str_radix(std::str::from_utf8(digits)?, 16)
+            .map_err(|_| self.error("malformed escape"))?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> crate::Result<String> {
+        self.pos += 1; // opening quote
+        let mut out = Vec::new();
+        loop {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = *self
+                        .bytes
+                        .get(self.pos)
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            // Surrogate pair
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.bytes[self.pos..].starts_with(b"\\u")
+                            {
+                                let resume = self.pos;
+                                self.pos += 2;
+                                match self.hex4()? {
+                                    low @ 0xDC00..0xE000 => code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00),
+                                    // Not a low surrogate: the high one stands alone (and is
+                                    // replaced), and the next escape is read on its own
+                                    _ => self.pos = resume,
+                                }
+                            }
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(self.error("unknown escape")),
+                    };
+                    let mut buf = [0u8; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => out.push(byte),
+            }
+        }
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    fn array(&mut self) -> crate::Result<Value> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> crate::Result<Value> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.whitespace();
+            if self.bytes.get(self.pos) != Some(&b'"') {
+                return Err(self.error("expected object key"));
+            }
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(":")?;
+            members.push((key, self.value()?));
+            self.whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(input: &str) -> String {
+        parse(input).unwrap().as_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn reads_scalars() {
+        assert_eq!(parse(" null ").unwrap(), Value::Null);
+        assert_eq!(parse("true").unwrap().as_bool(), Some(true));
+        assert_eq!(parse("false").unwrap().as_bool(), Some(false));
+        assert_eq!(string(r#""""#), "");
+        assert!(parse("nul").is_err());
+        assert!(parse("True").is_err());
+    }
+
+    #[test]
+    fn unescapes_strings() {
+        assert_eq!(string(r#""a\"b\\c\/d""#), r#"a"b\c/d"#);
+        assert_eq!(string(r#""\b\f\n\r\t""#), "\u{8}\u{c}\n\r\t");
+        assert_eq!(string(r#""\u00e9\u4E2D""#), "é中");
+        assert_eq!(string(r#""héllo""#), "héllo");
+        assert!(parse(r#""\q""#).is_err());
+        assert!(parse(r#""\u12""#).is_err());
+        assert!(parse(r#""\u+123""#).is_err());
+        assert!(parse(r#""\u12G4""#).is_err());
+    }
+
+    #[test]
+    fn joins_surrogate_pairs() {
+        assert_eq!(string(r#""\ud83d\ude00""#), "😀");
+        assert_eq!(string(r#""\uD834\uDD1E""#), "𝄞");
+        // Unpaired halves become U+FFFD; what follows a lone high half is kept
+        assert_eq!(string(r#""\ud83d""#), "\u{fffd}");
+        assert_eq!(string(r#""\ud83dx""#), "\u{fffd}x");
+        assert_eq!(string(r#""\ud83d\u0041""#), "\u{fffd}A");
+        assert_eq!(string(r#""\ud83d\ud83d\ude00""#), "\u{fffd}😀");
+        assert_eq!(string(r#""\ude00""#), "\u{fffd}");
+    }
+
+    #[test]
+    fn reads_numbers() {
+        let number = |input| match parse(input).unwrap() {
+            Value::Number(n) => n,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(number("0"), 0.0);
+        assert_eq!(number("-12"), -12.0);
+        assert_eq!(number("3.5"), 3.5);
+        assert_eq!(number("1e3"), 1000.0);
+        assert_eq!(number("-2.5E-1"), -0.25);
+        assert_eq!(parse("42").unwrap().as_i64(), Some(42));
+        assert_eq!(parse("-7").unwrap().as_i64(), Some(-7));
+        assert_eq!(parse("1.5").unwrap().as_i64(), None);
+        for malformed in ["-", "1-2", "1e", "--1", "1.2.3"] {
+            assert!(parse(malformed).is_err(), "{malformed}");
+        }
+    }
+
+    #[test]
+    fn reads_nested_arrays_and_objects() {
+        let value = parse(r#"{"a": [1, {"b": [true, null]}, []], "c": {}, "d": "x", "a": 2}"#).unwrap();
+        assert_eq!(value.get("a").as_array().len(), 3);
+        assert_eq!(value.get("a").as_array()[1].get("b").as_array(), [Value::Bool(true), Value::Null]);
+        assert_eq!(value.get("c"), &Value::Object(Vec::new()));
+        assert_eq!(value.get("d").as_str(), Some("x"));
+        // The first of duplicate keys wins; missing keys and non-objects read as null
+        assert_eq!(value.get("a").as_array()[0].as_i64(), Some(1));
+        assert_eq!(value.get("missing"), &Value::Null);
+        assert_eq!(value.get("d").get("e"), &Value::Null);
+        assert!(value.get("d").as_array().is_empty());
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(parse(&"[".repeat(100_000)).is_err());
+        let objects = r#"{"a":"#.repeat(MAX_DEPTH + 1) + "1" + &"}".repeat(MAX_DEPTH + 1);
+        assert!(parse(&objects).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let document = r#"{"title": "aé", "ids": [1, 2.5e1, -3], "ok": true}"#;
+        assert!(parse(document).is_ok());
+        for end in 0..document.len() {
+            if document.is_char_boundary(end) {
+                assert!(parse(&document[..end]).is_err(), "{:?}", &document[..end]);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_structure() {
+        for malformed in ["[1,]", "[1 2]", r#"{"a" 1}"#, r#"{"a":1,}"#, "{1: 2}", "[] []", "}", ""] {
+            assert!(parse(malformed).is_err(), "{malformed}");
+        }
+    }
+}
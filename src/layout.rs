@@ -0,0 +1,337 @@
+//! Saving where every window is and putting windows back there later.
+
+use crate::backend::unless_unsupported;
+use crate::{MonitorInfo, Window, WindowInfo, WindowState, WindowingBackend};
+
+/// Every window's placement at one point in time, from `capture_layout`, to be
+/// put back with `restore_layout` after a monitor change or a restart.
+///
+/// Entries are plain data (strings, numbers and `WindowInfo`s), so a layout
+/// can be written out field by field and read back with `Layout { windows }`,
+/// or saved as a whole with the `serde` feature.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Layout {
+    pub windows: Vec<LayoutEntry>,
+}
+
+/// One window in a `Layout`: what identifies it, and where it was.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutEntry {
+    /// The handle it had when captured; only meaningful until it closes.
+    pub window: Window,
+    pub pid: Option<u32>,
+    /// The application id (WM_CLASS on X11), the main key for finding the
+    /// window again once its handle is gone.
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+    /// The frame, in the backend's `WindowInfo` units.
+    pub info: WindowInfo,
+    /// The monitor holding most of the frame, if the backend reports monitors.
+    /// Restoring places the window relative to the monitor with this name,
+    /// so it lands in the same spot after the layout of displays changes.
+    pub monitor: Option<MonitorInfo>,
+    pub workspace: Option<u32>,
+    pub maximized: bool,
+    pub minimized: bool,
+}
+
+/// How `restore_layout` finds the live window for each saved one.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchStrategy {
+    /// Only the window that was captured: same handle, same process.
+    Exact,
+    /// The captured window while it is open, else a window of the same
+    /// application (app id, or title where there is no app id) with the most
+    /// similar title. Survives the application restarting.
+    #[default]
+    SameApp,
+}
+
+/// What `restore_layout` did with each saved window, in layout order.
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// Saved windows put back in place, with the live window used.
+    pub restored: Vec<(Window, Window)>,
+    /// Saved windows whose live window couldn't be placed, with the error.
+    pub skipped: Vec<(Window, crate::Error)>,
+    /// Saved windows with no live window to put back.
+    pub unmatched: Vec<Window>,
+}
+
+/// The windows of `list_windows` with their placement, through the default backend.
+pub fn capture_layout() -> crate::Result<Layout> {
+    Layout::capture_in(crate::default_backend())
+}
+
+/// Put the windows of `layout` back where they were, through the default backend.
+pub fn restore_layout(layout: &Layout, strategy: MatchStrategy) -> crate::Result<RestoreReport> {
+    layout.restore_in(crate::default_backend(), strategy)
+}
+
+/// A live window's identity, read once per restore
+struct Candidate {
+    window: Window,
+    pid: Option<u32>,
+    app_id: Option<String>,
+    title: Option<String>,
+}
+
+impl Layout {
+    /// `capture_layout`, through `backend`. Windows closing during the capture
+    /// are left out.
+    pub fn capture_in<B: WindowingBackend + ?Sized>(backend: &B) -> crate::Result<Self> {
+        let mut windows = Vec::new();
+        for window in backend.list_windows()? {
+            match capture_entry(backend, window) {
+                Ok(entry) => windows.push(entry),
+                Err(crate::Error::WindowGone(_) | crate::Error::WindowNotFound(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Self { windows })
+    }
+
+    /// `restore_layout`, through `backend`. Each live window is used for one
+    /// saved window at most. Fails only if the live windows can't be listed;
+    /// a window that can't be placed is reported in `skipped`.
+    pub fn restore_in<B: WindowingBackend + ?Sized>(
+        &self,
+        backend: &B,
+        strategy: MatchStrategy,
+    ) -> crate::Result<RestoreReport> {
+        let mut candidates = Vec::new();
+        for window in backend.list_windows()? {
+            let identity = (|| -> crate::Result<Candidate> {
+                Ok(Candidate {
+                    window,
+                    pid: unless_unsupported(backend.get_window_pid(window))?.flatten(),
+                    app_id: unless_unsupported(backend.get_window_app_id(window))?.flatten(),
+                    title: unless_unsupported(backend.get_window_title(window))?.flatten(),
+                })
+            })();
+            match identity {
+                Ok(candidate) => candidates.push(candidate),
+                Err(crate::Error::WindowGone(_) | crate::Error::WindowNotFound(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        let monitors = unless_unsupported(backend.list_monitors())?.unwrap_or_default();
+
+        let mut report = RestoreReport::default();
+        for entry in &self.windows {
+            let Some(index) = find_match(entry, &candidates, strategy) else {
+                report.unmatched.push(entry.window);
+                continue;
+            };
+            let live = candidates.remove(index).window;
+            match place(backend, live, entry, &monitors) {
+                Ok(()) => report.restored.push((entry.window, live)),
+                Err(error) => report.skipped.push((entry.window, error)),
+            }
+        }
+        Ok(report)
+    }
+}
+
+fn capture_entry<B: WindowingBackend + ?Sized>(backend: &B, window: Window) -> crate::Result<LayoutEntry> {
+    let details = backend.get_window_details(window)?;
+    let has_state = |state| unless_unsupported(backend.window_has_state(window, state)).map(|has| has == Some(true));
+    Ok(LayoutEntry {
+        window,
+        pid: details.pid,
+        app_id: unless_unsupported(backend.get_window_app_id(window))?.flatten(),
+        title: details.title,
+        info: details.info,
+        monitor: unless_unsupported(backend.get_window_monitor(window))?.flatten(),
+        workspace: unless_unsupported(backend.get_window_workspace(window))?.flatten(),
+        maximized: has_state(WindowState::Maximized)?,
+        minimized: has_state(WindowState::Minimized)?,
+    })
+}
+
+/// The candidate `strategy` picks for `entry`, as an index into `candidates`
+fn find_match(entry: &LayoutEntry, candidates: &[Candidate], strategy: MatchStrategy) -> Option<usize> {
+    let exact = candidates
+        .iter()
+        .position(|candidate| candidate.window == entry.window && candidate.pid == entry.pid);
+    if exact.is_some() || strategy == MatchStrategy::Exact {
+        return exact;
+    }
+    let same_app = |candidate: &Candidate| match (&entry.app_id, &candidate.app_id) {
+        (Some(saved), Some(live)) => saved.eq_ignore_ascii_case(live),
+        (None, None) => entry.title.is_some() && candidate.title == entry.title,
+        _ => false,
+    };
+    candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, candidate)| same_app(candidate))
+        // The first of equally similar titles, in list_windows order
+        .max_by_key(|(index, candidate)| (title_similarity(&entry.title, &candidate.title), std::cmp::Reverse(*index)))
+        .map(|(index, _)| index)
+}
+
+/// How alike two titles are: identical titles beat any others, then the
+/// longer the common prefix the better ("Inbox - Mail" and "Inbox (3) - Mail")
+fn title_similarity(saved: &Option<String>, live: &Option<String>) -> (bool, usize) {
+    match (saved, live) {
+        (Some(saved), Some(live)) => {
+            (saved == live, saved.chars().zip(live.chars()).take_while(|(a, b)| a == b).count())
+        }
+        _ => (false, 0),
+    }
+}
+
+/// The saved frame moved onto the monitor of the same name as it is now: at
+/// the same relative spot, scaled if the monitor's size changed, and no larger
+/// than the monitor. Without that monitor, the saved frame as it was.
+fn target_frame(entry: &LayoutEntry, monitors: &[MonitorInfo]) -> WindowInfo {
+    let Some(saved) = &entry.monitor else {
+        return entry.info;
+    };
+    let Some(current) = monitors.iter().find(|monitor| monitor.name == saved.name) else {
+        return entry.info;
+    };
+    let (old, new) = (saved.bounds, current.bounds);
+    let scale = |value: i64, from: u32, to: u32| {
+        if from == 0 { value } else { value * to as i64 / from as i64 }
+    };
+    let offset_x = scale((entry.info.pos.0 - old.pos.0) as i64, old.size.0, new.size.0);
+    let offset_y = scale((entry.info.pos.1 - old.pos.1) as i64, old.size.1, new.size.1);
+    let width = scale(entry.info.size.0 as i64, old.size.0, new.size.0).clamp(1, new.size.0.max(1) as i64);
+    let height = scale(entry.info.size.1 as i64, old.size.1, new.size.1).clamp(1, new.size.1.max(1) as i64);
+    WindowInfo {
+        pos: ((new.pos.0 as i64 + offset_x) as i32, (new.pos.1 as i64 + offset_y) as i32),
+        size: (width as u32, height as u32),
+    }
+}
+
+/// Un-maximize `window` if needed, give it the saved frame, workspace and
+/// state. Moving to a workspace is skipped where the backend has none.
+fn place<B: WindowingBackend + ?Sized>(
+    backend: &B,
+    window: Window,
+    entry: &LayoutEntry,
+    monitors: &[MonitorInfo],
+) -> crate::Result<()> {
+    if backend.window_has_state(window, WindowState::Maximized)? {
+        backend.toggle_maximize(window)?;
+    }
+    let frame = target_frame(entry, monitors);
+    backend.set_window_rect(window, frame.pos.0, frame.pos.1, frame.size.0, frame.size.1)?;
+    if let Some(workspace) = entry.workspace
+        && unless_unsupported(backend.get_window_workspace(window))?.flatten() != Some(workspace)
+    {
+        unless_unsupported(backend.move_window_to_workspace(window, workspace))?;
+    }
+    if entry.maximized {
+        backend.maximize_window(window)?;
+    }
+    if entry.minimized {
+        backend.minimize_window(window)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBackend, MockFailure, MockWindow};
+    use crate::WindowStateFlag;
+
+    fn window(id: u64) -> Window {
+        Window::from_id(id).unwrap()
+    }
+
+    fn monitor(name: &str, x: i32, y: i32, width: u32, height: u32) -> MonitorInfo {
+        MonitorInfo { name: name.into(), bounds: WindowInfo { pos: (x, y), size: (width, height) }, scale_factor: 1.0 }
+    }
+
+    fn editor(id: u64) -> MockWindow {
+        MockWindow::new(window(id)).with_pid(42).with_app_id("editor").with_title("notes.txt - Editor")
+    }
+
+    #[test]
+    fn capture_records_the_monitor_holding_most_of_each_frame() {
+        let backend = MockBackend::new()
+            .with_monitor(monitor("DP-1", 0, 0, 1920, 1080))
+            .with_monitor(monitor("DP-2", 1920, 0, 1920, 1080))
+            .with_window(editor(1).with_geometry(1800, 100, 800, 600).with_workspace(2));
+
+        let layout = Layout::capture_in(&backend).unwrap();
+        let [entry] = &layout.windows[..] else { panic!("{layout:?}") };
+        assert_eq!(entry.info, WindowInfo { pos: (1800, 100), size: (800, 600) });
+        assert_eq!(entry.monitor.as_ref().map(|monitor| monitor.name.as_str()), Some("DP-2"));
+        assert_eq!((entry.pid, entry.app_id.as_deref(), entry.workspace), (Some(42), Some("editor"), Some(2)));
+    }
+
+    #[test]
+    fn restore_follows_a_monitor_that_moved() {
+        let before = MockBackend::new()
+            .with_monitor(monitor("DP-1", 0, 0, 1920, 1080))
+            .with_monitor(monitor("DP-2", 1920, 0, 1920, 1080))
+            .with_window(editor(1).with_geometry(2020, 100, 800, 600));
+        let layout = Layout::capture_in(&before).unwrap();
+
+        // DP-2 now sits left of DP-1, at half the resolution
+        let after = MockBackend::new()
+            .with_monitor(monitor("DP-2", -960, 0, 960, 540))
+            .with_monitor(monitor("DP-1", 0, 0, 1920, 1080))
+            .with_window(editor(1).with_geometry(0, 0, 640, 480));
+        let report = layout.restore_in(&after, MatchStrategy::Exact).unwrap();
+
+        assert_eq!(report.restored, [(window(1), window(1))]);
+        assert_eq!(after.window(window(1)).unwrap().geometry(), WindowInfo { pos: (-910, 50), size: (400, 300) });
+    }
+
+    #[test]
+    fn restore_matches_a_restarted_application_by_app_id() {
+        let before = MockBackend::new().with_window(editor(1).with_geometry(300, 200, 800, 600));
+        let layout = Layout::capture_in(&before).unwrap();
+
+        // The editor came back with a new handle and process
+        let after = MockBackend::new()
+            .with_window(MockWindow::new(window(7)).with_app_id("browser").with_title("notes.txt - Editor"))
+            .with_window(MockWindow::new(window(8)).with_pid(99).with_app_id("Editor").with_title("Editor"));
+
+        let exact = layout.restore_in(&after, MatchStrategy::Exact).unwrap();
+        assert_eq!(exact.unmatched, [window(1)]);
+        let report = layout.restore_in(&after, MatchStrategy::SameApp).unwrap();
+        assert_eq!(report.restored, [(window(1), window(8))]);
+        assert_eq!(after.window(window(8)).unwrap().geometry(), WindowInfo { pos: (300, 200), size: (800, 600) });
+    }
+
+    #[test]
+    fn restore_reapplies_maximized_and_workspace() {
+        let maximized = editor(1)
+            .with_workspace(3)
+            .with_state(WindowStateFlag::MaximizedVert)
+            .with_state(WindowStateFlag::MaximizedHorz);
+        let before = MockBackend::new().with_window(maximized);
+        let layout = Layout::capture_in(&before).unwrap();
+        assert!(layout.windows[0].maximized);
+
+        let after = MockBackend::new().with_window(editor(1).with_workspace(1));
+        layout.restore_in(&after, MatchStrategy::Exact).unwrap();
+        let restored = after.window(window(1)).unwrap();
+        assert_eq!(restored.workspace(), Some(3));
+        assert!(after.window_has_state(window(1), WindowState::Maximized).unwrap());
+    }
+
+    #[test]
+    fn restore_skips_a_window_that_fails_to_move() {
+        let before = MockBackend::new().with_window(editor(1)).with_window(editor(2).with_title("todo.txt - Editor"));
+        let layout = Layout::capture_in(&before).unwrap();
+
+        let after = MockBackend::new()
+            .with_window(editor(1))
+            .with_window(editor(2))
+            .with_failure("set_window_rect", MockFailure::PermissionDenied);
+        let report = layout.restore_in(&after, MatchStrategy::Exact).unwrap();
+        assert!(report.restored.is_empty());
+        assert_eq!(report.skipped.iter().map(|(saved, _)| *saved).collect::<Vec<_>>(), [window(1), window(2)]);
+    }
+}
@@ -1,295 +1,776 @@
+pub mod input;
+
+// First, so its macros are in scope in the modules below
+#[macro_use]
+mod trace;
+
+mod backend;
+mod batch;
+mod error;
+mod events;
+mod focus;
+#[cfg_attr(
+    any(target_os = "windows", all(target_os = "linux", not(any(feature = "hyprland", feature = "sway-ipc")))),
+    allow(dead_code)
+)]
+mod geometry;
+mod guard;
+mod handle;
+mod hide;
+#[cfg(all(
+    target_os = "linux",
+    any(feature = "hyprland", feature = "sway-ipc", feature = "gnome-shell", feature = "kwin")
+))]
+mod json;
+mod layout;
+// Also built for the crate's own tests, which run against it
+#[cfg(any(test, feature = "mock"))]
+#[cfg_attr(not(feature = "mock"), allow(dead_code))]
+mod mock;
+mod query;
+mod snapshot;
+mod state;
+mod tracker;
+
+pub use backend::{
+    activate_window, active_window_snapshot, can_focus, close_window, default_backend, detect_backend,
+    find_visible_windows_by_pid, find_window_by_pid, find_window_by_pid_strict, find_windows_by_app_id,
+    find_windows_by_pid, find_windows_by_pids, focus_window, get_active_window, get_active_window_pid,
+    get_class_for_pid, get_current_workspace, get_root_window, get_virtual_screen, get_window_app_id, get_window_bounds,
+    get_window_details, get_window_icon, get_window_info, get_window_info_by_id, get_window_info_with_retry,
+    get_window_infos, get_window_monitor, get_window_monitors, get_window_pid, get_window_pid_by_id,
+    get_window_properties, get_window_states, get_window_title, get_window_title_by_id, get_window_work_area,
+    get_window_workspace, hide_window, is_always_below, is_always_on_top, is_main_window, is_sticky, list_windows,
+    list_windows_including_desktop, maximize_window, minimize_window, move_window, move_window_clamped,
+    move_window_to_workspace, pid_has_window, pointer_state, resize_window, set_always_below, set_always_on_top,
+    set_click_through, set_global_backend, set_skip_switcher, set_skip_taskbar, set_sticky, set_user_time,
+    set_window_rect, set_window_state, subscribe_window_events, switch_workspace, toggle_maximize, window_count,
+    window_exists, window_has_state, WindowingBackend,
+};
+pub use batch::{best_effort, BatchReport};
+pub use error::{Error, Result};
+pub use events::{WindowEvent, WindowEvents};
+pub use focus::FocusTracker;
+pub use geometry::{bounding_box, get_relative_bounds};
+pub use guard::{always_on_top_scoped, AlwaysOnTopGuard};
+pub use layout::{capture_layout, restore_layout, Layout, LayoutEntry, MatchStrategy, RestoreReport};
+pub use hide::{hide_window_with, hide_window_with_in, show_window, show_window_in, HideOptions};
+pub use handle::{windows, windows_in, WindowHandle, Windows};
+#[cfg(feature = "mock")]
+pub use mock::{MockBackend, MockFailure, MockWindow};
+pub use snapshot::{
+    diff_snapshots, snapshot_desktop, ActiveWindowSnapshot, DesktopSnapshot, WindowChange, WindowSnapshot,
+};
+pub use state::{wait_for_state, WindowState, WindowStateFlag};
+pub use query::WindowQuery;
+pub use tracker::WindowTracker;
+
 /// Api Definition:
-/// get_window_info(window) -> returns a WindowInfo struct with the position and size of the window
+/// active_window_snapshot() -> the focused window with its pid, process name, title, app id and geometry, read together and re-read if focus moved meanwhile; the call for time trackers and activity loggers
+/// detect_backend() -> returns the BackendKind serving these calls (override with WINDOWING_BACKEND=<name>)
+/// capabilities() -> Capabilities: the backend, session type, compositor and which operations are available here, to gray out features up front
+/// is_display_available() -> false when headless (no DISPLAY/WAYLAND_DISPLAY, or nothing answers); calls then fail with Error::NoDisplay
+/// default_backend() -> the WindowingBackend (a trait with every operation below) serving these free functions
+/// set_global_backend(Box<dyn WindowingBackend>) -> route the free functions through another backend; the first call wins
+/// MockBackend::new().with_window(MockWindow::new(window)...) -> `mock` feature: an in-memory WindowingBackend for tests
+/// serde_json::to_string(&details) -> `serde` feature: WindowInfo, PhysicalWindowInfo, WindowDetails, WindowProperties, MonitorInfo, IconData, WindowState, WindowStateFlag, WindowEvent, the snapshot and layout types serialize with their Rust field and variant names, which are part of the API (structs as maps, tuples as arrays, enums externally tagged: "Visible", {"Other":"_KDE_NET_WM_STATE"}); Window and WindowId as a plain integer id
+/// RUST_LOG=windowing=debug -> `tracing` feature, with a subscriber such as tracing-subscriber's EnvFilter: a debug span per backend call (backend, window, pid), events naming the backend picked and the ones found unavailable, and why enumeration skipped each window (no _NET_WM_PID, desktop type, override-redirect, not a main window and which check failed); warnings when EWMH properties the crate relies on are missing
+/// WindowSystem::new() -> a WindowingBackend owning its own connection, reused by every call made through it
+/// WindowSystem::with_backend(BackendKind::X11) -> Linux: a WindowSystem on a chosen backend instead of the detected one, e.g. XWayland windows from a Wayland session
+/// WindowSystem::default().with_request_timeout(duration) -> X11 calls fail with Error::Timeout instead of blocking on a stalled server
+/// get_window_info(window) -> returns a WindowInfo struct with the position and size of the window (restored bounds if minimized on Windows)
+/// Window::from_id(id) / "0x1c00007".parse::<Window>() -> a Window from a raw id (e.g. xdotool or xwininfo output)
+/// window.id() / u64::from(window) -> the platform-neutral u64 id for config files and IPC, turned back by Window::from_id / Window::try_from
+/// WindowId::from(window) / WindowId::from_x11(xid) / WindowId::from_hwnd(hwnd) -> the same id as its own type, for serde structs and IPC messages; Window::try_from(id) and id.as_raw() back
+/// Window::from_raw(raw, validate) / window.as_raw() -> a Window from another library's u64 handle, with validate also checking a window has it now (window_exists: GetWindowAttributes on X11, IsWindow on Windows); Window::from_handle(raw) / window.raw() -> the handle in the platform's type for FFI
+/// window.handle() / WindowHandle::list() -> a Window with its backend, for methods: handle.title()?, handle.move_to(x, y)?, handle.window() for the plain Window
+/// windows() / backend.windows() / windows_in(&dyn backend) -> list_windows as an iterator of WindowHandles, fetching per-window data only when asked: windows()?.find(|w| w.pid().ok().flatten() == Some(pid))
+/// get_window_properties(&windows) -> each window's pid, app id, title and workspace, read together (pipelined on X11); what WindowQuery filters on
+/// get_window_info_by_id(id) / get_window_title_by_id(id) / get_window_pid_by_id(id) -> the same calls, taking a raw id
+/// WindowInfo::from((x, y, width, height)) / RECT::from(info) -> conversions to and from a geometry tuple, a Win32 RECT (Windows) and an x11rb GetGeometryReply (X11)
+/// info.contains_point((x, y)) / info.intersection(&other) / info.intersects(&other) / info.area() / info.center() / info.right() / info.bottom() -> rectangle arithmetic on a WindowInfo, overflow-safe near the i32 limits
+/// get_window_bounds(window) -> returns PhysicalWindowInfo, the frame in device pixels
+/// get_window_infos(windows) -> get_window_info for a whole slice in one batch (pipelined on X11), one Result per window
+/// get_window_details(window) -> WindowDetails: geometry, title, pid, state flags and whether it is on the current workspace, in one round trip on X11
+/// best_effort(&windows, |window| move_window(window, x, y)) -> BatchReport of which windows the operation succeeded and failed on, continuing past per-window errors
+/// bounding_box(windows) -> the smallest WindowInfo containing every given window (an error for an empty slice)
+/// get_relative_bounds(window, reference) -> window's WindowInfo with its position relative to reference's top-left corner
+/// get_window_info_with_retry(window, retries) -> get_window_info, retrying transient X errors (e.g. mid-drag)
 /// find_window_by_pid(target_pid) -> returns the given process's first matching Window
 /// find_windows_by_pid(target_pid) -> returns all the given process's matching Windows
+/// find_windows_by_pids(&[pid, ...]) -> Vec<(pid, Window)> for every window of any of the processes, from one pass over the window list
+/// WindowQuery::new().pid(pid).app_id(id).title_contains(text).workspace(n).visible(true).all() / .first() -> windows meeting every criterion, from one pass (Unsupported where the backend can't check one)
+/// find_window_by_pid_strict(pid) -> find_window_by_pid, but Err(ProcessNotFound) when the process isn't running (Ok(None): running, no window); pid 0 is Err(InvalidPid) everywhere
+/// process_exists(pid) -> whether the process is running (/proc on Linux, OpenProcess on Windows)
+/// get_process_name(pid) -> the process's name (/proc/<pid>/comm on Linux, the executable file name on Windows)
+/// pid_has_window(target_pid) -> whether the process has any top-level window, without collecting them (e.g. GUI app or console tool)
+/// find_visible_windows_by_pid(target_pid) -> find_windows_by_pid limited to windows passing is_main_window
+/// is_main_window(window) -> whether the window looks like its application's primary window
 /// get_active_window_pid() -> returns the active window's pid
-
-#[derive(Debug, Copy, Clone)]
+/// get_window_pid(window) -> returns the pid of the process owning the window
+/// client_window_of(frame) -> Linux: the client window (with WM_STATE) inside a window manager frame
+/// pick_window() -> Linux/X11: blocks until the user clicks a window (crosshair cursor, like xwininfo) and returns it
+/// send_client_message(window, message_type, data) -> Linux/X11: raw 32-bit ClientMessage to the root, for unwrapped _NET_* requests
+/// get_panels() / get_strut(window) -> Linux/X11: dock-type and strut-reserving windows (panels, taskbars) and the Strut each reserves, for working out the usable area yourself
+/// is_urgent(window) / set_urgency(window, urgent) -> Linux/X11: the ICCCM WM_HINTS urgency flag, the other hints left intact
+/// get_property_raw(window, property, type) / set_property_raw(window, property, type, format, data) -> Linux/X11: any property as raw bytes
+/// get_active_window() -> returns the active Window
+/// list_windows() -> returns all top-level Windows, leaving out the root/desktop window and desktop-type windows (wallpaper, icons)
+/// list_windows_including_desktop() -> list_windows with those desktop pseudo-windows, the root window first
+/// list_windows_with(options) -> macOS: list_windows, optionally including offscreen, other users' and non-normal-layer windows
+/// list_window_entries(options) / get_window_entry(window) -> macOS: WindowListEntry (pid, layer, bounds, name) per window
+/// get_window_at_point(x, y) -> macOS: the frontmost normal-layer window containing the point
+/// macos_permissions() / request_macos_permissions() -> macOS: PermissionStatus for Accessibility and Screen Recording, optionally prompting
+/// window_count() -> number of open windows, without fetching them where the backend allows (X11 counts desktop windows too)
+/// get_window_title(window) -> returns the window's title
+/// get_window_app_id(window) -> returns the window's application id (Wayland app_id; _GTK_APPLICATION_ID/WM_CLASS on X11)
+/// find_windows_by_app_id(app_id) -> returns all Windows with the given app_id
+/// WindowTracker::by_app_id(app_id).with_title(text).resolve() -> finds the app's window again after it restarts
+/// get_class_for_pid(target_pid) -> WM_CLASS (instance, class) on X11 / class name on Windows of the process's main window
+/// enumerate_child_windows(parent) / get_window_class(window) -> Windows only: a window's child controls and class names
+/// find_desktop_entry(app_id) -> resolves an app_id to its .desktop entry name and icon path (Linux)
+/// hide_window(window) -> takes the window off screen and off the taskbar (unmapped on X11, SW_HIDE on Windows, minimized on macOS)
+/// hide_window_with(window, HideOptions { skip_taskbar, skip_switcher, unmap }) / show_window(window) -> hide in the chosen ways (HideOptions::default() is hide_window); show_window undoes exactly what was applied
+/// set_skip_switcher(window, skip) -> keeps a still-visible window out of Alt-Tab (KWin skipSwitcher; WS_EX_TOOLWINDOW on Windows, shared with set_skip_taskbar)
+/// set_skip_taskbar(window, skip) -> keeps a still-visible window off the taskbar and pager (EWMH skip states, WS_EX_TOOLWINDOW on Windows)
+/// minimize_window(window) / maximize_window(window) / close_window(window)
+/// toggle_maximize(window) -> maximizes the window, or restores it if already maximized
+/// restore_window(window) -> un-minimize a window (macOS only)
+/// get_window_placement(window) / set_window_placement(window, normal, state) -> Windows: the restored frame and ShowState, set together
+/// get_window_icon(window) -> returns the window's largest icon as RGBA pixels
+/// move_window(window, x, y) / resize_window(window, width, height) / focus_window(window)
+/// set_window_rect(window, x, y, width, height) -> move and resize in one call (macOS needs Accessibility permission)
+/// can_focus(window) -> whether the window accepts input focus (X11 and Windows)
+/// activate_window(window, update_user_time) / set_user_time(window, timestamp) -> focus-stealing-safe activation
+/// get_window_workspace(window) / move_window_to_workspace(window, workspace) -> zero-based workspaces
+/// get_current_workspace() / switch_workspace(workspace)
+/// subscribe_window_events() -> returns a blocking iterator of WindowEvents
+/// FocusTracker::start() / recent_windows() -> windows in most-recently-focused order, following focus events
+/// get_virtual_screen() -> returns the bounding rectangle of all monitors combined
+/// list_monitors() -> returns every display as a MonitorInfo with its name and scale factor (X11, Windows, macOS, Hyprland and sway); also WindowingBackend::list_monitors
+/// get_window_monitor(window) -> the MonitorInfo of the display holding most of the window, None when it is off-screen
+/// get_window_monitors(window) -> every MonitorInfo the window overlaps, ordered by overlap (largest first), e.g. for a window stretched across two displays
+/// get_window_work_area(window) -> the work area of the window's monitor, less panels, docks and the taskbar (X11 struts, Hyprland reserved space, rcWork on Windows)
+/// move_window_clamped(window, x, y) -> move_window, pulled back so the frame stays inside its monitor's work area
+/// pointer_state() -> (x, y, window under the pointer) from one query, so the position and window agree (X11 and Windows)
+/// get_root_window() -> returns the root/desktop Window, usable with get_window_info
+/// capture_window_portal(window) / capture_screen_portal() -> RGBA Capture via xdg-desktop-portal (Linux, `portal` feature)
+/// window_has_state(window, state) -> whether the window is currently in a WindowState
+/// wait_for_state(window, state, timeout) -> blocks until the window reaches a WindowState
+/// capture_layout() / restore_layout(&layout, MatchStrategy::SameApp) -> save every window's frame, monitor, workspace and state, and put them back (by app id and closest title once the handles are gone), with a RestoreReport
+/// snapshot_desktop() / diff_snapshots(old, new) -> capture all window geometry and list what appeared, disappeared, moved or resized
+/// get_window_states(window) -> returns every WindowStateFlag (_NET_WM_STATE) set on the window
+/// set_window_state(window, flag, enabled) -> adds or removes a WindowStateFlag (_NET_WM_STATE) on the window
+/// is_sticky(window) / set_sticky(window, sticky) -> whether the window shows on all workspaces (unsupported on Windows)
+/// is_always_on_top(window) / set_always_on_top(window, on_top) -> _NET_WM_STATE_ABOVE on X11, keepAbove on KWin, HWND_TOPMOST on Windows
+/// is_always_below(window) / set_always_below(window, below) -> _NET_WM_STATE_BELOW on X11, keepBelow on KWin, HWND_BOTTOM re-applied on every activation on Windows
+/// set_click_through(window, click_through) -> clicks pass to the window beneath (empty X Shape input region, WS_EX_TRANSPARENT on Windows)
+/// always_on_top_scoped(window) -> AlwaysOnTopGuard keeping the window on top until dropped, then restoring its prior state
+///
+/// A window's frame in the units the backend reports natively: physical
+/// pixels on X11, logical (scaled) pixels under Wayland compositors, points on
+/// macOS, and on Windows physical pixels only if the process is per-monitor
+/// DPI aware (DPI-virtualized logical pixels otherwise). Use
+/// `get_window_bounds` when device pixels are needed.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowInfo {
     pub pos: (i32, i32),
     pub size: (u32, u32),
 }
 
-#[cfg(target_os = "windows")]
-pub type Window = windows::Win32::Foundation::HWND;
+/// From `(x, y, width, height)`, the order X11 geometry and most toolkits use.
+impl From<(i32, i32, u32, u32)> for WindowInfo {
+    fn from((x, y, width, height): (i32, i32, u32, u32)) -> Self {
+        Self { pos: (x, y), size: (width, height) }
+    }
+}
 
-#[cfg(target_os = "linux")]
-pub type Window =  x11rb::protocol::xproto::Window;
+impl From<WindowInfo> for (i32, i32, u32, u32) {
+    fn from(info: WindowInfo) -> Self {
+        (info.pos.0, info.pos.1, info.size.0, info.size.1)
+    }
+}
 
-#[cfg(target_os = "linux")]
-mod platform {
-    use crate::WindowInfo;
-    use std::error::Error;
-    use x11rb::{
-        connection::Connection,
-        protocol::xproto::{AtomEnum, ConnectionExt, GetGeometryReply, PropMode},
-        rust_connection::RustConnection,
-    };
-
-    impl Into<WindowInfo> for GetGeometryReply {
-        fn into(self) -> WindowInfo {
-            WindowInfo {
-                pos: (self.x as i32, self.y as i32),
-                size: (self.width as u32, self.height as u32),
-            }
+/// From a Win32 `RECT` (left, top, right, bottom). An inverted rectangle gives
+/// a zero size rather than wrapping around.
+#[cfg(target_os = "windows")]
+impl From<windows::Win32::Foundation::RECT> for WindowInfo {
+    fn from(rect: windows::Win32::Foundation::RECT) -> Self {
+        Self {
+            pos: (rect.left, rect.top),
+            size: ((rect.right - rect.left).max(0) as u32, (rect.bottom - rect.top).max(0) as u32),
         }
     }
+}
 
-    /// Get the active (foreground) window ID.
-    fn get_active_window(
-        conn: &RustConnection,
-        root: crate::Window,
-    ) -> Result<crate::Window, Box<dyn std::error::Error>> {
-        let net_active_window = conn
-            .intern_atom(false, b"_NET_ACTIVE_WINDOW")?
-            .reply()?
-            .atom;
-        let prop = conn
-            .get_property(false, root, net_active_window, AtomEnum::WINDOW, 0, 1)?
-            .reply()?;
-
-        if prop.value_len == 0 || prop.format != 32 {
-            return Err("No active window found".into());
+#[cfg(target_os = "windows")]
+impl From<WindowInfo> for windows::Win32::Foundation::RECT {
+    fn from(info: WindowInfo) -> Self {
+        Self {
+            left: info.pos.0,
+            top: info.pos.1,
+            right: info.right(),
+            bottom: info.bottom(),
         }
+    }
+}
+
+/// What a window picker typically shows for one window, from
+/// `get_window_details`. `title`, `pid` and `states` are empty where the
+/// backend can't report them, and `on_active_desktop` is `true` on backends
+/// without workspaces and for windows shown on all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowDetails {
+    pub info: WindowInfo,
+    pub title: Option<String>,
+    pub pid: Option<u32>,
+    pub states: Vec<WindowStateFlag>,
+    /// On the current workspace, so focusing it won't switch workspaces.
+    pub on_active_desktop: bool,
+}
 
-        // Extract window ID (convert bytes to u32)
-        let active_window = prop
-            .value32()
-            .ok_or("Failed to parse active window ID")?
-            .next()
-            .ok_or("Active window property is empty")?;
-        Ok(active_window)
-    }
-
-    /// Get the geometry (x, y, width, height) of a window.
-    pub fn get_window_info(window: crate::Window) -> Result<WindowInfo, Box<dyn std::error::Error>> {
-        let (conn, _) = RustConnection::connect(None).unwrap();
-        let geom = conn.get_geometry(window)?.reply()?;
-        Ok(geom.into())
-    }
-
-    /// Get a list of top-level windows from the root window (_NET_CLIENT_LIST)
-    fn get_top_level_windows(
-        conn: &RustConnection,
-        root: crate::Window,
-    ) -> Result<Vec<crate::Window>, Box<dyn Error>> {
-        let client_list_atom = conn.intern_atom(false, b"_NET_CLIENT_LIST")?.reply()?.atom;
-        let prop = conn
-            .get_property(false, root, client_list_atom, AtomEnum::WINDOW, 0, u32::MAX)?
-            .reply()?;
-
-        Ok(prop
-            .value32()
-            .ok_or("Failed to read _NET_CLIENT_LIST")?
-            .collect())
-    }
-
-    /// Get the process ID (PID) of a given window
-    fn get_window_pid(
-        conn: &RustConnection,
-        window: crate::Window,
-    ) -> Result<Option<u32>, Box<dyn Error>> {
-        let net_wm_pid_atom = conn.intern_atom(false, b"_NET_WM_PID")?.reply()?.atom;
-
-        let reply = conn
-            .get_property(false, window, net_wm_pid_atom, AtomEnum::CARDINAL, 0, 1)?
-            .reply()?;
-
-        if reply.value_len == 0 || reply.format != 32 {
-            return Ok(None);
+/// The properties `WindowQuery` filters on, for one window, from
+/// `get_window_properties`. Each is `None` where the window doesn't set it or
+/// the backend can't read it; `workspace` is also `None` for a window shown
+/// on every workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowProperties {
+    pub pid: Option<u32>,
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+    pub workspace: Option<u32>,
+}
+
+/// One window's outcome within a batch call such as `get_window_infos`, where
+/// each window can fail independently of the rest.
+pub type WindowResult<T> = crate::Result<T>;
+
+/// A window's frame in physical (device) pixels, as returned by `get_window_bounds`.
+/// Kept distinct from `WindowInfo` so the two can't be mixed up by accident.
+/// Under compositors without a global pixel space the position is the
+/// monitor's layout origin plus the window's offset on it in pixels.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalWindowInfo {
+    pub pos: (i32, i32),
+    pub size: (u32, u32),
+}
+
+/// One display. `bounds` is in the same units as `WindowInfo` on this
+/// backend; `scale_factor` is how many physical pixels one of those units
+/// spans (2.0 on a Retina display, the output scale under Wayland).
+///
+/// `name` identifies the display across reconnects and reordering: the RandR
+/// output name on X11 and the connector name under Hyprland and sway (both
+/// like `HDMI-1` or `eDP-1`), the GDI device name (`\\.\DISPLAY1`) on Windows,
+/// and `Display <CGDirectDisplayID>` on macOS.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonitorInfo {
+    pub name: String,
+    pub bounds: WindowInfo,
+    pub scale_factor: f64,
+}
+
+/// A window icon as tightly packed, non-premultiplied RGBA rows (top row first).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IconData {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// The platform's native window handle: an `HWND` value on Windows, an X11
+/// window id (or a backend-assigned id under compositor backends) on Linux,
+/// a `CGWindowID` on macOS.
+#[cfg(target_os = "windows")]
+pub type RawWindow = isize;
+
+#[cfg(target_os = "linux")]
+pub type RawWindow = u32;
+
+#[cfg(target_os = "macos")]
+pub type RawWindow = u32;
+
+/// A window handle with the same trait impls on every platform, so it can be
+/// used as a `HashMap`/`HashSet` key and compared without `#[cfg]`-specific code.
+/// To store one or send it to another process, convert it to its `WindowId`
+/// (or its `u64` id, `id`) and back with `Window::try_from` (or `from_id`).
+/// With the `serde` feature it serializes as that id, a plain integer, and
+/// deserializing rejects an id this platform can't have.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "WindowId", try_from = "WindowId")
+)]
+pub struct Window(RawWindow);
+
+impl Window {
+    /// A window from a raw handle another library handed out, as a `u64`: an
+    /// X11 window id (or a compositor backend's id) on Linux, an `HWND` value
+    /// taken as unsigned (`hwnd as usize as u64`) on Windows, a `CGWindowID`
+    /// on macOS. Fails with `InvalidWindowId` for 0 or a value this
+    /// platform's handles can't hold. With `validate`, the handle is also
+    /// checked against the live desktop with `window_exists` (one round trip):
+    /// `WindowNotFound` if no window has it right now.
+    pub fn from_raw(raw: u64, validate: bool) -> crate::Result<Self> {
+        let window = Self::from_id(raw)?;
+        if validate && !window_exists(window)? {
+            return Err(Error::WindowNotFound(window));
         }
+        Ok(window)
+    }
+
+    /// The raw handle as a `u64`, which `from_raw` turns back into the same
+    /// window on 32- and 64-bit Windows alike; the same number as `id`.
+    pub const fn as_raw(self) -> u64 {
+        self.id()
+    }
 
-        let pid = reply
-            .value32()
-            .ok_or("Failed to parse PID")?
-            .next()
-            .ok_or("PID property is empty")?;
+    /// A window from the platform's handle in its native type, for FFI.
+    pub const fn from_handle(handle: RawWindow) -> Self {
+        Self(handle)
+    }
 
-        Ok(Some(pid))
+    /// The platform's handle in its native type, for FFI: an X11 window id
+    /// (or the backend's id under compositor backends) on Linux, the `HWND`
+    /// value on Windows, the `CGWindowID` on macOS.
+    pub const fn raw(self) -> RawWindow {
+        self.0
     }
 
-    /// Search for a window by process ID (exact match)
-    pub fn find_window_by_pid(target_pid: u32) -> Result<Option<crate::Window>, Box<dyn Error>> {
-        let (conn, screen_num) = RustConnection::connect(None)?;
-        let screen = &conn.setup().roots[screen_num];
-        let windows = get_top_level_windows(&conn, screen.root)?;
+    /// The handle as a platform-neutral id, which `from_id` turns back into
+    /// the same window. An `HWND` is taken as unsigned, so every handle,
+    /// 32- or 64-bit, maps to a distinct id and back.
+    pub const fn id(self) -> u64 {
+        #[cfg(target_os = "windows")]
+        return self.0 as usize as u64;
+        #[cfg(not(target_os = "windows"))]
+        return self.0 as u64;
+    }
 
-        for window in windows {
-            if let Some(pid) = get_window_pid(&conn, window)? {
-                if pid == target_pid {
-                    return Ok(Some(window));
-                }
-            }
+    /// A window from an id obtained outside this crate (`xdotool` output, a
+    /// config file, another process), checked to be non-zero and to fit this
+    /// platform's handle type. Whether the window exists is left to the call
+    /// it is used with.
+    pub fn from_id(id: u64) -> Result<Self, InvalidWindowId> {
+        #[cfg(target_os = "windows")]
+        let raw = usize::try_from(id).map(|raw| raw as isize);
+        #[cfg(not(target_os = "windows"))]
+        let raw = RawWindow::try_from(id);
+        match raw {
+            Ok(raw) if id != 0 => Ok(Self(raw)),
+            _ => Err(InvalidWindowId::new(id.to_string())),
         }
+    }
+}
 
-        Ok(None)
+impl From<Window> for u64 {
+    fn from(window: Window) -> Self {
+        window.id()
     }
+}
 
-    /// Search for all windows belonging to a specific process ID
-    pub fn find_windows_by_pid(target_pid: u32) -> Result<Vec<crate::Window>, Box<dyn Error>> {
-        let (conn, screen_num) = RustConnection::connect(None)?;
-        let screen = &conn.setup().roots[screen_num];
-        let windows = get_top_level_windows(&conn, screen.root)?;
-        let mut matching_windows = Vec::new();
+impl TryFrom<u64> for Window {
+    type Error = InvalidWindowId;
 
-        for window in windows {
-            if let Some(pid) = get_window_pid(&conn, window)? {
-                if pid == target_pid {
-                    matching_windows.push(window);
-                }
-            }
-        }
+    fn try_from(id: u64) -> Result<Self, Self::Error> {
+        Window::from_id(id)
+    }
+}
 
-        Ok(matching_windows)
-    }
-
-    /// Get the process ID of the currently active window
-    pub fn get_active_window_pid() -> Result<Option<u32>, Box<dyn Error>> {
-        let (conn, screen_num) = RustConnection::connect(None)?;
-        let screen = &conn.setup().roots[screen_num];
-        let active_window = get_active_window(&conn, screen.root)?;
-        get_window_pid(&conn, active_window)
-    }
-
-    pub fn hide_window(window: crate::Window) -> Result<(), Box<dyn std::error::Error>> {
-        let (conn, _) = RustConnection::connect(None)?;
-        // Unmap the window first
-        conn.unmap_window(window)?;
-        
-        // Get required atoms
-        let net_wm_state = conn.intern_atom(false, b"_NET_WM_STATE")?
-            .reply()?
-            .atom;
-        
-        let skip_taskbar = conn.intern_atom(false, b"_NET_WM_STATE_SKIP_TASKBAR")?
-            .reply()?
-            .atom;
-        
-        let skip_pager = conn.intern_atom(false, b"_NET_WM_STATE_SKIP_PAGER")?
-            .reply()?
-            .atom;
-        
-        // Set both properties to hide from taskbar AND alt-tab
-        let properties = [skip_taskbar, skip_pager];
-        
-        conn.change_property(
-            PropMode::REPLACE,
-            window,
-            net_wm_state,
-            AtomEnum::ATOM,
-            32,
-            properties.len() as u32,
-            bytemuck::cast_slice(&properties), // Convert to bytes
-        )?;
-        
-        // Map the window back
-        conn.map_window(window)?;
-        conn.flush()?;
-        
-        Ok(())
+/// Parses a window id in decimal, or in hex with a `0x` prefix as `xwininfo` prints it.
+impl std::str::FromStr for Window {
+    type Err = InvalidWindowId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let id = match trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => trimmed.parse(),
+        };
+        id.ok()
+            .and_then(|id| Window::from_id(id).ok())
+            .ok_or_else(|| InvalidWindowId::new(s))
+    }
+}
+
+impl std::fmt::Display for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
 #[cfg(target_os = "windows")]
-mod platform {
-    use windows::{
-        core::BOOL, Win32::{
-            Foundation::{FALSE, HWND, LPARAM, RECT, TRUE},
-            UI::WindowsAndMessaging::{
-                EnumWindows, GetForegroundWindow, GetWindowRect, GetWindowTextLengthW, GetWindowThreadProcessId, IsWindowVisible, SetWindowLongA, ShowWindow, GWL_EXSTYLE, SW_HIDE, SW_SHOW, WS_EX_TOOLWINDOW
-            },
-        }
-    };
+impl From<windows::Win32::Foundation::HWND> for Window {
+    fn from(hwnd: windows::Win32::Foundation::HWND) -> Self {
+        Self(hwnd.0 as isize)
+    }
+}
 
-    use crate::WindowInfo;
-    struct EnumWindowsData {
-        process_id: u32,
-        windows: Vec<HWND>,
+#[cfg(target_os = "windows")]
+impl From<Window> for windows::Win32::Foundation::HWND {
+    fn from(window: Window) -> Self {
+        windows::Win32::Foundation::HWND(window.0 as *mut core::ffi::c_void)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl From<RawWindow> for Window {
+    fn from(raw: RawWindow) -> Self {
+        Self(raw)
     }
+}
 
-    // Callback function for EnumWindows
-    unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
-        let data = unsafe { &mut *(lparam.0 as *mut EnumWindowsData) };
-        let mut window_process_id: u32 = 0;
+/// A window's platform-neutral id, the same type on every platform, for
+/// storing a window in a config file or a serde struct and sending it to
+/// another process; with the `serde` feature it serializes as a plain
+/// integer. `Window` turns into one with `WindowId::from`, and back with
+/// `Window::try_from`, which rejects an id this platform can't have. Ids of
+/// foreign handles are built with `from_x11` and `from_hwnd`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct WindowId(u64);
+
+impl WindowId {
+    /// The id of an X11 window (or of a compositor backend's window on Linux).
+    pub const fn from_x11(window: u32) -> Self {
+        Self(window as u64)
+    }
 
-        // Get the process ID that owns this window
-        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut window_process_id)) };
+    /// The id of a Win32 `HWND` value, taken as unsigned so every handle, 32-
+    /// or 64-bit, has its own id.
+    pub const fn from_hwnd(hwnd: isize) -> Self {
+        Self(hwnd as usize as u64)
+    }
 
-        // If it matches our target process ID, add it to the list
-        if window_process_id == data.process_id {
-            data.windows.push(hwnd);
-        }
+    /// The id as a number, e.g. for `xdotool` or a database column.
+    pub const fn as_raw(self) -> u64 {
+        self.0
+    }
+}
 
-        TRUE // Continue enumeration
+impl From<Window> for WindowId {
+    fn from(window: Window) -> Self {
+        Self(window.id())
     }
+}
 
-    pub fn find_windows_by_pid(process_id: u32) -> Result<Vec<crate::Window>, Box<dyn std::error::Error>> {
-        let mut data = EnumWindowsData {
-            process_id,
-            windows: Vec::new(),
-        };
+impl TryFrom<WindowId> for Window {
+    type Error = InvalidWindowId;
 
-        unsafe {
-            EnumWindows(
-                Some(enum_windows_proc),
-                LPARAM(&mut data as *mut _ as isize),
-            )?;
-        }
+    fn try_from(id: WindowId) -> Result<Self, Self::Error> {
+        Window::from_id(id.0)
+    }
+}
+
+impl From<u64> for WindowId {
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<WindowId> for u64 {
+    fn from(id: WindowId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for WindowId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Returned when the active backend can't perform an operation at all, as
+/// opposed to the operation failing (e.g. geometry under `wayland-wlr`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Unsupported {
+    pub operation: &'static str,
+    pub backend: &'static str,
+}
+
+impl Unsupported {
+    pub fn new(operation: &'static str, backend: &'static str) -> Self {
+        Self { operation, backend }
+    }
+}
+
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not supported by the {} backend", self.operation, self.backend)
+    }
+}
 
-        Ok(data.windows)
+impl std::error::Error for Unsupported {}
+
+/// A system permission the user grants to this process once, rather than per request.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Permission {
+    /// macOS Privacy & Security > Accessibility, needed to manipulate other apps' windows.
+    Accessibility,
+    /// macOS Privacy & Security > Screen Recording, needed for other apps' window titles.
+    ScreenRecording,
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Permission::Accessibility => "Accessibility",
+            Permission::ScreenRecording => "Screen Recording",
+        })
+    }
+}
+
+/// Returned when the user (or a stored permission) refused an operation that
+/// needs consent, e.g. a portal screenshot, as opposed to a technical failure.
+/// `permission` names the standing permission to ask the user for, if any.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PermissionDenied {
+    pub operation: &'static str,
+    pub permission: Option<Permission>,
+}
+
+impl PermissionDenied {
+    pub fn new(operation: &'static str) -> Self {
+        Self { operation, permission: None }
     }
 
-    pub fn find_window_by_pid(process_id: u32) -> Result<Option<crate::Window>, Box<dyn std::error::Error>> {
-        let windows = find_windows_by_pid(process_id)?;
+    /// An operation that fails until the user grants `permission` to this process.
+    pub fn needs(operation: &'static str, permission: Permission) -> Self {
+        Self { operation, permission: Some(permission) }
+    }
+}
 
-        for &hwnd in &windows {
-            unsafe {
-                // Check if window is visible and has a title
-                if IsWindowVisible(hwnd) != FALSE {
-                    let title_length = GetWindowTextLengthW(hwnd);
-                    if title_length > 0 {
-                        return Ok(Some(hwnd)); // Return first visible window with title
-                    }
-                }
-            }
+impl std::fmt::Display for PermissionDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.permission {
+            Some(permission) => write!(f, "{} needs the {permission} permission", self.operation),
+            None => write!(f, "{} was denied or cancelled by the user", self.operation),
         }
+    }
+}
+
+impl std::error::Error for PermissionDenied {}
+
+/// Returned when the window manager or compositor declined to focus a window,
+/// typically because of focus-stealing prevention (no recent user input).
+/// Requesting the user's attention for the window is the usual fallback.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FocusRefused {
+    pub window: Window,
+}
+
+impl FocusRefused {
+    pub fn new(window: Window) -> Self {
+        Self { window }
+    }
+}
+
+impl std::fmt::Display for FocusRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Focusing window {} was refused", self.window)
+    }
+}
+
+impl std::error::Error for FocusRefused {}
+
+/// Returned by `Window::from_id` and `str::parse` when an id can't name a
+/// window on this platform (zero, too large, or not a number).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InvalidWindowId {
+    pub id: String,
+}
 
-        // If no main window found, return first window (if any)
-        Ok(windows.first().copied())
+impl InvalidWindowId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into() }
     }
+}
 
-    pub fn get_window_info(window:crate::Window) -> Result<Option<WindowInfo>, Box<dyn std::error::Error>> {
-        let mut window_rect = RECT {
-            left: 0,
-            top: 0,
-            right: 0,
-            bottom: 0,
-        }; unsafe { GetWindowRect(window, &mut window_rect)?};
-        Ok(Some(WindowInfo{
-            size: ((window_rect.right - window_rect.left) as u32, (window_rect.bottom - window_rect.top) as u32),
-            pos: (window_rect.left, window_rect.top)
-        }))
-        
+impl std::fmt::Display for InvalidWindowId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is not a valid window id", self.id)
     }
+}
+
+impl std::error::Error for InvalidWindowId {}
+
+/// Returned by `set_global_backend` when a backend was already registered;
+/// the first registration stays in place for the life of the process.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BackendAlreadySet;
 
-    pub fn get_active_window_pid() -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        let active_window = unsafe{GetForegroundWindow()};
-        let mut pid = 0;
-        unsafe{GetWindowThreadProcessId(active_window, Some(&mut pid))};
-        Ok(Some(pid))
+impl std::fmt::Display for BackendAlreadySet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a global windowing backend is already set")
     }
+}
 
-    pub fn hide_window(window:crate::Window) -> Result<(), Box<dyn std::error::Error>>{
-        unsafe {
-        ShowWindow(window, SW_HIDE).ok()?;
-        SetWindowLongA(window, GWL_EXSTYLE, WS_EX_TOOLWINDOW.0 as i32);
-        ShowWindow(window, SW_SHOW).ok()?;
-        };
-        Ok(())
+impl std::error::Error for BackendAlreadySet {}
+
+/// A windowing backend the crate's free functions can be served by.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BackendKind {
+    X11,
+    WaylandWlr,
+    Hyprland,
+    SwayIpc,
+    GnomeShell,
+    KWin,
+    Win32,
+    Quartz,
+}
+
+impl BackendKind {
+    /// The name used in `Unsupported` errors and accepted by `WINDOWING_BACKEND`.
+    pub fn name(self) -> &'static str {
+        match self {
+            BackendKind::X11 => "x11",
+            BackendKind::WaylandWlr => "wayland-wlr",
+            BackendKind::Hyprland => "hyprland",
+            BackendKind::SwayIpc => "sway-ipc",
+            BackendKind::GnomeShell => "gnome-shell",
+            BackendKind::KWin => "kwin",
+            BackendKind::Win32 => "win32",
+            BackendKind::Quartz => "quartz",
+        }
+    }
+}
+
+/// What the current environment supports, from `capabilities`, so features
+/// can be offered or grayed out up front instead of failing when tried. Read
+/// from the detected backend, the window manager's `_NET_SUPPORTED` hints on
+/// X11 and the session type; a probe that fails (no display) counts as `false`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The backend serving the free functions, as `detect_backend`.
+    pub backend: BackendKind,
+    /// A Wayland session, also when windows are reached through XWayland.
+    pub is_wayland: bool,
+    /// A compositing manager is running (on X11, one owns `_NET_WM_CM_S<screen>`);
+    /// always true on Wayland, Windows and macOS.
+    pub has_compositor: bool,
+    /// The window manager lists `_NET_WM_STATE` in `_NET_SUPPORTED`, so window
+    /// states can be read and set (X11 only).
+    pub supports_ewmh_state: bool,
+    /// `move_window` and `resize_window` are available (macOS also needs the
+    /// Accessibility permission).
+    pub can_move_windows: bool,
+    /// `move_window_to_workspace` is available.
+    pub can_move_to_desktop: bool,
+    /// `set_always_on_top` is available.
+    pub can_set_always_on_top: bool,
+    /// `subscribe_window_events` is available.
+    pub can_watch_events: bool,
+    /// Window opacity can be set. Always `false` for now: the crate has no
+    /// opacity call yet, and this reports it once one lands.
+    pub can_set_opacity: bool,
+    /// Mouse and keyboard input can be simulated (the `input` module's
+    /// buttons and modifiers). Always `false` until the crate can send input.
+    pub supports_input_simulation: bool,
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
     }
 }
 
-#[cfg(any(target_os="windows",target_os="linux"))]
+// The public types keep these traits on every platform: callers cache them,
+// key maps with them and send them between threads. Compiling is the test.
+const _: fn() = || {
+    fn key<T: Copy + Eq + Ord + std::hash::Hash + Send + Sync>() {}
+    fn value<T: Clone + Eq + std::hash::Hash + Default + Send + Sync>() {}
+    fn hashed<T: Clone + std::fmt::Debug + Eq + std::hash::Hash + Send + Sync>() {}
+    fn plain<T: Clone + std::fmt::Debug + PartialEq + Send + Sync>() {}
+    key::<Window>();
+    key::<WindowId>();
+    value::<WindowInfo>();
+    value::<PhysicalWindowInfo>();
+    value::<DesktopSnapshot>();
+    value::<MatchStrategy>();
+    value::<WindowQuery>();
+    value::<WindowProperties>();
+    hashed::<IconData>();
+    hashed::<WindowState>();
+    hashed::<WindowStateFlag>();
+    hashed::<WindowEvent>();
+    hashed::<WindowSnapshot>();
+    hashed::<ActiveWindowSnapshot>();
+    hashed::<WindowChange>();
+    hashed::<Capabilities>();
+    hashed::<BackendKind>();
+    hashed::<input::MouseButton>();
+    hashed::<input::Modifier>();
+    plain::<MonitorInfo>();
+    plain::<Layout>();
+    plain::<WindowDetails>();
+    fn error<T: std::error::Error + Send + Sync + 'static>() {}
+    error::<Error>();
+    error::<Unsupported>();
+    error::<PermissionDenied>();
+    error::<FocusRefused>();
+    error::<InvalidWindowId>();
+    error::<BackendAlreadySet>();
+};
+
+mod platform;
+
+#[cfg(any(target_os="windows",target_os="linux",target_os="macos"))]
 pub use platform::*;
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_ids_round_trip() {
+        let window = Window::from_id(0x1c0_0007).unwrap();
+        let id = WindowId::from(window);
+        assert_eq!(id.as_raw(), 0x1c0_0007);
+        assert_eq!(u64::from(id), window.id());
+        assert_eq!(Window::try_from(id).unwrap(), window);
+        assert_eq!(id.to_string(), "29360135");
+    }
+
+    #[test]
+    fn raw_handles_round_trip_without_validation() {
+        let window = Window::from_raw(0x1c0_0007, false).unwrap();
+        assert_eq!(window.as_raw(), 0x1c0_0007);
+        assert_eq!(Window::from_handle(window.raw()), window);
+        assert!(matches!(Window::from_raw(0, false), Err(Error::InvalidWindowId(_))));
+        // Checked before validation, so no window system is needed
+        assert!(matches!(Window::from_raw(0, true), Err(Error::InvalidWindowId(_))));
+    }
+
+    #[test]
+    fn x11_and_hwnd_ids_are_distinct_unsigned_numbers() {
+        assert_eq!(WindowId::from_x11(u32::MAX).as_raw(), u32::MAX as u64);
+        assert_eq!(WindowId::from_hwnd(0x1_0046).as_raw(), 0x1_0046);
+        // Handles with the sign bit set stay distinct from every other id
+        assert_eq!(WindowId::from_hwnd(-2).as_raw(), usize::MAX as u64 - 1);
+        assert_ne!(WindowId::from_hwnd(-2), WindowId::from_hwnd(2));
+    }
+
+    #[test]
+    fn ids_this_platform_cannot_have_are_rejected() {
+        assert!(Window::try_from(WindowId::from(0)).is_err());
+        #[cfg(not(target_os = "windows"))]
+        assert!(Window::try_from(WindowId::from(u32::MAX as u64 + 1)).is_err());
+        #[cfg(not(target_os = "windows"))]
+        assert_eq!(Window::try_from(WindowId::from_x11(42)).unwrap().raw(), 42);
+        #[cfg(target_os = "windows")]
+        assert_eq!(Window::try_from(WindowId::from_hwnd(-2)).unwrap().raw(), -2);
+    }
+}
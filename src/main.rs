@@ -1,6 +1,22 @@
 use windowing::hide_window;
 
 fn main() {
-    let window = windowing::find_window_by_pid(4160).unwrap().unwrap();
-    hide_window(window);
+    let Some(pid) = std::env::args().nth(1).and_then(|arg| arg.parse::<u32>().ok()) else {
+        eprintln!("Usage: windowing <pid>");
+        return;
+    };
+    let window = match windowing::find_window_by_pid(pid) {
+        Ok(Some(window)) => window,
+        Ok(None) => {
+            eprintln!("No window found for process {pid}");
+            return;
+        }
+        Err(error) => {
+            eprintln!("Failed to find a window for process {pid}: {error}");
+            return;
+        }
+    };
+    if let Err(error) = hide_window(window) {
+        eprintln!("Failed to hide window {window}: {error}");
+    }
 }
@@ -0,0 +1,646 @@
+//! An in-memory `WindowingBackend` for testing window-handling code without a
+//! display server (the `mock` feature).
+
+use crate::{
+    BackendKind, FocusRefused, IconData, MonitorInfo, PermissionDenied, PhysicalWindowInfo, Unsupported, Window, WindowEvent,
+    WindowEvents, WindowInfo, WindowState, WindowStateFlag, WindowingBackend,
+};
+use crate::geometry::{monitor_of, monitors_of};
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Mutex, MutexGuard, PoisonError},
+};
+
+/// A failure `MockBackend` can be told to return from an operation, in place
+/// of acting on its model.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MockFailure {
+    /// The window closed under the caller: "Window … does not exist".
+    WindowGone,
+    /// A `PermissionDenied` error.
+    PermissionDenied,
+    /// An `Unsupported` error naming the mock's `kind`.
+    Unsupported,
+}
+
+/// A fake window for `MockBackend`, set up builder-style:
+/// `MockWindow::new(window).with_pid(42).with_title("Editor")`.
+#[derive(Debug, Clone)]
+pub struct MockWindow {
+    window: Window,
+    pid: Option<u32>,
+    title: Option<String>,
+    app_id: Option<String>,
+    class: Option<(String, String)>,
+    info: WindowInfo,
+    states: Vec<WindowStateFlag>,
+    workspace: Option<u32>,
+    icon: Option<IconData>,
+    focusable: bool,
+}
+
+impl MockWindow {
+    /// An untitled, focusable 800x600 window at the origin, with no process.
+    pub fn new(window: Window) -> Self {
+        Self {
+            window,
+            pid: None,
+            title: None,
+            app_id: None,
+            class: None,
+            info: WindowInfo { pos: (0, 0), size: (800, 600) },
+            states: Vec::new(),
+            workspace: None,
+            icon: None,
+            focusable: true,
+        }
+    }
+
+    pub fn with_pid(mut self, pid: u32) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.app_id = Some(app_id.into());
+        self
+    }
+
+    /// The (instance, class) pair reported by `get_class_for_pid`.
+    pub fn with_class(mut self, instance: impl Into<String>, class: impl Into<String>) -> Self {
+        self.class = Some((instance.into(), class.into()));
+        self
+    }
+
+    pub fn with_geometry(mut self, x: i32, y: i32, width: u32, height: u32) -> Self {
+        self.info = WindowInfo { pos: (x, y), size: (width, height) };
+        self
+    }
+
+    pub fn with_state(mut self, flag: WindowStateFlag) -> Self {
+        if !self.states.contains(&flag) {
+            self.states.push(flag);
+        }
+        self
+    }
+
+    pub fn with_workspace(mut self, workspace: u32) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    pub fn with_icon(mut self, icon: IconData) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Make `focus_window` fail with `FocusRefused`, as under focus-stealing prevention.
+    pub fn unfocusable(mut self) -> Self {
+        self.focusable = false;
+        self
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn app_id(&self) -> Option<&str> {
+        self.app_id.as_deref()
+    }
+
+    pub fn geometry(&self) -> WindowInfo {
+        self.info
+    }
+
+    pub fn states(&self) -> &[WindowStateFlag] {
+        &self.states
+    }
+
+    pub fn workspace(&self) -> Option<u32> {
+        self.workspace
+    }
+
+    fn has(&self, flag: &WindowStateFlag) -> bool {
+        self.states.contains(flag)
+    }
+
+    fn set(&mut self, flag: WindowStateFlag, enabled: bool) {
+        if enabled {
+            if !self.has(&flag) {
+                self.states.push(flag);
+            }
+        } else {
+            self.states.retain(|existing| *existing != flag);
+        }
+    }
+
+    /// Titled and not kept off the taskbar, like the platform heuristics
+    fn is_main(&self) -> bool {
+        self.title.as_deref().is_some_and(|title| !title.is_empty()) && !self.has(&WindowStateFlag::SkipTaskbar)
+    }
+}
+
+#[derive(Debug)]
+struct Model {
+    /// In `list_windows` order
+    windows: Vec<MockWindow>,
+    active: Option<Window>,
+    current_workspace: Option<u32>,
+    virtual_screen: WindowInfo,
+    /// Each monitor with its work area
+    monitors: Vec<(MonitorInfo, WindowInfo)>,
+    failures: HashMap<&'static str, MockFailure>,
+    subscribers: Vec<mpsc::Sender<WindowEvent>>,
+}
+
+impl Model {
+    fn find(&mut self, window: Window) -> crate::Result<&mut MockWindow> {
+        self.windows
+            .iter_mut()
+            .find(|mock| mock.window == window)
+            .ok_or_else(|| crate::Error::WindowGone(window))
+    }
+
+    fn monitors(&self) -> Vec<MonitorInfo> {
+        self.monitors.iter().map(|(monitor, _)| monitor.clone()).collect()
+    }
+
+    fn emit(&mut self, event: WindowEvent) {
+        // Streams that were dropped stop receiving
+        self.subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+/// A `WindowingBackend` over an in-memory set of fake windows, so code taking
+/// a `&dyn WindowingBackend` can be tested deterministically. Operations act
+/// on the model (closing a window removes it, moving it changes its geometry)
+/// and are reported to `subscribe_window_events` streams.
+///
+/// ```ignore
+/// let mock = MockBackend::new()
+///     .with_window(MockWindow::new(editor).with_pid(42).with_title("Editor"))
+///     .with_failure("get_window_info", MockFailure::WindowGone);
+/// ```
+#[derive(Debug)]
+pub struct MockBackend {
+    kind: BackendKind,
+    model: Mutex<Model>,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockBackend {
+    /// No windows, no focus, a 1920x1080 screen and no monitors; reports
+    /// itself as `x11`.
+    pub fn new() -> Self {
+        Self {
+            kind: BackendKind::X11,
+            model: Mutex::new(Model {
+                windows: Vec::new(),
+                active: None,
+                current_workspace: None,
+                virtual_screen: WindowInfo { pos: (0, 0), size: (1920, 1080) },
+                monitors: Vec::new(),
+                failures: HashMap::new(),
+                subscribers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Report another backend from `kind`, for code that branches on it.
+    pub fn with_kind(mut self, kind: BackendKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_window(self, window: MockWindow) -> Self {
+        self.lock().windows.push(window);
+        self
+    }
+
+    pub fn with_active(self, window: Window) -> Self {
+        self.lock().active = Some(window);
+        self
+    }
+
+    pub fn with_current_workspace(self, workspace: u32) -> Self {
+        self.lock().current_workspace = Some(workspace);
+        self
+    }
+
+    pub fn with_virtual_screen(self, screen: WindowInfo) -> Self {
+        self.lock().virtual_screen = screen;
+        self
+    }
+
+    /// Add a monitor, in `list_monitors` order, with no space reserved by panels.
+    pub fn with_monitor(self, monitor: MonitorInfo) -> Self {
+        let work_area = monitor.bounds;
+        self.with_monitor_work_area(monitor, work_area)
+    }
+
+    /// Add a monitor whose work area (see `get_window_work_area`) is `work_area`.
+    pub fn with_monitor_work_area(self, monitor: MonitorInfo, work_area: WindowInfo) -> Self {
+        self.lock().monitors.push((monitor, work_area));
+        self
+    }
+
+    /// Make every call of `operation` (a trait method name) fail until cleared.
+    pub fn with_failure(self, operation: &'static str, failure: MockFailure) -> Self {
+        self.fail(operation, failure);
+        self
+    }
+
+    /// `with_failure` on a backend already in use.
+    pub fn fail(&self, operation: &'static str, failure: MockFailure) {
+        self.lock().failures.insert(operation, failure);
+    }
+
+    pub fn clear_failure(&self, operation: &'static str) {
+        self.lock().failures.remove(operation);
+    }
+
+    /// Open a window, reporting `WindowEvent::Opened`.
+    pub fn add_window(&self, window: MockWindow) {
+        let mut model = self.lock();
+        let handle = window.window;
+        model.windows.push(window);
+        model.emit(WindowEvent::Opened(handle));
+    }
+
+    /// Close a window without going through `close_window` (i.e. as if its
+    /// application quit), reporting `WindowEvent::Closed`.
+    pub fn remove_window(&self, window: Window) {
+        let mut model = self.lock();
+        remove(&mut model, window);
+    }
+
+    /// Retitle a window, reporting `WindowEvent::TitleChanged`.
+    pub fn set_title(&self, window: Window, title: impl Into<String>) -> crate::Result<()> {
+        let mut model = self.lock();
+        model.find(window)?.title = Some(title.into());
+        model.emit(WindowEvent::TitleChanged(window));
+        Ok(())
+    }
+
+    /// The window as the model has it now, for assertions.
+    pub fn window(&self, window: Window) -> Option<MockWindow> {
+        self.lock().windows.iter().find(|mock| mock.window == window).cloned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Model> {
+        self.model.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The model, unless a failure is set for `operation`
+    fn model(&self, operation: &'static str, window: Option<Window>) -> crate::Result<MutexGuard<'_, Model>> {
+        let model = self.lock();
+        match model.failures.get(operation) {
+            None => Ok(model),
+            Some(MockFailure::WindowGone) => Err(match window {
+                Some(window) => crate::Error::WindowGone(window),
+                None => format!("{operation}: the window no longer exists").into(),
+            }),
+            Some(MockFailure::PermissionDenied) => Err(PermissionDenied::new(operation).into()),
+            Some(MockFailure::Unsupported) => Err(Unsupported::new(operation, self.kind.name()).into()),
+        }
+    }
+
+    /// Run `op` on one window of the model
+    fn on_window<T>(
+        &self,
+        operation: &'static str,
+        window: Window,
+        op: impl FnOnce(&mut MockWindow) -> T,
+    ) -> crate::Result<T> {
+        let mut model = self.model(operation, Some(window))?;
+        Ok(op(model.find(window)?))
+    }
+}
+
+fn remove(model: &mut Model, window: Window) {
+    let before = model.windows.len();
+    model.windows.retain(|mock| mock.window != window);
+    if model.windows.len() == before {
+        return;
+    }
+    model.emit(WindowEvent::Closed(window));
+    if model.active == Some(window) {
+        model.active = None;
+        model.emit(WindowEvent::Focused(None));
+    }
+}
+
+impl WindowingBackend for MockBackend {
+    fn kind(&self) -> BackendKind {
+        self.kind
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<Window>> {
+        Ok(self.model("list_windows", None)?.windows.iter().map(MockWindow::window).collect())
+    }
+
+    fn window_count(&self) -> crate::Result<usize> {
+        Ok(self.model("window_count", None)?.windows.len())
+    }
+
+    fn get_window_info(&self, window: Window) -> crate::Result<WindowInfo> {
+        self.on_window("get_window_info", window, |mock| mock.info)
+    }
+
+    fn get_window_info_with_retry(&self, window: Window, _retries: u32) -> crate::Result<WindowInfo> {
+        self.on_window("get_window_info_with_retry", window, |mock| mock.info)
+    }
+
+    fn get_window_bounds(&self, window: Window) -> crate::Result<PhysicalWindowInfo> {
+        self.on_window("get_window_bounds", window, |mock| {
+            PhysicalWindowInfo { pos: mock.info.pos, size: mock.info.size }
+        })
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<Window>> {
+        let model = self.model("find_window_by_pid", None)?;
+        let mut owned = model.windows.iter().filter(|mock| mock.pid == Some(target_pid));
+        let first = owned.clone().next().map(MockWindow::window);
+        Ok(owned.find(|mock| mock.is_main()).map(MockWindow::window).or(first))
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<Window>> {
+        let model = self.model("find_windows_by_pid", None)?;
+        Ok(model.windows.iter().filter(|mock| mock.pid == Some(target_pid)).map(MockWindow::window).collect())
+    }
+
+    fn find_visible_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<Window>> {
+        let model = self.model("find_visible_windows_by_pid", None)?;
+        Ok(model
+            .windows
+            .iter()
+            .filter(|mock| mock.pid == Some(target_pid) && mock.is_main())
+            .map(MockWindow::window)
+            .collect())
+    }
+
+    fn find_windows_by_app_id(&self, app_id: &str) -> crate::Result<Vec<Window>> {
+        let model = self.model("find_windows_by_app_id", None)?;
+        Ok(model
+            .windows
+            .iter()
+            .filter(|mock| mock.app_id.as_deref().is_some_and(|id| id.eq_ignore_ascii_case(app_id)))
+            .map(MockWindow::window)
+            .collect())
+    }
+
+    fn get_class_for_pid(&self, target_pid: u32) -> crate::Result<Option<(String, String)>> {
+        let Some(window) = self.find_window_by_pid(target_pid)? else {
+            return Ok(None);
+        };
+        self.on_window("get_class_for_pid", window, |mock| mock.class.clone())
+    }
+
+    fn is_main_window(&self, window: Window) -> crate::Result<bool> {
+        self.on_window("is_main_window", window, |mock| mock.is_main())
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<Window>> {
+        Ok(self.model("get_active_window", None)?.active)
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        let mut model = self.model("get_active_window_pid", None)?;
+        match model.active {
+            Some(window) => Ok(model.find(window)?.pid),
+            None => Ok(None),
+        }
+    }
+
+    fn get_window_pid(&self, window: Window) -> crate::Result<Option<u32>> {
+        self.on_window("get_window_pid", window, |mock| mock.pid)
+    }
+
+    fn get_window_title(&self, window: Window) -> crate::Result<Option<String>> {
+        self.on_window("get_window_title", window, |mock| mock.title.clone())
+    }
+
+    fn get_window_app_id(&self, window: Window) -> crate::Result<Option<String>> {
+        self.on_window("get_window_app_id", window, |mock| mock.app_id.clone())
+    }
+
+    fn get_window_icon(&self, window: Window) -> crate::Result<Option<IconData>> {
+        self.on_window("get_window_icon", window, |mock| mock.icon.clone())
+    }
+
+    fn hide_window(&self, window: Window) -> crate::Result<()> {
+        self.on_window("hide_window", window, |mock| mock.set(WindowStateFlag::Hidden, true))
+    }
+
+    fn show_window(&self, window: Window) -> crate::Result<()> {
+        self.on_window("show_window", window, |mock| mock.set(WindowStateFlag::Hidden, false))
+    }
+
+    fn set_skip_taskbar(&self, window: Window, skip: bool) -> crate::Result<()> {
+        self.on_window("set_skip_taskbar", window, |mock| {
+            mock.set(WindowStateFlag::SkipTaskbar, skip);
+            mock.set(WindowStateFlag::SkipPager, skip);
+        })
+    }
+
+    fn minimize_window(&self, window: Window) -> crate::Result<()> {
+        self.on_window("minimize_window", window, |mock| mock.set(WindowStateFlag::Hidden, true))
+    }
+
+    fn maximize_window(&self, window: Window) -> crate::Result<()> {
+        self.on_window("maximize_window", window, |mock| {
+            mock.set(WindowStateFlag::MaximizedVert, true);
+            mock.set(WindowStateFlag::MaximizedHorz, true);
+        })
+    }
+
+    fn toggle_maximize(&self, window: Window) -> crate::Result<()> {
+        self.on_window("toggle_maximize", window, |mock| {
+            let maximized = mock.has(&WindowStateFlag::MaximizedVert) && mock.has(&WindowStateFlag::MaximizedHorz);
+            mock.set(WindowStateFlag::MaximizedVert, !maximized);
+            mock.set(WindowStateFlag::MaximizedHorz, !maximized);
+        })
+    }
+
+    fn close_window(&self, window: Window) -> crate::Result<()> {
+        let mut model = self.model("close_window", Some(window))?;
+        model.find(window)?;
+        remove(&mut model, window);
+        Ok(())
+    }
+
+    fn move_window(&self, window: Window, x: i32, y: i32) -> crate::Result<()> {
+        self.on_window("move_window", window, |mock| mock.info.pos = (x, y))
+    }
+
+    fn resize_window(&self, window: Window, width: u32, height: u32) -> crate::Result<()> {
+        self.on_window("resize_window", window, |mock| mock.info.size = (width, height))
+    }
+
+    fn set_window_rect(
+        &self,
+        window: Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> crate::Result<()> {
+        self.on_window("set_window_rect", window, |mock| {
+            mock.info = WindowInfo { pos: (x, y), size: (width, height) }
+        })
+    }
+
+    fn focus_window(&self, window: Window) -> crate::Result<()> {
+        let mut model = self.model("focus_window", Some(window))?;
+        let mock = model.find(window)?;
+        if !mock.focusable {
+            return Err(FocusRefused::new(window).into());
+        }
+        mock.set(WindowStateFlag::Hidden, false);
+        if model.active != Some(window) {
+            model.active = Some(window);
+            model.emit(WindowEvent::Focused(Some(window)));
+        }
+        Ok(())
+    }
+
+    fn can_focus(&self, window: Window) -> crate::Result<bool> {
+        self.on_window("can_focus", window, |mock| mock.focusable)
+    }
+
+    fn activate_window(&self, window: Window, _update_user_time: bool) -> crate::Result<()> {
+        // Checked for an injected failure only; focusing takes the lock again
+        drop(self.model("activate_window", Some(window))?);
+        self.focus_window(window)
+    }
+
+    fn set_user_time(&self, window: Window, _timestamp: u32) -> crate::Result<()> {
+        self.on_window("set_user_time", window, |_| ())
+    }
+
+    fn get_window_workspace(&self, window: Window) -> crate::Result<Option<u32>> {
+        self.on_window("get_window_workspace", window, |mock| mock.workspace)
+    }
+
+    fn move_window_to_workspace(&self, window: Window, workspace: u32) -> crate::Result<()> {
+        let mut model = self.model("move_window_to_workspace", Some(window))?;
+        model.find(window)?.workspace = Some(workspace);
+        model.emit(WindowEvent::WorkspaceChanged(window, Some(workspace)));
+        Ok(())
+    }
+
+    fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+        Ok(self.model("get_current_workspace", None)?.current_workspace)
+    }
+
+    fn switch_workspace(&self, workspace: u32) -> crate::Result<()> {
+        self.model("switch_workspace", None)?.current_workspace = Some(workspace);
+        Ok(())
+    }
+
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        let (sender, receiver) = mpsc::channel();
+        self.model("subscribe_window_events", None)?.subscribers.push(sender);
+        Ok(WindowEvents::new(receiver.into_iter().map(Ok)))
+    }
+
+    fn get_virtual_screen(&self) -> crate::Result<WindowInfo> {
+        Ok(self.model("get_virtual_screen", None)?.virtual_screen)
+    }
+
+    fn list_monitors(&self) -> crate::Result<Vec<MonitorInfo>> {
+        Ok(self.model("list_monitors", None)?.monitors())
+    }
+
+    fn get_window_monitor(&self, window: Window) -> crate::Result<Option<MonitorInfo>> {
+        let mut model = self.model("get_window_monitor", Some(window))?;
+        let info = model.find(window)?.info;
+        let monitors = model.monitors();
+        Ok(monitor_of(&info, &monitors).cloned())
+    }
+
+    fn get_window_monitors(&self, window: Window) -> crate::Result<Vec<MonitorInfo>> {
+        let mut model = self.model("get_window_monitors", Some(window))?;
+        let info = model.find(window)?.info;
+        let monitors = model.monitors();
+        Ok(monitors_of(&info, &monitors))
+    }
+
+    fn get_window_work_area(&self, window: Window) -> crate::Result<Option<WindowInfo>> {
+        let mut model = self.model("get_window_work_area", Some(window))?;
+        let info = model.find(window)?.info;
+        let monitors = model.monitors();
+        let work_area = monitor_of(&info, &monitors)
+            .and_then(|found| model.monitors.iter().find(|(monitor, _)| monitor.name == found.name))
+            .map(|&(_, work_area)| work_area);
+        Ok(work_area)
+    }
+
+    fn window_has_state(&self, window: Window, state: WindowState) -> crate::Result<bool> {
+        let mut model = self.model("window_has_state", Some(window))?;
+        let active = model.active == Some(window);
+        let Ok(mock) = model.find(window) else {
+            return Ok(state == WindowState::Closed);
+        };
+        Ok(match state {
+            WindowState::Visible => !mock.has(&WindowStateFlag::Hidden),
+            WindowState::Minimized => mock.has(&WindowStateFlag::Hidden),
+            WindowState::Maximized => {
+                mock.has(&WindowStateFlag::MaximizedVert) && mock.has(&WindowStateFlag::MaximizedHorz)
+            }
+            WindowState::Fullscreen => mock.has(&WindowStateFlag::Fullscreen),
+            WindowState::Focused => active,
+            WindowState::Closed => false,
+        })
+    }
+
+    fn get_window_states(&self, window: Window) -> crate::Result<Vec<WindowStateFlag>> {
+        self.on_window("get_window_states", window, |mock| mock.states.clone())
+    }
+
+    fn set_window_state(&self, window: Window, flag: WindowStateFlag, enabled: bool) -> crate::Result<()> {
+        self.on_window("set_window_state", window, |mock| mock.set(flag, enabled))
+    }
+
+    fn is_sticky(&self, window: Window) -> crate::Result<bool> {
+        self.on_window("is_sticky", window, |mock| mock.has(&WindowStateFlag::Sticky))
+    }
+
+    fn set_sticky(&self, window: Window, sticky: bool) -> crate::Result<()> {
+        self.on_window("set_sticky", window, |mock| mock.set(WindowStateFlag::Sticky, sticky))
+    }
+
+    fn is_always_on_top(&self, window: Window) -> crate::Result<bool> {
+        self.on_window("is_always_on_top", window, |mock| mock.has(&WindowStateFlag::Above))
+    }
+
+    fn set_always_on_top(&self, window: Window, on_top: bool) -> crate::Result<()> {
+        self.on_window("set_always_on_top", window, |mock| mock.set(WindowStateFlag::Above, on_top))
+    }
+
+    fn is_always_below(&self, window: Window) -> crate::Result<bool> {
+        self.on_window("is_always_below", window, |mock| mock.has(&WindowStateFlag::Below))
+    }
+
+    fn set_always_below(&self, window: Window, below: bool) -> crate::Result<()> {
+        self.on_window("set_always_below", window, |mock| mock.set(WindowStateFlag::Below, below))
+    }
+}
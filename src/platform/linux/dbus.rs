@@ -0,0 +1,916 @@
+//! Minimal D-Bus client for the session bus.
+//!
+//! Implements just enough of the wire protocol for the desktop-specific
+//! backends: EXTERNAL authentication, signature-driven (un)marshalling,
+//! method calls with replies, signals and replying to incoming calls.
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{ErrorKind, Read, Write},
+    os::unix::{fs::MetadataExt, net::UnixStream},
+    time::Instant,
+};
+
+pub(super) const METHOD_CALL: u8 = 1;
+pub(super) const METHOD_RETURN: u8 = 2;
+pub(super) const ERROR: u8 = 3;
+#[cfg_attr(not(feature = "portal"), allow(dead_code))]
+pub(super) const SIGNAL: u8 = 4;
+
+const NO_REPLY_EXPECTED: u8 = 0x1;
+
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+const BUS_NAME: &str = "org.freedesktop.DBus";
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+
+/// A D-Bus value. Containers carry enough type information to be re-marshalled.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Byte(u8),
+    Bool(bool),
+    Int16(i16),
+    Uint16(u16),
+    Int32(i32),
+    Uint32(u32),
+    Int64(i64),
+    Uint64(u64),
+    Double(f64),
+    String(String),
+    ObjectPath(String),
+    Signature(String),
+    /// Element signature plus items (so empty arrays stay typed).
+    Array(String, Vec<Value>),
+    Struct(Vec<Value>),
+    DictEntry(Box<Value>, Box<Value>),
+    Variant(Box<Value>),
+    UnixFd(u32),
+}
+
+impl Value {
+    pub fn signature(&self) -> String {
+        match self {
+            Value::Byte(_) => "y".into(),
+            Value::Bool(_) => "b".into(),
+            Value::Int16(_) => "n".into(),
+            Value::Uint16(_) => "q".into(),
+            Value::Int32(_) => "i".into(),
+            Value::Uint32(_) => "u".into(),
+            Value::Int64(_) => "x".into(),
+            Value::Uint64(_) => "t".into(),
+            Value::Double(_) => "d".into(),
+            Value::String(_) => "s".into(),
+            Value::ObjectPath(_) => "o".into(),
+            Value::Signature(_) => "g".into(),
+            Value::Array(element, _) => format!("a{element}"),
+            Value::Struct(fields) => {
+                format!("({})", fields.iter().map(Value::signature).collect::<String>())
+            }
+            Value::DictEntry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+            Value::Variant(_) => "v".into(),
+            Value::UnixFd(_) => "h".into(),
+        }
+    }
+
+    /// Build an `a{sv}` dictionary.
+    #[cfg_attr(not(feature = "portal"), allow(dead_code))]
+    pub fn dict(entries: Vec<(&str, Value)>) -> Value {
+        Value::Array(
+            "{sv}".into(),
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    Value::DictEntry(
+                        Box::new(Value::String(key.into())),
+                        Box::new(Value::Variant(Box::new(value))),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) | Value::ObjectPath(s) | Value::Signature(s) => Some(s),
+            Value::Variant(inner) => inner.as_str(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Value::Uint32(n) => Some(*n),
+            Value::Byte(n) => Some(*n as u32),
+            Value::Uint16(n) => Some(*n as u32),
+            Value::Variant(inner) => inner.as_u32(),
+            _ => None,
+        }
+    }
+
+    /// Look up a key in an `a{sv}`/`a{s*}` dictionary, unwrapping the variant.
+    #[cfg_attr(not(feature = "portal"), allow(dead_code))]
+    pub fn lookup(&self, key: &str) -> Option<&Value> {
+        let Value::Array(_, entries) = self else {
+            return None;
+        };
+        entries.iter().find_map(|entry| match entry {
+            Value::DictEntry(k, v) if k.as_str() == Some(key) => Some(match &**v {
+                Value::Variant(inner) => &**inner,
+                other => other,
+            }),
+            _ => None,
+        })
+    }
+}
+
+/// An error reply from a remote method.
+#[derive(Debug, Clone)]
+pub(crate) struct MethodError {
+    pub name: String,
+    pub message: String,
+}
+
+impl fmt::Display for MethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+impl Error for MethodError {}
+
+impl From<MethodError> for crate::Error {
+    fn from(error: MethodError) -> Self {
+        crate::Error::Platform(Box::new(error))
+    }
+}
+
+impl MethodError {
+    /// The method error `error` carries, if it came from an error reply.
+    #[cfg_attr(not(any(feature = "gnome-shell", feature = "kwin")), allow(dead_code))]
+    pub fn of(error: &crate::Error) -> Option<&MethodError> {
+        match error {
+            crate::Error::Platform(source) => source.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// A received message.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Message {
+    pub kind: u8,
+    #[cfg_attr(not(any(feature = "kwin", feature = "portal")), allow(dead_code))]
+    pub flags: u8,
+    #[cfg_attr(not(any(feature = "kwin", feature = "portal")), allow(dead_code))]
+    pub serial: u32,
+    pub reply_serial: Option<u32>,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<Value>,
+}
+
+// ---- marshalling ------------------------------------------------------------
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, n: usize) {
+        while !self.buf.len().is_multiple_of(n) {
+            self.buf.push(0);
+        }
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.buf.push(value.len() as u8);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Byte(v) => self.buf.push(*v),
+            Value::Bool(v) => self.u32(*v as u32),
+            Value::Int16(v) => {
+                self.align(2);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Uint16(v) => {
+                self.align(2);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Int32(v) => self.u32(*v as u32),
+            Value::Uint32(v) | Value::UnixFd(v) => self.u32(*v),
+            Value::Int64(v) => {
+                self.align(8);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Uint64(v) => {
+                self.align(8);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Double(v) => {
+                self.align(8);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::String(v) | Value::ObjectPath(v) => self.string(v),
+            Value::Signature(v) => self.signature(v),
+            Value::Array(element, items) => {
+                self.u32(0);
+                let len_pos = self.buf.len() - 4;
+                self.align(alignment(element.as_bytes()[0]));
+                let start = self.buf.len();
+                for item in items {
+                    self.value(item);
+                }
+                let len = (self.buf.len() - start) as u32;
+                self.buf[len_pos..len_pos + 4].copy_from_slice(&len.to_le_bytes());
+            }
+            Value::Struct(fields) => {
+                self.align(8);
+                for field in fields {
+                    self.value(field);
+                }
+            }
+            Value::DictEntry(key, value) => {
+                self.align(8);
+                self.value(key);
+                self.value(value);
+            }
+            Value::Variant(inner) => {
+                self.signature(&inner.signature());
+                self.value(inner);
+            }
+        }
+    }
+}
+
+fn alignment(code: u8) -> usize {
+    match code {
+        b'n' | b'q' => 2,
+        b'b' | b'i' | b'u' | b's' | b'o' | b'a' | b'h' => 4,
+        b'x' | b't' | b'd' | b'(' | b'{' => 8,
+        _ => 1,
+    }
+}
+
+/// Length of the first complete type in `signature`.
+fn single_type_len(signature: &[u8]) -> crate::Result<usize> {
+    match signature.first() {
+        Some(b'a') => Ok(1 + single_type_len(&signature[1..])?),
+        Some(&open @ (b'(' | b'{')) => {
+            let close = if open == b'(' { b')' } else { b'}' };
+            let mut pos = 1;
+            while signature.get(pos) != Some(&close) {
+                if pos >= signature.len() {
+                    return Err("Unterminated D-Bus container signature".into());
+                }
+                pos += single_type_len(&signature[pos..])?;
+            }
+            Ok(pos + 1)
+        }
+        Some(_) => Ok(1),
+        None => Err("Empty D-Bus signature".into()),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn align(&mut self, n: usize) {
+        self.pos = self.pos.next_multiple_of(n);
+    }
+
+    fn take(&mut self, n: usize) -> crate::Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or("Truncated D-Bus message")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn fixed<const N: usize>(&mut self) -> crate::Result<[u8; N]> {
+        self.align(N);
+        let mut bytes: [u8; N] = self.take(N)?.try_into().unwrap();
+        if self.big_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> crate::Result<u32> {
+        Ok(u32::from_le_bytes(self.fixed()?))
+    }
+
+    fn string(&mut self) -> crate::Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+    }
+
+    fn signature(&mut self) -> crate::Result<String> {
+        let len = self.take(1)?[0] as usize;
+        let bytes = self.take(len + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+    }
+
+    /// Read one value of the single complete type `signature`.
+    fn value(&mut self, signature: &[u8]) -> crate::Result<Value> {
+        let code = *signature.first().ok_or("Empty D-Bus signature")?;
+        Ok(match code {
+            b'y' => Value::Byte(self.take(1)?[0]),
+            b'b' => Value::Bool(self.u32()? != 0),
+            b'n' => Value::Int16(i16::from_le_bytes(self.fixed()?)),
+            b'q' => Value::Uint16(u16::from_le_bytes(self.fixed()?)),
+            b'i' => Value::Int32(i32::from_le_bytes(self.fixed()?)),
+            b'u' => Value::Uint32(self.u32()?),
+            b'h' => Value::UnixFd(self.u32()?),
+            b'x' => Value::Int64(i64::from_le_bytes(self.fixed()?)),
+            b't' => Value::Uint64(u64::from_le_bytes(self.fixed()?)),
+            b'd' => Value::Double(f64::from_le_bytes(self.fixed()?)),
+            b's' => Value::String(self.string()?),
+            b'o' => Value::ObjectPath(self.string()?),
+            b'g' => Value::Signature(self.signature()?),
+            b'v' => {
+                let inner = self.signature()?;
+                if single_type_len(inner.as_bytes())? != inner.len() {
+                    return Err(format!("D-Bus variant signature `{inner}` is not a single type").into());
+                }
+                Value::Variant(Box::new(self.value(inner.as_bytes())?))
+            }
+            b'a' => {
+                let element = &signature[1..1 + single_type_len(&signature[1..])?];
+                let len = self.u32()? as usize;
+                self.align(alignment(element[0]));
+                let end = self.pos + len;
+                let mut items = Vec::new();
+                while self.pos < end {
+                    let start = self.pos;
+                    items.push(self.value(element)?);
+                    if self.pos == start {
+                        return Err("D-Bus array of zero-sized elements".into());
+                    }
+                }
+                Value::Array(String::from_utf8_lossy(element).into_owned(), items)
+            }
+            b'(' => {
+                self.align(8);
+                let mut fields = Vec::new();
+                let mut rest = &signature[1..single_type_len(signature)? - 1];
+                while !rest.is_empty() {
+                    let len = single_type_len(rest)?;
+                    fields.push(self.value(&rest[..len])?);
+                    rest = &rest[len..];
+                }
+                Value::Struct(fields)
+            }
+            b'{' => {
+                self.align(8);
+                let key_len = single_type_len(&signature[1..])?;
+                let key = self.value(&signature[1..1 + key_len])?;
+                let value = self.value(&signature[1 + key_len..])?;
+                Value::DictEntry(Box::new(key), Box::new(value))
+            }
+            other => return Err(format!("Unsupported D-Bus type code `{}`", other as char).into()),
+        })
+    }
+
+    fn values(&mut self, signature: &str) -> crate::Result<Vec<Value>> {
+        let mut rest = signature.as_bytes();
+        let mut values = Vec::new();
+        while !rest.is_empty() {
+            let len = single_type_len(rest)?;
+            values.push(self.value(&rest[..len])?);
+            rest = &rest[len..];
+        }
+        Ok(values)
+    }
+}
+
+// ---- connection -------------------------------------------------------------
+
+/// A connection to the session bus.
+pub(super) struct Connection {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+    serial: u32,
+    /// Messages read while waiting for a specific reply.
+    queued: VecDeque<Message>,
+    unique_name: String,
+}
+
+/// Outgoing message description.
+pub(super) struct Outgoing<'a> {
+    pub kind: u8,
+    pub destination: Option<&'a str>,
+    pub path: Option<&'a str>,
+    pub interface: Option<&'a str>,
+    pub member: Option<&'a str>,
+    pub reply_serial: Option<u32>,
+    pub error_name: Option<&'a str>,
+    pub no_reply: bool,
+}
+
+fn session_bus_stream() -> crate::Result<UnixStream> {
+    let address = match std::env::var("DBUS_SESSION_BUS_ADDRESS") {
+        Ok(address) => address,
+        Err(_) => {
+            let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+                .map_err(|_| crate::Error::connection("Neither DBUS_SESSION_BUS_ADDRESS nor XDG_RUNTIME_DIR is set"))?;
+            format!("unix:path={runtime_dir}/bus")
+        }
+    };
+    // The address may list several alternatives separated by `;`
+    for candidate in address.split(';') {
+        let Some(params) = candidate.strip_prefix("unix:") else {
+            continue;
+        };
+        for param in params.split(',') {
+            if let Some(path) = param.strip_prefix("path=") {
+                return UnixStream::connect(path).map_err(crate::Error::connection);
+            }
+            if let Some(name) = param.strip_prefix("abstract=") {
+                use std::os::linux::net::SocketAddrExt;
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                return UnixStream::connect_addr(&addr).map_err(crate::Error::connection);
+            }
+        }
+    }
+    Err(format!("Unsupported D-Bus session bus address `{address}`").into())
+}
+
+impl Connection {
+    /// Connect and authenticate to the session bus, then register with `Hello`.
+    pub fn session() -> crate::Result<Self> {
+        let mut stream = session_bus_stream()?;
+        let uid = std::fs::metadata("/proc/self")?.uid();
+        let hex_uid: String = uid.to_string().bytes().map(|b| format!("{b:02x}")).collect();
+        stream.write_all(format!("\0AUTH EXTERNAL {hex_uid}\r\n").as_bytes())?;
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        while !line.ends_with(b"\r\n") {
+            stream.read_exact(&mut byte)?;
+            line.push(byte[0]);
+        }
+        if !line.starts_with(b"OK ") {
+            return Err(format!(
+                "D-Bus authentication failed: {}",
+                String::from_utf8_lossy(&line).trim()
+            )
+            .into());
+        }
+        stream.write_all(b"BEGIN\r\n")?;
+
+        let mut conn = Self {
+            stream,
+            buffer: Vec::new(),
+            serial: 0,
+            queued: VecDeque::new(),
+            unique_name: String::new(),
+        };
+        let reply = conn.call(BUS_NAME, BUS_PATH, BUS_NAME, "Hello", vec![])?;
+        conn.unique_name = reply
+            .first()
+            .and_then(Value::as_str)
+            .ok_or("D-Bus Hello returned no unique name")?
+            .to_string();
+        Ok(conn)
+    }
+
+    /// Our unique bus name (e.g. `:1.42`).
+    #[cfg_attr(not(feature = "kwin"), allow(dead_code))]
+    pub fn unique_name(&self) -> &str {
+        &self.unique_name
+    }
+
+    /// Marshal and send a message, returning its serial.
+    pub fn send(&mut self, message: Outgoing<'_>, body: &[Value]) -> crate::Result<u32> {
+        self.serial += 1;
+        self.stream.write_all(&marshal(self.serial, &message, body))?;
+        Ok(self.serial)
+    }
+
+    /// Call a method and wait for its reply; error replies become [`MethodError`].
+    pub fn call(
+        &mut self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        args: Vec<Value>,
+    ) -> crate::Result<Vec<Value>> {
+        let serial = self.send(
+            Outgoing {
+                kind: METHOD_CALL,
+                destination: Some(destination),
+                path: Some(path),
+                interface: Some(interface),
+                member: Some(member),
+                reply_serial: None,
+                error_name: None,
+                no_reply: false,
+            },
+            &args,
+        )?;
+        loop {
+            let message = self.read_message(None)?.ok_or("D-Bus connection stalled")?;
+            if message.reply_serial != Some(serial) {
+                self.queued.push_back(message);
+                continue;
+            }
+            return match message.kind {
+                METHOD_RETURN => Ok(message.body),
+                ERROR => Err(crate::Error::from(MethodError {
+                    name: message.error_name.unwrap_or_default(),
+                    message: message
+                        .body
+                        .first()
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                })),
+                _ => continue,
+            };
+        }
+    }
+
+    /// Reply to an incoming method call (unless the caller asked for no reply).
+    #[cfg_attr(not(feature = "kwin"), allow(dead_code))]
+    pub fn reply(&mut self, call: &Message, body: &[Value]) -> crate::Result<()> {
+        if call.flags & NO_REPLY_EXPECTED != 0 {
+            return Ok(());
+        }
+        self.send(
+            Outgoing {
+                kind: METHOD_RETURN,
+                destination: call.sender.as_deref(),
+                path: None,
+                interface: None,
+                member: None,
+                reply_serial: Some(call.serial),
+                error_name: None,
+                no_reply: true,
+            },
+            body,
+        )?;
+        Ok(())
+    }
+
+    /// Ask the bus to route matching signals to us.
+    #[cfg_attr(not(feature = "portal"), allow(dead_code))]
+    pub fn add_match(&mut self, rule: &str) -> crate::Result<()> {
+        self.call(BUS_NAME, BUS_PATH, BUS_NAME, "AddMatch", vec![Value::String(rule.into())])?;
+        Ok(())
+    }
+
+    /// The next message not consumed by `call`, waiting until `deadline` (`Ok(None)` on timeout).
+    #[cfg_attr(not(any(feature = "kwin", feature = "portal")), allow(dead_code))]
+    pub fn next_message(&mut self, deadline: Option<Instant>) -> crate::Result<Option<Message>> {
+        if let Some(message) = self.queued.pop_front() {
+            return Ok(Some(message));
+        }
+        self.read_message(deadline)
+    }
+
+    fn read_message(&mut self, deadline: Option<Instant>) -> crate::Result<Option<Message>> {
+        loop {
+            if let Some(message) = self.parse_buffered()? {
+                return Ok(Some(message));
+            }
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) if !left.is_zero() => Some(left),
+                    _ => return Ok(None),
+                },
+                None => None,
+            };
+            self.stream.set_read_timeout(timeout)?;
+            let mut chunk = [0u8; 8192];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err("The D-Bus daemon closed the connection".into()),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return Ok(None);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Decode one complete message from the front of the buffer, if present.
+    fn parse_buffered(&mut self) -> crate::Result<Option<Message>> {
+        if self.buffer.len() < 16 {
+            return Ok(None);
+        }
+        let big_endian = match self.buffer[0] {
+            b'l' => false,
+            b'B' => true,
+            _ => {
+                // Nothing after this can be framed either
+                self.buffer.clear();
+                return Err("Invalid D-Bus message endianness".into());
+            }
+        };
+        let mut header = Reader {
+            data: &self.buffer,
+            pos: 4,
+            big_endian,
+        };
+        let body_len = header.u32()? as usize;
+        header.u32()?;
+        let fields_len = header.u32()? as usize;
+        let total = (16 + fields_len).next_multiple_of(8) + body_len;
+        if self.buffer.len() < total {
+            return Ok(None);
+        }
+        let message = parse_message(&self.buffer[..total], big_endian);
+        // Dropped even if it didn't parse, so one malformed message doesn't
+        // fail every read after it
+        self.buffer.drain(..total);
+        message.map(Some)
+    }
+}
+
+/// A message's bytes on the wire, in little-endian order.
+fn marshal(serial: u32, message: &Outgoing<'_>, body: &[Value]) -> Vec<u8> {
+    let mut body_writer = Writer { buf: Vec::new() };
+    for value in body {
+        body_writer.value(value);
+    }
+    let signature: String = body.iter().map(Value::signature).collect();
+
+    let mut fields = Vec::new();
+    let mut field = |code: u8, value: Value| {
+        fields.push(Value::Struct(vec![Value::Byte(code), Value::Variant(Box::new(value))]));
+    };
+    if let Some(path) = message.path {
+        field(FIELD_PATH, Value::ObjectPath(path.into()));
+    }
+    if let Some(interface) = message.interface {
+        field(FIELD_INTERFACE, Value::String(interface.into()));
+    }
+    if let Some(member) = message.member {
+        field(FIELD_MEMBER, Value::String(member.into()));
+    }
+    if let Some(error_name) = message.error_name {
+        field(FIELD_ERROR_NAME, Value::String(error_name.into()));
+    }
+    if let Some(reply_serial) = message.reply_serial {
+        field(FIELD_REPLY_SERIAL, Value::Uint32(reply_serial));
+    }
+    if let Some(destination) = message.destination {
+        field(FIELD_DESTINATION, Value::String(destination.into()));
+    }
+    if !signature.is_empty() {
+        field(FIELD_SIGNATURE, Value::Signature(signature));
+    }
+
+    let mut writer = Writer { buf: Vec::new() };
+    writer.buf.extend_from_slice(&[
+        b'l',
+        message.kind,
+        if message.no_reply { NO_REPLY_EXPECTED } else { 0 },
+        1,
+    ]);
+    writer.u32(body_writer.buf.len() as u32);
+    writer.u32(serial);
+    writer.value(&Value::Array("(yv)".into(), fields));
+    writer.align(8);
+    writer.buf.extend_from_slice(&body_writer.buf);
+    writer.buf
+}
+
+/// Decode a complete message, `data` being exactly its bytes.
+fn parse_message(data: &[u8], big_endian: bool) -> crate::Result<Message> {
+    let mut header = Reader { data, pos: 8, big_endian };
+    let serial = header.u32()?;
+    let fields_len = header.u32()? as usize;
+    let body_start = (16 + fields_len).next_multiple_of(8);
+
+    let mut message = Message {
+        kind: data[1],
+        flags: data[2],
+        serial,
+        ..Default::default()
+    };
+    let mut signature = String::new();
+    let mut reader = Reader {
+        data: &data[..16 + fields_len],
+        pos: 12,
+        big_endian,
+    };
+    let Value::Array(_, fields) = reader.value(b"a(yv)")? else {
+        unreachable!()
+    };
+    for field in fields {
+        let Value::Struct(parts) = field else { continue };
+        let (Some(Value::Byte(code)), Some(Value::Variant(value))) = (parts.first(), parts.get(1))
+        else {
+            continue;
+        };
+        let text = value.as_str().map(String::from);
+        match *code {
+            FIELD_PATH => message.path = text,
+            FIELD_INTERFACE => message.interface = text,
+            FIELD_MEMBER => message.member = text,
+            FIELD_ERROR_NAME => message.error_name = text,
+            FIELD_REPLY_SERIAL => message.reply_serial = value.as_u32(),
+            FIELD_SENDER => message.sender = text,
+            FIELD_SIGNATURE => signature = text.unwrap_or_default(),
+            _ => {}
+        }
+    }
+
+    let mut body = Reader {
+        data: &data[body_start..],
+        pos: 0,
+        big_endian,
+    };
+    message.body = body.values(&signature)?;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader(data: &[u8]) -> Reader<'_> {
+        Reader { data, pos: 0, big_endian: false }
+    }
+
+    /// `value` marshalled then read back by its own signature.
+    fn round_trip(value: &Value) -> Value {
+        let mut writer = Writer { buf: Vec::new() };
+        writer.value(value);
+        let mut reader = reader(&writer.buf);
+        let read = reader.value(value.signature().as_bytes()).unwrap();
+        assert_eq!(reader.pos, writer.buf.len(), "{value:?} read to its end");
+        read
+    }
+
+    /// A connection with nothing on the other end, for feeding `buffer`.
+    fn connection() -> Connection {
+        Connection {
+            stream: UnixStream::pair().unwrap().0,
+            buffer: Vec::new(),
+            serial: 0,
+            queued: VecDeque::new(),
+            unique_name: String::new(),
+        }
+    }
+
+    fn signal(member: &str) -> Outgoing<'_> {
+        Outgoing {
+            kind: SIGNAL,
+            destination: None,
+            path: Some("/org/example/Object"),
+            interface: Some("org.example.Interface"),
+            member: Some(member),
+            reply_serial: None,
+            error_name: None,
+            no_reply: true,
+        }
+    }
+
+    #[test]
+    fn basic_values_round_trip() {
+        let values = [
+            Value::Byte(0xfe),
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Int16(-2),
+            Value::Uint16(0xfffe),
+            Value::Int32(i32::MIN),
+            Value::Uint32(u32::MAX),
+            Value::Int64(-1 << 40),
+            Value::Uint64(u64::MAX - 1),
+            Value::Double(-0.25),
+            Value::String("héllo".into()),
+            Value::String(String::new()),
+            Value::ObjectPath("/org/freedesktop/portal/desktop".into()),
+            Value::Signature("a{sv}".into()),
+            Value::UnixFd(3),
+        ];
+        for value in values {
+            assert_eq!(round_trip(&value), value);
+        }
+    }
+
+    #[test]
+    fn containers_round_trip_with_their_padding() {
+        let values = [
+            // Elements padded to 8 after a 4-byte length
+            Value::Array("t".into(), vec![Value::Uint64(1), Value::Uint64(2)]),
+            Value::Array("s".into(), Vec::new()),
+            Value::Array("as".into(), vec![Value::Array("s".into(), vec![Value::String("a".into())])]),
+            Value::Struct(vec![Value::Byte(1), Value::Uint64(2), Value::Byte(3), Value::Uint16(4)]),
+            Value::dict(vec![
+                ("handle_token", Value::String("token".into())),
+                ("interactive", Value::Bool(false)),
+                ("size", Value::Struct(vec![Value::Int32(640), Value::Int32(480)])),
+            ]),
+            Value::Variant(Box::new(Value::Variant(Box::new(Value::Byte(7))))),
+        ];
+        for value in values {
+            assert_eq!(round_trip(&value), value);
+        }
+        // A struct after a byte starts on the next multiple of 8
+        let mut writer = Writer { buf: vec![0] };
+        writer.value(&Value::Struct(vec![Value::Byte(9)]));
+        assert_eq!(writer.buf, [0, 0, 0, 0, 0, 0, 0, 0, 9]);
+    }
+
+    #[test]
+    fn reads_big_endian_values() {
+        let mut reader = Reader { data: &[0, 0, 1, 2, 0xff, 0xfe], pos: 0, big_endian: true };
+        assert_eq!(reader.value(b"u").unwrap(), Value::Uint32(0x0102));
+        assert_eq!(reader.value(b"n").unwrap(), Value::Int16(-2));
+    }
+
+    #[test]
+    fn malformed_values_are_errors() {
+        // A variant with an empty signature, then one with two types
+        assert!(reader(&[0, 0]).value(b"v").is_err());
+        assert!(reader(&[2, b'i', b'i', 0, 1, 0, 0, 0, 2, 0, 0, 0]).value(b"v").is_err());
+        assert!(reader(&[]).value(b"").is_err());
+        // Truncated: a string running past the end, an array of empty structs
+        assert!(reader(&[9, 0, 0, 0, b'a', 0]).value(b"s").is_err());
+        assert!(reader(&[8, 0, 0, 0, 0, 0, 0, 0]).value(b"a()").is_err());
+        assert!(reader(&[0]).value(b"a(i").is_err());
+        assert!(reader(&[0]).value(b"z").is_err());
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let body = [Value::String("org.example".into()), Value::dict(vec![("uri", Value::String("file:///".into()))])];
+        let mut conn = connection();
+        conn.buffer = marshal(7, &signal("Response"), &body);
+        // Split across reads: nothing until the whole message is buffered
+        let rest = conn.buffer.split_off(20);
+        assert!(conn.parse_buffered().unwrap().is_none());
+        conn.buffer.extend_from_slice(&rest);
+
+        let message = conn.parse_buffered().unwrap().unwrap();
+        assert_eq!((message.kind, message.flags, message.serial), (SIGNAL, NO_REPLY_EXPECTED, 7));
+        assert_eq!(message.path.as_deref(), Some("/org/example/Object"));
+        assert_eq!(message.interface.as_deref(), Some("org.example.Interface"));
+        assert_eq!(message.member.as_deref(), Some("Response"));
+        assert_eq!(message.body, body);
+        assert!(conn.buffer.is_empty());
+    }
+
+    #[test]
+    fn a_malformed_message_is_dropped() {
+        // A variant body whose signature length is zeroed: `v` with an empty signature
+        let mut bad = marshal(1, &signal("Bad"), &[Value::Variant(Box::new(Value::Byte(1)))]);
+        let body_start = bad.len() - 4;
+        bad[body_start] = 0;
+        let good = marshal(2, &signal("Good"), &[Value::Uint32(5)]);
+        let mut conn = connection();
+        conn.buffer = [bad, good].concat();
+
+        assert!(conn.parse_buffered().is_err());
+        let message = conn.parse_buffered().unwrap().unwrap();
+        assert_eq!((message.serial, message.member.as_deref()), (2, Some("Good")));
+        assert_eq!(message.body, [Value::Uint32(5)]);
+        assert!(conn.parse_buffered().unwrap().is_none());
+    }
+
+    #[test]
+    fn a_message_of_unknown_endianness_clears_the_buffer() {
+        let mut conn = connection();
+        conn.buffer = [b"X".as_slice(), &[0; 31]].concat();
+        assert!(conn.parse_buffered().is_err());
+        assert!(conn.buffer.is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Resolve an app_id to its `.desktop` entry.
+//!
+//! Searches `applications/` under `$XDG_DATA_HOME` and `$XDG_DATA_DIRS`, first
+//! for `<app_id>.desktop`, then for an entry whose `StartupWMClass` or file
+//! name matches (X11 class names and reverse-DNS ids rarely agree on case or
+//! prefix). Icons are looked up in the `hicolor` fallback theme and
+//! `/usr/share/pixmaps`; the user's selected icon theme is not consulted.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The parts of a desktop entry a UI needs to present an application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    /// The `.desktop` file that matched.
+    pub path: PathBuf,
+    /// The untranslated `Name` key.
+    pub name: Option<String>,
+    /// The resolved `Icon` file, if the icon could be found.
+    pub icon: Option<PathBuf>,
+}
+
+const ICON_EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, with the spec's defaults.
+fn data_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")));
+    let system = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    home.into_iter()
+        .chain(system.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from))
+        .collect()
+}
+
+/// Keys of the `[Desktop Entry]` group, ignoring localized variants.
+fn parse(contents: &str) -> Vec<(&str, &str)> {
+    let mut in_main_group = false;
+    let mut keys = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+        } else if in_main_group && let Some((key, value)) = line.split_once('=') {
+            keys.push((key.trim(), value.trim()));
+        }
+    }
+    keys
+}
+
+fn get<'a>(keys: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    keys.iter().find(|(key, _)| *key == name).map(|(_, value)| *value)
+}
+
+/// Whether a desktop file is a loose match for `app_id`: same file name ignoring
+/// case, a reverse-DNS id ending in it (`org.mozilla.firefox` for `firefox`),
+/// or a matching `StartupWMClass`.
+fn matches_loosely(path: &Path, keys: &[(&str, &str)], app_id: &str) -> bool {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    stem.eq_ignore_ascii_case(app_id)
+        || stem
+            .rsplit('.')
+            .next()
+            .is_some_and(|last| last.eq_ignore_ascii_case(app_id))
+        || get(keys, "StartupWMClass").is_some_and(|class| class.eq_ignore_ascii_case(app_id))
+}
+
+/// Find the icon file for an `Icon` value, preferring the largest hicolor size.
+fn resolve_icon(icon: &str, data_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let path = Path::new(icon);
+    if path.is_absolute() {
+        return path.exists().then(|| path.to_path_buf());
+    }
+
+    let mut best: Option<(u32, PathBuf)> = None;
+    for dir in data_dirs {
+        let Ok(sizes) = fs::read_dir(dir.join("icons/hicolor")) else {
+            continue;
+        };
+        for size_dir in sizes.flatten() {
+            let name = size_dir.file_name();
+            let name = name.to_string_lossy();
+            let size = match name.as_ref() {
+                "scalable" => u32::MAX,
+                name => match name.split_once('x').and_then(|(width, _)| width.parse().ok()) {
+                    Some(size) => size,
+                    None => continue,
+                },
+            };
+            if best.as_ref().is_some_and(|(best_size, _)| *best_size >= size) {
+                continue;
+            }
+            if let Some(file) = ICON_EXTENSIONS
+                .iter()
+                .map(|extension| size_dir.path().join("apps").join(format!("{icon}.{extension}")))
+                .find(|file| file.exists())
+            {
+                best = Some((size, file));
+            }
+        }
+    }
+    best.map(|(_, file)| file).or_else(|| {
+        ICON_EXTENSIONS
+            .iter()
+            .map(|extension| Path::new("/usr/share/pixmaps").join(format!("{icon}.{extension}")))
+            .find(|file| file.exists())
+    })
+}
+
+fn entry(path: PathBuf, keys: &[(&str, &str)], data_dirs: &[PathBuf]) -> DesktopEntry {
+    DesktopEntry {
+        name: get(keys, "Name").map(String::from),
+        icon: get(keys, "Icon").and_then(|icon| resolve_icon(icon, data_dirs)),
+        path,
+    }
+}
+
+/// Resolve an app_id (as returned by `get_window_app_id`) to its desktop entry.
+pub fn find_desktop_entry(app_id: &str) -> crate::Result<Option<DesktopEntry>> {
+    let data_dirs = data_dirs();
+    let application_dirs: Vec<PathBuf> = data_dirs.iter().map(|dir| dir.join("applications")).collect();
+
+    for dir in &application_dirs {
+        let path = dir.join(format!("{app_id}.desktop"));
+        if let Ok(contents) = fs::read_to_string(&path) {
+            return Ok(Some(entry(path, &parse(&contents), &data_dirs)));
+        }
+    }
+
+    for dir in &application_dirs {
+        let Ok(files) = fs::read_dir(dir) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let path = file.path();
+            if path.extension().is_none_or(|extension| extension != "desktop") {
+                continue;
+            }
+            let Ok(contents) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let keys = parse(&contents);
+            if matches_loosely(&path, &keys, app_id) {
+                return Ok(Some(entry(path.clone(), &keys, &data_dirs)));
+            }
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,277 @@
+//! `gnome-shell` backend for GNOME on Wayland.
+//!
+//! Mutter exposes no window-management protocol to clients, so this talks to
+//! the "Window Calls" Shell extension (https://github.com/ickyicky/window-calls)
+//! over the session bus. The extension serialises `global.get_window_actors()`
+//! as JSON and uses the Mutter window id, which is used directly as `Window`.
+
+use super::dbus::{self, Value};
+use crate::{json, BackendKind, WindowInfo, WindowState, WindowingBackend};
+
+pub(super) const NAME: &str = "gnome-shell";
+
+const DESTINATION: &str = "org.gnome.Shell";
+const PATH: &str = "/org/gnome/Shell/Extensions/Windows";
+const INTERFACE: &str = "org.gnome.Shell.Extensions.Windows";
+
+const MISSING_EXTENSION: &str = "The GNOME Shell \"Window Calls\" extension is not available. \
+Install it from https://github.com/ickyicky/window-calls (or extensions.gnome.org), \
+enable it with `gnome-extensions enable window-calls@domandoman.xyz`, then log out and back in";
+
+/// Whether this is a GNOME Wayland session. The extension itself is checked per call
+/// so that a missing extension surfaces as an actionable error instead of a silent fallback.
+pub(super) fn is_available() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        && std::env::var("XDG_CURRENT_DESKTOP")
+            .is_ok_and(|desktop| desktop.split(':').any(|name| name.eq_ignore_ascii_case("GNOME")))
+}
+
+fn call(method: &str, args: Vec<Value>) -> crate::Result<Vec<Value>> {
+    let mut conn = dbus::Connection::session()?;
+    conn.call(DESTINATION, PATH, INTERFACE, method, args)
+        .map_err(|e| match dbus::MethodError::of(&e) {
+            Some(error)
+                if matches!(
+                    error.name.as_str(),
+                    "org.freedesktop.DBus.Error.ServiceUnknown"
+                        | "org.freedesktop.DBus.Error.UnknownObject"
+                        | "org.freedesktop.DBus.Error.UnknownInterface"
+                        | "org.freedesktop.DBus.Error.UnknownMethod"
+                ) =>
+            {
+                MISSING_EXTENSION.into()
+            }
+            _ => e,
+        })
+}
+
+/// Call a method that returns a JSON document as its single string.
+fn call_json(method: &str, args: Vec<Value>) -> crate::Result<json::Value> {
+    let reply = call(method, args)?;
+    let text = reply
+        .first()
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{INTERFACE}.{method} returned no string"))?;
+    json::parse(text)
+}
+
+fn windows() -> crate::Result<Vec<json::Value>> {
+    Ok(call_json("List", vec![])?.as_array().to_vec())
+}
+
+fn window_id(window: &json::Value) -> Option<crate::Window> {
+    window.get("id").as_i64().and_then(|id| u32::try_from(id).ok()).map(crate::Window::from)
+}
+
+fn window_pid(window: &json::Value) -> Option<u32> {
+    window.get("pid").as_i64().and_then(|pid| u32::try_from(pid).ok())
+}
+
+pub fn get_window_info(window: crate::Window) -> crate::Result<WindowInfo> {
+    let rect = call_json("GetFrameRect", vec![Value::Uint32(window.raw())])?;
+    let field = |name| rect.get(name).as_i64().ok_or("Window Calls returned no frame rect");
+    Ok(WindowInfo {
+        pos: (field("x")? as i32, field("y")? as i32),
+        size: (field("width")? as u32, field("height")? as u32),
+    })
+}
+
+pub fn list_windows() -> crate::Result<Vec<crate::Window>> {
+    Ok(windows()?.iter().filter_map(window_id).collect())
+}
+
+pub fn find_windows_by_pid(target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+    Ok(windows()?
+        .iter()
+        .filter(|window| window_pid(window) == Some(target_pid))
+        .filter_map(window_id)
+        .collect())
+}
+
+pub fn find_window_by_pid(target_pid: u32) -> crate::Result<Option<crate::Window>> {
+    Ok(find_windows_by_pid(target_pid)?.into_iter().next())
+}
+
+fn focused() -> crate::Result<Option<json::Value>> {
+    Ok(windows()?
+        .into_iter()
+        .find(|window| window.get("focus").as_bool() == Some(true)))
+}
+
+pub fn get_active_window() -> crate::Result<Option<crate::Window>> {
+    Ok(focused()?.as_ref().and_then(window_id))
+}
+
+pub fn get_active_window_pid() -> crate::Result<Option<u32>> {
+    Ok(focused()?.as_ref().and_then(window_pid))
+}
+
+pub fn get_window_pid(window: crate::Window) -> crate::Result<Option<u32>> {
+    let listed = windows()?
+        .into_iter()
+        .find(|w| window_id(w) == Some(window))
+        .ok_or_else(|| crate::Error::WindowNotFound(window))?;
+    Ok(window_pid(&listed))
+}
+
+pub fn get_window_title(window: crate::Window) -> crate::Result<Option<String>> {
+    let reply = call("GetTitle", vec![Value::Uint32(window.raw())])?;
+    Ok(reply.first().and_then(Value::as_str).map(String::from))
+}
+
+/// The window's `WM_CLASS` (for native Wayland clients, Mutter reports the app_id here).
+pub fn get_window_app_id(window: crate::Window) -> crate::Result<Option<String>> {
+    let details = call_json("Details", vec![Value::Uint32(window.raw())])?;
+    Ok(details.get("wm_class").as_str().map(String::from))
+}
+
+pub fn focus_window(window: crate::Window) -> crate::Result<()> {
+    call("Activate", vec![Value::Uint32(window.raw())]).map(drop)
+}
+
+pub fn move_window(window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+    call("Move", vec![Value::Uint32(window.raw()), Value::Int32(x), Value::Int32(y)]).map(drop)
+}
+
+pub fn resize_window(window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+    call(
+        "Resize",
+        vec![Value::Uint32(window.raw()), Value::Uint32(width), Value::Uint32(height)],
+    )
+    .map(drop)
+}
+
+pub fn minimize_window(window: crate::Window) -> crate::Result<()> {
+    call("Minimize", vec![Value::Uint32(window.raw())]).map(drop)
+}
+
+pub fn maximize_window(window: crate::Window) -> crate::Result<()> {
+    call("Maximize", vec![Value::Uint32(window.raw())]).map(drop)
+}
+
+pub fn close_window(window: crate::Window) -> crate::Result<()> {
+    call("Close", vec![Value::Uint32(window.raw())]).map(drop)
+}
+
+pub fn move_window_to_workspace(window: crate::Window, workspace: u32) -> crate::Result<()> {
+    call(
+        "MoveToWorkspace",
+        vec![Value::Uint32(window.raw()), Value::Uint32(workspace)],
+    )
+    .map(drop)
+}
+
+/// Mutter's `get_maximized()` is a flag set (1 = horizontal, 2 = vertical);
+/// older extension versions report a boolean instead.
+fn is_maximized(details: &json::Value) -> Option<bool> {
+    let maximized = details.get("maximized");
+    maximized
+        .as_bool()
+        .or_else(|| maximized.as_i64().map(|flags| flags & 3 == 3))
+}
+
+pub fn window_has_state(window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    let Some(listed) = windows()?.into_iter().find(|w| window_id(w) == Some(window)) else {
+        return Ok(state == WindowState::Closed);
+    };
+    let details = || call_json("Details", vec![Value::Uint32(window.raw())]);
+    let missing = |field: &str| format!("Window Calls does not report `{field}` for windows");
+    Ok(match state {
+        WindowState::Closed => false,
+        WindowState::Focused => listed.get("focus").as_bool() == Some(true),
+        WindowState::Minimized | WindowState::Visible => {
+            let minimized = details()?
+                .get("minimized")
+                .as_bool()
+                .ok_or_else(|| missing("minimized"))?;
+            minimized == (state == WindowState::Minimized)
+        }
+        WindowState::Maximized => is_maximized(&details()?).ok_or_else(|| missing("maximized"))?,
+        WindowState::Fullscreen => details()?
+            .get("fullscreen")
+            .as_bool()
+            .ok_or_else(|| missing("fullscreen"))?,
+    })
+}
+
+/// The `gnome-shell` backend, calling the Window Calls extension on the session bus
+#[derive(Debug, Default)]
+pub(super) struct GnomeShellBackend;
+
+impl WindowingBackend for GnomeShellBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::GnomeShell
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        list_windows()
+    }
+
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        get_window_info(window)
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        find_window_by_pid(target_pid)
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        find_windows_by_pid(target_pid)
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        get_active_window()
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        get_active_window_pid()
+    }
+
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        get_window_pid(window)
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_title(window)
+    }
+
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_app_id(window)
+    }
+
+    fn minimize_window(&self, window: crate::Window) -> crate::Result<()> {
+        minimize_window(window)
+    }
+
+    fn maximize_window(&self, window: crate::Window) -> crate::Result<()> {
+        maximize_window(window)
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        close_window(window)
+    }
+
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        move_window(window, x, y)
+    }
+
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        resize_window(window, width, height)
+    }
+
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn activate_window(&self, window: crate::Window, _update_user_time: bool) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn move_window_to_workspace(&self, window: crate::Window, workspace: u32) -> crate::Result<()> {
+        move_window_to_workspace(window, workspace)
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        window_has_state(window, state)
+    }
+}
@@ -0,0 +1,492 @@
+//! `hyprland` backend speaking Hyprland's IPC sockets.
+//!
+//! Requests go to `.socket.sock` (one request per connection, `j/` prefix for
+//! JSON replies); events are read line by line from `.socket2.sock`.
+//! Hyprland identifies windows by their address (a 64-bit pointer), which
+//! is mapped onto the crate's 32-bit `Window` through a process-wide table.
+
+use crate::{
+    geometry::{monitor_of, monitors_of, to_physical},
+    json, BackendKind, MonitorInfo, PhysicalWindowInfo, WindowEvent, WindowEvents, WindowInfo, WindowState,
+    WindowingBackend,
+};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+pub(super) const NAME: &str = "hyprland";
+
+/// Address <-> handle table. Handles are never reused within a process.
+#[derive(Default)]
+struct Handles {
+    by_address: HashMap<u64, crate::Window>,
+    addresses: Vec<u64>,
+}
+
+static HANDLES: Mutex<Option<Handles>> = Mutex::new(None);
+
+fn handle_for(address: u64) -> crate::Window {
+    let mut guard = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+    let handles = guard.get_or_insert_with(Handles::default);
+    *handles.by_address.entry(address).or_insert_with(|| {
+        handles.addresses.push(address);
+        crate::Window::from(handles.addresses.len() as u32)
+    })
+}
+
+fn address_of(window: crate::Window) -> crate::Result<u64> {
+    let guard = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+    guard
+        .as_ref()
+        .and_then(|handles| handles.addresses.get((window.raw() as usize).checked_sub(1)?))
+        .copied()
+        .ok_or(crate::Error::WindowNotFound(window))
+}
+
+fn parse_address(value: &str) -> Option<u64> {
+    u64::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+fn socket_dir() -> crate::Result<PathBuf> {
+    let signature = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")
+        .ok_or_else(|| crate::Error::connection("HYPRLAND_INSTANCE_SIGNATURE is not set"))?;
+    // Hyprland >= 0.40 uses $XDG_RUNTIME_DIR/hypr, older releases /tmp/hypr
+    if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        let dir = PathBuf::from(runtime_dir).join("hypr").join(&signature);
+        if dir.exists() {
+            return Ok(dir);
+        }
+    }
+    Ok(PathBuf::from("/tmp/hypr").join(signature))
+}
+
+/// Send one request and return the complete reply.
+fn request(command: &str) -> crate::Result<String> {
+    let path = socket_dir()?.join(".socket.sock");
+    let mut stream = UnixStream::connect(&path).map_err(|e| {
+        crate::Error::connection(format!("Failed to connect to Hyprland socket {}: {e}", path.display()))
+    })?;
+    stream.write_all(command.as_bytes())?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
+
+fn query(command: &str) -> crate::Result<json::Value> {
+    json::parse(&request(&format!("j/{command}"))?)
+}
+
+fn dispatch(args: &str) -> crate::Result<()> {
+    let reply = request(&format!("dispatch {args}"))?;
+    if reply.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(format!("Hyprland rejected `dispatch {args}`: {}", reply.trim()).into())
+    }
+}
+
+/// Whether a Hyprland instance is reachable from this process.
+pub(super) fn is_available() -> bool {
+    std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() && request("j/version").is_ok()
+}
+
+/// Mapped client windows as reported by `j/clients`.
+fn clients() -> crate::Result<Vec<json::Value>> {
+    Ok(query("clients")?
+        .as_array()
+        .iter()
+        .filter(|client| client.get("mapped").as_bool() != Some(false))
+        .cloned()
+        .collect())
+}
+
+fn client_handle(client: &json::Value) -> Option<crate::Window> {
+    client.get("address").as_str().and_then(parse_address).map(handle_for)
+}
+
+fn find_client(window: crate::Window) -> crate::Result<json::Value> {
+    let address = address_of(window)?;
+    clients()?
+        .into_iter()
+        .find(|client| client.get("address").as_str().and_then(parse_address) == Some(address))
+        .ok_or_else(|| crate::Error::WindowNotFound(window))
+}
+
+fn selector(window: crate::Window) -> crate::Result<String> {
+    Ok(format!("address:0x{:x}", address_of(window)?))
+}
+
+/// Hyprland workspace ids are 1-based; special (scratchpad) workspaces are negative.
+fn workspace_index(id: i64) -> Option<u32> {
+    u32::try_from(id).ok()?.checked_sub(1)
+}
+
+fn pair(value: &json::Value) -> Option<(i64, i64)> {
+    match value.as_array() {
+        [a, b] => Some((a.as_i64()?, b.as_i64()?)),
+        _ => None,
+    }
+}
+
+pub fn get_window_info(window: crate::Window) -> crate::Result<WindowInfo> {
+    let client = find_client(window)?;
+    let (x, y) = pair(client.get("at")).ok_or("Hyprland client has no position")?;
+    let (width, height) = pair(client.get("size")).ok_or("Hyprland client has no size")?;
+    Ok(WindowInfo {
+        pos: (x as i32, y as i32),
+        size: (width as u32, height as u32),
+    })
+}
+
+/// Each monitor's rectangle in layout coordinates (its pixel size divided by its scale).
+pub fn monitors() -> crate::Result<Vec<MonitorInfo>> {
+    Ok(monitors_with_reserved()?.into_iter().map(|(monitor, _)| monitor).collect())
+}
+
+/// Every monitor with the space layer-shell bars and docks reserve along its
+/// edges (`reserved`: left, top, right, bottom, in layout units)
+fn monitors_with_reserved() -> crate::Result<Vec<(MonitorInfo, [u32; 4])>> {
+    let monitors = query("monitors")?;
+    Ok(monitors
+        .as_array()
+        .iter()
+        .filter_map(|monitor| {
+            let (x, y) = (monitor.get("x").as_i64()?, monitor.get("y").as_i64()?);
+            let (width, height) = (monitor.get("width").as_i64()?, monitor.get("height").as_i64()?);
+            let scale = match monitor.get("scale") {
+                json::Value::Number(scale) if *scale > 0.0 => *scale,
+                _ => 1.0,
+            };
+            let rect = WindowInfo {
+                pos: (x as i32, y as i32),
+                size: ((width as f64 / scale).round() as u32, (height as f64 / scale).round() as u32),
+            };
+            let name = monitor.get("name").as_str()?.to_string();
+            let mut reserved = [0u32; 4];
+            for (side, value) in reserved.iter_mut().zip(monitor.get("reserved").as_array()) {
+                *side = value.as_i64().unwrap_or(0).clamp(0, u32::MAX as i64) as u32;
+            }
+            Some((MonitorInfo { name, bounds: rect, scale_factor: scale }, reserved))
+        })
+        .collect())
+}
+
+/// The work area of the monitor holding most of the window: its bounds less
+/// the reserved space, `None` if the window is on no monitor
+pub fn get_window_work_area(window: crate::Window) -> crate::Result<Option<WindowInfo>> {
+    let info = get_window_info(window)?;
+    // As monitor_of: the most overlap, the first monitor on a tie
+    let Some((_, bounds, [left, top, right, bottom])) = monitors_with_reserved()?
+        .into_iter()
+        .rev()
+        .filter_map(|(monitor, reserved)| Some((info.intersection(&monitor.bounds)?.area(), monitor.bounds, reserved)))
+        .max_by_key(|&(area, ..)| area)
+    else {
+        return Ok(None);
+    };
+    Ok(Some(WindowInfo {
+        pos: (bounds.pos.0.saturating_add_unsigned(left), bounds.pos.1.saturating_add_unsigned(top)),
+        size: (
+            bounds.size.0.saturating_sub(left.saturating_add(right)),
+            bounds.size.1.saturating_sub(top.saturating_add(bottom)),
+        ),
+    }))
+}
+
+/// Union of all monitors in layout coordinates (monitor pixel sizes divided by their scale).
+pub fn get_virtual_screen() -> crate::Result<WindowInfo> {
+    let mut bounds: Option<(i64, i64, i64, i64)> = None;
+    for MonitorInfo { bounds: rect, .. } in monitors()? {
+        let (x, y) = (rect.pos.0 as i64, rect.pos.1 as i64);
+        let (right, bottom) = (x + rect.size.0 as i64, y + rect.size.1 as i64);
+        bounds = Some(match bounds {
+            Some((l, t, r, b)) => (l.min(x), t.min(y), r.max(right), b.max(bottom)),
+            None => (x, y, right, bottom),
+        });
+    }
+    let (left, top, right, bottom) = bounds.ok_or("Hyprland reported no monitors")?;
+    Ok(WindowInfo {
+        pos: (left as i32, top as i32),
+        size: ((right - left) as u32, (bottom - top) as u32),
+    })
+}
+
+pub fn list_windows() -> crate::Result<Vec<crate::Window>> {
+    Ok(clients()?.iter().filter_map(client_handle).collect())
+}
+
+pub fn find_windows_by_pid(target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+    Ok(clients()?
+        .iter()
+        .filter(|client| client.get("pid").as_i64() == Some(target_pid as i64))
+        .filter_map(client_handle)
+        .collect())
+}
+
+pub fn find_window_by_pid(target_pid: u32) -> crate::Result<Option<crate::Window>> {
+    Ok(find_windows_by_pid(target_pid)?.into_iter().next())
+}
+
+pub fn get_active_window() -> crate::Result<Option<crate::Window>> {
+    // An empty object means nothing is focused
+    Ok(client_handle(&query("activewindow")?))
+}
+
+pub fn get_active_window_pid() -> crate::Result<Option<u32>> {
+    Ok(query("activewindow")?
+        .get("pid")
+        .as_i64()
+        .and_then(|pid| u32::try_from(pid).ok()))
+}
+
+pub fn get_window_pid(window: crate::Window) -> crate::Result<Option<u32>> {
+    Ok(find_client(window)?
+        .get("pid")
+        .as_i64()
+        .and_then(|pid| u32::try_from(pid).ok()))
+}
+
+pub fn get_window_title(window: crate::Window) -> crate::Result<Option<String>> {
+    Ok(find_client(window)?.get("title").as_str().map(String::from))
+}
+
+/// Hyprland reports the Wayland app_id as the client's class.
+pub fn get_window_app_id(window: crate::Window) -> crate::Result<Option<String>> {
+    Ok(find_client(window)?.get("class").as_str().map(String::from))
+}
+
+/// Moves a window in global layout coordinates. Only floating windows can be positioned freely.
+pub fn move_window(window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+    dispatch(&format!("movewindowpixel exact {x} {y},{}", selector(window)?))
+}
+
+pub fn resize_window(window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+    dispatch(&format!("resizewindowpixel exact {width} {height},{}", selector(window)?))
+}
+
+pub fn focus_window(window: crate::Window) -> crate::Result<()> {
+    dispatch(&format!("focuswindow {}", selector(window)?))
+}
+
+pub fn is_sticky(window: crate::Window) -> crate::Result<bool> {
+    Ok(find_client(window)?.get("pinned").as_bool() == Some(true))
+}
+
+/// Hyprland only pins floating windows; `pin` toggles, so check the current state first
+pub fn set_sticky(window: crate::Window, sticky: bool) -> crate::Result<()> {
+    if is_sticky(window)? == sticky {
+        return Ok(());
+    }
+    dispatch(&format!("pin {}", selector(window)?))
+}
+
+pub fn close_window(window: crate::Window) -> crate::Result<()> {
+    dispatch(&format!("closewindow {}", selector(window)?))
+}
+
+pub fn get_window_workspace(window: crate::Window) -> crate::Result<Option<u32>> {
+    Ok(find_client(window)?
+        .get("workspace")
+        .get("id")
+        .as_i64()
+        .and_then(workspace_index))
+}
+
+pub fn move_window_to_workspace(window: crate::Window, workspace: u32) -> crate::Result<()> {
+    dispatch(&format!("movetoworkspacesilent {},{}", workspace + 1, selector(window)?))
+}
+
+pub fn get_current_workspace() -> crate::Result<Option<u32>> {
+    Ok(query("activeworkspace")?.get("id").as_i64().and_then(workspace_index))
+}
+
+pub fn switch_workspace(workspace: u32) -> crate::Result<()> {
+    dispatch(&format!("workspace {}", workspace + 1))
+}
+
+/// Hyprland has no minimize; "hidden" covers windows in a collapsed group.
+pub fn window_has_state(window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    let address = address_of(window)?;
+    let Some(client) = clients()?
+        .into_iter()
+        .find(|client| client.get("address").as_str().and_then(parse_address) == Some(address))
+    else {
+        return Ok(state == WindowState::Closed);
+    };
+    // Newer releases report `fullscreen` as 0 (none), 1 (maximized), 2 (fullscreen);
+    // older ones as a bool qualified by `fullscreenMode` (0 fullscreen, 1 maximized)
+    let fullscreen_mode = match client.get("fullscreen") {
+        json::Value::Bool(true) => match client.get("fullscreenMode").as_i64() {
+            Some(1) => 1,
+            _ => 2,
+        },
+        value => value.as_i64().unwrap_or(0),
+    };
+    Ok(match state {
+        WindowState::Closed | WindowState::Minimized => false,
+        WindowState::Visible => client.get("hidden").as_bool() != Some(true),
+        WindowState::Maximized => fullscreen_mode == 1,
+        WindowState::Fullscreen => fullscreen_mode == 2,
+        WindowState::Focused => get_active_window()? == Some(window),
+    })
+}
+
+/// Translate one `.socket2.sock` line (`event>>data`) into a crate event.
+fn parse_event(line: &str) -> Option<WindowEvent> {
+    let (name, data) = line.split_once(">>")?;
+    let window = || data.split(',').next().and_then(parse_address).map(handle_for);
+    match name {
+        "openwindow" => Some(WindowEvent::Opened(window()?)),
+        "closewindow" => Some(WindowEvent::Closed(window()?)),
+        // An empty address means focus went to nothing
+        "activewindowv2" => Some(WindowEvent::Focused(window())),
+        "windowtitle" | "windowtitlev2" => Some(WindowEvent::TitleChanged(window()?)),
+        "movewindowv2" => {
+            let workspace = data.split(',').nth(1).and_then(|id| id.parse().ok());
+            Some(WindowEvent::WorkspaceChanged(
+                window()?,
+                workspace.and_then(workspace_index),
+            ))
+        }
+        _ => None,
+    }
+}
+
+pub fn subscribe_window_events() -> crate::Result<WindowEvents> {
+    let path = socket_dir()?.join(".socket2.sock");
+    let stream = UnixStream::connect(&path)
+        .map_err(|e| {
+        crate::Error::connection(format!("Failed to connect to Hyprland event socket {}: {e}", path.display()))
+    })?;
+    let lines = BufReader::new(stream).lines();
+    Ok(WindowEvents::new(lines.filter_map(|line| match line {
+        Ok(line) => parse_event(&line).map(Ok),
+        Err(e) => Some(Err(e.into())),
+    })))
+}
+
+/// The `hyprland` backend; every call opens its own IPC socket connection
+#[derive(Debug, Default)]
+pub(super) struct HyprlandBackend;
+
+impl WindowingBackend for HyprlandBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Hyprland
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        list_windows()
+    }
+
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        get_window_info(window)
+    }
+
+    fn get_window_bounds(&self, window: crate::Window) -> crate::Result<PhysicalWindowInfo> {
+        Ok(to_physical(get_window_info(window)?, &monitors()?))
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        find_window_by_pid(target_pid)
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        find_windows_by_pid(target_pid)
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        get_active_window()
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        get_active_window_pid()
+    }
+
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        get_window_pid(window)
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_title(window)
+    }
+
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_app_id(window)
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        close_window(window)
+    }
+
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        move_window(window, x, y)
+    }
+
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        resize_window(window, width, height)
+    }
+
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn activate_window(&self, window: crate::Window, _update_user_time: bool) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn get_window_workspace(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        get_window_workspace(window)
+    }
+
+    fn move_window_to_workspace(&self, window: crate::Window, workspace: u32) -> crate::Result<()> {
+        move_window_to_workspace(window, workspace)
+    }
+
+    fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+        get_current_workspace()
+    }
+
+    fn switch_workspace(&self, workspace: u32) -> crate::Result<()> {
+        switch_workspace(workspace)
+    }
+
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        subscribe_window_events()
+    }
+
+    fn list_monitors(&self) -> crate::Result<Vec<MonitorInfo>> {
+        monitors()
+    }
+
+    fn get_window_monitor(&self, window: crate::Window) -> crate::Result<Option<MonitorInfo>> {
+        Ok(monitor_of(&get_window_info(window)?, &monitors()?).cloned())
+    }
+
+    fn get_window_monitors(&self, window: crate::Window) -> crate::Result<Vec<MonitorInfo>> {
+        Ok(monitors_of(&get_window_info(window)?, &monitors()?))
+    }
+
+    fn get_window_work_area(&self, window: crate::Window) -> crate::Result<Option<WindowInfo>> {
+        get_window_work_area(window)
+    }
+
+    fn get_virtual_screen(&self) -> crate::Result<WindowInfo> {
+        get_virtual_screen()
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        window_has_state(window, state)
+    }
+
+    fn is_sticky(&self, window: crate::Window) -> crate::Result<bool> {
+        is_sticky(window)
+    }
+
+    fn set_sticky(&self, window: crate::Window, sticky: bool) -> crate::Result<()> {
+        set_sticky(window, sticky)
+    }
+}
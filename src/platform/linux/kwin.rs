@@ -0,0 +1,472 @@
+//! `kwin` backend for KDE Plasma on Wayland.
+//!
+//! KWin has no client protocol for other applications' windows, but it runs
+//! JavaScript loaded through `org.kde.kwin.Scripting`. Every query loads a
+//! short-lived script that does the work and calls back into our D-Bus
+//! connection with a JSON result; the script is unloaded again afterwards.
+//! KWin identifies windows by a UUID (`internalId`), which is mapped onto
+//! small integer handles for the crate's `Window`.
+
+use super::dbus::{self, Value};
+use crate::{json, BackendKind, WindowInfo, WindowState, WindowingBackend};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+pub(super) const NAME: &str = "kwin";
+
+const SERVICE: &str = "org.kde.KWin";
+const SCRIPTING_PATH: &str = "/Scripting";
+const SCRIPTING_INTERFACE: &str = "org.kde.kwin.Scripting";
+const SCRIPT_INTERFACE: &str = "org.kde.kwin.Script";
+
+/// Where loaded scripts call us back.
+const CALLBACK_PATH: &str = "/windowing";
+const CALLBACK_INTERFACE: &str = "org.windowing.KWinScript";
+
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Helpers shared by every script; works with the Plasma 5 and Plasma 6 APIs.
+const PRELUDE: &str = r#"
+const windows = (workspace.windowList ? workspace.windowList() : workspace.clientList())
+    .filter(w => w.normalWindow);
+const active = workspace.activeWindow !== undefined ? workspace.activeWindow : workspace.activeClient;
+function describe(w) {
+    const g = w.frameGeometry || w.geometry;
+    return {
+        id: String(w.internalId),
+        pid: w.pid,
+        caption: w.caption,
+        appId: String(w.desktopFileName || w.resourceClass || ""),
+        x: g.x, y: g.y, width: g.width, height: g.height,
+        minimized: w.minimized,
+        fullScreen: w.fullScreen,
+        keepAbove: w.keepAbove,
+        keepBelow: w.keepBelow,
+        maximized: w.maximizeMode === undefined ? null : w.maximizeMode === 3,
+        active: w === active,
+    };
+}
+function find(id) {
+    for (const w of windows) {
+        if (String(w.internalId) === id) return w;
+    }
+    throw new Error("no window " + id);
+}
+"#;
+
+#[derive(Default)]
+struct Handles {
+    by_id: HashMap<String, crate::Window>,
+    ids: Vec<String>,
+}
+
+static HANDLES: Mutex<Option<Handles>> = Mutex::new(None);
+
+fn handle_for(id: &str) -> crate::Window {
+    let mut guard = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+    let handles = guard.get_or_insert_with(Handles::default);
+    if let Some(&window) = handles.by_id.get(id) {
+        return window;
+    }
+    handles.ids.push(id.to_string());
+    let window = crate::Window::from(handles.ids.len() as u32);
+    handles.by_id.insert(id.to_string(), window);
+    window
+}
+
+fn id_of(window: crate::Window) -> crate::Result<String> {
+    let guard = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+    guard
+        .as_ref()
+        .and_then(|handles| handles.ids.get((window.raw() as usize).checked_sub(1)?))
+        .cloned()
+        .ok_or(crate::Error::WindowNotFound(window))
+}
+
+/// Whether this is a Plasma Wayland session.
+pub(super) fn is_available() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        && std::env::var("XDG_CURRENT_DESKTOP")
+            .is_ok_and(|desktop| desktop.split(':').any(|name| name.eq_ignore_ascii_case("KDE")))
+}
+
+/// A script registered with KWin; unloaded and deleted on drop, whatever happened in between.
+struct LoadedScript<'a> {
+    conn: &'a mut dbus::Connection,
+    plugin: String,
+    path: PathBuf,
+}
+
+impl Drop for LoadedScript<'_> {
+    fn drop(&mut self) {
+        let _ = self.conn.call(
+            SERVICE,
+            SCRIPTING_PATH,
+            SCRIPTING_INTERFACE,
+            "unloadScript",
+            vec![Value::String(self.plugin.clone())],
+        );
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl LoadedScript<'_> {
+    /// Start the script; Plasma 6 exports it under `/Scripting/ScriptN`, Plasma 5 under `/N`.
+    fn run(&mut self, id: i32) -> crate::Result<()> {
+        let plasma6 = format!("{SCRIPTING_PATH}/Script{id}");
+        match self.conn.call(SERVICE, &plasma6, SCRIPT_INTERFACE, "run", vec![]) {
+            Ok(_) => Ok(()),
+            Err(e) if dbus::MethodError::of(&e).is_some() => {
+                self.conn.call(SERVICE, &format!("/{id}"), SCRIPT_INTERFACE, "run", vec![])?;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Wait for the script's `result`/`error` callback.
+    fn wait(&mut self) -> crate::Result<json::Value> {
+        let deadline = Instant::now() + SCRIPT_TIMEOUT;
+        loop {
+            let message = self
+                .conn
+                .next_message(Some(deadline))?
+                .ok_or("Timed out waiting for the KWin script to report back")?;
+            if message.kind != dbus::METHOD_CALL
+                || message.path.as_deref() != Some(CALLBACK_PATH)
+                || message.interface.as_deref() != Some(CALLBACK_INTERFACE)
+            {
+                continue;
+            }
+            self.conn.reply(&message, &[])?;
+            let payload = message.body.first().and_then(Value::as_str).unwrap_or_default();
+            return match message.member.as_deref() {
+                Some("result") => json::parse(payload),
+                _ => Err(format!("KWin script failed: {payload}").into()),
+            };
+        }
+    }
+}
+
+/// Run `body` (a JavaScript function body whose return value is the result)
+/// inside KWin and return the JSON-decoded result.
+fn run_script(body: &str) -> crate::Result<json::Value> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut conn = dbus::Connection::session()?;
+    let plugin = format!(
+        "windowing-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let path = std::env::temp_dir().join(format!("{plugin}.js"));
+    let callback = |member: &str, value: &str| {
+        format!(
+            "callDBus(\"{}\", \"{CALLBACK_PATH}\", \"{CALLBACK_INTERFACE}\", \"{member}\", {value});",
+            conn.unique_name()
+        )
+    };
+    let source = format!(
+        "{PRELUDE}\ntry {{\n    const result = (function () {{ {body} }})();\n    {}\n}} catch (e) {{\n    {}\n}}\n",
+        callback("result", "JSON.stringify(result === undefined ? null : result)"),
+        callback("error", "String(e)"),
+    );
+    std::fs::write(&path, source)?;
+
+    let id = match conn.call(
+        SERVICE,
+        SCRIPTING_PATH,
+        SCRIPTING_INTERFACE,
+        "loadScript",
+        vec![
+            Value::String(path.to_string_lossy().into_owned()),
+            Value::String(plugin.clone()),
+        ],
+    ) {
+        Ok(reply) => reply,
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            return Err(format!("Failed to load a KWin script (is KWin running?): {e}").into());
+        }
+    };
+    let mut script = LoadedScript {
+        conn: &mut conn,
+        plugin,
+        path,
+    };
+    let id = match id.first() {
+        Some(Value::Int32(id)) if *id >= 0 => *id,
+        _ => return Err("KWin refused to load the script".into()),
+    };
+    script.run(id)?;
+    script.wait()
+}
+
+fn windows() -> crate::Result<Vec<json::Value>> {
+    Ok(run_script("return windows.map(describe);")?.as_array().to_vec())
+}
+
+fn window_handle(window: &json::Value) -> Option<crate::Window> {
+    window.get("id").as_str().map(handle_for)
+}
+
+fn window_pid(window: &json::Value) -> Option<u32> {
+    window.get("pid").as_i64().and_then(|pid| u32::try_from(pid).ok())
+}
+
+/// Describe one window, or `None` if it no longer exists.
+fn find(window: crate::Window) -> crate::Result<Option<json::Value>> {
+    let id = id_of(window)?;
+    Ok(windows()?
+        .into_iter()
+        .find(|w| w.get("id").as_str() == Some(id.as_str())))
+}
+
+fn describe(window: crate::Window) -> crate::Result<json::Value> {
+    find(window)?.ok_or_else(|| crate::Error::WindowNotFound(window))
+}
+
+/// Run `statement` with `w` bound to the window.
+fn with_window(window: crate::Window, statement: &str) -> crate::Result<()> {
+    let id = id_of(window)?;
+    run_script(&format!("const w = find(\"{id}\"); {statement}")).map(drop)
+}
+
+pub fn get_window_info(window: crate::Window) -> crate::Result<WindowInfo> {
+    let described = describe(window)?;
+    let field = |name| described.get(name).as_i64().ok_or("KWin reported no geometry");
+    Ok(WindowInfo {
+        pos: (field("x")? as i32, field("y")? as i32),
+        size: (field("width")? as u32, field("height")? as u32),
+    })
+}
+
+pub fn list_windows() -> crate::Result<Vec<crate::Window>> {
+    Ok(windows()?.iter().filter_map(window_handle).collect())
+}
+
+pub fn find_windows_by_pid(target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+    Ok(windows()?
+        .iter()
+        .filter(|window| window_pid(window) == Some(target_pid))
+        .filter_map(window_handle)
+        .collect())
+}
+
+pub fn find_window_by_pid(target_pid: u32) -> crate::Result<Option<crate::Window>> {
+    Ok(find_windows_by_pid(target_pid)?.into_iter().next())
+}
+
+fn focused() -> crate::Result<Option<json::Value>> {
+    Ok(windows()?
+        .into_iter()
+        .find(|window| window.get("active").as_bool() == Some(true)))
+}
+
+pub fn get_active_window() -> crate::Result<Option<crate::Window>> {
+    Ok(focused()?.as_ref().and_then(window_handle))
+}
+
+pub fn get_active_window_pid() -> crate::Result<Option<u32>> {
+    Ok(focused()?.as_ref().and_then(window_pid))
+}
+
+pub fn get_window_pid(window: crate::Window) -> crate::Result<Option<u32>> {
+    Ok(window_pid(&describe(window)?))
+}
+
+pub fn get_window_title(window: crate::Window) -> crate::Result<Option<String>> {
+    Ok(describe(window)?.get("caption").as_str().map(String::from))
+}
+
+/// The desktop file name, falling back to the window class.
+pub fn get_window_app_id(window: crate::Window) -> crate::Result<Option<String>> {
+    Ok(describe(window)?
+        .get("appId")
+        .as_str()
+        .filter(|app_id| !app_id.is_empty())
+        .map(String::from))
+}
+
+pub fn focus_window(window: crate::Window) -> crate::Result<()> {
+    with_window(
+        window,
+        "if (workspace.activeWindow !== undefined) workspace.activeWindow = w; else workspace.activeClient = w;",
+    )
+}
+
+pub fn move_window(window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+    with_window(
+        window,
+        &format!("const g = w.frameGeometry; w.frameGeometry = {{x: {x}, y: {y}, width: g.width, height: g.height}};"),
+    )
+}
+
+pub fn resize_window(window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+    with_window(
+        window,
+        &format!("const g = w.frameGeometry; w.frameGeometry = {{x: g.x, y: g.y, width: {width}, height: {height}}};"),
+    )
+}
+
+pub fn minimize_window(window: crate::Window) -> crate::Result<()> {
+    with_window(window, "w.minimized = true;")
+}
+
+pub fn maximize_window(window: crate::Window) -> crate::Result<()> {
+    with_window(window, "w.setMaximize(true, true);")
+}
+
+pub fn close_window(window: crate::Window) -> crate::Result<()> {
+    with_window(window, "w.closeWindow();")
+}
+
+pub fn is_always_on_top(window: crate::Window) -> crate::Result<bool> {
+    Ok(describe(window)?.get("keepAbove").as_bool() == Some(true))
+}
+
+pub fn set_always_on_top(window: crate::Window, on_top: bool) -> crate::Result<()> {
+    with_window(window, &format!("w.keepAbove = {on_top};"))
+}
+
+pub fn is_always_below(window: crate::Window) -> crate::Result<bool> {
+    Ok(describe(window)?.get("keepBelow").as_bool() == Some(true))
+}
+
+pub fn set_always_below(window: crate::Window, below: bool) -> crate::Result<()> {
+    with_window(window, &format!("w.keepBelow = {below};"))
+}
+
+pub fn set_skip_taskbar(window: crate::Window, skip: bool) -> crate::Result<()> {
+    with_window(window, &format!("w.skipTaskbar = {skip}; w.skipPager = {skip};"))
+}
+
+pub fn set_skip_switcher(window: crate::Window, skip: bool) -> crate::Result<()> {
+    with_window(window, &format!("w.skipSwitcher = {skip};"))
+}
+
+pub fn window_has_state(window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    let Some(described) = find(window)? else {
+        return Ok(state == WindowState::Closed);
+    };
+    let flag = |name: &str| described.get(name).as_bool();
+    Ok(match state {
+        WindowState::Closed => false,
+        WindowState::Visible => flag("minimized") != Some(true),
+        WindowState::Minimized => flag("minimized") == Some(true),
+        WindowState::Fullscreen => flag("fullScreen") == Some(true),
+        WindowState::Focused => flag("active") == Some(true),
+        WindowState::Maximized => {
+            flag("maximized").ok_or("This KWin version does not report maximization to scripts")?
+        }
+    })
+}
+
+/// The `kwin` backend, running KWin scripts over the session bus
+#[derive(Debug, Default)]
+pub(super) struct KWinBackend;
+
+impl WindowingBackend for KWinBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::KWin
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        list_windows()
+    }
+
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        get_window_info(window)
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        find_window_by_pid(target_pid)
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        find_windows_by_pid(target_pid)
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        get_active_window()
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        get_active_window_pid()
+    }
+
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        get_window_pid(window)
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_title(window)
+    }
+
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_app_id(window)
+    }
+
+    fn set_skip_switcher(&self, window: crate::Window, skip: bool) -> crate::Result<()> {
+        set_skip_switcher(window, skip)
+    }
+
+    fn set_skip_taskbar(&self, window: crate::Window, skip: bool) -> crate::Result<()> {
+        set_skip_taskbar(window, skip)
+    }
+
+    fn minimize_window(&self, window: crate::Window) -> crate::Result<()> {
+        minimize_window(window)
+    }
+
+    fn maximize_window(&self, window: crate::Window) -> crate::Result<()> {
+        maximize_window(window)
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        close_window(window)
+    }
+
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        move_window(window, x, y)
+    }
+
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        resize_window(window, width, height)
+    }
+
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn activate_window(&self, window: crate::Window, _update_user_time: bool) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        window_has_state(window, state)
+    }
+
+    fn is_always_on_top(&self, window: crate::Window) -> crate::Result<bool> {
+        is_always_on_top(window)
+    }
+
+    fn set_always_on_top(&self, window: crate::Window, on_top: bool) -> crate::Result<()> {
+        set_always_on_top(window, on_top)
+    }
+
+    fn is_always_below(&self, window: crate::Window) -> crate::Result<bool> {
+        is_always_below(window)
+    }
+
+    fn set_always_below(&self, window: crate::Window, below: bool) -> crate::Result<()> {
+        set_always_below(window, below)
+    }
+}
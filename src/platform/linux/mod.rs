@@ -0,0 +1,1073 @@
+//! Linux backends. X11 (including XWayland) is the default; compositor IPC
+//! (`hyprland`, `sway-ipc`) is preferred when its socket is present, GNOME and
+//! Plasma Wayland sessions go through the `gnome-shell` extension and `kwin`
+//! scripting, otherwise native Wayland windows are handled by `wayland-wlr`
+//! when supported. `WINDOWING_BACKEND` overrides the detection.
+//!
+//! Each backend sits behind its Cargo feature.
+
+#[cfg(any(feature = "gnome-shell", feature = "kwin", feature = "portal"))]
+mod dbus;
+mod desktop_entry;
+#[cfg(feature = "gnome-shell")]
+mod gnome_shell;
+#[cfg(feature = "hyprland")]
+mod hyprland;
+#[cfg(feature = "kwin")]
+mod kwin;
+#[cfg(feature = "portal")]
+mod png;
+#[cfg(feature = "portal")]
+mod portal;
+#[cfg(feature = "sway-ipc")]
+mod sway;
+#[cfg(feature = "wayland-wlr")]
+mod wayland;
+#[cfg(feature = "wayland-wlr")]
+mod wlr;
+#[cfg(feature = "x11")]
+mod x11;
+
+pub use desktop_entry::{find_desktop_entry, DesktopEntry};
+#[cfg(feature = "portal")]
+pub use portal::{capture_screen_portal, capture_window_portal, Capture};
+
+use crate::{
+    BackendKind, IconData, MonitorInfo, PhysicalWindowInfo, Unsupported, WindowDetails, WindowEvents, WindowInfo,
+    WindowProperties, WindowState, WindowStateFlag, WindowingBackend,
+};
+use std::{
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    sync::OnceLock,
+    time::Duration,
+};
+
+/// Environment variable forcing a backend by its `BackendKind::name`.
+const BACKEND_ENV: &str = "WINDOWING_BACKEND";
+
+/// The backends compiled in; each has a Cargo feature of the same name as its
+/// `BackendKind::name`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Backend {
+    #[cfg(feature = "x11")]
+    X11,
+    #[cfg(feature = "wayland-wlr")]
+    WaylandWlr,
+    #[cfg(feature = "hyprland")]
+    Hyprland,
+    #[cfg(feature = "sway-ipc")]
+    SwayIpc,
+    #[cfg(feature = "gnome-shell")]
+    GnomeShell,
+    #[cfg(feature = "kwin")]
+    KWin,
+}
+
+impl Backend {
+    const ALL: &[Backend] = &[
+        #[cfg(feature = "x11")]
+        Backend::X11,
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr,
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland,
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc,
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell,
+        #[cfg(feature = "kwin")]
+        Backend::KWin,
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|backend| BackendKind::from(*backend).name().eq_ignore_ascii_case(name))
+    }
+
+    /// Auto-detection order: compositor IPC, then wlr-foreign-toplevel, then X11/XWayland.
+    /// Without the `x11` feature the first compiled-in backend is the fallback.
+    fn detect() -> Self {
+        #[cfg(feature = "hyprland")]
+        if hyprland::is_available() {
+            return Backend::Hyprland;
+        }
+        #[cfg(feature = "hyprland")]
+        trace_debug!(backend = hyprland::NAME, "not available in this session");
+        #[cfg(feature = "sway-ipc")]
+        if sway::is_available() {
+            return Backend::SwayIpc;
+        }
+        #[cfg(feature = "sway-ipc")]
+        trace_debug!(backend = sway::NAME, "not available in this session");
+        #[cfg(feature = "gnome-shell")]
+        if gnome_shell::is_available() {
+            return Backend::GnomeShell;
+        }
+        #[cfg(feature = "gnome-shell")]
+        trace_debug!(backend = gnome_shell::NAME, "not available in this session");
+        #[cfg(feature = "kwin")]
+        if kwin::is_available() {
+            return Backend::KWin;
+        }
+        #[cfg(feature = "kwin")]
+        trace_debug!(backend = kwin::NAME, "not available in this session");
+        #[cfg(feature = "wayland-wlr")]
+        if wlr::is_available() {
+            return Backend::WaylandWlr;
+        }
+        #[cfg(feature = "wayland-wlr")]
+        trace_debug!(backend = wlr::NAME, "not available in this session");
+        #[cfg(feature = "x11")]
+        return Backend::X11;
+        #[cfg(not(feature = "x11"))]
+        Self::ALL[0]
+    }
+}
+
+impl From<Backend> for BackendKind {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            #[cfg(feature = "x11")]
+            Backend::X11 => BackendKind::X11,
+            #[cfg(feature = "wayland-wlr")]
+            Backend::WaylandWlr => BackendKind::WaylandWlr,
+            #[cfg(feature = "hyprland")]
+            Backend::Hyprland => BackendKind::Hyprland,
+            #[cfg(feature = "sway-ipc")]
+            Backend::SwayIpc => BackendKind::SwayIpc,
+            #[cfg(feature = "gnome-shell")]
+            Backend::GnomeShell => BackendKind::GnomeShell,
+            #[cfg(feature = "kwin")]
+            Backend::KWin => BackendKind::KWin,
+        }
+    }
+}
+
+/// The backend picked for this process, decided once on first use.
+fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+    *BACKEND.get_or_init(|| {
+        let forced = std::env::var(BACKEND_ENV).ok();
+        if let Some(backend) = forced.as_deref().and_then(Backend::from_name) {
+            trace_debug!(backend = BackendKind::from(backend).name(), "backend forced by {BACKEND_ENV}");
+            return backend;
+        }
+        if forced.is_some() {
+            trace_warn!(name = ?forced, "{BACKEND_ENV} names no compiled-in backend, detecting one instead");
+        }
+        let backend = Backend::detect();
+        trace_debug!(backend = BackendKind::from(backend).name(), "backend detected");
+        backend
+    })
+}
+
+/// The backend serving this process's calls. `WINDOWING_BACKEND` forces one by
+/// name (`x11`, `wayland-wlr`, `hyprland`, `sway-ipc`, `gnome-shell`, `kwin`);
+/// unknown names, and those of backends whose feature is off, fall back to
+/// auto-detection. The choice is made once and cached.
+pub fn detect_backend() -> BackendKind {
+    backend().into()
+}
+
+/// Whether a process with this pid is running: `/proc/<pid>` exists and it
+/// isn't a zombie waiting to be reaped.
+pub fn process_exists(pid: u32) -> crate::Result<bool> {
+    if pid == 0 {
+        return Ok(false);
+    }
+    match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+        // `pid (comm) state ...`, where comm may itself contain ") "
+        Ok(stat) => Ok(stat.rsplit_once(") ").is_some_and(|(_, fields)| !fields.starts_with('Z'))),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// The name of a running process: `/proc/<pid>/comm`, which the kernel
+/// truncates to 15 bytes. `None` if the process isn't running.
+pub fn get_process_name(pid: u32) -> crate::Result<Option<String>> {
+    if pid == 0 {
+        return Ok(None);
+    }
+    match std::fs::read_to_string(format!("/proc/{pid}/comm")) {
+        Ok(comm) => Ok(Some(comm.trim_end_matches('\n').to_owned())),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// What the detected backend can do in this session. Under X11 this asks the
+/// window manager (`_NET_SUPPORTED`, `_NET_WM_CM_S<screen>`); the compositor
+/// backends support a fixed set of operations.
+pub fn capabilities() -> crate::Capabilities {
+    let is_wayland = std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland");
+    let none = crate::Capabilities {
+        backend: detect_backend(),
+        is_wayland,
+        has_compositor: is_wayland,
+        supports_ewmh_state: false,
+        can_move_windows: false,
+        can_move_to_desktop: false,
+        can_set_always_on_top: false,
+        can_watch_events: false,
+        can_set_opacity: false,
+        supports_input_simulation: false,
+    };
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => {
+            x11::thread_backend().with_x11(|session| x11::capabilities(session, is_wayland)).unwrap_or(none)
+        }
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => crate::Capabilities { can_watch_events: true, ..none },
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => {
+            crate::Capabilities { can_move_windows: true, can_move_to_desktop: true, can_watch_events: true, ..none }
+        }
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => {
+            crate::Capabilities { can_move_windows: true, can_move_to_desktop: true, can_watch_events: true, ..none }
+        }
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => crate::Capabilities { can_move_windows: true, can_move_to_desktop: true, ..none },
+        #[cfg(feature = "kwin")]
+        Backend::KWin => crate::Capabilities { can_move_windows: true, can_set_always_on_top: true, ..none },
+    }
+}
+
+/// Whether there is a display to work with: a compositor listening on
+/// `WAYLAND_DISPLAY`, or (with the `x11` feature) an X server on `DISPLAY`
+/// that completes the connection handshake. Lets headless runs (containers,
+/// CI, text consoles) turn their window features off up front.
+pub fn is_display_available() -> bool {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| {
+        let mut path = PathBuf::from(display);
+        if path.is_relative() {
+            let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") else {
+                return false;
+            };
+            path = PathBuf::from(runtime_dir).join(path);
+        }
+        UnixStream::connect(path).is_ok()
+    });
+    // Bounded, so a wedged X server reads as unavailable instead of hanging the check
+    #[cfg(feature = "x11")]
+    let x11 = || x11::Session::connect(Some(Duration::from_secs(2))).is_ok();
+    #[cfg(not(feature = "x11"))]
+    let x11 = || false;
+    wayland || x11()
+}
+
+/// Map a window manager frame, e.g. from a pointer query, to the client window
+/// the other functions (and `_NET_WM_PID`) refer to. Only X11 reparents;
+/// compositor backends already hand out client windows, returned unchanged.
+pub fn client_window_of(frame: crate::Window) -> crate::Result<Option<crate::Window>> {
+    trace_span!("client_window_of", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend().with_x11(|session| x11::client_window_of(session, frame)),
+        // Unreachable with X11 the only backend compiled in
+        #[cfg_attr(
+            not(any(
+                feature = "wayland-wlr",
+                feature = "hyprland",
+                feature = "sway-ipc",
+                feature = "gnome-shell",
+                feature = "kwin"
+            )),
+            allow(unreachable_patterns)
+        )]
+        _ => Ok(Some(frame)),
+    }
+}
+
+/// Let the user pick a window by clicking it, with a crosshair cursor (X11 only;
+/// Wayland doesn't let clients grab the pointer)
+pub fn pick_window() -> crate::Result<crate::Window> {
+    trace_span!("pick_window", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend().with_x11(x11::pick_window),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("pick_window", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("pick_window", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("pick_window", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("pick_window", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("pick_window", kwin::NAME).into()),
+    }
+}
+
+/// Send a ClientMessage of type `message_type` (interned by name) about
+/// `window` to the root window, for EWMH requests this crate doesn't wrap,
+/// e.g. `_NET_WM_MOVERESIZE` with `_NET_WM_MOVERESIZE_CANCEL` (X11 only)
+#[cfg_attr(not(feature = "x11"), allow(unused_variables))]
+pub fn send_client_message(window: crate::Window, message_type: &str, data: [u32; 5]) -> crate::Result<()> {
+    trace_span!("send_client_message", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend()
+            .with_x11(|session| x11::send_client_message(session, window, message_type, data)),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("send_client_message", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("send_client_message", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("send_client_message", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("send_client_message", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("send_client_message", kwin::NAME).into()),
+    }
+}
+
+/// Panels, docks and taskbars: windows of type `_NET_WM_WINDOW_TYPE_DOCK` or
+/// with a strut, including ones the window manager leaves out of
+/// `list_windows`. Their frames come from `get_window_info` (X11 only)
+pub fn get_panels() -> crate::Result<Vec<crate::Window>> {
+    trace_span!("get_panels", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend().with_x11(x11::get_panels),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("get_panels", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("get_panels", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("get_panels", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("get_panels", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("get_panels", kwin::NAME).into()),
+    }
+}
+
+/// The screen space a panel reserves, from `_NET_WM_STRUT_PARTIAL` or
+/// `_NET_WM_STRUT`; `None` if it reserves none (X11 only)
+#[cfg_attr(not(feature = "x11"), allow(unused_variables))]
+pub fn get_strut(window: crate::Window) -> crate::Result<Option<Strut>> {
+    trace_span!("get_strut", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend().with_x11(|session| x11::get_strut(session, window)),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("get_strut", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("get_strut", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("get_strut", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("get_strut", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("get_strut", kwin::NAME).into()),
+    }
+}
+
+/// Space reserved along the edges of the root window (the whole X screen),
+/// as `_NET_WM_STRUT_PARTIAL` gives it: how many pixels in from each edge,
+/// and the range along that edge the reservation covers (first and last
+/// pixel, inclusive)
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Strut {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+    pub left_y: (u32, u32),
+    pub right_y: (u32, u32),
+    pub top_x: (u32, u32),
+    pub bottom_x: (u32, u32),
+}
+
+/// Whether the ICCCM urgency hint (the UrgencyHint flag of `WM_HINTS`) is set
+/// on the window (X11 only)
+#[cfg_attr(not(feature = "x11"), allow(unused_variables))]
+pub fn is_urgent(window: crate::Window) -> crate::Result<bool> {
+    trace_span!("is_urgent", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend().with_x11(|session| x11::is_urgent(session, window)),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("is_urgent", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("is_urgent", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("is_urgent", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("is_urgent", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("is_urgent", kwin::NAME).into()),
+    }
+}
+
+/// Set or clear the ICCCM urgency hint, keeping the rest of `WM_HINTS` as it
+/// is. For window managers that only honour the classic hint; pair it with
+/// `WindowStateFlag::DemandsAttention` for EWMH ones (X11 only)
+#[cfg_attr(not(feature = "x11"), allow(unused_variables))]
+pub fn set_urgency(window: crate::Window, urgent: bool) -> crate::Result<()> {
+    trace_span!("set_urgency", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend().with_x11(|session| x11::set_urgency(session, window, urgent)),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("set_urgency", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("set_urgency", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("set_urgency", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("set_urgency", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("set_urgency", kwin::NAME).into()),
+    }
+}
+
+/// A window property as stored by the X server, returned by `get_property_raw`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawProperty {
+    /// Name of the property's type atom, e.g. `STRING`, `UTF8_STRING` or `CARDINAL`
+    pub type_name: String,
+    /// Bits per item: 8, 16 or 32
+    pub format: u8,
+    /// The items in native byte order
+    pub data: Vec<u8>,
+}
+
+impl RawProperty {
+    /// The bytes of an 8-bit property (strings, mostly)
+    pub fn value8(&self) -> Option<&[u8]> {
+        (self.format == 8).then_some(self.data.as_slice())
+    }
+
+    /// The items of a 32-bit property (cardinals, atoms, window ids)
+    pub fn value32(&self) -> Option<Vec<u32>> {
+        (self.format == 32).then(|| {
+            self.data
+                .chunks_exact(4)
+                .map(|item| u32::from_ne_bytes([item[0], item[1], item[2], item[3]]))
+                .collect()
+        })
+    }
+}
+
+/// Read a property the crate doesn't model, by name (e.g. `WM_WINDOW_ROLE`),
+/// optionally requiring a type (`Some("STRING")`). `None` if the window
+/// doesn't have it (X11 only)
+#[cfg_attr(not(feature = "x11"), allow(unused_variables))]
+pub fn get_property_raw(
+    window: crate::Window,
+    property: &str,
+    property_type: Option<&str>,
+) -> crate::Result<Option<RawProperty>> {
+    trace_span!("get_property_raw", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend()
+            .with_x11(|session| x11::get_property_raw(session, window, property, property_type)),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("get_property_raw", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("get_property_raw", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("get_property_raw", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("get_property_raw", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("get_property_raw", kwin::NAME).into()),
+    }
+}
+
+/// Replace a property with `data`, `format`-bit items (8, 16 or 32) in native
+/// byte order, of type `property_type` (X11 only)
+#[cfg_attr(not(feature = "x11"), allow(unused_variables))]
+pub fn set_property_raw(
+    window: crate::Window,
+    property: &str,
+    property_type: &str,
+    format: u8,
+    data: &[u8],
+) -> crate::Result<()> {
+    trace_span!("set_property_raw", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend()
+            .with_x11(|session| x11::set_property_raw(session, window, property, property_type, format, data)),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("set_property_raw", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => Err(Unsupported::new("set_property_raw", hyprland::NAME).into()),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => Err(Unsupported::new("set_property_raw", sway::NAME).into()),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("set_property_raw", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("set_property_raw", kwin::NAME).into()),
+    }
+}
+
+/// Every monitor with its name and scale factor
+pub fn list_monitors() -> crate::Result<Vec<MonitorInfo>> {
+    trace_span!("list_monitors", backend = detect_backend().name());
+    match backend() {
+        #[cfg(feature = "x11")]
+        Backend::X11 => x11::thread_backend().with_x11(x11::list_monitors),
+        #[cfg(feature = "wayland-wlr")]
+        Backend::WaylandWlr => Err(Unsupported::new("list_monitors", wlr::NAME).into()),
+        #[cfg(feature = "hyprland")]
+        Backend::Hyprland => hyprland::monitors(),
+        #[cfg(feature = "sway-ipc")]
+        Backend::SwayIpc => sway::outputs(),
+        #[cfg(feature = "gnome-shell")]
+        Backend::GnomeShell => Err(Unsupported::new("list_monitors", gnome_shell::NAME).into()),
+        #[cfg(feature = "kwin")]
+        Backend::KWin => Err(Unsupported::new("list_monitors", kwin::NAME).into()),
+    }
+}
+
+/// The platform's window system: one of the Linux backends, picked by
+/// `detect_backend` or chosen with `with_backend`. It is `Send` and `Sync`, so
+/// one instance (e.g. an `Arc<WindowSystem>`) can serve every thread. On X11 it
+/// keeps one connection open for every call made through it; requests from
+/// several threads go out on the shared connection and x11rb matches each reply
+/// to its request, so a thread only waits for its own round trips. A call that
+/// finds the connection dead (e.g. after an X server restart) reconnects once
+/// and is retried. Event streams from `subscribe_window_events` open a
+/// connection of their own.
+pub struct WindowSystem {
+    backend: Box<dyn WindowingBackend>,
+}
+
+impl std::fmt::Debug for WindowSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WindowSystem").field("backend", &self.backend.kind()).finish()
+    }
+}
+
+impl Default for WindowSystem {
+    /// The detected backend, without connecting to anything yet
+    fn default() -> Self {
+        WindowSystem { backend: Backend::build(backend(), None) }
+    }
+}
+
+impl WindowSystem {
+    /// Connect to the window system. On X11 the connection is opened here, so
+    /// a missing display is reported now rather than by the first call.
+    pub fn new() -> crate::Result<Self> {
+        Self::connect(backend())
+    }
+
+    /// A window system on `kind` rather than the detected backend, e.g. to
+    /// drive XWayland windows from a Wayland session. As with `new`, an X11
+    /// connection is opened here. `Unsupported` if the backend's feature is
+    /// off, or `kind` is not a Linux backend.
+    pub fn with_backend(kind: BackendKind) -> crate::Result<Self> {
+        let backend = Backend::ALL
+            .iter()
+            .copied()
+            .find(|backend| BackendKind::from(*backend) == kind)
+            .ok_or_else(|| Unsupported::new("with_backend", kind.name()))?;
+        Self::connect(backend)
+    }
+
+    /// The detected backend for the free functions: on X11, each calling thread
+    /// gets a connection of its own, opened by its first call
+    pub(crate) fn per_thread() -> Self {
+        match backend() {
+            #[cfg(feature = "x11")]
+            Backend::X11 => WindowSystem { backend: Box::new(x11::X11Backend::per_thread()) },
+            // Unreachable with X11 the only backend compiled in
+            #[cfg_attr(
+                not(any(
+                    feature = "wayland-wlr",
+                    feature = "hyprland",
+                    feature = "sway-ipc",
+                    feature = "gnome-shell",
+                    feature = "kwin"
+                )),
+                allow(unreachable_patterns)
+            )]
+            backend => WindowSystem { backend: backend.build(None) },
+        }
+    }
+
+    fn connect(backend: Backend) -> crate::Result<Self> {
+        #[cfg(feature = "x11")]
+        if backend == Backend::X11 {
+            let x11 = x11::X11Backend::default();
+            x11.session()?;
+            return Ok(WindowSystem { backend: Box::new(x11) });
+        }
+        Ok(WindowSystem { backend: Backend::build(backend, None) })
+    }
+
+    /// Give up on an X11 request whose reply takes longer than `timeout`,
+    /// failing the call with `Error::Timeout` instead of blocking it (e.g. on
+    /// a frozen X server or a dying SSH tunnel). The connection is dropped and
+    /// the next call reconnects. By default calls wait as long as it takes.
+    /// Other backends ignore it.
+    ///
+    /// To apply it to the free functions:
+    /// `set_global_backend(Box::new(WindowSystem::default().with_request_timeout(timeout)))`.
+    #[cfg_attr(not(feature = "x11"), allow(unused_mut, unused_variables))]
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        #[cfg(feature = "x11")]
+        if self.backend.kind() == BackendKind::X11 {
+            // A connection opened by `new` was opened without it
+            self.backend = Backend::build(Backend::X11, Some(timeout));
+        }
+        self
+    }
+}
+
+impl Backend {
+    /// The backend's `WindowingBackend`, not connected yet. `request_timeout`
+    /// only applies to X11.
+    #[cfg_attr(not(feature = "x11"), allow(unused_variables))]
+    fn build(self, request_timeout: Option<Duration>) -> Box<dyn WindowingBackend> {
+        match self {
+            #[cfg(feature = "x11")]
+            Backend::X11 => Box::new(x11::X11Backend::with_request_timeout(request_timeout)),
+            #[cfg(feature = "wayland-wlr")]
+            Backend::WaylandWlr => Box::new(wlr::WlrBackend),
+            #[cfg(feature = "hyprland")]
+            Backend::Hyprland => Box::new(hyprland::HyprlandBackend),
+            #[cfg(feature = "sway-ipc")]
+            Backend::SwayIpc => Box::new(sway::SwayBackend),
+            #[cfg(feature = "gnome-shell")]
+            Backend::GnomeShell => Box::new(gnome_shell::GnomeShellBackend),
+            #[cfg(feature = "kwin")]
+            Backend::KWin => Box::new(kwin::KWinBackend),
+        }
+    }
+}
+
+impl WindowingBackend for WindowSystem {
+    fn kind(&self) -> BackendKind {
+        self.backend.kind()
+    }
+
+    /// List all top-level windows
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("list_windows", backend = self.kind().name());
+        self.backend.list_windows()
+    }
+
+    /// Also the root window and desktop-type windows on X11; compositors list
+    /// no desktop windows and have no root, so elsewhere this is `list_windows`
+    fn list_windows_including_desktop(&self) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("list_windows_including_desktop", backend = self.kind().name());
+        self.backend.list_windows_including_desktop()
+    }
+
+    /// Any X window, including unmapped and override-redirect ones, on X11;
+    /// elsewhere a window `list_windows` has
+    fn window_exists(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("window_exists", backend = self.kind().name(), %window);
+        self.backend.window_exists(window)
+    }
+
+    /// Number of open windows. X11 reads it from the size of _NET_CLIENT_LIST, which
+    /// counts desktop windows `list_windows` leaves out; other backends have no
+    /// cheaper query than listing.
+    fn window_count(&self) -> crate::Result<usize> {
+        trace_span!("window_count", backend = self.kind().name());
+        self.backend.window_count()
+    }
+
+    /// Get the geometry (x, y, width, height) of a window.
+    /// The wlr protocol doesn't expose geometry, so this is `Unsupported` there.
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        trace_span!("get_window_info", backend = self.kind().name(), %window);
+        self.backend.get_window_info(window)
+    }
+
+    /// Get many windows' geometry; X11 pipelines the requests, other backends
+    /// query each window in turn
+    fn get_window_infos(
+        &self,
+        windows: &[crate::Window],
+    ) -> crate::Result<Vec<crate::WindowResult<WindowInfo>>> {
+        trace_span!("get_window_infos", backend = self.kind().name());
+        self.backend.get_window_infos(windows)
+    }
+
+    /// Gather a window's details in one round trip on X11, one request per part elsewhere
+    fn get_window_details(&self, window: crate::Window) -> crate::Result<WindowDetails> {
+        trace_span!("get_window_details", backend = self.kind().name(), %window);
+        self.backend.get_window_details(window)
+    }
+
+    /// Read many windows' pid, app id, title and workspace; X11 pipelines the
+    /// requests, other backends query each part of each window in turn
+    fn get_window_properties(
+        &self,
+        windows: &[crate::Window],
+    ) -> crate::Result<Vec<crate::WindowResult<WindowProperties>>> {
+        trace_span!("get_window_properties", backend = self.kind().name());
+        self.backend.get_window_properties(windows)
+    }
+
+    /// Get a window's geometry, retrying up to `retries` times on errors that can occur
+    /// transiently while the window is being dragged (X11 only; other backends don't
+    /// report such errors and make a single attempt)
+    fn get_window_info_with_retry(&self, window: crate::Window, retries: u32) -> crate::Result<WindowInfo> {
+        trace_span!("get_window_info_with_retry", backend = self.kind().name(), %window);
+        self.backend.get_window_info_with_retry(window, retries)
+    }
+
+    /// Get a window's frame in physical pixels. X11 already reports pixels;
+    /// Hyprland and sway frames are scaled by the monitor holding most of the window.
+    fn get_window_bounds(&self, window: crate::Window) -> crate::Result<PhysicalWindowInfo> {
+        trace_span!("get_window_bounds", backend = self.kind().name(), %window);
+        self.backend.get_window_bounds(window)
+    }
+
+    /// Search for a window by process ID (exact match)
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        trace_span!("find_window_by_pid", backend = self.kind().name(), pid = target_pid);
+        self.backend.find_window_by_pid(target_pid)
+    }
+
+    /// Search for all windows belonging to a specific process ID
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("find_windows_by_pid", backend = self.kind().name(), pid = target_pid);
+        self.backend.find_windows_by_pid(target_pid)
+    }
+
+    /// Windows of any of `pids`, each with its pid, in one pass (pipelined on X11)
+    fn find_windows_by_pids(&self, pids: &[u32]) -> crate::Result<Vec<(u32, crate::Window)>> {
+        trace_span!("find_windows_by_pids", backend = self.kind().name());
+        self.backend.find_windows_by_pids(pids)
+    }
+
+    /// Whether the process has a client window, reading PIDs only up to the first match on X11
+    fn pid_has_window(&self, target_pid: u32) -> crate::Result<bool> {
+        trace_span!("pid_has_window", backend = self.kind().name(), pid = target_pid);
+        self.backend.pid_has_window(target_pid)
+    }
+
+    /// Search for a process's windows that pass `is_main_window`
+    fn find_visible_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("find_visible_windows_by_pid", backend = self.kind().name(), pid = target_pid);
+        self.backend.find_visible_windows_by_pid(target_pid)
+    }
+
+    /// Find all windows whose app_id matches (ignoring ASCII case, since X11 class
+    /// names are only an approximation of Wayland app_ids)
+    fn find_windows_by_app_id(&self, app_id: &str) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("find_windows_by_app_id", backend = self.kind().name(), app_id);
+        self.backend.find_windows_by_app_id(app_id)
+    }
+
+    /// The WM_CLASS (instance, class) of a process's main window (X11 only)
+    fn get_class_for_pid(&self, target_pid: u32) -> crate::Result<Option<(String, String)>> {
+        trace_span!("get_class_for_pid", backend = self.kind().name(), pid = target_pid);
+        self.backend.get_class_for_pid(target_pid)
+    }
+
+    /// Whether a window looks like an application's primary window. X11 requires a
+    /// normal, titled, viewable, non-transient window; compositor backends only list
+    /// toplevels, so there a non-empty title is enough.
+    fn is_main_window(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_main_window", backend = self.kind().name(), %window);
+        self.backend.is_main_window(window)
+    }
+
+    /// Get the currently active window
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        trace_span!("get_active_window", backend = self.kind().name());
+        self.backend.get_active_window()
+    }
+
+    /// The focused window with its pid, title, app id and geometry
+    fn active_window_snapshot(&self) -> crate::Result<Option<crate::ActiveWindowSnapshot>> {
+        trace_span!("active_window_snapshot", backend = self.kind().name());
+        self.backend.active_window_snapshot()
+    }
+
+    /// Get the process ID of the currently active window
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        trace_span!("get_active_window_pid", backend = self.kind().name());
+        self.backend.get_active_window_pid()
+    }
+
+    /// Get the process ID owning a window
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        trace_span!("get_window_pid", backend = self.kind().name(), %window);
+        self.backend.get_window_pid(window)
+    }
+
+    /// Get a window's title
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        trace_span!("get_window_title", backend = self.kind().name(), %window);
+        self.backend.get_window_title(window)
+    }
+
+    /// Get a window's application id (e.g. `org.mozilla.firefox`)
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        trace_span!("get_window_app_id", backend = self.kind().name(), %window);
+        self.backend.get_window_app_id(window)
+    }
+
+    /// Get the window's largest icon as RGBA pixels
+    fn get_window_icon(&self, window: crate::Window) -> crate::Result<Option<IconData>> {
+        trace_span!("get_window_icon", backend = self.kind().name(), %window);
+        self.backend.get_window_icon(window)
+    }
+
+    /// Withdraw a window: off screen, the taskbar and the pager
+    fn hide_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("hide_window", backend = self.kind().name(), %window);
+        self.backend.hide_window(window)
+    }
+
+    /// Map a window withdrawn by `hide_window` again
+    fn show_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("show_window", backend = self.kind().name(), %window);
+        self.backend.show_window(window)
+    }
+
+    /// Leave a window out of the window switcher (KWin only: EWMH has no
+    /// switcher hint, and X11 WMs' switchers mostly follow skip-taskbar)
+    fn set_skip_switcher(&self, window: crate::Window, skip: bool) -> crate::Result<()> {
+        trace_span!("set_skip_switcher", backend = self.kind().name(), %window);
+        self.backend.set_skip_switcher(window, skip)
+    }
+
+    /// Remove a window from the taskbar and pager, or restore it, leaving it on screen
+    fn set_skip_taskbar(&self, window: crate::Window, skip: bool) -> crate::Result<()> {
+        trace_span!("set_skip_taskbar", backend = self.kind().name(), %window);
+        self.backend.set_skip_taskbar(window, skip)
+    }
+
+    /// Minimize (iconify) a window
+    fn minimize_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("minimize_window", backend = self.kind().name(), %window);
+        self.backend.minimize_window(window)
+    }
+
+    /// Maximize a window
+    fn maximize_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("maximize_window", backend = self.kind().name(), %window);
+        self.backend.maximize_window(window)
+    }
+
+    /// Maximize a window, or restore it if it is already maximized on both axes
+    fn toggle_maximize(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("toggle_maximize", backend = self.kind().name(), %window);
+        self.backend.toggle_maximize(window)
+    }
+
+    /// Ask a window to close
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("close_window", backend = self.kind().name(), %window);
+        self.backend.close_window(window)
+    }
+
+    /// Move a window's top-left corner to (x, y)
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        trace_span!("move_window", backend = self.kind().name(), %window);
+        self.backend.move_window(window, x, y)
+    }
+
+    /// Resize a window
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        trace_span!("resize_window", backend = self.kind().name(), %window);
+        self.backend.resize_window(window, width, height)
+    }
+
+    /// Move and resize a window. X11 does it in one request; elsewhere it is a
+    /// move followed by a resize.
+    fn set_window_rect(
+        &self,
+        window: crate::Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> crate::Result<()> {
+        trace_span!("set_window_rect", backend = self.kind().name(), %window);
+        self.backend.set_window_rect(window, x, y, width, height)
+    }
+
+    /// Give a window input focus and raise it
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("focus_window", backend = self.kind().name(), %window);
+        self.backend.focus_window(window)
+    }
+
+    /// Whether a window accepts input focus (X11 only: ICCCM input hint, WM_TAKE_FOCUS and dock type)
+    fn can_focus(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("can_focus", backend = self.kind().name(), %window);
+        self.backend.can_focus(window)
+    }
+
+    /// Activate a window, optionally refreshing its user time first (X11 only;
+    /// compositor backends just focus the window)
+    fn activate_window(&self, window: crate::Window, update_user_time: bool) -> crate::Result<()> {
+        trace_span!("activate_window", backend = self.kind().name(), %window);
+        self.backend.activate_window(window, update_user_time)
+    }
+
+    /// Set a window's _NET_WM_USER_TIME (an X server timestamp)
+    fn set_user_time(&self, window: crate::Window, timestamp: u32) -> crate::Result<()> {
+        trace_span!("set_user_time", backend = self.kind().name(), %window);
+        self.backend.set_user_time(window, timestamp)
+    }
+
+    /// Get the zero-based workspace a window is on (`None` if on all/no regular workspace)
+    fn get_window_workspace(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        trace_span!("get_window_workspace", backend = self.kind().name(), %window);
+        self.backend.get_window_workspace(window)
+    }
+
+    /// Move a window to a zero-based workspace without following it
+    fn move_window_to_workspace(&self, window: crate::Window, workspace: u32) -> crate::Result<()> {
+        trace_span!("move_window_to_workspace", backend = self.kind().name(), %window, workspace);
+        self.backend.move_window_to_workspace(window, workspace)
+    }
+
+    /// Get the zero-based index of the current workspace
+    fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+        trace_span!("get_current_workspace", backend = self.kind().name());
+        self.backend.get_current_workspace()
+    }
+
+    /// Switch to a zero-based workspace
+    fn switch_workspace(&self, workspace: u32) -> crate::Result<()> {
+        trace_span!("switch_workspace", backend = self.kind().name(), workspace);
+        self.backend.switch_workspace(workspace)
+    }
+
+    /// Subscribe to window lifecycle, focus and title events
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        trace_span!("subscribe_window_events", backend = self.kind().name());
+        self.backend.subscribe_window_events()
+    }
+
+    /// Every monitor with its name and scale factor
+    fn list_monitors(&self) -> crate::Result<Vec<MonitorInfo>> {
+        trace_span!("list_monitors", backend = self.kind().name());
+        self.backend.list_monitors()
+    }
+
+    /// The monitor holding most of a window's frame, among `list_monitors`
+    fn get_window_monitor(&self, window: crate::Window) -> crate::Result<Option<MonitorInfo>> {
+        trace_span!("get_window_monitor", backend = self.kind().name(), %window);
+        self.backend.get_window_monitor(window)
+    }
+
+    /// Every monitor a window's frame overlaps, among `list_monitors`
+    fn get_window_monitors(&self, window: crate::Window) -> crate::Result<Vec<MonitorInfo>> {
+        trace_span!("get_window_monitors", backend = self.kind().name(), %window);
+        self.backend.get_window_monitors(window)
+    }
+
+    /// The work area of the monitor holding most of a window's frame: from the
+    /// panels' struts on X11, Hyprland's reserved space on Hyprland
+    fn get_window_work_area(&self, window: crate::Window) -> crate::Result<Option<WindowInfo>> {
+        trace_span!("get_window_work_area", backend = self.kind().name(), %window);
+        self.backend.get_window_work_area(window)
+    }
+
+    /// Move a window, keeping its frame inside the work area of its monitor
+    fn move_window_clamped(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        trace_span!("move_window_clamped", backend = self.kind().name(), %window);
+        self.backend.move_window_clamped(window, x, y)
+    }
+
+    /// The bounding rectangle of all monitors
+    fn get_virtual_screen(&self) -> crate::Result<WindowInfo> {
+        trace_span!("get_virtual_screen", backend = self.kind().name());
+        self.backend.get_virtual_screen()
+    }
+
+    /// Pointer position and the window under it (X11 only: Wayland doesn't
+    /// expose the pointer outside a client's own surfaces)
+    fn pointer_state(&self) -> crate::Result<(i32, i32, Option<crate::Window>)> {
+        trace_span!("pointer_state", backend = self.kind().name());
+        self.backend.pointer_state()
+    }
+
+    /// Get the root (desktop) window, whose geometry `get_window_info` reports as the desktop bounds.
+    /// Only X11 has a root window; compositor backends should use `get_virtual_screen`.
+    fn get_root_window(&self) -> crate::Result<crate::Window> {
+        trace_span!("get_root_window", backend = self.kind().name());
+        self.backend.get_root_window()
+    }
+
+    /// Check whether a window is currently in the given state
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        trace_span!("window_has_state", backend = self.kind().name(), %window);
+        self.backend.window_has_state(window, state)
+    }
+
+    /// List every state flag (`_NET_WM_STATE`) currently set on a window
+    fn get_window_states(&self, window: crate::Window) -> crate::Result<Vec<WindowStateFlag>> {
+        trace_span!("get_window_states", backend = self.kind().name(), %window);
+        self.backend.get_window_states(window)
+    }
+
+    /// Add or remove a state flag (`_NET_WM_STATE`) on a window
+    fn set_window_state(
+        &self,
+        window: crate::Window,
+        flag: WindowStateFlag,
+        enabled: bool,
+    ) -> crate::Result<()> {
+        trace_span!("set_window_state", backend = self.kind().name(), %window);
+        self.backend.set_window_state(window, flag, enabled)
+    }
+
+    /// Whether a window is shown on all workspaces
+    fn is_sticky(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_sticky", backend = self.kind().name(), %window);
+        self.backend.is_sticky(window)
+    }
+
+    /// Show a window on all workspaces (or only its own again). Hyprland and Sway
+    /// only apply this to floating windows.
+    fn set_sticky(&self, window: crate::Window, sticky: bool) -> crate::Result<()> {
+        trace_span!("set_sticky", backend = self.kind().name(), %window);
+        self.backend.set_sticky(window, sticky)
+    }
+
+    /// Whether a window is kept above normal windows
+    fn is_always_on_top(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_always_on_top", backend = self.kind().name(), %window);
+        self.backend.is_always_on_top(window)
+    }
+
+    /// Keep a window above normal windows, or stack it normally again
+    fn set_always_on_top(&self, window: crate::Window, on_top: bool) -> crate::Result<()> {
+        trace_span!("set_always_on_top", backend = self.kind().name(), %window);
+        self.backend.set_always_on_top(window, on_top)
+    }
+
+    /// Whether a window is kept below normal windows
+    fn is_always_below(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_always_below", backend = self.kind().name(), %window);
+        self.backend.is_always_below(window)
+    }
+
+    /// Keep a window below normal windows, or stack it normally again
+    fn set_always_below(&self, window: crate::Window, below: bool) -> crate::Result<()> {
+        trace_span!("set_always_below", backend = self.kind().name(), %window);
+        self.backend.set_always_below(window, below)
+    }
+
+    /// Pass mouse input through a window, or have it take input again
+    fn set_click_through(&self, window: crate::Window, click_through: bool) -> crate::Result<()> {
+        trace_span!("set_click_through", backend = self.kind().name(), %window);
+        self.backend.set_click_through(window, click_through)
+    }
+}
@@ -0,0 +1,476 @@
+//! Minimal PNG decoder for portal screenshots.
+//!
+//! Handles what desktop portals actually write: non-interlaced, 8-bit
+//! grayscale, RGB or RGBA images. Includes a small DEFLATE decoder since
+//! the IDAT stream is zlib-compressed.
+
+/// A decoded image as tightly packed RGBA rows (top row first).
+pub(super) struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+const COLOR_GRAY: u8 = 0;
+const COLOR_RGB: u8 = 2;
+const COLOR_GRAY_ALPHA: u8 = 4;
+const COLOR_RGBA: u8 = 6;
+
+pub(super) fn decode(data: &[u8]) -> crate::Result<Image> {
+    let mut rest = data.strip_prefix(SIGNATURE).ok_or("Not a PNG file")?;
+    let mut header = None;
+    let mut compressed = Vec::new();
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let kind = &rest[4..8];
+        let body = rest.get(8..8 + len).ok_or("Truncated PNG chunk")?;
+        match kind {
+            b"IHDR" if body.len() >= 13 => header = Some(body.to_vec()),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        rest = rest.get(12 + len..).ok_or("Truncated PNG chunk")?;
+    }
+
+    let header = header.ok_or("PNG has no IHDR chunk")?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    let (bit_depth, color_type, interlace) = (header[8], header[9], header[12]);
+    let channels = match color_type {
+        COLOR_GRAY => 1,
+        COLOR_RGB => 3,
+        COLOR_GRAY_ALPHA => 2,
+        COLOR_RGBA => 4,
+        _ => return Err(format!("Unsupported PNG color type {color_type}").into()),
+    };
+    if bit_depth != 8 || interlace != 0 {
+        return Err("Only 8-bit, non-interlaced PNGs are supported".into());
+    }
+
+    // Each row is a filter byte and its pixels; a stream inflating past that
+    // is malformed, and stopping there bounds what a hostile file can allocate
+    let stride = (width as usize).checked_mul(channels).ok_or("PNG is too large")?;
+    let expected = (stride + 1).checked_mul(height as usize).ok_or("PNG is too large")?;
+    let raw = unzlib(&compressed, expected)?;
+    if raw.len() < expected {
+        return Err("PNG image data is truncated".into());
+    }
+
+    let mut pixels = vec![0u8; stride * height as usize];
+    for y in 0..height as usize {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (done, current) = pixels.split_at_mut(y * stride);
+        let previous = done.get(done.len().saturating_sub(stride)..).filter(|_| y > 0);
+        let current = &mut current[..stride];
+        for x in 0..stride {
+            let left = if x >= channels { current[x - channels] } else { 0 };
+            let up = previous.map_or(0, |row| row[x]);
+            let up_left = if x >= channels { previous.map_or(0, |row| row[x - channels]) } else { 0 };
+            current[x] = line[x].wrapping_add(match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(format!("Invalid PNG filter type {filter}").into()),
+            });
+        }
+    }
+
+    let rgba = match color_type {
+        COLOR_RGBA => pixels,
+        COLOR_RGB => pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        COLOR_GRAY => pixels.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        _ => pixels.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+    };
+    Ok(Image { width, height, rgba })
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// ---- DEFLATE ----------------------------------------------------------------
+
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl Bits<'_> {
+    fn bit(&mut self) -> crate::Result<u32> {
+        let byte = *self.data.get(self.pos).ok_or("Truncated DEFLATE stream")?;
+        let value = (byte >> self.bit) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+        Ok(value as u32)
+    }
+
+    fn bits(&mut self, count: u32) -> crate::Result<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+/// Canonical Huffman decoding table: symbol counts per length plus symbols by code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..16 {
+            offsets[len] = offsets[len - 1] + counts[len - 1];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> crate::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.bit()? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Invalid Huffman code in DEFLATE stream".into())
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which code length code lengths are stored in dynamic blocks.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Decompress a zlib stream of at most `limit` bytes, checking its Adler-32.
+fn unzlib(data: &[u8], limit: usize) -> crate::Result<Vec<u8>> {
+    let [method, flags, ..] = *data else {
+        return Err("Empty PNG image data".into());
+    };
+    if method & 0x0f != 8 || !(method as u16 * 256 + flags as u16).is_multiple_of(31) || flags & 0x20 != 0 {
+        return Err("Invalid zlib header in PNG image data".into());
+    }
+    let (out, end) = inflate(&data[2..], limit)?;
+    let checksum = data.get(2 + end..2 + end + 4).ok_or("PNG image data has no Adler-32 checksum")?;
+    if u32::from_be_bytes(checksum.try_into().unwrap()) != adler32(&out) {
+        return Err("PNG image data fails its Adler-32 check".into());
+    }
+    Ok(out)
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    // 5552 bytes is the most that can be summed before `b` could overflow
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        (a, b) = (a % MOD, b % MOD);
+    }
+    b << 16 | a
+}
+
+/// Inflate a raw DEFLATE stream, failing once the output passes `limit`
+/// bytes. Returns the output and the length of the stream in `data`.
+fn inflate(data: &[u8], limit: usize) -> crate::Result<(Vec<u8>, usize)> {
+    let mut bits = Bits { data, pos: 0, bit: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = bits.bit()? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align_to_byte();
+                let header = data.get(bits.pos..bits.pos + 4).ok_or("Truncated stored block")?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                if len != !u16::from_le_bytes([header[2], header[3]]) as usize {
+                    return Err("Corrupt stored block length".into());
+                }
+                if out.len() + len > limit {
+                    return Err(TOO_LONG.into());
+                }
+                bits.pos += 4;
+                out.extend_from_slice(data.get(bits.pos..bits.pos + len).ok_or("Truncated stored block")?);
+                bits.pos += len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                inflate_block(&mut bits, &mut out, limit, &Huffman::new(&lengths), &Huffman::new(&[5; 30]))?;
+            }
+            2 => {
+                let literal_count = bits.bits(5)? as usize + 257;
+                let distance_count = bits.bits(5)? as usize + 1;
+                let code_length_count = bits.bits(4)? as usize + 4;
+                let mut code_lengths = [0u8; 19];
+                for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+                    code_lengths[index] = bits.bits(3)? as u8;
+                }
+                let code_length_codes = Huffman::new(&code_lengths);
+                let mut lengths = Vec::with_capacity(literal_count + distance_count);
+                while lengths.len() < literal_count + distance_count {
+                    let (value, repeat) = match code_length_codes.decode(&mut bits)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 => (*lengths.last().ok_or("Repeat with no previous length")?, 3 + bits.bits(2)?),
+                        17 => (0, 3 + bits.bits(3)?),
+                        _ => (0, 11 + bits.bits(7)?),
+                    };
+                    lengths.extend(std::iter::repeat_n(value, repeat as usize));
+                }
+                if lengths.len() > literal_count + distance_count {
+                    return Err("Code lengths overrun in DEFLATE stream".into());
+                }
+                let literals = Huffman::new(&lengths[..literal_count]);
+                let distances = Huffman::new(&lengths[literal_count..literal_count + distance_count]);
+                inflate_block(&mut bits, &mut out, limit, &literals, &distances)?;
+            }
+            _ => return Err("Invalid DEFLATE block type".into()),
+        }
+        if last {
+            bits.align_to_byte();
+            return Ok((out, bits.pos));
+        }
+    }
+}
+
+const TOO_LONG: &str = "PNG image data is longer than the image";
+
+fn inflate_block(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    limit: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> crate::Result<()> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 if out.len() == limit => return Err(TOO_LONG.into()),
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                let len = *LENGTH_BASE.get(index).ok_or("Invalid DEFLATE length")? as usize
+                    + bits.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                let index = distances.decode(bits)? as usize;
+                let distance = *DISTANCE_BASE.get(index).ok_or("Invalid DEFLATE distance")? as usize
+                    + bits.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                let start = out.len().checked_sub(distance).ok_or("DEFLATE distance too far back")?;
+                if out.len() + len > limit {
+                    return Err(TOO_LONG.into());
+                }
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PNG of one IDAT chunk holding `zlib`. CRCs are left zero: `decode`
+    /// doesn't check them.
+    fn png(width: u32, height: u32, color_type: u8, zlib: &[u8]) -> Vec<u8> {
+        let mut file = SIGNATURE.to_vec();
+        let mut chunk = |kind: &[u8], body: &[u8]| {
+            file.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            file.extend_from_slice(kind);
+            file.extend_from_slice(body);
+            file.extend_from_slice(&[0; 4]);
+        };
+        let mut header = [width.to_be_bytes(), height.to_be_bytes()].concat();
+        header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+        chunk(b"IHDR", &header);
+        chunk(b"IDAT", zlib);
+        chunk(b"IEND", &[]);
+        file
+    }
+
+    /// `raw` as a zlib stream of one stored block.
+    fn stored(raw: &[u8]) -> Vec<u8> {
+        let len = raw.len() as u16;
+        let mut zlib = vec![0x78, 0x01, 0x01];
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(raw);
+        zlib.extend_from_slice(&adler32(raw).to_be_bytes());
+        zlib
+    }
+
+    /// The gray level of each pixel of a gray image.
+    fn gray(image: &Image) -> Vec<u8> {
+        image.rgba.chunks_exact(4).map(|pixel| pixel[0]).collect()
+    }
+
+    #[test]
+    fn adler32_matches_the_reference_value() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        // Long enough to need the modulo in the middle
+        assert_eq!(adler32(&[0xff; 10_000]), 0xb623_eb2b);
+    }
+
+    #[test]
+    fn decodes_a_stored_block() {
+        let image = decode(&png(3, 2, COLOR_GRAY, &stored(&[0, 1, 2, 3, 0, 4, 5, 6]))).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.rgba[..4], [1, 1, 1, 255]);
+        assert_eq!(gray(&image), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn decodes_a_fixed_huffman_block() {
+        // zlib.compress of 4 unfiltered rows of 10, 20, 30, 40, 10, 20, 30, 40
+        let zlib = [
+            0x78, 0xda, 0x63, 0xe0, 0x12, 0x91, 0xd3, 0x00, 0x61, 0x06, 0xdc, 0x0c, 0x00, 0x36, 0xd4, 0x03, 0x21,
+        ];
+        assert_eq!((zlib[2] >> 1) & 3, 1);
+        let image = decode(&png(8, 4, COLOR_GRAY, &zlib)).unwrap();
+        assert_eq!(gray(&image), [10, 20, 30, 40].repeat(8));
+    }
+
+    #[test]
+    fn decodes_a_dynamic_huffman_block() {
+        // zlib.compress of unfiltered rows of (x * x + 7 * y) % 5 * 50
+        let zlib = [
+            0x78, 0xda, 0x05, 0xc1, 0x81, 0x00, 0x00, 0x00, 0x00, 0xc2, 0xb0, 0x33, 0x9d, 0x29, 0xa6, 0x98,
+            0xc2, 0x6a, 0x03, 0x37, 0x49, 0xb5, 0x8c, 0x16, 0x0c, 0xe4, 0x5a, 0xea, 0x06, 0xd7,
+        ];
+        assert_eq!((zlib[2] >> 1) & 3, 2);
+        let image = decode(&png(5, 4, COLOR_GRAY, &zlib)).unwrap();
+        let expected: Vec<u8> = (0..4).flat_map(|y| (0..5).map(move |x| (x * x + 7 * y) % 5 * 50)).collect();
+        assert_eq!(gray(&image), expected);
+    }
+
+    #[test]
+    fn undoes_each_filter_type() {
+        // The second row is 1, 2, 3 under each filter, below an unfiltered 10, 20, 30
+        let expected: [[u8; 3]; 5] = [[1, 2, 3], [1, 3, 6], [11, 22, 33], [6, 15, 25], [11, 22, 33]];
+        for (filter, expected) in expected.into_iter().enumerate() {
+            let raw = [0, 10, 20, 30, filter as u8, 1, 2, 3];
+            let image = decode(&png(3, 2, COLOR_GRAY, &stored(&raw))).unwrap();
+            assert_eq!(gray(&image)[3..], expected, "filter {filter}");
+        }
+        // Sub and Paeth count whole pixels back, not bytes; sums wrap
+        let raw = [1, 200, 0, 100, 0, 100, 7];
+        let image = decode(&png(3, 1, COLOR_GRAY_ALPHA, &stored(&raw))).unwrap();
+        assert_eq!(image.rgba, [200, 200, 200, 0, 44, 44, 44, 0, 144, 144, 144, 7]);
+    }
+
+    #[test]
+    fn expands_every_color_type_to_rgba() {
+        let rgb = decode(&png(1, 1, COLOR_RGB, &stored(&[0, 1, 2, 3]))).unwrap();
+        assert_eq!(rgb.rgba, [1, 2, 3, 255]);
+        let rgba = decode(&png(1, 1, COLOR_RGBA, &stored(&[0, 1, 2, 3, 4]))).unwrap();
+        assert_eq!(rgba.rgba, [1, 2, 3, 4]);
+        let gray_alpha = decode(&png(1, 1, COLOR_GRAY_ALPHA, &stored(&[0, 9, 8]))).unwrap();
+        assert_eq!(gray_alpha.rgba, [9, 9, 9, 8]);
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let file = png(3, 2, COLOR_GRAY, &stored(&[0, 1, 2, 3, 0, 4, 5, 6]));
+        assert!(decode(&file[..4]).is_err());
+        // Cut inside the IDAT chunk
+        assert!(decode(&file[..50]).is_err());
+        // Cut before any IDAT chunk
+        assert!(decode(&file[..33]).is_err());
+
+        let zlib = stored(&[0, 1, 2, 3, 0, 4, 5, 6]);
+        for len in [0, 1, 2, 5, zlib.len() - 5, zlib.len() - 1] {
+            assert!(decode(&png(3, 2, COLOR_GRAY, &zlib[..len])).is_err(), "{len} bytes of zlib");
+        }
+        // A complete stream holding too few rows
+        assert!(decode(&png(3, 2, COLOR_GRAY, &stored(&[0, 1, 2, 3]))).is_err());
+    }
+
+    #[test]
+    fn rejects_a_wrong_adler32() {
+        let mut zlib = stored(&[0, 1, 2, 3]);
+        *zlib.last_mut().unwrap() ^= 1;
+        assert!(decode(&png(3, 1, COLOR_GRAY, &zlib)).is_err());
+    }
+
+    #[test]
+    fn stops_inflating_at_the_image_size() {
+        // A stored block and a fixed block each holding one row too many
+        assert!(decode(&png(3, 1, COLOR_GRAY, &stored(&[0, 1, 2, 3, 0, 4, 5, 6]))).is_err());
+        let fixed = [
+            0x78, 0xda, 0x63, 0xe0, 0x12, 0x91, 0xd3, 0x00, 0x61, 0x06, 0xdc, 0x0c, 0x00, 0x36, 0xd4, 0x03, 0x21,
+        ];
+        assert!(decode(&png(8, 3, COLOR_GRAY, &fixed)).is_err());
+        assert_eq!(inflate(&fixed[2..], 36).unwrap().0.len(), 36);
+        assert!(inflate(&fixed[2..], 35).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_filters_and_formats() {
+        assert!(decode(&png(1, 1, COLOR_GRAY, &stored(&[5, 0]))).is_err());
+        assert!(decode(&png(1, 1, 3, &stored(&[0, 0]))).is_err());
+        assert!(decode(b"GIF89a").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Screen and window capture through xdg-desktop-portal (`portal` feature).
+//!
+//! Uses `org.freedesktop.portal.Screenshot`: the portal writes a PNG, reports
+//! its URI in the `Response` signal of the request object, and leaves the file
+//! for the caller to delete. Window captures are cropped out of a full
+//! screenshot using the backend's window geometry, so they need a backend
+//! that reports it.
+//!
+//! The ScreenCast portal is not used: grabbing a frame from it requires a
+//! PipeWire client, which this crate does not implement.
+
+use super::{
+    dbus::{self, Value},
+    png,
+};
+use crate::{PermissionDenied, Unsupported};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PATH: &str = "/org/freedesktop/portal/desktop";
+const SCREENSHOT_INTERFACE: &str = "org.freedesktop.portal.Screenshot";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+/// `Response` codes: 1 means the user cancelled the dialog, 2 that the
+/// interaction ended otherwise, which is how a denied permission is reported.
+const RESPONSE_SUCCESS: u32 = 0;
+const RESPONSE_CANCELLED: u32 = 1;
+const RESPONSE_ENDED: u32 = 2;
+
+/// A captured image as tightly packed, non-premultiplied RGBA rows (top row first).
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Decode the `%XX` escapes of a `file://` URI into a path.
+fn path_from_uri(uri: &str) -> crate::Result<String> {
+    let encoded = uri
+        .strip_prefix("file://")
+        .ok_or_else(|| format!("The portal returned a non-file URI: {uri}"))?;
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let [byte, tail @ ..] = rest {
+        match (byte, tail) {
+            (b'%', [high, low, after @ ..]) => {
+                let hex = std::str::from_utf8(&[*high, *low])?.to_owned();
+                bytes.push(u8::from_str_radix(&hex, 16)?);
+                rest = after;
+            }
+            _ => {
+                bytes.push(*byte);
+                rest = tail;
+            }
+        }
+    }
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Take a screenshot through the portal, waiting for the user if a dialog is shown.
+fn screenshot(operation: &'static str) -> crate::Result<png::Image> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut conn = dbus::Connection::session()?;
+    conn.add_match(&format!(
+        "type='signal',interface='{REQUEST_INTERFACE}',member='Response'"
+    ))?;
+    let token = format!(
+        "windowing{}_{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let reply = conn.call(
+        DESTINATION,
+        PATH,
+        SCREENSHOT_INTERFACE,
+        "Screenshot",
+        vec![
+            Value::String(String::new()),
+            Value::dict(vec![
+                ("handle_token", Value::String(token)),
+                ("interactive", Value::Bool(false)),
+            ]),
+        ],
+    )?;
+    let request = reply
+        .first()
+        .and_then(Value::as_str)
+        .ok_or("The screenshot portal returned no request handle")?
+        .to_string();
+
+    let results = loop {
+        let message = conn
+            .next_message(None)?
+            .ok_or("The D-Bus connection stalled waiting for the portal")?;
+        if message.kind == dbus::SIGNAL
+            && message.member.as_deref() == Some("Response")
+            && message.path.as_deref() == Some(request.as_str())
+        {
+            break message.body;
+        }
+    };
+    match results.first().and_then(Value::as_u32) {
+        Some(RESPONSE_SUCCESS) => {}
+        Some(RESPONSE_CANCELLED | RESPONSE_ENDED) => return Err(PermissionDenied::new(operation).into()),
+        other => return Err(format!("The screenshot portal failed (response {other:?})").into()),
+    }
+
+    let uri = results
+        .get(1)
+        .and_then(|results| results.lookup("uri"))
+        .and_then(Value::as_str)
+        .ok_or("The screenshot portal returned no image URI")?;
+    let path = path_from_uri(uri)?;
+    let data = std::fs::read(&path);
+    // Removed whether or not it could be read, so failed captures don't pile up
+    // in the user's screenshot folder; failing to remove it isn't worth an error
+    let _ = std::fs::remove_file(&path);
+    png::decode(&data?)
+}
+
+/// Capture every monitor through the Screenshot portal.
+///
+/// The desktop may show a permission dialog; refusing it yields `PermissionDenied`.
+pub fn capture_screen_portal() -> crate::Result<Capture> {
+    let image = screenshot("capture_screen_portal")?;
+    Ok(Capture {
+        width: image.width,
+        height: image.height,
+        rgba: image.rgba,
+    })
+}
+
+/// Capture a window through the Screenshot portal.
+///
+/// The screenshot is cropped to the window's frame, so overlapping windows
+/// show through. Backends without geometry (e.g. `wayland-wlr`) can't say
+/// where to crop and yield `Unsupported`; the portal's interactive mode isn't
+/// used instead, since the user could pick any area rather than `window`.
+pub fn capture_window_portal(window: crate::Window) -> crate::Result<Capture> {
+    const OPERATION: &str = "capture_window_portal";
+    let info = match crate::get_window_info(window) {
+        Ok(info) => info,
+        Err(crate::Error::Unsupported(unsupported)) => {
+            return Err(Unsupported::new(OPERATION, unsupported.backend).into());
+        }
+        Err(e) => return Err(e),
+    };
+    let image = screenshot(OPERATION)?;
+
+    // The image is in physical pixels of the whole layout; scale from layout coordinates
+    let (origin, scale) = match crate::get_virtual_screen() {
+        Ok(screen) if screen.size.0 > 0 => (screen.pos, image.width as f64 / screen.size.0 as f64),
+        _ => ((0, 0), 1.0),
+    };
+    let to_pixels = |value: i32, origin: i32, limit: u32| {
+        (((value - origin) as f64 * scale).round().max(0.0) as u32).min(limit)
+    };
+    let left = to_pixels(info.pos.0, origin.0, image.width);
+    let top = to_pixels(info.pos.1, origin.1, image.height);
+    let right = to_pixels(info.pos.0 + info.size.0 as i32, origin.0, image.width);
+    let bottom = to_pixels(info.pos.1 + info.size.1 as i32, origin.1, image.height);
+    if right <= left || bottom <= top {
+        return Err(format!("Window {window} is outside the captured screen").into());
+    }
+
+    let stride = image.width as usize * 4;
+    let rgba = (top..bottom)
+        .flat_map(|y| {
+            let row = y as usize * stride;
+            image.rgba[row + left as usize * 4..row + right as usize * 4].iter().copied()
+        })
+        .collect();
+    Ok(Capture {
+        width: right - left,
+        height: bottom - top,
+        rgba,
+    })
+}
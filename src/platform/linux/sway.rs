@@ -0,0 +1,464 @@
+//! `sway-ipc` backend speaking the i3 IPC protocol (sway, and i3 on X11).
+//!
+//! Messages are framed as `"i3-ipc"`, a native-endian payload length and
+//! message type, followed by a JSON payload. Container ids from `get_tree`
+//! are used directly as the crate's `Window`.
+
+use crate::{
+    geometry::{monitor_of, monitors_of, to_physical},
+    json, BackendKind, MonitorInfo, PhysicalWindowInfo, WindowEvent, WindowEvents, WindowInfo, WindowState,
+    WindowingBackend,
+};
+use std::{
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+};
+
+pub(super) const NAME: &str = "sway-ipc";
+
+const MAGIC: &[u8] = b"i3-ipc";
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_OUTPUTS: u32 = 3;
+const GET_TREE: u32 = 4;
+
+/// Event replies have the high bit set; window events are type 3.
+const EVENT_WINDOW: u32 = 0x8000_0003;
+
+fn socket_path() -> crate::Result<String> {
+    std::env::var("SWAYSOCK")
+        .or_else(|_| std::env::var("I3SOCK"))
+        .map_err(|_| crate::Error::connection("Neither SWAYSOCK nor I3SOCK is set"))
+}
+
+struct Connection {
+    stream: UnixStream,
+}
+
+impl Connection {
+    fn connect() -> crate::Result<Self> {
+        let path = socket_path()?;
+        let stream = UnixStream::connect(&path)
+            .map_err(|e| crate::Error::connection(format!("Failed to connect to i3/sway IPC socket {path}: {e}")))?;
+        Ok(Self { stream })
+    }
+
+    fn send(&mut self, message_type: u32, payload: &str) -> crate::Result<()> {
+        let mut message = Vec::with_capacity(MAGIC.len() + 8 + payload.len());
+        message.extend_from_slice(MAGIC);
+        message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        message.extend_from_slice(&message_type.to_ne_bytes());
+        message.extend_from_slice(payload.as_bytes());
+        self.stream.write_all(&message)?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> crate::Result<(u32, json::Value)> {
+        let mut header = [0u8; 14];
+        self.stream.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err("Invalid i3-ipc reply header".into());
+        }
+        let len = u32::from_ne_bytes(header[6..10].try_into().unwrap()) as usize;
+        let message_type = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+        let mut payload = vec![0u8; len];
+        self.stream.read_exact(&mut payload)?;
+        Ok((message_type, json::parse(&String::from_utf8_lossy(&payload))?))
+    }
+
+    fn request(&mut self, message_type: u32, payload: &str) -> crate::Result<json::Value> {
+        self.send(message_type, payload)?;
+        loop {
+            let (reply_type, reply) = self.receive()?;
+            if reply_type == message_type {
+                return Ok(reply);
+            }
+        }
+    }
+}
+
+fn request(message_type: u32, payload: &str) -> crate::Result<json::Value> {
+    Connection::connect()?.request(message_type, payload)
+}
+
+/// Run a command; the reply holds one `{success, error}` object per command.
+fn command(command: &str) -> crate::Result<()> {
+    let reply = request(RUN_COMMAND, command)?;
+    for outcome in reply.as_array() {
+        if outcome.get("success").as_bool() != Some(true) {
+            let error = outcome.get("error").as_str().unwrap_or("unknown error");
+            return Err(format!("`{command}` failed: {error}").into());
+        }
+    }
+    Ok(())
+}
+
+/// Whether an i3/sway IPC socket is reachable.
+pub(super) fn is_available() -> bool {
+    socket_path().is_ok() && request(GET_WORKSPACES, "").is_ok()
+}
+
+/// A window container and the workspace it lives on.
+struct Node {
+    container: json::Value,
+    workspace: Option<u32>,
+}
+
+fn is_window(node: &json::Value) -> bool {
+    matches!(node.get("type").as_str(), Some("con" | "floating_con"))
+        && (node.get("pid").as_i64().is_some() || node.get("window").as_i64().is_some())
+}
+
+/// Workspace `num` is 1-based; named-only workspaces report -1.
+fn workspace_index(node: &json::Value) -> Option<u32> {
+    u32::try_from(node.get("num").as_i64()?).ok()?.checked_sub(1)
+}
+
+fn collect_windows(node: &json::Value, workspace: Option<u32>, out: &mut Vec<Node>) {
+    let workspace = if node.get("type").as_str() == Some("workspace") {
+        workspace_index(node)
+    } else {
+        workspace
+    };
+    if is_window(node) {
+        out.push(Node {
+            container: node.clone(),
+            workspace,
+        });
+    }
+    for child in node
+        .get("nodes")
+        .as_array()
+        .iter()
+        .chain(node.get("floating_nodes").as_array())
+    {
+        collect_windows(child, workspace, out);
+    }
+}
+
+fn windows() -> crate::Result<Vec<Node>> {
+    let tree = request(GET_TREE, "")?;
+    let mut out = Vec::new();
+    collect_windows(&tree, None, &mut out);
+    Ok(out)
+}
+
+fn node_id(node: &json::Value) -> Option<crate::Window> {
+    node.get("id").as_i64().and_then(|id| u32::try_from(id).ok()).map(crate::Window::from)
+}
+
+fn find_node(window: crate::Window) -> crate::Result<Node> {
+    windows()?
+        .into_iter()
+        .find(|node| node_id(&node.container) == Some(window))
+        .ok_or_else(|| crate::Error::WindowNotFound(window))
+}
+
+pub fn get_window_info(window: crate::Window) -> crate::Result<WindowInfo> {
+    let node = find_node(window)?;
+    let rect = node.container.get("rect");
+    let field = |name| rect.get(name).as_i64().ok_or("Container has no rect");
+    Ok(WindowInfo {
+        pos: (field("x")? as i32, field("y")? as i32),
+        size: (field("width")? as u32, field("height")? as u32),
+    })
+}
+
+/// Each active output in layout coordinates (scale is always 1 under i3).
+pub fn outputs() -> crate::Result<Vec<MonitorInfo>> {
+    let outputs = request(GET_OUTPUTS, "")?;
+    Ok(outputs
+        .as_array()
+        .iter()
+        .filter(|output| output.get("active").as_bool() == Some(true))
+        .filter_map(|output| {
+            let rect = output.get("rect");
+            let rect = WindowInfo {
+                pos: (rect.get("x").as_i64()? as i32, rect.get("y").as_i64()? as i32),
+                size: (rect.get("width").as_i64()? as u32, rect.get("height").as_i64()? as u32),
+            };
+            let scale = match output.get("scale") {
+                json::Value::Number(scale) if *scale > 0.0 => *scale,
+                _ => 1.0,
+            };
+            let name = output.get("name").as_str()?.to_string();
+            Some(MonitorInfo { name, bounds: rect, scale_factor: scale })
+        })
+        .collect())
+}
+
+pub fn list_windows() -> crate::Result<Vec<crate::Window>> {
+    Ok(windows()?.iter().filter_map(|node| node_id(&node.container)).collect())
+}
+
+/// Only sway reports PIDs; under i3 this finds nothing.
+pub fn find_windows_by_pid(target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+    Ok(windows()?
+        .iter()
+        .filter(|node| node.container.get("pid").as_i64() == Some(target_pid as i64))
+        .filter_map(|node| node_id(&node.container))
+        .collect())
+}
+
+pub fn find_window_by_pid(target_pid: u32) -> crate::Result<Option<crate::Window>> {
+    Ok(find_windows_by_pid(target_pid)?.into_iter().next())
+}
+
+fn focused() -> crate::Result<Option<json::Value>> {
+    Ok(windows()?
+        .into_iter()
+        .map(|node| node.container)
+        .find(|container| container.get("focused").as_bool() == Some(true)))
+}
+
+pub fn get_active_window() -> crate::Result<Option<crate::Window>> {
+    Ok(focused()?.as_ref().and_then(node_id))
+}
+
+pub fn get_active_window_pid() -> crate::Result<Option<u32>> {
+    Ok(focused()?
+        .and_then(|container| container.get("pid").as_i64())
+        .and_then(|pid| u32::try_from(pid).ok()))
+}
+
+pub fn get_window_pid(window: crate::Window) -> crate::Result<Option<u32>> {
+    Ok(find_node(window)?
+        .container
+        .get("pid")
+        .as_i64()
+        .and_then(|pid| u32::try_from(pid).ok()))
+}
+
+pub fn get_window_title(window: crate::Window) -> crate::Result<Option<String>> {
+    Ok(find_node(window)?.container.get("name").as_str().map(String::from))
+}
+
+/// The Wayland app_id, or the X11 class for XWayland/i3 windows.
+pub fn get_window_app_id(window: crate::Window) -> crate::Result<Option<String>> {
+    let node = find_node(window)?;
+    let container = &node.container;
+    Ok(container
+        .get("app_id")
+        .as_str()
+        .or_else(|| container.get("window_properties").get("class").as_str())
+        .map(String::from))
+}
+
+/// Moves a window in layout coordinates. Only floating windows can be positioned freely.
+pub fn move_window(window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+    command(&format!("[con_id={window}] move absolute position {x} {y}"))
+}
+
+pub fn resize_window(window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+    command(&format!("[con_id={window}] resize set {width} px {height} px"))
+}
+
+pub fn focus_window(window: crate::Window) -> crate::Result<()> {
+    command(&format!("[con_id={window}] focus"))
+}
+
+pub fn is_sticky(window: crate::Window) -> crate::Result<bool> {
+    Ok(find_node(window)?.container.get("sticky").as_bool() == Some(true))
+}
+
+/// Sway only keeps floating windows on screen across workspaces
+pub fn set_sticky(window: crate::Window, sticky: bool) -> crate::Result<()> {
+    let action = if sticky { "enable" } else { "disable" };
+    command(&format!("[con_id={window}] sticky {action}"))
+}
+
+pub fn close_window(window: crate::Window) -> crate::Result<()> {
+    command(&format!("[con_id={window}] kill"))
+}
+
+pub fn get_window_workspace(window: crate::Window) -> crate::Result<Option<u32>> {
+    Ok(find_node(window)?.workspace)
+}
+
+pub fn move_window_to_workspace(window: crate::Window, workspace: u32) -> crate::Result<()> {
+    command(&format!(
+        "[con_id={window}] move container to workspace number {}",
+        workspace + 1
+    ))
+}
+
+pub fn get_current_workspace() -> crate::Result<Option<u32>> {
+    Ok(request(GET_WORKSPACES, "")?
+        .as_array()
+        .iter()
+        .find(|workspace| workspace.get("focused").as_bool() == Some(true))
+        .and_then(workspace_index))
+}
+
+pub fn switch_workspace(workspace: u32) -> crate::Result<()> {
+    command(&format!("workspace number {}", workspace + 1))
+}
+
+/// Tiling layouts have no minimize/maximize; those states are never reported.
+pub fn window_has_state(window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    let Some(node) = windows()?
+        .into_iter()
+        .find(|node| node_id(&node.container) == Some(window))
+    else {
+        return Ok(state == WindowState::Closed);
+    };
+    let container = &node.container;
+    Ok(match state {
+        WindowState::Closed | WindowState::Minimized | WindowState::Maximized => false,
+        // i3 has no `visible` field; treat its windows as visible
+        WindowState::Visible => container.get("visible").as_bool() != Some(false),
+        WindowState::Fullscreen => container.get("fullscreen_mode").as_i64().unwrap_or(0) != 0,
+        WindowState::Focused => container.get("focused").as_bool() == Some(true),
+    })
+}
+
+/// Translate a `window` event payload into a crate event.
+fn parse_event(payload: &json::Value) -> Option<WindowEvent> {
+    let window = node_id(payload.get("container"))?;
+    match payload.get("change").as_str()? {
+        "new" => Some(WindowEvent::Opened(window)),
+        "close" => Some(WindowEvent::Closed(window)),
+        "focus" => Some(WindowEvent::Focused(Some(window))),
+        "title" => Some(WindowEvent::TitleChanged(window)),
+        "move" => Some(WindowEvent::WorkspaceChanged(
+            window,
+            get_window_workspace(window).ok().flatten(),
+        )),
+        _ => None,
+    }
+}
+
+pub fn subscribe_window_events() -> crate::Result<WindowEvents> {
+    let mut conn = Connection::connect()?;
+    let reply = conn.request(SUBSCRIBE, r#"["window"]"#)?;
+    if reply.get("success").as_bool() != Some(true) {
+        return Err("The i3/sway IPC server rejected the window event subscription".into());
+    }
+    Ok(WindowEvents::new(std::iter::from_fn(move || loop {
+        match conn.receive() {
+            Ok((EVENT_WINDOW, payload)) => {
+                if let Some(event) = parse_event(&payload) {
+                    return Some(Ok(event));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => return Some(Err(e)),
+        }
+    })))
+}
+
+/// The `sway-ipc` backend; every call opens its own IPC socket connection
+#[derive(Debug, Default)]
+pub(super) struct SwayBackend;
+
+impl WindowingBackend for SwayBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::SwayIpc
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        list_windows()
+    }
+
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        get_window_info(window)
+    }
+
+    fn get_window_bounds(&self, window: crate::Window) -> crate::Result<PhysicalWindowInfo> {
+        Ok(to_physical(get_window_info(window)?, &outputs()?))
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        find_window_by_pid(target_pid)
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        find_windows_by_pid(target_pid)
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        get_active_window()
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        get_active_window_pid()
+    }
+
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        get_window_pid(window)
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_title(window)
+    }
+
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_app_id(window)
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        close_window(window)
+    }
+
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        move_window(window, x, y)
+    }
+
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        resize_window(window, width, height)
+    }
+
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn activate_window(&self, window: crate::Window, _update_user_time: bool) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn get_window_workspace(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        get_window_workspace(window)
+    }
+
+    fn move_window_to_workspace(&self, window: crate::Window, workspace: u32) -> crate::Result<()> {
+        move_window_to_workspace(window, workspace)
+    }
+
+    fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+        get_current_workspace()
+    }
+
+    fn switch_workspace(&self, workspace: u32) -> crate::Result<()> {
+        switch_workspace(workspace)
+    }
+
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        subscribe_window_events()
+    }
+
+    fn list_monitors(&self) -> crate::Result<Vec<MonitorInfo>> {
+        outputs()
+    }
+
+    fn get_window_monitor(&self, window: crate::Window) -> crate::Result<Option<MonitorInfo>> {
+        Ok(monitor_of(&get_window_info(window)?, &outputs()?).cloned())
+    }
+
+    fn get_window_monitors(&self, window: crate::Window) -> crate::Result<Vec<MonitorInfo>> {
+        Ok(monitors_of(&get_window_info(window)?, &outputs()?))
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        window_has_state(window, state)
+    }
+
+    fn is_sticky(&self, window: crate::Window) -> crate::Result<bool> {
+        is_sticky(window)
+    }
+
+    fn set_sticky(&self, window: crate::Window, sticky: bool) -> crate::Result<()> {
+        set_sticky(window, sticky)
+    }
+}
@@ -0,0 +1,427 @@
+//! Minimal Wayland wire-protocol client.
+//!
+//! Only what the Wayland backends need: a socket connection, request
+//! marshalling, event parsing and `wl_display.sync` round trips. Objects are
+//! plain ids; interpreting events is left to the backend.
+
+use std::{
+    io::{ErrorKind, Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// The `wl_display` singleton always has object id 1.
+pub(super) const DISPLAY_ID: u32 = 1;
+
+const DISPLAY_SYNC: u16 = 0;
+const DISPLAY_GET_REGISTRY: u16 = 1;
+const DISPLAY_EVENT_ERROR: u16 = 0;
+const REGISTRY_BIND: u16 = 0;
+const REGISTRY_EVENT_GLOBAL: u16 = 0;
+const CALLBACK_EVENT_DONE: u16 = 0;
+
+/// How long a round trip waits for the compositor before failing with
+/// `Error::Timeout`, so a hung compositor can't block a call forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A global advertised by the compositor's registry.
+#[derive(Debug, Clone)]
+pub(super) struct Global {
+    pub name: u32,
+    pub interface: String,
+    pub version: u32,
+}
+
+/// A single decoded event.
+#[derive(Debug)]
+pub(super) struct Event {
+    pub object: u32,
+    pub opcode: u16,
+    pub args: Vec<u8>,
+}
+
+impl Event {
+    pub fn reader(&self) -> ArgReader<'_> {
+        ArgReader { data: &self.args }
+    }
+}
+
+/// Sequential reader over an event's argument payload.
+pub(super) struct ArgReader<'a> {
+    data: &'a [u8],
+}
+
+impl ArgReader<'_> {
+    pub fn uint(&mut self) -> crate::Result<u32> {
+        if self.data.len() < 4 {
+            return Err("Truncated Wayland event".into());
+        }
+        let (head, rest) = self.data.split_at(4);
+        self.data = rest;
+        Ok(u32::from_ne_bytes(head.try_into().unwrap()))
+    }
+
+    pub fn array(&mut self) -> crate::Result<&[u8]> {
+        let len = self.uint()? as usize;
+        let padded = (len + 3) & !3;
+        if self.data.len() < padded {
+            return Err("Truncated Wayland event".into());
+        }
+        let (head, rest) = self.data.split_at(padded);
+        self.data = rest;
+        Ok(&head[..len])
+    }
+
+    /// Reads a string argument; a null string is returned as `None`.
+    pub fn string(&mut self) -> crate::Result<Option<String>> {
+        let bytes = self.array()?;
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        // Strip the trailing NUL the wire format includes in the length
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Ok(Some(String::from_utf8_lossy(bytes).into_owned()))
+    }
+}
+
+/// Builder for a request's argument payload.
+#[derive(Default)]
+pub(super) struct Args {
+    data: Vec<u8>,
+}
+
+impl Args {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn uint(mut self, value: u32) -> Self {
+        self.data.extend_from_slice(&value.to_ne_bytes());
+        self
+    }
+
+    pub fn string(mut self, value: &str) -> Self {
+        let len = value.len() + 1;
+        self.data.extend_from_slice(&(len as u32).to_ne_bytes());
+        self.data.extend_from_slice(value.as_bytes());
+        self.data.push(0);
+        self.pad();
+        self
+    }
+
+    fn pad(&mut self) {
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+    }
+}
+
+/// A connection to the compositor's Wayland socket.
+pub(super) struct Connection {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+    next_id: u32,
+    request_timeout: Duration,
+}
+
+impl Connection {
+    /// Connect to `$WAYLAND_DISPLAY` (relative to `$XDG_RUNTIME_DIR` unless absolute).
+    pub fn connect() -> crate::Result<Self> {
+        let display = std::env::var_os("WAYLAND_DISPLAY").ok_or(crate::Error::NoDisplay { display: None })?;
+        let mut path = PathBuf::from(&display);
+        if path.is_relative() {
+            let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+                .ok_or_else(|| crate::Error::connection("XDG_RUNTIME_DIR is not set"))?;
+            path = PathBuf::from(runtime_dir).join(display);
+        }
+        let stream = UnixStream::connect(&path)
+            .map_err(|_| crate::Error::NoDisplay { display: Some(path.display().to_string()) })?;
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            next_id: DISPLAY_ID + 1,
+            request_timeout: REQUEST_TIMEOUT,
+        })
+    }
+
+    /// A connection over an already connected socket, e.g. one end of a pair.
+    #[cfg(test)]
+    pub fn from_stream(stream: UnixStream, request_timeout: Duration) -> Self {
+        Self { stream, buffer: Vec::new(), next_id: DISPLAY_ID + 1, request_timeout }
+    }
+
+    /// Allocate a fresh client-side object id.
+    pub fn new_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Send a request to `object`.
+    pub fn send(&mut self, object: u32, opcode: u16, args: Args) -> crate::Result<()> {
+        let size = 8 + args.data.len();
+        let mut message = Vec::with_capacity(size);
+        message.extend_from_slice(&object.to_ne_bytes());
+        message.extend_from_slice(&(((size as u32) << 16) | opcode as u32).to_ne_bytes());
+        message.extend_from_slice(&args.data);
+        self.stream.write_all(&message)?;
+        Ok(())
+    }
+
+    /// Ask the registry to bind `global` as a new object and return its id.
+    pub fn bind(
+        &mut self,
+        registry: u32,
+        global: &Global,
+        version: u32,
+    ) -> crate::Result<u32> {
+        let id = self.new_id();
+        self.send(
+            registry,
+            REGISTRY_BIND,
+            Args::new()
+                .uint(global.name)
+                .string(&global.interface)
+                .uint(version.min(global.version))
+                .uint(id),
+        )?;
+        Ok(id)
+    }
+
+    /// Create the registry and collect the globals it initially advertises.
+    pub fn registry(&mut self) -> crate::Result<(u32, Vec<Global>)> {
+        let registry = self.new_id();
+        self.send(DISPLAY_ID, DISPLAY_GET_REGISTRY, Args::new().uint(registry))?;
+        let mut globals = Vec::new();
+        self.roundtrip(|event| {
+            if event.object == registry && event.opcode == REGISTRY_EVENT_GLOBAL {
+                let mut args = event.reader();
+                let name = args.uint()?;
+                let interface = args.string()?.unwrap_or_default();
+                let version = args.uint()?;
+                globals.push(Global {
+                    name,
+                    interface,
+                    version,
+                });
+            }
+            Ok(())
+        })?;
+        Ok((registry, globals))
+    }
+
+    /// Block until the next event arrives, or until `deadline` passes (`Ok(None)`).
+    pub fn read_event(&mut self, deadline: Option<Instant>) -> crate::Result<Option<Event>> {
+        loop {
+            if self.buffer.len() >= 8 {
+                let object = u32::from_ne_bytes(self.buffer[0..4].try_into().unwrap());
+                let header = u32::from_ne_bytes(self.buffer[4..8].try_into().unwrap());
+                let size = (header >> 16) as usize;
+                if size < 8 {
+                    // Nothing after this can be framed either
+                    self.buffer.clear();
+                    return Err("Malformed Wayland event header".into());
+                }
+                if self.buffer.len() >= size {
+                    let args = self.buffer[8..size].to_vec();
+                    self.buffer.drain(..size);
+                    let event = Event {
+                        object,
+                        opcode: (header & 0xffff) as u16,
+                        args,
+                    };
+                    if event.object == DISPLAY_ID && event.opcode == DISPLAY_EVENT_ERROR {
+                        let mut args = event.reader();
+                        let object = args.uint()?;
+                        let code = args.uint()?;
+                        let message = args.string()?.unwrap_or_default();
+                        return Err(format!(
+                            "Wayland protocol error on object {object} (code {code}): {message}"
+                        )
+                        .into());
+                    }
+                    return Ok(Some(event));
+                }
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) if !left.is_zero() => Some(left),
+                    _ => return Ok(None),
+                },
+                None => None,
+            };
+            self.stream.set_read_timeout(timeout)?;
+
+            let mut chunk = [0u8; 4096];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err("Wayland compositor closed the connection".into()),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return Ok(None);
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Send `wl_display.sync` and feed every event to `handler` until the
+    /// callback fires, failing with `Error::Timeout` if it hasn't within the
+    /// request timeout.
+    pub fn roundtrip(
+        &mut self,
+        mut handler: impl FnMut(Event) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        let callback = self.new_id();
+        self.send(DISPLAY_ID, DISPLAY_SYNC, Args::new().uint(callback))?;
+        let deadline = Instant::now() + self.request_timeout;
+        loop {
+            let event = self.read_event(Some(deadline))?.ok_or(crate::Error::Timeout)?;
+            if event.object == callback && event.opcode == CALLBACK_EVENT_DONE {
+                return Ok(());
+            }
+            handler(event)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A connection and the compositor's end of its socket.
+    fn connection() -> (Connection, UnixStream) {
+        let (client, compositor) = UnixStream::pair().unwrap();
+        (Connection::from_stream(client, Duration::from_millis(50)), compositor)
+    }
+
+    fn event(object: u32, opcode: u16, args: Args) -> Vec<u8> {
+        let size = 8 + args.data.len() as u32;
+        [&object.to_ne_bytes()[..], &(size << 16 | opcode as u32).to_ne_bytes(), &args.data].concat()
+    }
+
+    fn soon() -> Option<Instant> {
+        Some(Instant::now() + Duration::from_millis(50))
+    }
+
+    #[test]
+    fn strings_are_nul_terminated_and_padded() {
+        let args = Args::new().string("abc").string("abcd").string("").uint(7);
+        let expected = [
+            &4u32.to_ne_bytes()[..],
+            b"abc\0",
+            &5u32.to_ne_bytes(),
+            b"abcd\0\0\0\0",
+            &1u32.to_ne_bytes(),
+            &[0; 4],
+            &7u32.to_ne_bytes(),
+        ]
+        .concat();
+        assert_eq!(args.data, expected);
+
+        let mut reader = ArgReader { data: &args.data };
+        assert_eq!(reader.string().unwrap().as_deref(), Some("abc"));
+        assert_eq!(reader.string().unwrap().as_deref(), Some("abcd"));
+        assert_eq!(reader.string().unwrap().as_deref(), Some(""));
+        assert_eq!(reader.uint().unwrap(), 7);
+        assert!(reader.uint().is_err());
+    }
+
+    #[test]
+    fn arrays_skip_their_padding() {
+        let data = Args::new().uint(5).uint(u32::from_ne_bytes([1, 2, 3, 4])).uint(5).uint(9).data;
+        let mut reader = ArgReader { data: &data };
+        assert_eq!(reader.array().unwrap(), [1, 2, 3, 4, 5]);
+        assert_eq!(reader.uint().unwrap(), 9);
+        let empty = Args::new().uint(0).uint(3).data;
+        let mut reader = ArgReader { data: &empty };
+        assert!(reader.array().unwrap().is_empty());
+        assert_eq!(reader.uint().unwrap(), 3);
+    }
+
+    #[test]
+    fn a_zero_length_string_is_null() {
+        let data = Args::new().uint(0).data;
+        assert_eq!(ArgReader { data: &data }.string().unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_arguments_are_errors() {
+        // The length says 8 bytes, 4 follow
+        let data = Args::new().uint(8).uint(0).data;
+        assert!(ArgReader { data: &data }.array().is_err());
+        assert!(ArgReader { data: &data }.string().is_err());
+        assert!(ArgReader { data: &[1, 2] }.uint().is_err());
+        // Unpadded: 5 bytes of string, 3 of padding missing
+        let data = [Args::new().uint(5).data, b"abcd\0".to_vec()].concat();
+        assert!(ArgReader { data: &data }.string().is_err());
+    }
+
+    #[test]
+    fn events_are_read_once_complete() {
+        let (mut conn, mut compositor) = connection();
+        let bytes = event(3, 2, Args::new().uint(42).string("title"));
+        compositor.write_all(&bytes[..10]).unwrap();
+        assert!(conn.read_event(soon()).unwrap().is_none());
+        compositor.write_all(&bytes[10..]).unwrap();
+
+        let event = conn.read_event(soon()).unwrap().unwrap();
+        assert_eq!((event.object, event.opcode), (3, 2));
+        let mut args = event.reader();
+        assert_eq!(args.uint().unwrap(), 42);
+        assert_eq!(args.string().unwrap().as_deref(), Some("title"));
+    }
+
+    #[test]
+    fn a_truncated_event_fails_once_the_compositor_hangs_up() {
+        let (mut conn, mut compositor) = connection();
+        compositor.write_all(&event(3, 0, Args::new().uint(1))[..10]).unwrap();
+        drop(compositor);
+        assert!(conn.read_event(soon()).is_err());
+    }
+
+    #[test]
+    fn a_malformed_header_is_an_error() {
+        let (mut conn, mut compositor) = connection();
+        // A size of 4 can't even hold the header
+        compositor.write_all(&[3u32.to_ne_bytes(), (4u32 << 16).to_ne_bytes()].concat()).unwrap();
+        assert!(conn.read_event(soon()).is_err());
+        // What follows is read from a clean buffer
+        compositor.write_all(&event(4, 0, Args::new())).unwrap();
+        assert_eq!(conn.read_event(soon()).unwrap().unwrap().object, 4);
+    }
+
+    #[test]
+    fn protocol_errors_are_reported() {
+        let (mut conn, mut compositor) = connection();
+        let error = event(DISPLAY_ID, DISPLAY_EVENT_ERROR, Args::new().uint(5).uint(1).string("invalid arguments"));
+        compositor.write_all(&error).unwrap();
+        let message = conn.read_event(soon()).unwrap_err().to_string();
+        assert!(message.contains("object 5 (code 1): invalid arguments"), "{message}");
+    }
+
+    #[test]
+    fn roundtrip_times_out_without_a_reply() {
+        let (mut conn, _compositor) = connection();
+        assert!(matches!(conn.roundtrip(|_| Ok(())), Err(crate::Error::Timeout)));
+    }
+
+    #[test]
+    fn roundtrip_passes_events_until_the_callback_is_done() {
+        let (mut conn, mut compositor) = connection();
+        // The sync callback gets the next id
+        compositor.write_all(&event(7, 1, Args::new())).unwrap();
+        compositor.write_all(&event(DISPLAY_ID + 1, CALLBACK_EVENT_DONE, Args::new().uint(0))).unwrap();
+        compositor.write_all(&event(8, 0, Args::new())).unwrap();
+        let mut seen = Vec::new();
+        conn.roundtrip(|event| {
+            seen.push(event.object);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(seen, [7]);
+        assert_eq!(conn.read_event(soon()).unwrap().unwrap().object, 8);
+    }
+}
@@ -0,0 +1,652 @@
+//! `wayland-wlr` backend built on `zwlr_foreign_toplevel_management_v1`.
+//!
+//! The protocol identifies toplevels by per-connection object ids, so the
+//! backend keeps one connection alive for the lifetime of the process and
+//! builds the crate's `Window` from the handle's object id. The compositor
+//! allocates those ids in `0xff000000..`, so only their low 24 bits are kept;
+//! the top byte holds the connection's generation instead, so a `Window` from
+//! before a reconnect is not found rather than naming whichever toplevel now
+//! has its object id. Every call does a round trip first so the toplevel list
+//! is current.
+//!
+//! Focusing uses the handle's `activate` request with the first `wl_seat`.
+//! `xdg_activation_v1` tokens can't help here: a token only lets the client
+//! that requested it activate its own surfaces, and nothing delivers one to
+//! another application's toplevel.
+
+use super::wayland::{Args, Connection, Event, Global};
+use crate::{
+    BackendKind, FocusRefused, Unsupported, WindowEvent, WindowEvents, WindowState, WindowStateFlag, WindowingBackend,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+pub(super) const NAME: &str = "wayland-wlr";
+
+const MANAGER_INTERFACE: &str = "zwlr_foreign_toplevel_manager_v1";
+const MANAGER_VERSION: u32 = 3;
+const SEAT_INTERFACE: &str = "wl_seat";
+
+const MANAGER_EVENT_TOPLEVEL: u16 = 0;
+const MANAGER_EVENT_FINISHED: u16 = 1;
+
+const HANDLE_EVENT_TITLE: u16 = 0;
+const HANDLE_EVENT_APP_ID: u16 = 1;
+const HANDLE_EVENT_STATE: u16 = 4;
+const HANDLE_EVENT_DONE: u16 = 5;
+const HANDLE_EVENT_CLOSED: u16 = 6;
+
+const HANDLE_SET_MAXIMIZED: u16 = 0;
+const HANDLE_UNSET_MAXIMIZED: u16 = 1;
+const HANDLE_SET_MINIMIZED: u16 = 2;
+const HANDLE_UNSET_MINIMIZED: u16 = 3;
+const HANDLE_ACTIVATE: u16 = 4;
+const HANDLE_CLOSE: u16 = 5;
+const HANDLE_DESTROY: u16 = 7;
+const HANDLE_SET_FULLSCREEN: u16 = 8;
+const HANDLE_UNSET_FULLSCREEN: u16 = 9;
+/// set_fullscreen/unset_fullscreen were added in version 2.
+const FULLSCREEN_SINCE: u32 = 2;
+
+/// How long to wait for the compositor to confirm a requested state change.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The top byte of every object id the compositor allocates.
+const SERVER_ID_BASE: u32 = 0xff00_0000;
+
+const STATE_MAXIMIZED: u32 = 0;
+const STATE_MINIMIZED: u32 = 1;
+const STATE_ACTIVATED: u32 = 2;
+const STATE_FULLSCREEN: u32 = 3;
+
+/// Double-buffered toplevel properties; `pending` is committed on `done`.
+#[derive(Debug, Default, Clone)]
+struct ToplevelProps {
+    title: Option<String>,
+    app_id: Option<String>,
+    states: Vec<u32>,
+}
+
+#[derive(Debug, Default)]
+struct Toplevel {
+    current: ToplevelProps,
+    pending: ToplevelProps,
+    /// Set once the first `done` event has been received.
+    ready: bool,
+}
+
+struct WlrState {
+    conn: Connection,
+    /// In the top byte of this connection's `Window`s; never 0, so no `Window` is 0.
+    generation: u8,
+    manager: u32,
+    manager_version: u32,
+    /// The first advertised seat, needed to activate toplevels.
+    seat: Option<u32>,
+    toplevels: BTreeMap<u32, Toplevel>,
+}
+
+static STATE: Mutex<Option<WlrState>> = Mutex::new(None);
+
+/// Connections made so far, counting toward each one's generation.
+static GENERATION: AtomicU32 = AtomicU32::new(0);
+
+impl WlrState {
+    fn connect() -> crate::Result<Self> {
+        let mut conn = Connection::connect()?;
+        let (registry, globals) = conn.registry()?;
+        let manager_global = find_global(&globals, MANAGER_INTERFACE).ok_or_else(|| {
+            format!("The compositor does not advertise {MANAGER_INTERFACE}")
+        })?;
+        let manager = conn.bind(registry, manager_global, MANAGER_VERSION)?;
+        let manager_version = MANAGER_VERSION.min(manager_global.version);
+        let seat = match find_global(&globals, SEAT_INTERFACE) {
+            Some(global) => Some(conn.bind(registry, global, 1)?),
+            None => None,
+        };
+
+        let mut state = WlrState {
+            conn,
+            generation: (GENERATION.fetch_add(1, Ordering::Relaxed) % 255 + 1) as u8,
+            manager,
+            manager_version,
+            seat,
+            toplevels: BTreeMap::new(),
+        };
+        // The first round trip delivers the toplevel handles, the second their initial properties
+        state.sync()?;
+        state.sync()?;
+        Ok(state)
+    }
+
+    /// Process all events the compositor has queued up to now.
+    fn sync(&mut self) -> crate::Result<()> {
+        let mut events = Vec::new();
+        self.conn.roundtrip(|event| {
+            events.push(event);
+            Ok(())
+        })?;
+        for event in events {
+            self.handle_event(&event)?;
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &Event) -> crate::Result<()> {
+        let mut args = event.reader();
+        if event.object == self.manager {
+            match event.opcode {
+                MANAGER_EVENT_TOPLEVEL => {
+                    self.toplevels.insert(args.uint()?, Toplevel::default());
+                }
+                MANAGER_EVENT_FINISHED => {
+                    return Err("The compositor stopped the foreign toplevel manager".into());
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let Some(toplevel) = self.toplevels.get_mut(&event.object) else {
+            return Ok(());
+        };
+        match event.opcode {
+            HANDLE_EVENT_TITLE => toplevel.pending.title = args.string()?,
+            HANDLE_EVENT_APP_ID => toplevel.pending.app_id = args.string()?,
+            HANDLE_EVENT_STATE => {
+                toplevel.pending.states = args
+                    .array()?
+                    .chunks_exact(4)
+                    .map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap()))
+                    .collect();
+            }
+            HANDLE_EVENT_DONE => {
+                toplevel.current = toplevel.pending.clone();
+                toplevel.ready = true;
+            }
+            HANDLE_EVENT_CLOSED => {
+                self.toplevels.remove(&event.object);
+                self.conn.send(event.object, HANDLE_DESTROY, Args::new())?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The `Window` for a toplevel handle of this connection
+    fn window(&self, object: u32) -> crate::Window {
+        crate::Window::from((self.generation as u32) << 24 | object & !SERVER_ID_BASE)
+    }
+
+    /// The handle's object id, if `window` is from this connection
+    fn object(&self, window: crate::Window) -> Option<u32> {
+        (window.raw() >> 24 == self.generation as u32).then_some(SERVER_ID_BASE | window.raw() & !SERVER_ID_BASE)
+    }
+
+    /// The toplevel `window` names, once its initial properties arrived
+    fn ready(&self, window: crate::Window) -> Option<&Toplevel> {
+        self.toplevels.get(&self.object(window)?).filter(|toplevel| toplevel.ready)
+    }
+
+    fn toplevel(&self, window: crate::Window) -> crate::Result<&ToplevelProps> {
+        self.ready(window)
+            .map(|toplevel| &toplevel.current)
+            .ok_or_else(|| crate::Error::WindowNotFound(window))
+    }
+
+    fn active(&self) -> Option<crate::Window> {
+        self.toplevels
+            .iter()
+            .find(|(_, toplevel)| toplevel.current.states.contains(&STATE_ACTIVATED))
+            .map(|(&id, _)| self.window(id))
+    }
+
+    /// Send a handle request, then wait until `confirmed` holds for the toplevel's
+    /// committed properties (`None` once it closed). Returns whether it did before
+    /// `CONFIRM_TIMEOUT`.
+    fn request(
+        &mut self,
+        window: crate::Window,
+        opcode: u16,
+        args: Args,
+        confirmed: impl Fn(Option<&ToplevelProps>) -> bool,
+    ) -> crate::Result<bool> {
+        self.toplevel(window)?;
+        let object = self.object(window).expect("toplevel() checked the generation");
+        self.conn.send(object, opcode, args)?;
+        let deadline = Instant::now() + CONFIRM_TIMEOUT;
+        loop {
+            if confirmed(self.toplevels.get(&object).map(|toplevel| &toplevel.current)) {
+                return Ok(true);
+            }
+            match self.conn.read_event(Some(deadline))? {
+                Some(event) => self.handle_event(&event)?,
+                None => return Ok(false),
+            }
+        }
+    }
+
+    /// See `focus_window`
+    fn focus(&mut self, window: crate::Window) -> crate::Result<()> {
+        let seat = self.seat.ok_or("The compositor does not advertise a wl_seat")?;
+        let activated = self.request(window, HANDLE_ACTIVATE, Args::new().uint(seat), |toplevel| {
+            toplevel.is_some_and(|toplevel| toplevel.states.contains(&STATE_ACTIVATED))
+        })?;
+        if activated {
+            return Ok(());
+        }
+        self.sync()?;
+        if self.active() != Some(window) {
+            return Err(FocusRefused::new(window).into());
+        }
+        Ok(())
+    }
+
+    /// Request a state change and fail unless the handle's `state` event reflects it.
+    fn request_state(
+        &mut self,
+        window: crate::Window,
+        opcode: u16,
+        args: Args,
+        state: u32,
+        enabled: bool,
+    ) -> crate::Result<()> {
+        let confirmed = self.request(window, opcode, args, |toplevel| {
+            toplevel.is_some_and(|toplevel| toplevel.states.contains(&state) == enabled)
+        })?;
+        if !confirmed {
+            return Err(format!("The compositor did not apply the requested state to window {window}").into());
+        }
+        Ok(())
+    }
+
+    fn set_fullscreen(&mut self, window: crate::Window, enabled: bool) -> crate::Result<()> {
+        if self.manager_version < FULLSCREEN_SINCE {
+            return Err(format!(
+                "Fullscreen needs {MANAGER_INTERFACE} version {FULLSCREEN_SINCE}, the compositor offers {}",
+                self.manager_version
+            )
+            .into());
+        }
+        if enabled {
+            // A null output lets the compositor pick the monitor
+            self.request_state(window, HANDLE_SET_FULLSCREEN, Args::new().uint(0), STATE_FULLSCREEN, true)
+        } else {
+            self.request_state(window, HANDLE_UNSET_FULLSCREEN, Args::new(), STATE_FULLSCREEN, false)
+        }
+    }
+}
+
+fn find_global<'a>(globals: &'a [Global], interface: &str) -> Option<&'a Global> {
+    globals.iter().find(|global| global.interface == interface)
+}
+
+/// Run `f` against the shared, up-to-date backend state, connecting on first use.
+fn with_state<T>(
+    f: impl FnOnce(&mut WlrState) -> crate::Result<T>,
+) -> crate::Result<T> {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let state = match guard.as_mut() {
+        Some(state) => state,
+        None => guard.insert(WlrState::connect()?),
+    };
+    let result = state.sync().and_then(|_| f(state));
+    // After a timeout, don't wait on the compositor a second time to find out
+    if matches!(result, Err(crate::Error::Timeout)) || result.is_err() && state.sync().is_err() {
+        // The connection is unusable; start over on the next call
+        *guard = None;
+    }
+    result
+}
+
+/// Whether a Wayland compositor is reachable and advertises the foreign toplevel manager.
+pub(super) fn is_available() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some() && with_state(|_| Ok(())).is_ok()
+}
+
+pub fn list_windows() -> crate::Result<Vec<crate::Window>> {
+    with_state(|state| {
+        Ok(state
+            .toplevels
+            .iter()
+            .filter(|(_, toplevel)| toplevel.ready)
+            .map(|(&id, _)| state.window(id))
+            .collect())
+    })
+}
+
+pub fn get_active_window() -> crate::Result<Option<crate::Window>> {
+    with_state(|state| Ok(state.active()))
+}
+
+pub fn get_window_title(window: crate::Window) -> crate::Result<Option<String>> {
+    with_state(|state| Ok(state.toplevel(window)?.title.clone()))
+}
+
+pub fn get_window_app_id(window: crate::Window) -> crate::Result<Option<String>> {
+    with_state(|state| Ok(state.toplevel(window)?.app_id.clone()))
+}
+
+/// Ask the compositor to activate the toplevel. Compositors with focus-stealing
+/// prevention may ignore the request: if the toplevel isn't activated within
+/// `CONFIRM_TIMEOUT` but the compositor still answers a round trip, it saw the
+/// request and declined, reported as `FocusRefused`. A compositor that doesn't
+/// answer at all yields `Error::Timeout`.
+pub fn focus_window(window: crate::Window) -> crate::Result<()> {
+    with_state(|state| state.focus(window))
+}
+
+pub fn minimize_window(window: crate::Window) -> crate::Result<()> {
+    with_state(|state| state.request_state(window, HANDLE_SET_MINIMIZED, Args::new(), STATE_MINIMIZED, true))
+}
+
+pub fn maximize_window(window: crate::Window) -> crate::Result<()> {
+    with_state(|state| state.request_state(window, HANDLE_SET_MAXIMIZED, Args::new(), STATE_MAXIMIZED, true))
+}
+
+/// Ask the client to close; fails if its handle isn't closed within the timeout
+/// (e.g. the application showed an "unsaved changes" prompt).
+pub fn close_window(window: crate::Window) -> crate::Result<()> {
+    with_state(|state| {
+        if !state.request(window, HANDLE_CLOSE, Args::new(), |toplevel| toplevel.is_none())? {
+            return Err(format!("Window {window} did not close").into());
+        }
+        Ok(())
+    })
+}
+
+/// Minimized (`Hidden`), maximized, fullscreen and activated (`Focused`, enable only)
+/// map onto the handle's requests; other flags have no foreign-toplevel equivalent.
+pub fn set_window_state(window: crate::Window, flag: WindowStateFlag, enabled: bool) -> crate::Result<()> {
+    match flag {
+        WindowStateFlag::Hidden => with_state(|state| {
+            let opcode = if enabled { HANDLE_SET_MINIMIZED } else { HANDLE_UNSET_MINIMIZED };
+            state.request_state(window, opcode, Args::new(), STATE_MINIMIZED, enabled)
+        }),
+        WindowStateFlag::MaximizedVert | WindowStateFlag::MaximizedHorz => with_state(|state| {
+            let opcode = if enabled { HANDLE_SET_MAXIMIZED } else { HANDLE_UNSET_MAXIMIZED };
+            state.request_state(window, opcode, Args::new(), STATE_MAXIMIZED, enabled)
+        }),
+        WindowStateFlag::Fullscreen => with_state(|state| state.set_fullscreen(window, enabled)),
+        WindowStateFlag::Focused if enabled => focus_window(window),
+        _ => Err(Unsupported::new("set_window_state", NAME).into()),
+    }
+}
+
+pub fn window_has_state(window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    with_state(|wlr| {
+        let Some(toplevel) = wlr.ready(window) else {
+            return Ok(state == WindowState::Closed);
+        };
+        let states = &toplevel.current.states;
+        Ok(match state {
+            WindowState::Closed => false,
+            WindowState::Visible => !states.contains(&STATE_MINIMIZED),
+            WindowState::Minimized => states.contains(&STATE_MINIMIZED),
+            WindowState::Maximized => states.contains(&STATE_MAXIMIZED),
+            WindowState::Fullscreen => states.contains(&STATE_FULLSCREEN),
+            WindowState::Focused => states.contains(&STATE_ACTIVATED),
+        })
+    })
+}
+
+/// The toplevel's state array mapped onto WindowStateFlags
+pub fn get_window_states(window: crate::Window) -> crate::Result<Vec<WindowStateFlag>> {
+    with_state(|wlr| {
+        let toplevel = wlr.toplevel(window)?;
+        Ok(toplevel
+            .states
+            .iter()
+            .flat_map(|&state| match state {
+                STATE_MAXIMIZED => vec![WindowStateFlag::MaximizedVert, WindowStateFlag::MaximizedHorz],
+                STATE_MINIMIZED => vec![WindowStateFlag::Hidden],
+                STATE_ACTIVATED => vec![WindowStateFlag::Focused],
+                STATE_FULLSCREEN => vec![WindowStateFlag::Fullscreen],
+                other => vec![WindowStateFlag::Other(format!("zwlr_foreign_toplevel_handle_v1.state {other}"))],
+            })
+            .collect())
+    })
+}
+
+/// Stream toplevel changes from a dedicated connection. Object ids are per
+/// connection, so the events' windows carry that connection's generation:
+/// other calls report them as not found rather than acting on whichever
+/// toplevel has the same object id on the shared connection. To act on a
+/// change, list the windows again.
+pub fn subscribe_window_events() -> crate::Result<WindowEvents> {
+    let mut state = WlrState::connect()?;
+    let mut active = state.active();
+    let mut queue = VecDeque::new();
+    let snapshot = |state: &WlrState, id| {
+        state
+            .toplevels
+            .get(&id)
+            .map(|toplevel: &Toplevel| (toplevel.ready, toplevel.current.title.clone()))
+    };
+
+    Ok(WindowEvents::new(std::iter::from_fn(move || loop {
+        if let Some(event) = queue.pop_front() {
+            return Some(Ok(event));
+        }
+        let event = match state.conn.read_event(None) {
+            Ok(Some(event)) => event,
+            Ok(None) => continue,
+            Err(e) => return Some(Err(e)),
+        };
+        let before = snapshot(&state, event.object);
+        if let Err(e) = state.handle_event(&event) {
+            return Some(Err(e));
+        }
+        match (before, snapshot(&state, event.object)) {
+            (Some((false, _)), Some((true, _))) => queue.push_back(WindowEvent::Opened(state.window(event.object))),
+            (Some((true, _)), None) => queue.push_back(WindowEvent::Closed(state.window(event.object))),
+            (Some((true, old)), Some((true, new))) if old != new => {
+                queue.push_back(WindowEvent::TitleChanged(state.window(event.object)))
+            }
+            _ => {}
+        }
+        let now_active = state.active();
+        if now_active != active {
+            active = now_active;
+            queue.push_back(WindowEvent::Focused(active));
+        }
+    })))
+}
+
+/// The `wayland-wlr` backend, on the process-wide toplevel manager connection
+#[derive(Debug, Default)]
+pub(super) struct WlrBackend;
+
+impl WindowingBackend for WlrBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::WaylandWlr
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        list_windows()
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        get_active_window()
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_title(window)
+    }
+
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        get_window_app_id(window)
+    }
+
+    fn minimize_window(&self, window: crate::Window) -> crate::Result<()> {
+        minimize_window(window)
+    }
+
+    fn maximize_window(&self, window: crate::Window) -> crate::Result<()> {
+        maximize_window(window)
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        close_window(window)
+    }
+
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn activate_window(&self, window: crate::Window, _update_user_time: bool) -> crate::Result<()> {
+        focus_window(window)
+    }
+
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        subscribe_window_events()
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        window_has_state(window, state)
+    }
+
+    fn get_window_states(&self, window: crate::Window) -> crate::Result<Vec<WindowStateFlag>> {
+        get_window_states(window)
+    }
+
+    fn set_window_state(
+        &self,
+        window: crate::Window,
+        flag: WindowStateFlag,
+        enabled: bool,
+    ) -> crate::Result<()> {
+        set_window_state(window, flag, enabled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+        thread::{self, JoinHandle},
+    };
+
+    const HANDLE: u32 = SERVER_ID_BASE | 5;
+    const SEAT: u32 = 3;
+
+    /// A message on the wire: object, size and opcode, then `args` as words.
+    fn message(object: u32, opcode: u16, args: &[u32]) -> Vec<u8> {
+        let size = 8 + 4 * args.len() as u32;
+        [object, size << 16 | opcode as u32].iter().chain(args).flat_map(|word| word.to_ne_bytes()).collect()
+    }
+
+    /// A compositor answering each request with `respond(object, opcode,
+    /// args)`; returns the requests it saw once the client hangs up.
+    fn compositor(
+        mut stream: UnixStream,
+        respond: impl Fn(u32, u16, &[u8]) -> Vec<u8> + Send + 'static,
+    ) -> JoinHandle<Vec<(u32, u16)>> {
+        thread::spawn(move || {
+            let mut seen = Vec::new();
+            let mut header = [0; 8];
+            while stream.read_exact(&mut header).is_ok() {
+                let object = u32::from_ne_bytes(header[..4].try_into().unwrap());
+                let word = u32::from_ne_bytes(header[4..].try_into().unwrap());
+                let mut args = vec![0; (word >> 16) as usize - 8];
+                stream.read_exact(&mut args).unwrap();
+                seen.push((object, word as u16));
+                stream.write_all(&respond(object, word as u16, &args)).unwrap();
+            }
+            seen
+        })
+    }
+
+    /// Answers `wl_display.sync` and nothing else
+    fn sync_only(object: u32, opcode: u16, args: &[u8]) -> Vec<u8> {
+        match (object, opcode) {
+            (1, 0) => message(u32::from_ne_bytes(args[..4].try_into().unwrap()), 0, &[0]),
+            _ => Vec::new(),
+        }
+    }
+
+    /// A state with one ready toplevel, `HANDLE`, on generation 3
+    fn state(stream: UnixStream) -> WlrState {
+        let toplevel = Toplevel { ready: true, ..Toplevel::default() };
+        WlrState {
+            conn: Connection::from_stream(stream, Duration::from_millis(100)),
+            generation: 3,
+            manager: 2,
+            manager_version: MANAGER_VERSION,
+            seat: Some(SEAT),
+            toplevels: BTreeMap::from([(HANDLE, toplevel)]),
+        }
+    }
+
+    #[test]
+    fn windows_carry_the_connection_generation() {
+        let state = state(UnixStream::pair().unwrap().0);
+        let window = state.window(HANDLE);
+        assert_eq!(window.raw(), 0x0300_0005);
+        assert_eq!(state.object(window), Some(HANDLE));
+        assert!(state.toplevel(window).is_ok());
+
+        // The same object id from an earlier connection
+        let stale = crate::Window::from(0x0200_0005);
+        assert_eq!(state.object(stale), None);
+        assert!(matches!(state.toplevel(stale), Err(crate::Error::WindowNotFound(_))));
+    }
+
+    #[test]
+    fn focus_succeeds_once_the_toplevel_is_activated() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let compositor = compositor(server, |object, opcode, args| match (object, opcode) {
+            (HANDLE, HANDLE_ACTIVATE) => {
+                [message(HANDLE, HANDLE_EVENT_STATE, &[4, STATE_ACTIVATED]), message(HANDLE, HANDLE_EVENT_DONE, &[])]
+                    .concat()
+            }
+            _ => sync_only(object, opcode, args),
+        });
+        let mut state = state(client);
+        let window = state.window(HANDLE);
+
+        state.focus(window).unwrap();
+        assert_eq!(state.active(), Some(window));
+        drop(state);
+        assert_eq!(compositor.join().unwrap(), [(HANDLE, HANDLE_ACTIVATE)]);
+    }
+
+    #[test]
+    fn focus_declined_by_an_answering_compositor_is_refused() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let compositor = compositor(server, sync_only);
+        let mut state = state(client);
+
+        let window = state.window(HANDLE);
+        assert!(matches!(state.focus(window), Err(crate::Error::FocusRefused(_))));
+        drop(state);
+        assert_eq!(compositor.join().unwrap(), [(HANDLE, HANDLE_ACTIVATE), (1, 0)]);
+    }
+
+    #[test]
+    fn focus_without_an_answer_times_out() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let compositor = compositor(server, |_, _, _| Vec::new());
+        let mut state = state(client);
+
+        let window = state.window(HANDLE);
+        assert!(matches!(state.focus(window), Err(crate::Error::Timeout)));
+        drop(state);
+        compositor.join().unwrap();
+    }
+
+    #[test]
+    fn focus_of_a_stale_window_is_not_found() {
+        let mut state = state(UnixStream::pair().unwrap().0);
+        let stale = crate::Window::from(0x0200_0005);
+        assert!(matches!(state.focus(stale), Err(crate::Error::WindowNotFound(_))));
+    }
+}
@@ -0,0 +1,2613 @@
+use super::{RawProperty, Strut};
+use crate::error::Context as _;
+use crate::{
+    geometry::{monitor_of, monitors_of},
+    BackendKind, Capabilities, IconData, MonitorInfo, PhysicalWindowInfo, WindowDetails, WindowEvent, WindowEvents,
+    WindowInfo, WindowProperties, WindowState, WindowStateFlag, WindowingBackend,
+};
+use std::{
+    cell::RefCell,
+    collections::{HashSet, VecDeque},
+    io::{self, IoSlice},
+    os::fd::AsFd,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    cookie::Cookie,
+    protocol::{
+        randr::ConnectionExt as _,
+        shape::{self, ConnectionExt as _, SK, SO},
+        xproto::{
+            Atom, AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ClipOrdering, ConfigureWindowAux,
+            ConnectionExt, CreateWindowAux, EventMask, GetGeometryReply, GetPropertyReply, GrabMode, GrabStatus, MapState,
+            PropMode, TranslateCoordinatesReply, UnmapNotifyEvent, Window as XWindow, WindowClass, UNMAP_NOTIFY_EVENT,
+        },
+        Event,
+    },
+    errors::{ConnectError, ConnectionError, DisplayParsingError, ReplyError, ReplyOrIdError},
+    x11_utils::X11Error,
+    protocol::ErrorKind,
+    reexports::x11rb_protocol::{parse_display::parse_display, xauth::get_auth},
+    rust_connection::{DefaultStream, PollMode, RustConnection, Stream},
+    utils::RawFdContainer,
+};
+
+x11rb::atom_manager! {
+    /// Every atom the backend uses, interned in one pipelined batch
+    Atoms: AtomsCookie {
+        UTF8_STRING,
+        WM_STATE,
+        WM_CHANGE_STATE,
+        WM_PROTOCOLS,
+        WM_TAKE_FOCUS,
+        _GTK_APPLICATION_ID,
+        _NET_ACTIVE_WINDOW,
+        _NET_CLIENT_LIST,
+        _NET_CLOSE_WINDOW,
+        _NET_CURRENT_DESKTOP,
+        _NET_WM_DESKTOP,
+        _NET_WM_ICON,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_MODAL,
+        _NET_WM_STATE_STICKY,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_SHADED,
+        _NET_WM_STATE_SKIP_TASKBAR,
+        _NET_WM_STATE_SKIP_PAGER,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_BELOW,
+        _NET_WM_STATE_DEMANDS_ATTENTION,
+        _NET_WM_STATE_FOCUSED,
+        _NET_SUPPORTED,
+        _NET_WM_STRUT,
+        _NET_WM_STRUT_PARTIAL,
+        _NET_WM_USER_TIME,
+        _NET_WM_USER_TIME_WINDOW,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_NORMAL,
+        _WINDOWING_TIMESTAMP,
+    }
+}
+
+/// An open X connection and its default screen, shared by every call made
+/// through the `WindowSystem` that owns it. Atoms are interned on first use
+/// and cached for the life of the connection.
+#[derive(Debug)]
+pub struct Session {
+    conn: XConnection,
+    root: XWindow,
+    atoms: OnceLock<Atoms>,
+}
+
+impl Session {
+    /// Connect to `DISPLAY`. With a `timeout`, a reply the server doesn't send
+    /// in time fails the call with `Error::Timeout` instead of blocking it.
+    pub fn connect(timeout: Option<Duration>) -> crate::Result<Self> {
+        // x11rb fails with InvalidScreen rather than return a screen the server lacks
+        let (conn, screen_num) = connect(timeout)?;
+        let root = conn.setup().roots[screen_num].root;
+        Ok(Session { conn, root, atoms: OnceLock::new() })
+    }
+
+    fn atoms(&self) -> crate::Result<&Atoms> {
+        if let Some(atoms) = self.atoms.get() {
+            return Ok(atoms);
+        }
+        let atoms = Atoms::new(&self.conn)?.reply()?;
+        Ok(self.atoms.get_or_init(|| atoms))
+    }
+}
+
+/// The X11 backend. It keeps a connection open for every call made through
+/// it, opened on first use; a call that finds the connection dead reconnects
+/// once and is retried.
+#[derive(Debug, Default)]
+pub(super) struct X11Backend {
+    slot: SessionSlot,
+    request_timeout: Option<Duration>,
+}
+
+/// Where an `X11Backend` keeps its connection
+#[derive(Debug)]
+enum SessionSlot {
+    /// One connection for every thread calling through the backend
+    Shared(Mutex<Option<Arc<Session>>>),
+    /// A connection per calling thread, in `THREAD_SESSION`
+    PerThread,
+}
+
+impl Default for SessionSlot {
+    fn default() -> Self {
+        SessionSlot::Shared(Mutex::new(None))
+    }
+}
+
+thread_local! {
+    /// The calling thread's connection for `SessionSlot::PerThread` backends
+    static THREAD_SESSION: RefCell<Option<Arc<Session>>> = const { RefCell::new(None) };
+}
+
+impl X11Backend {
+    /// See `WindowSystem::with_request_timeout`
+    pub(super) fn with_request_timeout(request_timeout: Option<Duration>) -> Self {
+        X11Backend { slot: SessionSlot::default(), request_timeout }
+    }
+
+    /// A backend giving each calling thread a connection of its own, so the
+    /// free functions never wait on another thread's round trips
+    pub(super) const fn per_thread() -> Self {
+        X11Backend { slot: SessionSlot::PerThread, request_timeout: None }
+    }
+
+    /// The X connection, opened on first use
+    pub(super) fn session(&self) -> crate::Result<Arc<Session>> {
+        let connect = |slot: &mut Option<Arc<Session>>| {
+            if let Some(session) = slot.as_ref() {
+                return Ok(Arc::clone(session));
+            }
+            let session = Arc::new(Session::connect(self.request_timeout)?);
+            *slot = Some(Arc::clone(&session));
+            Ok(session)
+        };
+        match &self.slot {
+            SessionSlot::Shared(slot) => connect(&mut slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner())),
+            SessionSlot::PerThread => THREAD_SESSION.with_borrow_mut(connect),
+        }
+    }
+
+    /// Forget `session` unless another thread already replaced it
+    fn discard(&self, session: &Arc<Session>) {
+        let discard = |slot: &mut Option<Arc<Session>>| {
+            if slot.as_ref().is_some_and(|current| Arc::ptr_eq(current, session)) {
+                *slot = None;
+            }
+        };
+        match &self.slot {
+            SessionSlot::Shared(slot) => discard(&mut slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner())),
+            SessionSlot::PerThread => THREAD_SESSION.with_borrow_mut(discard),
+        }
+    }
+
+    /// Run `op` on the X connection. If the connection turns out to be dead
+    /// (the X server restarted, or closed it), reconnect once and retry; a
+    /// second failure is returned as is.
+    pub(super) fn with_x11<T>(&self, op: impl Fn(&Session) -> crate::Result<T>) -> crate::Result<T> {
+        let session = self.session()?;
+        match op(&session) {
+            // Replies to abandoned requests would still be queued; start afresh next time
+            Err(crate::Error::Timeout) => {
+                self.discard(&session);
+                Err(crate::Error::Timeout)
+            }
+            Err(crate::Error::ConnectionFailed(_)) => {
+                self.discard(&session);
+                op(&*self.session()?)
+            }
+            result => result,
+        }
+    }
+}
+
+/// The backend of the X11-only free functions (`get_panels`, `pick_window`,
+/// ...) and `capabilities`, sharing each thread's connection with the
+/// `WindowSystem` behind the other free functions
+pub(super) fn thread_backend() -> &'static X11Backend {
+    static PER_THREAD: X11Backend = X11Backend::per_thread();
+    &PER_THREAD
+}
+
+type XConnection = RustConnection<TimedStream>;
+
+/// The connection's socket, giving up on a read once `timeout` has passed
+/// without the server sending anything. Only waits to read are bounded:
+/// abandoning a half-written request would leave the connection out of step.
+#[derive(Debug)]
+struct TimedStream {
+    inner: DefaultStream,
+    timeout: Option<Duration>,
+}
+
+impl Stream for TimedStream {
+    fn poll(&self, mode: PollMode) -> io::Result<()> {
+        let Some(timeout) = self.timeout.filter(|_| matches!(mode, PollMode::Readable)) else {
+            return self.inner.poll(mode);
+        };
+        let timeout = rustix::event::Timespec::try_from(timeout)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "request timeout is too long"))?;
+        let mut fds = [rustix::event::PollFd::new(&self.inner, rustix::event::PollFlags::IN)];
+        loop {
+            match rustix::event::poll(&mut fds, Some(&timeout)) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::TimedOut, "X server did not answer in time")),
+                Ok(_) => return Ok(()),
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    fn read(&self, buf: &mut [u8], fd_storage: &mut Vec<RawFdContainer>) -> io::Result<usize> {
+        self.inner.read(buf, fd_storage)
+    }
+
+    fn write(&self, buf: &[u8], fds: &mut Vec<RawFdContainer>) -> io::Result<usize> {
+        self.inner.write(buf, fds)
+    }
+
+    fn write_vectored(&self, bufs: &[IoSlice<'_>], fds: &mut Vec<RawFdContainer>) -> io::Result<usize> {
+        self.inner.write_vectored(bufs, fds)
+    }
+}
+
+impl AsFd for TimedStream {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+/// `RustConnection::connect`, over a `TimedStream`. An unset `DISPLAY`, or
+/// one nothing answers on, is `NoDisplay`.
+fn connect(timeout: Option<Duration>) -> crate::Result<(XConnection, usize)> {
+    let display = match parse_display(None) {
+        Ok(display) => display,
+        Err(DisplayParsingError::DisplayNotSet) => return Err(crate::Error::NoDisplay { display: None }),
+        Err(error) => return Err(ConnectError::from(error).into()),
+    };
+    for address in display.connect_instruction() {
+        let Ok((inner, (family, peer))) = DefaultStream::connect(&address) else {
+            continue;
+        };
+        // As in x11rb, an unreadable Xauthority means trying without auth
+        let (name, data) = get_auth(family, &peer, display.display).unwrap_or(None).unwrap_or_default();
+        let stream = TimedStream { inner, timeout };
+        let conn = RustConnection::connect_to_stream_with_auth_info(stream, display.screen.into(), name, data)?;
+        return Ok((conn, display.screen.into()));
+    }
+    Err(crate::Error::NoDisplay { display: std::env::var("DISPLAY").ok() })
+}
+
+impl From<ConnectError> for crate::Error {
+    fn from(error: ConnectError) -> Self {
+        match error {
+            ConnectError::IoError(error) if error.kind() == io::ErrorKind::TimedOut => crate::Error::Timeout,
+            error => crate::Error::connection(error),
+        }
+    }
+}
+
+/// A read given up by `TimedStream` is `Timeout`; anything else lost the connection
+impl From<ConnectionError> for crate::Error {
+    fn from(error: ConnectionError) -> Self {
+        match error {
+            ConnectionError::IoError(error) if error.kind() == io::ErrorKind::TimedOut => crate::Error::Timeout,
+            error => crate::Error::connection(error),
+        }
+    }
+}
+
+/// BadWindow becomes `WindowGone`; other X errors are `Platform` errors
+impl From<X11Error> for crate::Error {
+    fn from(error: X11Error) -> Self {
+        if error.error_kind == ErrorKind::Window {
+            crate::Error::WindowGone(crate::Window::from(error.bad_value))
+        } else {
+            crate::Error::Platform(Box::new(ReplyError::X11Error(error)))
+        }
+    }
+}
+
+impl From<ReplyError> for crate::Error {
+    fn from(error: ReplyError) -> Self {
+        match error {
+            ReplyError::ConnectionError(error) => error.into(),
+            ReplyError::X11Error(error) => error.into(),
+        }
+    }
+}
+
+impl From<ReplyOrIdError> for crate::Error {
+    fn from(error: ReplyOrIdError) -> Self {
+        match error {
+            ReplyOrIdError::ConnectionError(error) => error.into(),
+            ReplyOrIdError::X11Error(error) => error.into(),
+            error @ ReplyOrIdError::IdsExhausted => crate::Error::Platform(Box::new(error)),
+        }
+    }
+}
+
+/// The geometry relative to the parent window, as the server reports it;
+/// `get_window_info` translates the position to root coordinates
+impl From<GetGeometryReply> for WindowInfo {
+    fn from(geometry: GetGeometryReply) -> Self {
+        WindowInfo {
+            pos: (geometry.x as i32, geometry.y as i32),
+            size: (geometry.width as u32, geometry.height as u32),
+        }
+    }
+}
+
+/// The window _NET_ACTIVE_WINDOW names; `None` when nothing is focused, which
+/// the WM reports by leaving the property unset or setting it to None
+fn read_active_window(
+    conn: &XConnection,
+    atoms: &Atoms,
+    root: XWindow,
+) -> crate::Result<Option<XWindow>> {
+    let prop = conn
+        .get_property(false, root, atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW, 0, 1)?
+        .reply()?;
+    Ok(prop
+        .value32()
+        .and_then(|mut values| values.next())
+        .filter(|&window| window != x11rb::NONE))
+}
+
+/// Get the geometry (x, y, width, height) of a window.
+pub fn get_window_info(session: &Session, window: crate::Window) -> crate::Result<WindowInfo> {
+    let frame = FrameCookies::send(&session.conn, session.root, window.raw())?;
+    frame.reply().context("get_window_info", Some(window), "GetGeometry")
+}
+
+/// GetGeometry and TranslateCoordinates for one window, sent together so the
+/// position in root coordinates costs no extra round trip. GetGeometry alone
+/// is relative to the parent, which for a client the WM reparented is its
+/// frame rather than the root.
+struct FrameCookies<'c> {
+    geometry: Cookie<'c, XConnection, GetGeometryReply>,
+    origin: Cookie<'c, XConnection, TranslateCoordinatesReply>,
+}
+
+impl<'c> FrameCookies<'c> {
+    fn send(conn: &'c XConnection, root: XWindow, window: XWindow) -> Result<Self, ConnectionError> {
+        Ok(FrameCookies { geometry: conn.get_geometry(window)?, origin: conn.translate_coordinates(window, root, 0, 0)? })
+    }
+
+    /// The window's outer top-left corner in root coordinates, as `move_window`
+    /// takes it, and its size
+    fn reply(self) -> Result<WindowInfo, ReplyError> {
+        let geometry = self.geometry.reply()?;
+        let origin = self.origin.reply()?;
+        let border = geometry.border_width as i32;
+        Ok(WindowInfo {
+            pos: (origin.dst_x as i32 - border, origin.dst_y as i32 - border),
+            size: (geometry.width as u32, geometry.height as u32),
+        })
+    }
+}
+
+/// get_window_info for many windows with every request sent before the
+/// first reply is awaited, so the batch costs one round trip
+pub fn get_window_infos(
+    session: &Session,
+    windows: &[crate::Window],
+) -> crate::Result<Vec<crate::WindowResult<WindowInfo>>> {
+    let conn = &session.conn;
+    let cookies = windows
+        .iter()
+        .map(|window| FrameCookies::send(conn, session.root, window.raw()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(cookies
+        .into_iter()
+        .map(|cookie| Ok(cookie.reply()?))
+        .collect())
+}
+
+/// Geometry, title, pid, state and desktop of a window with every request sent
+/// before the first reply is awaited, so it costs one round trip
+pub fn get_window_details(session: &Session, window: crate::Window) -> crate::Result<WindowDetails> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let raw = window.raw();
+    let geometry = FrameCookies::send(conn, root, raw)?;
+    let net_name = conn.get_property(false, raw, atoms._NET_WM_NAME, atoms.UTF8_STRING, 0, u32::MAX)?;
+    let wm_name = conn.get_property(false, raw, AtomEnum::WM_NAME, AtomEnum::ANY, 0, u32::MAX)?;
+    let pid = conn.get_property(false, raw, atoms._NET_WM_PID, AtomEnum::CARDINAL, 0, 1)?;
+    let states = conn.get_property(false, raw, atoms._NET_WM_STATE, AtomEnum::ATOM, 0, u32::MAX)?;
+    let desktop = conn.get_property(false, raw, atoms._NET_WM_DESKTOP, AtomEnum::CARDINAL, 0, 1)?;
+    let current = conn.get_property(false, root, atoms._NET_CURRENT_DESKTOP, AtomEnum::CARDINAL, 0, 1)?;
+
+    const OPERATION: &str = "get_window_details";
+    let info = geometry.reply().context(OPERATION, Some(window), "GetGeometry")?;
+    let net_name = net_name.reply().context(OPERATION, Some(window), "_NET_WM_NAME")?;
+    let wm_name = wm_name.reply().context(OPERATION, Some(window), "WM_NAME")?;
+    let title = [net_name, wm_name]
+        .into_iter()
+        .find(|reply| reply.format == 8 && reply.value_len > 0)
+        .map(|reply| String::from_utf8_lossy(&reply.value).into_owned());
+    let pid = pid.reply().map_err(crate::Error::from).and_then(|reply| parse_pid(&reply));
+    let pid = pid.context(OPERATION, Some(window), "_NET_WM_PID")?;
+    let states = states.reply().map_err(crate::Error::from).and_then(|reply| parse_states(conn, atoms, &reply));
+    let states = states.context(OPERATION, Some(window), "_NET_WM_STATE")?;
+    let first = |reply: GetPropertyReply| reply.value32().and_then(|mut values| values.next());
+    let desktop = desktop.reply().context(OPERATION, Some(window), "_NET_WM_DESKTOP")?;
+    let current = current.reply().context(OPERATION, None, "_NET_CURRENT_DESKTOP")?;
+    let on_active_desktop = match (first(desktop), first(current)) {
+        (Some(desktop), Some(current)) => desktop == ALL_DESKTOPS || desktop == current,
+        _ => true,
+    };
+    Ok(WindowDetails { info, title, pid, states, on_active_desktop })
+}
+
+/// Pid, app id, title and desktop of many windows with every request sent
+/// before the first reply is awaited, so the whole batch costs one round trip
+pub fn get_window_properties(
+    session: &Session,
+    windows: &[crate::Window],
+) -> crate::Result<Vec<crate::WindowResult<WindowProperties>>> {
+    const OPERATION: &str = "get_window_properties";
+    let conn = &session.conn;
+    let atoms = session.atoms()?;
+    let property = |window: XWindow, property: Atom, kind: Atom| {
+        conn.get_property(false, window, property, kind, 0, u32::MAX)
+    };
+    let cookies = windows
+        .iter()
+        .map(|window| -> Result<_, ConnectionError> {
+            let raw = window.raw();
+            Ok([
+                property(raw, atoms._NET_WM_PID, AtomEnum::CARDINAL.into())?,
+                property(raw, atoms._GTK_APPLICATION_ID, atoms.UTF8_STRING)?,
+                property(raw, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())?,
+                property(raw, atoms._NET_WM_NAME, atoms.UTF8_STRING)?,
+                property(raw, AtomEnum::WM_NAME.into(), AtomEnum::ANY.into())?,
+                property(raw, atoms._NET_WM_DESKTOP, AtomEnum::CARDINAL.into())?,
+            ])
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let text = |reply: GetPropertyReply| {
+        (reply.format == 8 && reply.value_len > 0).then(|| String::from_utf8_lossy(&reply.value).into_owned())
+    };
+    let read = |window: crate::Window, cookies: [Cookie<'_, XConnection, GetPropertyReply>; 6]| {
+        let [pid, gtk_app_id, wm_class, net_name, wm_name, desktop] = cookies;
+        let pid = pid.reply().map_err(crate::Error::from).and_then(|reply| parse_pid(&reply));
+        let pid = pid.context(OPERATION, Some(window), "_NET_WM_PID")?;
+        let gtk_app_id = text(gtk_app_id.reply().context(OPERATION, Some(window), "_GTK_APPLICATION_ID")?);
+        let wm_class = wm_class.reply().context(OPERATION, Some(window), "WM_CLASS")?;
+        let class = parse_wm_class(&wm_class).map(|(_, class)| class).filter(|class| !class.is_empty());
+        let app_id = gtk_app_id.or(class);
+        let net_name = text(net_name.reply().context(OPERATION, Some(window), "_NET_WM_NAME")?);
+        let wm_name = text(wm_name.reply().context(OPERATION, Some(window), "WM_NAME")?);
+        let desktop = desktop.reply().context(OPERATION, Some(window), "_NET_WM_DESKTOP")?;
+        let workspace = desktop.value32().and_then(|mut values| values.next());
+        let workspace = workspace.filter(|&desktop| desktop != ALL_DESKTOPS);
+        Ok(WindowProperties { pid, app_id, title: net_name.or(wm_name), workspace })
+    };
+    Ok(windows.iter().zip(cookies).map(|(&window, cookies)| read(window, cookies)).collect())
+}
+
+/// Like get_window_info, but retries up to `retries` times on BadWindow/BadDrawable,
+/// which the server can report transiently while the WM reparents or reconfigures
+/// a window being dragged
+pub fn get_window_info_with_retry(
+    session: &Session,
+    window: crate::Window,
+    retries: u32,
+) -> crate::Result<WindowInfo> {
+    const RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(10);
+    let conn = &session.conn;
+    let mut attempt = 0;
+    loop {
+        match FrameCookies::send(conn, session.root, window.raw())?.reply() {
+            Ok(info) => return Ok(info),
+            Err(ReplyError::X11Error(e))
+                if attempt < retries && matches!(e.error_kind, ErrorKind::Window | ErrorKind::Drawable) =>
+            {
+                attempt += 1;
+                std::thread::sleep(RETRY_DELAY);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Get the root window of the default screen
+pub fn get_root_window(session: &Session) -> crate::Result<crate::Window> {
+    Ok(session.root.into())
+}
+
+/// One QueryPointer on the root: the pointer's root coordinates and the
+/// root's child under it (a WM frame), mapped to the client window inside
+pub fn pointer_state(session: &Session) -> crate::Result<(i32, i32, Option<crate::Window>)> {
+    let (conn, root) = (&session.conn, session.root);
+    let pointer = conn.query_pointer(root)?.reply().context("pointer_state", None, "QueryPointer")?;
+    let (x, y) = (pointer.root_x as i32, pointer.root_y as i32);
+    if pointer.child == x11rb::NONE {
+        return Ok((x, y, None));
+    }
+    let frame = crate::Window::from(pointer.child);
+    // A frame destroyed since the query still names the window that was under the pointer
+    let window = client_window_of(session, frame).unwrap_or(None).unwrap_or(frame);
+    Ok((x, y, Some(window)))
+}
+
+/// Get a list of top-level windows from the root window (_NET_CLIENT_LIST),
+/// or by walking the window tree where no EWMH window manager sets it
+fn get_top_level_windows(
+    conn: &XConnection,
+    atoms: &Atoms,
+    root: XWindow,
+) -> crate::Result<Vec<XWindow>> {
+    let prop = conn
+        .get_property(false, root, atoms._NET_CLIENT_LIST, AtomEnum::WINDOW, 0, u32::MAX)?
+        .reply()?;
+    if prop.type_ == x11rb::NONE {
+        trace_warn!("the window manager doesn't set _NET_CLIENT_LIST, walking the window tree instead");
+        return walk_top_level_windows(conn, atoms, root);
+    }
+
+    Ok(prop
+        .value32()
+        .ok_or("Failed to read _NET_CLIENT_LIST")?
+        .collect())
+}
+
+/// Top-level windows from QueryTree, bottom to top, for minimal window
+/// managers (or none). A child of the root is taken if it has WM_STATE, or
+/// stands for the first of its own children that does (a frame); with no WM
+/// to set WM_STATE, mapped InputOutput windows count. Override-redirect
+/// windows (menus, tooltips) never do, and windows destroyed mid-walk are skipped.
+fn walk_top_level_windows(conn: &XConnection, atoms: &Atoms, root: XWindow) -> crate::Result<Vec<XWindow>> {
+    fn skip_gone<T>(result: Result<T, ReplyError>) -> crate::Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+    let has_wm_state = |windows: &[XWindow]| -> crate::Result<Vec<bool>> {
+        let cookies = windows
+            .iter()
+            .map(|&window| conn.get_property(false, window, atoms.WM_STATE, AtomEnum::ANY, 0, 0))
+            .collect::<Result<Vec<_>, _>>()?;
+        cookies
+            .into_iter()
+            .map(|cookie| Ok(skip_gone(cookie.reply())?.is_some_and(|reply| reply.type_ != x11rb::NONE)))
+            .collect()
+    };
+
+    let children = conn.query_tree(root)?.reply()?.children;
+    let attribute_cookies = children
+        .iter()
+        .map(|&window| conn.get_window_attributes(window))
+        .collect::<Result<Vec<_>, _>>()?;
+    let clients = has_wm_state(&children)?;
+
+    let mut windows = Vec::new();
+    for ((window, cookie), is_client) in children.into_iter().zip(attribute_cookies).zip(clients) {
+        let Some(attributes) = skip_gone(cookie.reply())? else {
+            trace_debug!(window, "skipped: destroyed while walking the tree");
+            continue;
+        };
+        if attributes.override_redirect {
+            trace_debug!(window, "skipped: override-redirect (a menu, tooltip or popup)");
+            continue;
+        }
+        if is_client {
+            windows.push(window);
+            continue;
+        }
+        let Some(tree) = skip_gone(conn.query_tree(window)?.reply())? else {
+            trace_debug!(window, "skipped: destroyed while walking the tree");
+            continue;
+        };
+        let framed = has_wm_state(&tree.children)?;
+        if let Some((&client, _)) = tree.children.iter().zip(framed).find(|&(_, is_client)| is_client) {
+            windows.push(client);
+        } else if attributes.map_state == MapState::VIEWABLE && attributes.class == WindowClass::INPUT_OUTPUT {
+            windows.push(window);
+        } else {
+            trace_debug!(window, "skipped: no WM_STATE, and not a mapped InputOutput window");
+        }
+    }
+    Ok(windows)
+}
+
+/// Get the process ID (PID) of a given window
+fn read_window_pid(
+    conn: &XConnection,
+    atoms: &Atoms,
+    window: XWindow,
+) -> crate::Result<Option<u32>> {
+    let reply = conn
+        .get_property(false, window, atoms._NET_WM_PID, AtomEnum::CARDINAL, 0, 1)?
+        .reply()?;
+    parse_pid(&reply)
+}
+
+/// Read the _NET_WM_PID of many windows in one round trip: every request is
+/// sent before the first reply is awaited. A window destroyed in the meantime
+/// (BadWindow) reads as having no pid.
+fn read_window_pids(
+    conn: &XConnection,
+    atoms: &Atoms,
+    windows: &[XWindow],
+) -> crate::Result<Vec<Option<u32>>> {
+    let cookies = windows
+        .iter()
+        .map(|&window| conn.get_property(false, window, atoms._NET_WM_PID, AtomEnum::CARDINAL, 0, 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    cookies
+        .into_iter()
+        .map(|cookie| match cookie.reply() {
+            Ok(reply) => parse_pid(&reply),
+            // Closed since _NET_CLIENT_LIST was read
+            Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window => Ok(None),
+            Err(e) => Err(e.into()),
+        })
+        .collect()
+}
+
+/// Report the windows a pid lookup passes over for having no `_NET_WM_PID`
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn trace_missing_pids(windows: &[XWindow], pids: &[Option<u32>]) {
+    for (window, pid) in windows.iter().zip(pids) {
+        if pid.is_none() {
+            trace_debug!(window, "skipped: no _NET_WM_PID (closed, or its client doesn't set one)");
+        }
+    }
+}
+
+fn parse_pid(reply: &GetPropertyReply) -> crate::Result<Option<u32>> {
+    if reply.value_len == 0 || reply.format != 32 {
+        return Ok(None);
+    }
+
+    let pid = reply
+        .value32()
+        .ok_or("Failed to parse PID")?
+        .next()
+        .ok_or("PID property is empty")?;
+
+    Ok(Some(pid))
+}
+
+/// Search for a window by process ID (exact match)
+pub fn find_window_by_pid(session: &Session, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let windows = get_top_level_windows(conn, atoms, root)?;
+    let pids = read_window_pids(conn, atoms, &windows)?;
+    trace_missing_pids(&windows, &pids);
+
+    let mut first = None;
+    for (window, pid) in windows.into_iter().zip(pids) {
+        if pid == Some(target_pid) {
+            if check_main_window(conn, atoms, window)? {
+                return Ok(Some(window.into()));
+            }
+            trace_debug!(window, "not a main window, kept in case no other window of the process is");
+            first.get_or_insert(window.into());
+        }
+    }
+
+    // If no main window found, return the first match (if any)
+    Ok(first)
+}
+
+/// Normal (or untyped), titled, viewable and not transient for another window
+fn check_main_window(conn: &XConnection, atoms: &Atoms, window: XWindow) -> crate::Result<bool> {
+    let types = conn
+        .get_property(false, window, atoms._NET_WM_WINDOW_TYPE, AtomEnum::ATOM, 0, u32::MAX)?
+        .reply()?;
+    // EWMH: a managed window without _NET_WM_WINDOW_TYPE is treated as normal
+    let is_normal = types
+        .value32()
+        .is_some_and(|mut types| types.any(|atom| atom == atoms._NET_WM_WINDOW_TYPE_NORMAL));
+    if types.value_len > 0 && !is_normal {
+        trace_debug!(window, "not main: _NET_WM_WINDOW_TYPE isn't normal");
+        return Ok(false);
+    }
+
+    let transient_for = conn
+        .get_property(false, window, AtomEnum::WM_TRANSIENT_FOR, AtomEnum::WINDOW, 0, 1)?
+        .reply()?;
+    let owner = transient_for.value32().and_then(|mut owners| owners.next());
+    if owner.is_some_and(|owner| owner != x11rb::NONE) {
+        trace_debug!(window, owner, "not main: transient for another window");
+        return Ok(false);
+    }
+
+    if conn.get_window_attributes(window)?.reply()?.map_state != MapState::VIEWABLE {
+        trace_debug!(window, "not main: not viewable (unmapped or minimized)");
+        return Ok(false);
+    }
+    let titled = read_window_title(conn, atoms, window)?.is_some_and(|title| !title.is_empty());
+    if !titled {
+        trace_debug!(window, "not main: no title");
+    }
+    Ok(titled)
+}
+
+/// Whether a window looks like an application's primary window (see check_main_window)
+pub fn is_main_window(session: &Session, window: crate::Window) -> crate::Result<bool> {
+    check_main_window(&session.conn, session.atoms()?, window.raw())
+}
+
+/// Search for all windows belonging to a specific process ID
+pub fn find_windows_by_pid(session: &Session, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let windows = get_top_level_windows(conn, atoms, root)?;
+    let pids = read_window_pids(conn, atoms, &windows)?;
+    trace_missing_pids(&windows, &pids);
+
+    Ok(windows
+        .into_iter()
+        .zip(pids)
+        .filter(|&(_, pid)| pid == Some(target_pid))
+        .map(|(window, _)| window.into())
+        .collect())
+}
+
+/// Client windows whose _NET_WM_PID is one of `pids`, read in one pipelined batch
+pub fn find_windows_by_pids(session: &Session, pids: &[u32]) -> crate::Result<Vec<(u32, crate::Window)>> {
+    if pids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let windows = get_top_level_windows(conn, atoms, root)?;
+    let window_pids = read_window_pids(conn, atoms, &windows)?;
+    let pids: HashSet<u32> = pids.iter().copied().collect();
+
+    Ok(windows
+        .into_iter()
+        .zip(window_pids)
+        .filter_map(|(window, pid)| pid.filter(|pid| pids.contains(pid)).map(|pid| (pid, window.into())))
+        .collect())
+}
+
+/// Whether any client window has this _NET_WM_PID. Every request is sent up
+/// front, but replies are only read until the first match.
+pub fn pid_has_window(session: &Session, target_pid: u32) -> crate::Result<bool> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let windows = get_top_level_windows(conn, atoms, root)?;
+    let cookies = windows
+        .iter()
+        .map(|&window| conn.get_property(false, window, atoms._NET_WM_PID, AtomEnum::CARDINAL, 0, 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    for cookie in cookies {
+        match cookie.reply() {
+            Ok(reply) if parse_pid(&reply)? == Some(target_pid) => return Ok(true),
+            Ok(_) => {}
+            // Closed since _NET_CLIENT_LIST was read
+            Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(false)
+}
+
+/// Get the process ID of the currently active window
+pub fn get_active_window_pid(session: &Session) -> crate::Result<Option<u32>> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    match read_active_window(conn, atoms, root)? {
+        Some(active_window) => read_window_pid(conn, atoms, active_window),
+        None => Ok(None),
+    }
+}
+
+/// Get the process ID a window's _NET_WM_PID reports
+pub fn get_window_pid(session: &Session, window: crate::Window) -> crate::Result<Option<u32>> {
+    let conn = &session.conn;
+    read_window_pid(conn, session.atoms()?, window.raw()).context("get_window_pid", Some(window), "_NET_WM_PID")
+}
+
+/// Map a window manager frame (or any ancestor) to the client window inside it,
+/// the way `XmuClientWindow` does: the window itself if it has WM_STATE,
+/// otherwise the first descendant that has it, searching breadth-first.
+pub fn client_window_of(session: &Session, frame: crate::Window) -> crate::Result<Option<crate::Window>> {
+    let conn = &session.conn;
+    let wm_state = session.atoms()?.WM_STATE;
+    let mut queue = std::collections::VecDeque::from([frame.raw()]);
+    while let Some(window) = queue.pop_front() {
+        let state = match conn.get_property(false, window, wm_state, AtomEnum::ANY, 0, 0)?.reply() {
+            Ok(state) => state,
+            // A descendant destroyed mid-walk: skip it, but a missing frame is an error
+            Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window && window != frame.raw() => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if state.type_ != x11rb::NONE {
+            return Ok(Some(window.into()));
+        }
+        if let Ok(tree) = conn.query_tree(window)?.reply() {
+            queue.extend(tree.children);
+        }
+    }
+    Ok(None)
+}
+
+/// Let the user click a window, like `xwininfo`: grab the pointer with a
+/// crosshair cursor, wait for a button press and release, and return the
+/// client window inside the clicked frame (the frame itself if it has none,
+/// the root window for a click on the desktop)
+pub fn pick_window(session: &Session) -> crate::Result<crate::Window> {
+    let (conn, root) = (&session.conn, session.root);
+
+    // Glyph 34 of the standard cursor font is XC_crosshair; 35 is its mask
+    const XC_CROSSHAIR: u16 = 34;
+    let font = conn.generate_id()?;
+    conn.open_font(font, b"cursor")?;
+    let cursor = conn.generate_id()?;
+    conn.create_glyph_cursor(cursor, font, font, XC_CROSSHAIR, XC_CROSSHAIR + 1, 0, 0, 0, 0xFFFF, 0xFFFF, 0xFFFF)?;
+    conn.close_font(font)?;
+
+    let grab = conn
+        .grab_pointer(
+            false,
+            root,
+            EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE,
+            GrabMode::ASYNC,
+            GrabMode::ASYNC,
+            root,
+            cursor,
+            x11rb::CURRENT_TIME,
+        )?
+        .reply();
+    let picked = match grab {
+        Ok(grab) if grab.status == GrabStatus::SUCCESS => wait_for_click(conn),
+        Ok(_) => Err("Another client has grabbed the pointer".into()),
+        Err(e) => Err(e.into()),
+    };
+    conn.ungrab_pointer(x11rb::CURRENT_TIME)?;
+    conn.free_cursor(cursor)?;
+    conn.flush()?;
+
+    let frame = picked?;
+    if frame == x11rb::NONE {
+        return Ok(root.into());
+    }
+    Ok(client_window_of(session, frame.into())?.unwrap_or(frame.into()))
+}
+
+/// The root's child under the pointer at a button press, returned once the
+/// button is released so the click doesn't reach the window underneath
+fn wait_for_click(conn: &XConnection) -> crate::Result<XWindow> {
+    let mut pressed = None;
+    loop {
+        match conn.wait_for_event()? {
+            Event::ButtonPress(event) if pressed.is_none() => pressed = Some((event.detail, event.child)),
+            Event::ButtonRelease(event) => {
+                if let Some((button, child)) = pressed
+                    && button == event.detail
+                {
+                    return Ok(child);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Withdraw a window the ICCCM way: unmap it, then send the synthetic
+/// UnmapNotify that tells a reparenting WM the client did it. The window
+/// leaves the screen, taskbar and pager until it is mapped again.
+pub fn hide_window(session: &Session, window: crate::Window) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    // Checked, so a destroyed window is reported as WindowGone like on other platforms
+    conn.unmap_window(window.raw())?.check()?;
+    let event = UnmapNotifyEvent {
+        response_type: UNMAP_NOTIFY_EVENT,
+        sequence: 0,
+        event: root,
+        window: window.raw(),
+        from_configure: false,
+    };
+    conn.send_event(
+        false,
+        root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Map a window withdrawn by `hide_window`; the WM manages it again from scratch
+pub fn show_window(session: &Session, window: crate::Window) -> crate::Result<()> {
+    let conn = &session.conn;
+    conn.map_window(window.raw())?.check()?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Leave a window off the taskbar and pager (_NET_WM_STATE_SKIP_TASKBAR and
+/// _SKIP_PAGER), or put it back, without unmapping it. A mapped window's state
+/// belongs to the WM and is changed by request; an unmapped one's is set
+/// directly, for the WM to read when it is mapped.
+pub fn set_skip_taskbar(session: &Session, window: crate::Window, skip: bool) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let skip_atoms = [atoms._NET_WM_STATE_SKIP_TASKBAR, atoms._NET_WM_STATE_SKIP_PAGER];
+    if conn.get_window_attributes(window.raw())?.reply()?.map_state != MapState::UNMAPPED {
+        return change_wm_state(conn, atoms, root, window.raw(), skip, skip_atoms[0], Some(skip_atoms[1]));
+    }
+    let reply = conn
+        .get_property(false, window.raw(), atoms._NET_WM_STATE, AtomEnum::ATOM, 0, u32::MAX)?
+        .reply()?;
+    let mut states: Vec<Atom> = reply.value32().map(|states| states.collect()).unwrap_or_default();
+    states.retain(|atom| !skip_atoms.contains(atom));
+    if skip {
+        states.extend(skip_atoms);
+    }
+    conn.change_property(
+        PropMode::REPLACE,
+        window.raw(),
+        atoms._NET_WM_STATE,
+        AtomEnum::ATOM,
+        32,
+        states.len() as u32,
+        bytemuck::cast_slice(&states),
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// List all top-level client windows but desktop ones (_NET_WM_WINDOW_TYPE_DESKTOP),
+/// reading every window type in one pipelined batch
+pub fn list_windows(session: &Session) -> crate::Result<Vec<crate::Window>> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let windows = get_top_level_windows(conn, atoms, root)?;
+    let cookies = windows
+        .iter()
+        .map(|&window| conn.get_property(false, window, atoms._NET_WM_WINDOW_TYPE, AtomEnum::ATOM, 0, u32::MAX))
+        .collect::<Result<Vec<_>, _>>()?;
+    let is_desktop = |types: &GetPropertyReply| {
+        types.value32().is_some_and(|mut types| types.any(|atom| atom == atoms._NET_WM_WINDOW_TYPE_DESKTOP))
+    };
+    let mut listed = Vec::with_capacity(windows.len());
+    for (window, cookie) in windows.into_iter().zip(cookies) {
+        match cookie.reply() {
+            Ok(types) if is_desktop(&types) => {
+                trace_debug!(window, "skipped: a desktop window (_NET_WM_WINDOW_TYPE_DESKTOP)");
+            }
+            Ok(_) => listed.push(window.into()),
+            // Closed since the client list was read
+            Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window => {
+                trace_debug!(window, "skipped: closed since the client list was read");
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(listed)
+}
+
+/// The root window, then every top-level client window, desktop ones included
+pub fn list_windows_including_desktop(session: &Session) -> crate::Result<Vec<crate::Window>> {
+    let (conn, root) = (&session.conn, session.root);
+    let windows = get_top_level_windows(conn, session.atoms()?, root)?;
+    Ok(std::iter::once(root).chain(windows).map(crate::Window::from).collect())
+}
+
+/// Length of _NET_CLIENT_LIST, read from the property's size without fetching its
+/// contents. Unlike `list_windows`, no window type is read, so a desktop window the
+/// window manager lists is counted too. Without the property, the windows
+/// `get_top_level_windows` walks the tree for.
+pub fn window_count(session: &Session) -> crate::Result<usize> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let prop = conn
+        .get_property(false, root, atoms._NET_CLIENT_LIST, AtomEnum::WINDOW, 0, 0)?
+        .reply()?;
+    if prop.type_ == x11rb::NONE {
+        return Ok(walk_top_level_windows(conn, atoms, root)?.len());
+    }
+    if prop.format != 32 {
+        return Err("Failed to read _NET_CLIENT_LIST".into());
+    }
+    Ok(prop.bytes_after as usize / 4)
+}
+
+/// Get the currently active window
+pub fn get_active_window(session: &Session) -> crate::Result<Option<crate::Window>> {
+    let (conn, root) = (&session.conn, session.root);
+    Ok(read_active_window(conn, session.atoms()?, root)?.map(crate::Window::from))
+}
+
+/// Get a window's title (_NET_WM_NAME, falling back to WM_NAME)
+fn read_window_title(conn: &XConnection, atoms: &Atoms, window: XWindow) -> crate::Result<Option<String>> {
+    let reply = conn
+        .get_property(false, window, atoms._NET_WM_NAME, atoms.UTF8_STRING, 0, u32::MAX)?
+        .reply()?;
+    if reply.format == 8 && reply.value_len > 0 {
+        return Ok(Some(String::from_utf8_lossy(&reply.value).into_owned()));
+    }
+
+    let reply = conn
+        .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::ANY, 0, u32::MAX)?
+        .reply()?;
+    if reply.format == 8 && reply.value_len > 0 {
+        return Ok(Some(String::from_utf8_lossy(&reply.value).into_owned()));
+    }
+
+    Ok(None)
+}
+
+pub fn get_window_title(session: &Session, window: crate::Window) -> crate::Result<Option<String>> {
+    let conn = &session.conn;
+    let title = read_window_title(conn, session.atoms()?, window.raw());
+    title.context("get_window_title", Some(window), "_NET_WM_NAME/WM_NAME")
+}
+
+/// Approximate a Wayland app_id: GTK's _GTK_APPLICATION_ID when set, otherwise
+/// the class part of WM_CLASS (e.g. `firefox`)
+pub fn get_window_app_id(session: &Session, window: crate::Window) -> crate::Result<Option<String>> {
+    let conn = &session.conn;
+    let atoms = session.atoms()?;
+    let reply = conn
+        .get_property(false, window.raw(), atoms._GTK_APPLICATION_ID, atoms.UTF8_STRING, 0, u32::MAX)?
+        .reply()
+        .context("get_window_app_id", Some(window), "_GTK_APPLICATION_ID")?;
+    if reply.format == 8 && reply.value_len > 0 {
+        return Ok(Some(String::from_utf8_lossy(&reply.value).into_owned()));
+    }
+
+    Ok(read_wm_class(conn, window.raw())
+        .context("get_window_app_id", Some(window), "WM_CLASS")?
+        .map(|(_, class)| class)
+        .filter(|class| !class.is_empty()))
+}
+
+/// Read WM_CLASS as (instance, class)
+fn read_wm_class(conn: &XConnection, window: XWindow) -> crate::Result<Option<(String, String)>> {
+    // WM_CLASS is two NUL-terminated strings: instance, then class
+    let reply = conn
+        .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, u32::MAX)?
+        .reply()?;
+    Ok(parse_wm_class(&reply))
+}
+
+/// WM_CLASS as (instance, class), `None` if the window doesn't set it
+fn parse_wm_class(reply: &GetPropertyReply) -> Option<(String, String)> {
+    if reply.format != 8 || reply.value_len == 0 {
+        return None;
+    }
+    let mut parts = reply
+        .value
+        .split(|&byte| byte == 0)
+        .map(|part| String::from_utf8_lossy(part).into_owned());
+    let instance = parts.next().unwrap_or_default();
+    let class = parts.next().unwrap_or_default();
+    Some((instance, class))
+}
+
+/// WM_CLASS (instance, class) of the process's main window, or None if it has no window
+pub fn get_class_for_pid(session: &Session, target_pid: u32) -> crate::Result<Option<(String, String)>> {
+    let Some(window) = find_window_by_pid(session, target_pid)? else {
+        return Ok(None);
+    };
+    let conn = &session.conn;
+    read_wm_class(conn, window.raw())
+}
+
+/// Send a client message about `window` to the root window, the way EWMH/ICCCM expect
+fn send_root_message(
+    conn: &XConnection,
+    root: XWindow,
+    window: XWindow,
+    message_type: Atom,
+    data: [u32; 5],
+) -> crate::Result<()> {
+    let event = ClientMessageEvent::new(32, window, message_type, data);
+    conn.send_event(
+        false,
+        root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Send a 32-bit ClientMessage of type `message_type` about `window` to the
+/// root window, the way EWMH requests to the window manager are sent
+pub fn send_client_message(
+    session: &Session,
+    window: crate::Window,
+    message_type: &str,
+    data: [u32; 5],
+) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    let message_type = conn.intern_atom(false, message_type.as_bytes())?.reply()?.atom;
+    send_root_message(conn, root, window.raw(), message_type, data)
+}
+
+/// Read a whole property by name. `property_type` None accepts any type; a
+/// property of another type than the one asked for is an error.
+pub fn get_property_raw(
+    session: &Session,
+    window: crate::Window,
+    property: &str,
+    property_type: Option<&str>,
+) -> crate::Result<Option<RawProperty>> {
+    let conn = &session.conn;
+    let property_cookie = conn.intern_atom(true, property.as_bytes())?;
+    let type_cookie = property_type.map(|name| conn.intern_atom(false, name.as_bytes())).transpose()?;
+    let property_atom = property_cookie.reply()?.atom;
+    if property_atom == x11rb::NONE {
+        // Never interned, so no window can have it
+        return Ok(None);
+    }
+    let requested = match type_cookie {
+        Some(cookie) => cookie.reply()?.atom,
+        None => AtomEnum::ANY.into(),
+    };
+    let reply = conn
+        .get_property(false, window.raw(), property_atom, requested, 0, u32::MAX)?
+        .reply()?;
+    if reply.type_ == x11rb::NONE {
+        return Ok(None);
+    }
+    let type_name = String::from_utf8_lossy(&conn.get_atom_name(reply.type_)?.reply()?.name).into_owned();
+    if requested != u32::from(AtomEnum::ANY) && reply.type_ != requested {
+        return Err(format!(
+            "Property {property} of window {window} has type {type_name}, not {}",
+            property_type.unwrap_or_default()
+        )
+        .into());
+    }
+    Ok(Some(RawProperty { type_name, format: reply.format, data: reply.value }))
+}
+
+/// Replace a property, interning `property` and `property_type` by name.
+/// `data` holds `format`-bit items in native byte order.
+pub fn set_property_raw(
+    session: &Session,
+    window: crate::Window,
+    property: &str,
+    property_type: &str,
+    format: u8,
+    data: &[u8],
+) -> crate::Result<()> {
+    if !matches!(format, 8 | 16 | 32) {
+        return Err(format!("Property format must be 8, 16 or 32, not {format}").into());
+    }
+    let item_size = usize::from(format / 8);
+    if !data.len().is_multiple_of(item_size) {
+        return Err(format!("{} bytes are not a whole number of {format}-bit items", data.len()).into());
+    }
+    let conn = &session.conn;
+    let property_cookie = conn.intern_atom(false, property.as_bytes())?;
+    let type_cookie = conn.intern_atom(false, property_type.as_bytes())?;
+    let (property_atom, type_atom) = (property_cookie.reply()?.atom, type_cookie.reply()?.atom);
+    conn.change_property(
+        PropMode::REPLACE,
+        window.raw(),
+        property_atom,
+        type_atom,
+        format,
+        (data.len() / item_size) as u32,
+        data,
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Iconify a window (ICCCM WM_CHANGE_STATE to IconicState)
+pub fn minimize_window(session: &Session, window: crate::Window) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    const ICONIC_STATE: u32 = 3;
+    let message_type = session.atoms()?.WM_CHANGE_STATE;
+    send_root_message(conn, root, window.raw(), message_type, [ICONIC_STATE, 0, 0, 0, 0])
+}
+
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+
+/// Ask the window manager to add or remove up to two _NET_WM_STATE atoms
+fn change_wm_state(
+    conn: &XConnection,
+    atoms: &Atoms,
+    root: XWindow,
+    window: XWindow,
+    enabled: bool,
+    first: Atom,
+    second: Option<Atom>,
+) -> crate::Result<()> {
+    const SOURCE_APPLICATION: u32 = 1;
+    let action = if enabled { NET_WM_STATE_ADD } else { NET_WM_STATE_REMOVE };
+    send_root_message(
+        conn,
+        root,
+        window,
+        atoms._NET_WM_STATE,
+        [action, first, second.unwrap_or(x11rb::NONE), SOURCE_APPLICATION, 0],
+    )
+}
+
+/// Maximize a window both horizontally and vertically
+pub fn maximize_window(session: &Session, window: crate::Window) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    change_wm_state(
+        conn,
+        atoms,
+        root,
+        window.raw(),
+        true,
+        atoms._NET_WM_STATE_MAXIMIZED_VERT,
+        Some(atoms._NET_WM_STATE_MAXIMIZED_HORZ),
+    )
+}
+
+/// Ask the window manager to close a window (_NET_CLOSE_WINDOW)
+pub fn close_window(session: &Session, window: crate::Window) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    const SOURCE_APPLICATION: u32 = 1;
+    let message_type = session.atoms()?._NET_CLOSE_WINDOW;
+    send_root_message(conn, root, window.raw(), message_type, [0, SOURCE_APPLICATION, 0, 0, 0])
+}
+
+/// Get the largest icon from _NET_WM_ICON, converted from ARGB to RGBA
+pub fn get_window_icon(session: &Session, window: crate::Window) -> crate::Result<Option<IconData>> {
+    let conn = &session.conn;
+    let reply = conn
+        .get_property(false, window.raw(), session.atoms()?._NET_WM_ICON, AtomEnum::CARDINAL, 0, u32::MAX)?
+        .reply()
+        .context("get_window_icon", Some(window), "_NET_WM_ICON")?;
+    if reply.value_len == 0 || reply.format != 32 {
+        return Ok(None);
+    }
+    let data: Vec<u32> = reply
+        .value32()
+        .ok_or("Failed to parse _NET_WM_ICON")
+        .context("get_window_icon", Some(window), "_NET_WM_ICON")?
+        .collect();
+
+    // The property is a sequence of (width, height, width * height ARGB pixels)
+    let mut largest: Option<(u32, u32, &[u32])> = None;
+    let mut rest = data.as_slice();
+    while let [width, height, pixels @ ..] = rest {
+        let len = (*width as usize).saturating_mul(*height as usize);
+        if len == 0 || pixels.len() < len {
+            break;
+        }
+        if largest.is_none_or(|(w, h, _)| w * h < width * height) {
+            largest = Some((*width, *height, &pixels[..len]));
+        }
+        rest = &pixels[len..];
+    }
+
+    Ok(largest.map(|(width, height, pixels)| IconData {
+        width,
+        height,
+        rgba: pixels
+            .iter()
+            .flat_map(|argb| {
+                let [b, g, r, a] = argb.to_le_bytes();
+                [r, g, b, a]
+            })
+            .collect(),
+    }))
+}
+
+/// Read a single CARDINAL property value
+fn get_cardinal_property(
+    conn: &XConnection,
+    window: XWindow,
+    property: Atom,
+) -> crate::Result<Option<u32>> {
+    get_window_property(conn, window, property, AtomEnum::CARDINAL)
+}
+
+/// Read a single 32-bit property value of the given type
+fn get_window_property(
+    conn: &XConnection,
+    window: XWindow,
+    property: Atom,
+    property_type: AtomEnum,
+) -> crate::Result<Option<u32>> {
+    let reply = conn
+        .get_property(false, window, property, property_type, 0, 1)?
+        .reply()?;
+    if reply.value_len == 0 || reply.format != 32 {
+        return Ok(None);
+    }
+    Ok(reply.value32().and_then(|mut values| values.next()))
+}
+
+/// Move a window's top-left corner to (x, y) in root coordinates
+pub fn move_window(session: &Session, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+    let conn = &session.conn;
+    conn.configure_window(window.raw(), &ConfigureWindowAux::new().x(x).y(y))?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Resize a window's client area
+pub fn resize_window(session: &Session, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+    let conn = &session.conn;
+    conn.configure_window(window.raw(), &ConfigureWindowAux::new().width(width).height(height))?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Move and resize a window in a single ConfigureWindow request
+pub fn set_window_rect(
+    session: &Session,
+    window: crate::Window,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> crate::Result<()> {
+    let conn = &session.conn;
+    conn.configure_window(window.raw(), &ConfigureWindowAux::new().x(x).y(y).width(width).height(height))?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Activate a window via _NET_ACTIVE_WINDOW
+pub fn focus_window(session: &Session, window: crate::Window) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    // Source indication 2 (pager): WMs don't apply focus-stealing prevention to pagers
+    const SOURCE_PAGER: u32 = 2;
+    let message_type = session.atoms()?._NET_ACTIVE_WINDOW;
+    send_root_message(conn, root, window.raw(), message_type, [SOURCE_PAGER, 0, 0, 0, 0])
+}
+
+/// Whether a window can take input focus under ICCCM: docks never can; a window
+/// whose WM_HINTS input field is false only can if it supports WM_TAKE_FOCUS
+/// (the "globally active" model). A missing input hint counts as true, as
+/// most window managers treat it.
+pub fn can_focus(session: &Session, window: crate::Window) -> crate::Result<bool> {
+    let conn = &session.conn;
+    let atoms = session.atoms()?;
+    let window = window.raw();
+
+    let types = conn
+        .get_property(false, window, atoms._NET_WM_WINDOW_TYPE, AtomEnum::ATOM, 0, u32::MAX)?
+        .reply()?;
+    if types.value32().is_some_and(|mut types| types.any(|atom| atom == atoms._NET_WM_WINDOW_TYPE_DOCK)) {
+        return Ok(false);
+    }
+
+    // WM_HINTS is { flags, input, ... }; bit 0 of flags (InputHint) says whether input is set
+    const INPUT_HINT: u32 = 1;
+    let hints = conn
+        .get_property(false, window, AtomEnum::WM_HINTS, AtomEnum::WM_HINTS, 0, 2)?
+        .reply()?;
+    let input = match hints.value32().map(|values| values.collect::<Vec<_>>()).as_deref() {
+        Some(&[flags, input]) if flags & INPUT_HINT != 0 => input != 0,
+        _ => true,
+    };
+    if input {
+        return Ok(true);
+    }
+
+    let supported = conn
+        .get_property(false, window, atoms.WM_PROTOCOLS, AtomEnum::ATOM, 0, u32::MAX)?
+        .reply()?;
+    Ok(supported.value32().is_some_and(|mut protocols| protocols.any(|atom| atom == atoms.WM_TAKE_FOCUS)))
+}
+
+// WM_HINTS flags bit 8 (UrgencyHint): the ICCCM predecessor of _NET_WM_STATE_DEMANDS_ATTENTION
+const URGENCY_HINT: u32 = 1 << 8;
+
+/// Panels, docks and taskbars: windows typed _NET_WM_WINDOW_TYPE_DOCK or
+/// reserving screen space with a strut. Both the client list and the root's
+/// children are searched, since some WMs leave docks out of _NET_CLIENT_LIST
+/// and some panels are override-redirect.
+pub fn get_panels(session: &Session) -> crate::Result<Vec<crate::Window>> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let mut candidates = get_top_level_windows(conn, atoms, root)?;
+    let mut seen: HashSet<XWindow> = candidates.iter().copied().collect();
+    candidates.extend(conn.query_tree(root)?.reply()?.children.into_iter().filter(|window| seen.insert(*window)));
+
+    let cookies = candidates
+        .iter()
+        .map(|&window| {
+            Ok((
+                conn.get_property(false, window, atoms._NET_WM_WINDOW_TYPE, AtomEnum::ATOM, 0, u32::MAX)?,
+                conn.get_property(false, window, atoms._NET_WM_STRUT_PARTIAL, AtomEnum::CARDINAL, 0, 0)?,
+                conn.get_property(false, window, atoms._NET_WM_STRUT, AtomEnum::CARDINAL, 0, 0)?,
+            ))
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    let mut panels = Vec::new();
+    for (window, (types, strut_partial, strut)) in candidates.into_iter().zip(cookies) {
+        let replies = (|| Ok::<_, ReplyError>((types.reply()?, strut_partial.reply()?, strut.reply()?)))();
+        let (types, strut_partial, strut) = match replies {
+            Ok(replies) => replies,
+            // Closed while being checked
+            Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let is_dock = types.value32().is_some_and(|mut types| types.any(|atom| atom == atoms._NET_WM_WINDOW_TYPE_DOCK));
+        if is_dock || strut_partial.type_ != x11rb::NONE || strut.type_ != x11rb::NONE {
+            panels.push(window.into());
+        }
+    }
+    Ok(panels)
+}
+
+/// The space a window reserves at the screen edges: _NET_WM_STRUT_PARTIAL,
+/// else _NET_WM_STRUT taken to span each edge it reserves from end to end
+pub fn get_strut(session: &Session, window: crate::Window) -> crate::Result<Option<Strut>> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let partial = conn
+        .get_property(false, window.raw(), atoms._NET_WM_STRUT_PARTIAL, AtomEnum::CARDINAL, 0, 12)?
+        .reply()
+        .context("get_strut", Some(window), "_NET_WM_STRUT_PARTIAL")?;
+    let partial: Vec<u32> = partial.value32().map(|values| values.collect()).unwrap_or_default();
+    // left, right, top, bottom, then the start and end of each: left_y, right_y, top_x, bottom_x
+    if let &[left, right, top, bottom, left_y0, left_y1, right_y0, right_y1, top_x0, top_x1, bottom_x0, bottom_x1] =
+        &partial[..]
+    {
+        return Ok(Some(Strut {
+            left,
+            right,
+            top,
+            bottom,
+            left_y: (left_y0, left_y1),
+            right_y: (right_y0, right_y1),
+            top_x: (top_x0, top_x1),
+            bottom_x: (bottom_x0, bottom_x1),
+        }));
+    }
+    let strut = conn
+        .get_property(false, window.raw(), atoms._NET_WM_STRUT, AtomEnum::CARDINAL, 0, 4)?
+        .reply()
+        .context("get_strut", Some(window), "_NET_WM_STRUT")?;
+    let strut: Vec<u32> = strut.value32().map(|values| values.collect()).unwrap_or_default();
+    let &[left, right, top, bottom] = &strut[..] else {
+        return Ok(None);
+    };
+    let screen = conn.get_geometry(root)?.reply()?;
+    let (last_x, last_y) = ((screen.width as u32).saturating_sub(1), (screen.height as u32).saturating_sub(1));
+    Ok(Some(Strut {
+        left,
+        right,
+        top,
+        bottom,
+        left_y: (0, last_y),
+        right_y: (0, last_y),
+        top_x: (0, last_x),
+        bottom_x: (0, last_x),
+    }))
+}
+
+/// A monitor's bounds less the struts of the panels along its edges. Struts
+/// are measured from the edges of the root window, so each one counts against
+/// the monitor its inner edge falls on, where it covers that monitor's span.
+pub fn work_area(session: &Session, monitor: WindowInfo) -> crate::Result<WindowInfo> {
+    let screen = session.conn.get_geometry(session.root)?.reply()?;
+    let (screen_width, screen_height) = (screen.width as i64, screen.height as i64);
+    let (x0, y0) = (monitor.pos.0 as i64, monitor.pos.1 as i64);
+    let (x1, y1) = (monitor.right() as i64, monitor.bottom() as i64);
+    let (mut left, mut top, mut right, mut bottom) = (x0, y0, x1, y1);
+    // Whether an inclusive strut range meets [start, end)
+    let covers = |(first, last): (u32, u32), start: i64, end: i64| (first as i64) < end && last as i64 >= start;
+    for panel in get_panels(session)? {
+        let strut = match get_strut(session, panel) {
+            Ok(Some(strut)) => strut,
+            Ok(None) | Err(crate::Error::WindowGone(_) | crate::Error::WindowNotFound(_)) => continue,
+            Err(error) => return Err(error),
+        };
+        let edge = strut.left as i64;
+        if strut.left > 0 && edge > x0 && edge < x1 && covers(strut.left_y, y0, y1) {
+            left = left.max(edge);
+        }
+        let edge = screen_width - strut.right as i64;
+        if strut.right > 0 && edge > x0 && edge < x1 && covers(strut.right_y, y0, y1) {
+            right = right.min(edge);
+        }
+        let edge = strut.top as i64;
+        if strut.top > 0 && edge > y0 && edge < y1 && covers(strut.top_x, x0, x1) {
+            top = top.max(edge);
+        }
+        let edge = screen_height - strut.bottom as i64;
+        if strut.bottom > 0 && edge > y0 && edge < y1 && covers(strut.bottom_x, x0, x1) {
+            bottom = bottom.min(edge);
+        }
+    }
+    Ok(WindowInfo {
+        pos: (left as i32, top as i32),
+        size: ((right - left).max(0) as u32, (bottom - top).max(0) as u32),
+    })
+}
+
+/// What the window manager advertises in `_NET_SUPPORTED` on the root window,
+/// and whether a compositing manager owns the `_NET_WM_CM_S<screen>` selection
+pub fn capabilities(session: &Session, is_wayland: bool) -> crate::Result<Capabilities> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let screen = conn.setup().roots.iter().position(|screen| screen.root == root).unwrap_or_default();
+    let selection = conn.intern_atom(false, format!("_NET_WM_CM_S{screen}").as_bytes())?;
+    let supported = conn.get_property(false, root, atoms._NET_SUPPORTED, AtomEnum::ATOM, 0, u32::MAX)?;
+    let owner = conn.get_selection_owner(selection.reply()?.atom)?.reply()?.owner;
+    let supported: Vec<Atom> = supported.reply()?.value32().map(|atoms| atoms.collect()).unwrap_or_default();
+    Ok(Capabilities {
+        backend: BackendKind::X11,
+        is_wayland,
+        has_compositor: is_wayland || owner != x11rb::NONE,
+        supports_ewmh_state: supported.contains(&atoms._NET_WM_STATE),
+        can_move_windows: true,
+        can_move_to_desktop: supported.contains(&atoms._NET_WM_DESKTOP),
+        can_set_always_on_top: supported.contains(&atoms._NET_WM_STATE_ABOVE),
+        can_watch_events: true,
+        can_set_opacity: false,
+        supports_input_simulation: false,
+    })
+}
+
+/// Whether WM_HINTS has the UrgencyHint flag set; false without WM_HINTS
+pub fn is_urgent(session: &Session, window: crate::Window) -> crate::Result<bool> {
+    let hints = session
+        .conn
+        .get_property(false, window.raw(), AtomEnum::WM_HINTS, AtomEnum::WM_HINTS, 0, 1)?
+        .reply()
+        .context("is_urgent", Some(window), "WM_HINTS")?;
+    Ok(hints.value32().and_then(|mut values| values.next()).is_some_and(|flags| flags & URGENCY_HINT != 0))
+}
+
+/// Set or clear the UrgencyHint flag of WM_HINTS. Every other field is written
+/// back as read; a window without WM_HINTS gets one holding only the flag
+/// (nine fields, the ICCCM size), or is left alone when clearing.
+pub fn set_urgency(session: &Session, window: crate::Window, urgent: bool) -> crate::Result<()> {
+    let conn = &session.conn;
+    let reply = conn
+        .get_property(false, window.raw(), AtomEnum::WM_HINTS, AtomEnum::WM_HINTS, 0, u32::MAX)?
+        .reply()
+        .context("set_urgency", Some(window), "WM_HINTS")?;
+    let mut hints: Vec<u32> = match reply.value32() {
+        Some(values) => values.collect(),
+        None if !urgent => return Ok(()),
+        None => vec![0; 9],
+    };
+    if hints.is_empty() {
+        hints.resize(9, 0);
+    }
+    let flags = if urgent { hints[0] | URGENCY_HINT } else { hints[0] & !URGENCY_HINT };
+    if flags == hints[0] {
+        return Ok(());
+    }
+    hints[0] = flags;
+    conn.change_property(
+        PropMode::REPLACE,
+        window.raw(),
+        AtomEnum::WM_HINTS,
+        AtomEnum::WM_HINTS,
+        32,
+        hints.len() as u32,
+        bytemuck::cast_slice(&hints),
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Current X server time, obtained from the PropertyNotify of a zero-length
+/// append to a throwaway window (ICCCM's way of getting a timestamp without an input event)
+fn server_time(conn: &XConnection, atoms: &Atoms, root: XWindow) -> crate::Result<u32> {
+    let window = conn.generate_id()?;
+    conn.create_window(
+        0,
+        window,
+        root,
+        -1,
+        -1,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        0,
+        &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+    )?;
+    let time = append_and_wait(conn, atoms, window);
+    // Destroyed on every path; if the wait failed, its error is the one returned
+    let destroyed = conn.destroy_window(window).map(drop).and_then(|()| conn.flush());
+    let time = time?;
+    destroyed?;
+    Ok(time)
+}
+
+/// The time of the PropertyNotify a zero-length append to `window` generates
+fn append_and_wait(conn: &XConnection, atoms: &Atoms, window: XWindow) -> crate::Result<u32> {
+    conn.change_property(PropMode::APPEND, window, atoms._WINDOWING_TIMESTAMP, AtomEnum::STRING, 8, 0, &[])?;
+    conn.flush()?;
+    loop {
+        if let Event::PropertyNotify(event) = conn.wait_for_event()?
+            && event.window == window
+        {
+            return Ok(event.time);
+        }
+    }
+}
+
+/// Set _NET_WM_USER_TIME, on the client's _NET_WM_USER_TIME_WINDOW if it has one
+fn write_user_time(
+    conn: &XConnection,
+    atoms: &Atoms,
+    window: XWindow,
+    timestamp: u32,
+) -> crate::Result<()> {
+    let target = get_window_property(conn, window, atoms._NET_WM_USER_TIME_WINDOW, AtomEnum::WINDOW)?
+        .unwrap_or(window);
+    conn.change_property(
+        PropMode::REPLACE,
+        target,
+        atoms._NET_WM_USER_TIME,
+        AtomEnum::CARDINAL,
+        32,
+        1,
+        bytemuck::bytes_of(&timestamp),
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Set a window's _NET_WM_USER_TIME, the time of the last user interaction with it
+pub fn set_user_time(session: &Session, window: crate::Window, timestamp: u32) -> crate::Result<()> {
+    write_user_time(&session.conn, session.atoms()?, window.raw(), timestamp)
+}
+
+/// Activate a window as an application request, optionally bumping its
+/// _NET_WM_USER_TIME to the current server time first so focus-stealing
+/// prevention treats the request as fresh user interaction
+pub fn activate_window(session: &Session, window: crate::Window, update_user_time: bool) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    const SOURCE_APPLICATION: u32 = 1;
+    let timestamp = if update_user_time {
+        let time = server_time(conn, atoms, root)?;
+        write_user_time(conn, atoms, window.raw(), time)?;
+        time
+    } else {
+        x11rb::CURRENT_TIME
+    };
+    let data = [SOURCE_APPLICATION, timestamp, 0, 0, 0];
+    send_root_message(conn, root, window.raw(), atoms._NET_ACTIVE_WINDOW, data)
+}
+
+/// Get the desktop a window is on (_NET_WM_DESKTOP); `None` for sticky windows
+pub fn get_window_workspace(session: &Session, window: crate::Window) -> crate::Result<Option<u32>> {
+    let conn = &session.conn;
+    Ok(get_cardinal_property(conn, window.raw(), session.atoms()?._NET_WM_DESKTOP)
+        .context("get_window_workspace", Some(window), "_NET_WM_DESKTOP")?
+        .filter(|&desktop| desktop != ALL_DESKTOPS))
+}
+
+/// _NET_WM_DESKTOP of a window shown on every desktop
+const ALL_DESKTOPS: u32 = 0xFFFFFFFF;
+
+/// Ask the WM to move a window to another desktop
+pub fn move_window_to_workspace(
+    session: &Session,
+    window: crate::Window,
+    workspace: u32,
+) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    const SOURCE_APPLICATION: u32 = 1;
+    let message_type = session.atoms()?._NET_WM_DESKTOP;
+    send_root_message(conn, root, window.raw(), message_type, [workspace, SOURCE_APPLICATION, 0, 0, 0])
+}
+
+/// Get the current desktop (_NET_CURRENT_DESKTOP)
+pub fn get_current_workspace(session: &Session) -> crate::Result<Option<u32>> {
+    let (conn, root) = (&session.conn, session.root);
+    get_cardinal_property(conn, root, session.atoms()?._NET_CURRENT_DESKTOP)
+}
+
+/// Ask the WM to switch to another desktop
+pub fn switch_workspace(session: &Session, workspace: u32) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    send_root_message(conn, root, root, session.atoms()?._NET_CURRENT_DESKTOP, [workspace, 0, 0, 0, 0])
+}
+
+/// State for the event stream: one long-lived connection watching property changes
+struct EventWatcher {
+    conn: XConnection,
+    atoms: Atoms,
+    root: XWindow,
+    known: HashSet<XWindow>,
+    queue: VecDeque<WindowEvent>,
+}
+
+impl EventWatcher {
+    fn watch(&self, window: XWindow) -> crate::Result<()> {
+        self.conn.change_window_attributes(
+            window,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        Ok(())
+    }
+
+    fn refresh_clients(&mut self) -> crate::Result<()> {
+        let current: HashSet<_> = get_top_level_windows(&self.conn, &self.atoms, self.root)?
+            .into_iter()
+            .collect();
+        for &window in current.difference(&self.known) {
+            self.watch(window)?;
+            self.queue.push_back(WindowEvent::Opened(window.into()));
+        }
+        for &window in self.known.difference(&current) {
+            self.queue.push_back(WindowEvent::Closed(window.into()));
+        }
+        self.conn.flush()?;
+        self.known = current;
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> crate::Result<WindowEvent> {
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Ok(event);
+            }
+            // Errors for windows that vanished before we could watch them are expected
+            let Event::PropertyNotify(event) = self.conn.wait_for_event()? else {
+                continue;
+            };
+            if event.window == self.root {
+                if event.atom == self.atoms._NET_CLIENT_LIST {
+                    self.refresh_clients()?;
+                } else if event.atom == self.atoms._NET_ACTIVE_WINDOW {
+                    let active = read_active_window(&self.conn, &self.atoms, self.root)
+                        .ok()
+                        .flatten()
+                        .map(crate::Window::from);
+                    self.queue.push_back(WindowEvent::Focused(active));
+                }
+            } else if event.atom == self.atoms._NET_WM_NAME || event.atom == u32::from(AtomEnum::WM_NAME) {
+                self.queue.push_back(WindowEvent::TitleChanged(event.window.into()));
+            } else if event.atom == self.atoms._NET_WM_DESKTOP {
+                let desktop = get_cardinal_property(&self.conn, event.window, self.atoms._NET_WM_DESKTOP)
+                    .ok()
+                    .flatten()
+                    .filter(|&desktop| desktop != ALL_DESKTOPS);
+                self.queue.push_back(WindowEvent::WorkspaceChanged(event.window.into(), desktop));
+            }
+        }
+    }
+}
+
+/// Watch the root window and every client for lifecycle, focus and title changes
+pub fn subscribe_window_events() -> crate::Result<WindowEvents> {
+    let Session { conn, root, .. } = Session::connect(None)?;
+    let atoms = Atoms::new(&conn)?.reply()?;
+
+    let known: HashSet<_> = get_top_level_windows(&conn, &atoms, root)?.into_iter().collect();
+    let mut watcher = EventWatcher {
+        conn,
+        atoms,
+        root,
+        known,
+        queue: VecDeque::new(),
+    };
+    watcher.watch(root)?;
+    for &window in &watcher.known {
+        watcher.watch(window)?;
+    }
+    watcher.conn.flush()?;
+
+    Ok(WindowEvents::new(std::iter::from_fn(move || Some(watcher.next_event()))))
+}
+
+/// Get the bounding rectangle of all monitors: the root window's geometry
+pub fn get_virtual_screen(session: &Session) -> crate::Result<WindowInfo> {
+    let (conn, root) = (&session.conn, session.root);
+    Ok(conn.get_geometry(root)?.reply()?.into())
+}
+
+/// Every active RandR monitor (RandR 1.5), primary first, named after its
+/// output. X11 coordinates are pixels, so the scale factor is always 1.
+pub fn list_monitors(session: &Session) -> crate::Result<Vec<MonitorInfo>> {
+    let (conn, root) = (&session.conn, session.root);
+    let mut monitors = conn.randr_get_monitors(root, true)?.reply()?.monitors;
+    monitors.sort_by_key(|monitor| !monitor.primary);
+    let names = monitors
+        .iter()
+        .map(|monitor| conn.get_atom_name(monitor.name))
+        .collect::<Result<Vec<_>, _>>()?;
+    monitors
+        .into_iter()
+        .zip(names)
+        .map(|(monitor, name)| {
+            Ok(MonitorInfo {
+                name: String::from_utf8_lossy(&name.reply()?.name).into_owned(),
+                bounds: WindowInfo {
+                    pos: (monitor.x.into(), monitor.y.into()),
+                    size: (monitor.width.into(), monitor.height.into()),
+                },
+                scale_factor: 1.0,
+            })
+        })
+        .collect()
+}
+
+/// Whether the server knows the window id: GetWindowAttributes fails with
+/// BadWindow for destroyed and never-created windows
+pub fn window_exists(session: &Session, window: crate::Window) -> crate::Result<bool> {
+    match session.conn.get_window_attributes(window.raw())?.reply() {
+        Ok(_) => Ok(true),
+        Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Check whether a window is currently in the given state
+pub fn window_has_state(session: &Session, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    let (conn, root) = (&session.conn, session.root);
+    let window = window.raw();
+
+    let attributes = match conn.get_window_attributes(window)?.reply() {
+        Ok(attributes) => attributes,
+        Err(ReplyError::X11Error(e)) if e.error_kind == ErrorKind::Window => {
+            return Ok(state == WindowState::Closed);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let atoms = session.atoms()?;
+    let reply = conn
+        .get_property(false, window, atoms._NET_WM_STATE, AtomEnum::ATOM, 0, u32::MAX)?
+        .reply()?;
+    let states: Vec<Atom> = reply.value32().map(|states| states.collect()).unwrap_or_default();
+    let has = |atom: Atom| states.contains(&atom);
+
+    Ok(match state {
+        WindowState::Closed => false,
+        WindowState::Visible => {
+            attributes.map_state == MapState::VIEWABLE && !has(atoms._NET_WM_STATE_HIDDEN)
+        }
+        WindowState::Minimized => {
+            // ICCCM WM_STATE IconicState, or the EWMH hidden flag
+            const ICONIC_STATE: u32 = 3;
+            let reply = conn
+                .get_property(false, window, atoms.WM_STATE, atoms.WM_STATE, 0, 1)?
+                .reply()?;
+            reply.value32().and_then(|mut values| values.next()) == Some(ICONIC_STATE)
+                || has(atoms._NET_WM_STATE_HIDDEN)
+        }
+        WindowState::Maximized => {
+            has(atoms._NET_WM_STATE_MAXIMIZED_VERT) && has(atoms._NET_WM_STATE_MAXIMIZED_HORZ)
+        }
+        WindowState::Fullscreen => has(atoms._NET_WM_STATE_FULLSCREEN),
+        WindowState::Focused => read_active_window(conn, atoms, root).ok().flatten() == Some(window),
+    })
+}
+
+/// The EWMH atom behind each named WindowStateFlag
+fn state_atoms(atoms: &Atoms) -> [(Atom, WindowStateFlag); 13] {
+    [
+        (atoms._NET_WM_STATE_MODAL, WindowStateFlag::Modal),
+        (atoms._NET_WM_STATE_STICKY, WindowStateFlag::Sticky),
+        (atoms._NET_WM_STATE_MAXIMIZED_VERT, WindowStateFlag::MaximizedVert),
+        (atoms._NET_WM_STATE_MAXIMIZED_HORZ, WindowStateFlag::MaximizedHorz),
+        (atoms._NET_WM_STATE_SHADED, WindowStateFlag::Shaded),
+        (atoms._NET_WM_STATE_SKIP_TASKBAR, WindowStateFlag::SkipTaskbar),
+        (atoms._NET_WM_STATE_SKIP_PAGER, WindowStateFlag::SkipPager),
+        (atoms._NET_WM_STATE_HIDDEN, WindowStateFlag::Hidden),
+        (atoms._NET_WM_STATE_FULLSCREEN, WindowStateFlag::Fullscreen),
+        (atoms._NET_WM_STATE_ABOVE, WindowStateFlag::Above),
+        (atoms._NET_WM_STATE_BELOW, WindowStateFlag::Below),
+        (atoms._NET_WM_STATE_DEMANDS_ATTENTION, WindowStateFlag::DemandsAttention),
+        (atoms._NET_WM_STATE_FOCUSED, WindowStateFlag::Focused),
+    ]
+}
+
+/// Read every atom in _NET_WM_STATE and map it to a WindowStateFlag; only
+/// atoms outside the known set cost a GetAtomName round trip
+pub fn get_window_states(session: &Session, window: crate::Window) -> crate::Result<Vec<WindowStateFlag>> {
+    let conn = &session.conn;
+    let atoms = session.atoms()?;
+    let reply = conn
+        .get_property(false, window.raw(), atoms._NET_WM_STATE, AtomEnum::ATOM, 0, u32::MAX)?
+        .reply()
+        .context("get_window_states", Some(window), "_NET_WM_STATE")?;
+    parse_states(conn, atoms, &reply).context("get_window_states", Some(window), "_NET_WM_STATE")
+}
+
+/// Map a _NET_WM_STATE reply to flags, naming atoms outside `state_atoms`
+fn parse_states(conn: &XConnection, atoms: &Atoms, reply: &GetPropertyReply) -> crate::Result<Vec<WindowStateFlag>> {
+    let Some(states) = reply.value32() else {
+        return Ok(Vec::new());
+    };
+    let known = state_atoms(atoms);
+    states
+        .map(|atom| match known.iter().find(|(known, _)| *known == atom) {
+            Some((_, flag)) => Ok(flag.clone()),
+            None => {
+                let name = conn.get_atom_name(atom)?.reply()?.name;
+                Ok(WindowStateFlag::Other(String::from_utf8_lossy(&name).into_owned()))
+            }
+        })
+        .collect()
+}
+
+/// Add or remove one _NET_WM_STATE atom; `Other` flags name the atom directly
+pub fn set_window_state(
+    session: &Session,
+    window: crate::Window,
+    flag: WindowStateFlag,
+    enabled: bool,
+) -> crate::Result<()> {
+    let (conn, root) = (&session.conn, session.root);
+    let atoms = session.atoms()?;
+    let atom = match &flag {
+        WindowStateFlag::Other(name) => conn.intern_atom(false, name.as_bytes())?.reply()?.atom,
+        flag => state_atoms(atoms)
+            .into_iter()
+            .find(|(_, known)| known == flag)
+            .map(|(atom, _)| atom)
+            .ok_or("Unknown window state flag")?,
+    };
+    change_wm_state(conn, atoms, root, window.raw(), enabled, atom, None)
+}
+
+/// Whether _NET_WM_STATE_STICKY is set (the window shows on all desktops)
+pub fn is_sticky(session: &Session, window: crate::Window) -> crate::Result<bool> {
+    Ok(get_window_states(session, window)?.contains(&WindowStateFlag::Sticky))
+}
+
+/// Ask the WM to show the window on all desktops (_NET_WM_STATE_STICKY)
+pub fn set_sticky(session: &Session, window: crate::Window, sticky: bool) -> crate::Result<()> {
+    set_window_state(session, window, WindowStateFlag::Sticky, sticky)
+}
+
+/// Whether _NET_WM_STATE_ABOVE is set (the window stays above normal windows)
+pub fn is_always_on_top(session: &Session, window: crate::Window) -> crate::Result<bool> {
+    Ok(get_window_states(session, window)?.contains(&WindowStateFlag::Above))
+}
+
+/// Ask the WM to keep the window above normal windows (_NET_WM_STATE_ABOVE)
+pub fn set_always_on_top(session: &Session, window: crate::Window, on_top: bool) -> crate::Result<()> {
+    set_window_state(session, window, WindowStateFlag::Above, on_top)
+}
+
+/// Whether _NET_WM_STATE_BELOW is set (the window stays below normal windows)
+pub fn is_always_below(session: &Session, window: crate::Window) -> crate::Result<bool> {
+    Ok(get_window_states(session, window)?.contains(&WindowStateFlag::Below))
+}
+
+/// Ask the WM to keep the window below normal windows (_NET_WM_STATE_BELOW)
+pub fn set_always_below(session: &Session, window: crate::Window, below: bool) -> crate::Result<()> {
+    set_window_state(session, window, WindowStateFlag::Below, below)
+}
+
+/// Give the window an empty input region (X Shape extension) so clicks fall
+/// through to what is beneath, or drop the input shape to restore the full
+/// region. Under a reparenting WM the frame still takes clicks around the
+/// window, so this is meant for undecorated or override-redirect overlays.
+pub fn set_click_through(session: &Session, window: crate::Window, click_through: bool) -> crate::Result<()> {
+    let conn = &session.conn;
+    if conn.extension_information(shape::X11_EXTENSION_NAME)?.is_none() {
+        return Err(crate::Unsupported::new("set_click_through", crate::BackendKind::X11.name()).into());
+    }
+    if click_through {
+        conn.shape_rectangles(SO::SET, SK::INPUT, ClipOrdering::UNSORTED, window.raw(), 0, 0, &[])?.check()?;
+    } else {
+        conn.shape_mask(SO::SET, SK::INPUT, window.raw(), 0, 0, x11rb::NONE)?.check()?;
+    }
+    Ok(())
+}
+
+impl WindowingBackend for X11Backend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::X11
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        self.with_x11(list_windows)
+    }
+
+    fn list_windows_including_desktop(&self) -> crate::Result<Vec<crate::Window>> {
+        self.with_x11(list_windows_including_desktop)
+    }
+
+    fn window_exists(&self, window: crate::Window) -> crate::Result<bool> {
+        self.with_x11(|session| window_exists(session, window))
+    }
+
+    fn window_count(&self) -> crate::Result<usize> {
+        self.with_x11(window_count)
+    }
+
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        self.with_x11(|session| get_window_info(session, window))
+    }
+
+    fn get_window_infos(
+        &self,
+        windows: &[crate::Window],
+    ) -> crate::Result<Vec<crate::WindowResult<WindowInfo>>> {
+        self.with_x11(|session| get_window_infos(session, windows))
+    }
+
+    fn get_window_details(&self, window: crate::Window) -> crate::Result<WindowDetails> {
+        self.with_x11(|session| get_window_details(session, window))
+    }
+
+    fn get_window_properties(
+        &self,
+        windows: &[crate::Window],
+    ) -> crate::Result<Vec<crate::WindowResult<WindowProperties>>> {
+        self.with_x11(|session| get_window_properties(session, windows))
+    }
+
+    fn get_window_info_with_retry(&self, window: crate::Window, retries: u32) -> crate::Result<WindowInfo> {
+        self.with_x11(|session| get_window_info_with_retry(session, window, retries))
+    }
+
+    fn get_window_bounds(&self, window: crate::Window) -> crate::Result<PhysicalWindowInfo> {
+        let info = self.with_x11(|session| get_window_info(session, window))?;
+        Ok(PhysicalWindowInfo { pos: info.pos, size: info.size })
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        self.with_x11(|session| find_window_by_pid(session, target_pid))
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        self.with_x11(|session| find_windows_by_pid(session, target_pid))
+    }
+
+    fn find_windows_by_pids(&self, pids: &[u32]) -> crate::Result<Vec<(u32, crate::Window)>> {
+        self.with_x11(|session| find_windows_by_pids(session, pids))
+    }
+
+    fn pid_has_window(&self, target_pid: u32) -> crate::Result<bool> {
+        self.with_x11(|session| pid_has_window(session, target_pid))
+    }
+
+    fn get_class_for_pid(&self, target_pid: u32) -> crate::Result<Option<(String, String)>> {
+        self.with_x11(|session| get_class_for_pid(session, target_pid))
+    }
+
+    fn is_main_window(&self, window: crate::Window) -> crate::Result<bool> {
+        self.with_x11(|session| is_main_window(session, window))
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        self.with_x11(get_active_window)
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        self.with_x11(get_active_window_pid)
+    }
+
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        self.with_x11(|session| get_window_pid(session, window))
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        self.with_x11(|session| get_window_title(session, window))
+    }
+
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        self.with_x11(|session| get_window_app_id(session, window))
+    }
+
+    fn get_window_icon(&self, window: crate::Window) -> crate::Result<Option<IconData>> {
+        self.with_x11(|session| get_window_icon(session, window))
+    }
+
+    fn hide_window(&self, window: crate::Window) -> crate::Result<()> {
+        self.with_x11(|session| hide_window(session, window))
+    }
+
+    fn show_window(&self, window: crate::Window) -> crate::Result<()> {
+        self.with_x11(|session| show_window(session, window))
+    }
+
+    fn set_skip_taskbar(&self, window: crate::Window, skip: bool) -> crate::Result<()> {
+        self.with_x11(|session| set_skip_taskbar(session, window, skip))
+    }
+
+    fn minimize_window(&self, window: crate::Window) -> crate::Result<()> {
+        self.with_x11(|session| minimize_window(session, window))
+    }
+
+    fn maximize_window(&self, window: crate::Window) -> crate::Result<()> {
+        self.with_x11(|session| maximize_window(session, window))
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        self.with_x11(|session| close_window(session, window))
+    }
+
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        self.with_x11(|session| move_window(session, window, x, y))
+    }
+
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        self.with_x11(|session| resize_window(session, window, width, height))
+    }
+
+    fn set_window_rect(
+        &self,
+        window: crate::Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> crate::Result<()> {
+        self.with_x11(|session| set_window_rect(session, window, x, y, width, height))
+    }
+
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        self.with_x11(|session| focus_window(session, window))
+    }
+
+    fn can_focus(&self, window: crate::Window) -> crate::Result<bool> {
+        self.with_x11(|session| can_focus(session, window))
+    }
+
+    fn activate_window(&self, window: crate::Window, update_user_time: bool) -> crate::Result<()> {
+        self.with_x11(|session| activate_window(session, window, update_user_time))
+    }
+
+    fn set_user_time(&self, window: crate::Window, timestamp: u32) -> crate::Result<()> {
+        self.with_x11(|session| set_user_time(session, window, timestamp))
+    }
+
+    fn get_window_workspace(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        self.with_x11(|session| get_window_workspace(session, window))
+    }
+
+    fn move_window_to_workspace(&self, window: crate::Window, workspace: u32) -> crate::Result<()> {
+        self.with_x11(|session| move_window_to_workspace(session, window, workspace))
+    }
+
+    fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+        self.with_x11(get_current_workspace)
+    }
+
+    fn switch_workspace(&self, workspace: u32) -> crate::Result<()> {
+        self.with_x11(|session| switch_workspace(session, workspace))
+    }
+
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        subscribe_window_events()
+    }
+
+    fn list_monitors(&self) -> crate::Result<Vec<MonitorInfo>> {
+        self.with_x11(list_monitors)
+    }
+
+    fn get_window_monitor(&self, window: crate::Window) -> crate::Result<Option<MonitorInfo>> {
+        let monitors = self.with_x11(list_monitors)?;
+        let info = self.get_window_info(window)?;
+        Ok(monitor_of(&info, &monitors).cloned())
+    }
+
+    fn get_window_monitors(&self, window: crate::Window) -> crate::Result<Vec<MonitorInfo>> {
+        let monitors = self.with_x11(list_monitors)?;
+        let info = self.get_window_info(window)?;
+        Ok(monitors_of(&info, &monitors))
+    }
+
+    fn get_window_work_area(&self, window: crate::Window) -> crate::Result<Option<WindowInfo>> {
+        let monitors = self.with_x11(list_monitors)?;
+        let info = self.get_window_info(window)?;
+        let Some(monitor) = monitor_of(&info, &monitors) else {
+            return Ok(None);
+        };
+        self.with_x11(|session| work_area(session, monitor.bounds)).map(Some)
+    }
+
+    fn get_virtual_screen(&self) -> crate::Result<WindowInfo> {
+        self.with_x11(get_virtual_screen)
+    }
+
+    fn pointer_state(&self) -> crate::Result<(i32, i32, Option<crate::Window>)> {
+        self.with_x11(pointer_state)
+    }
+
+    fn get_root_window(&self) -> crate::Result<crate::Window> {
+        self.with_x11(get_root_window)
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        self.with_x11(|session| window_has_state(session, window, state))
+    }
+
+    fn get_window_states(&self, window: crate::Window) -> crate::Result<Vec<WindowStateFlag>> {
+        self.with_x11(|session| get_window_states(session, window))
+    }
+
+    fn set_window_state(
+        &self,
+        window: crate::Window,
+        flag: WindowStateFlag,
+        enabled: bool,
+    ) -> crate::Result<()> {
+        self.with_x11(|session| set_window_state(session, window, flag.clone(), enabled))
+    }
+
+    fn is_sticky(&self, window: crate::Window) -> crate::Result<bool> {
+        self.with_x11(|session| is_sticky(session, window))
+    }
+
+    fn set_sticky(&self, window: crate::Window, sticky: bool) -> crate::Result<()> {
+        self.with_x11(|session| set_sticky(session, window, sticky))
+    }
+
+    fn is_always_on_top(&self, window: crate::Window) -> crate::Result<bool> {
+        self.with_x11(|session| is_always_on_top(session, window))
+    }
+
+    fn set_always_on_top(&self, window: crate::Window, on_top: bool) -> crate::Result<()> {
+        self.with_x11(|session| set_always_on_top(session, window, on_top))
+    }
+
+    fn is_always_below(&self, window: crate::Window) -> crate::Result<bool> {
+        self.with_x11(|session| is_always_below(session, window))
+    }
+
+    fn set_always_below(&self, window: crate::Window, below: bool) -> crate::Result<()> {
+        self.with_x11(|session| set_always_below(session, window, below))
+    }
+
+    fn set_click_through(&self, window: crate::Window, click_through: bool) -> crate::Result<()> {
+        self.with_x11(|session| set_click_through(session, window, click_through))
+    }
+}
+
+/// The tests marked `#[ignore]` start their own X server and need `Xvfb` on
+/// `PATH`: `cargo test -- --ignored`.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::Cell,
+        path::Path,
+        process::{Child, Command, Stdio},
+        sync::MutexGuard,
+        time::Instant,
+    };
+    use x11rb::wrapper::ConnectionExt as _;
+
+    /// Serializes the tests that point `DISPLAY` somewhere
+    static ENV: Mutex<()> = Mutex::new(());
+
+    /// `DISPLAY` set to `display` (unset for `None`) until dropped
+    struct DisplayEnv {
+        saved: Option<std::ffi::OsString>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl DisplayEnv {
+        fn set(display: Option<&str>) -> Self {
+            let lock = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let saved = std::env::var_os("DISPLAY");
+            // SAFETY: every test reading or writing DISPLAY holds ENV
+            unsafe {
+                match display {
+                    Some(display) => std::env::set_var("DISPLAY", display),
+                    None => std::env::remove_var("DISPLAY"),
+                }
+            }
+            DisplayEnv { saved, _lock: lock }
+        }
+    }
+
+    impl Drop for DisplayEnv {
+        fn drop(&mut self) {
+            // SAFETY: ENV is still held
+            unsafe {
+                match self.saved.take() {
+                    Some(display) => std::env::set_var("DISPLAY", display),
+                    None => std::env::remove_var("DISPLAY"),
+                }
+            }
+        }
+    }
+
+    /// An Xvfb server on `:number`, killed when dropped
+    struct Xvfb {
+        child: Child,
+        number: u32,
+    }
+
+    impl Xvfb {
+        fn start(number: u32) -> Self {
+            let socket = format!("/tmp/.X11-unix/X{number}");
+            let child = Command::new("Xvfb")
+                .args([format!(":{number}").as_str(), "-nolisten", "tcp", "-screen", "0", "1280x800x24"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("Xvfb is not installed");
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while !Path::new(&socket).exists() {
+                assert!(Instant::now() < deadline, "Xvfb :{number} did not start");
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Xvfb { child, number }
+        }
+
+        fn display(&self) -> String {
+            format!(":{}", self.number)
+        }
+    }
+
+    impl Drop for Xvfb {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+            // Xvfb killed outright leaves its socket behind
+            let _ = std::fs::remove_file(format!("/tmp/.X11-unix/X{}", self.number));
+            let _ = std::fs::remove_file(format!("/tmp/.X{}-lock", self.number));
+        }
+    }
+
+    /// A request that needs a reply, so a dead connection is noticed
+    fn round_trip(session: &Session) -> crate::Result<XWindow> {
+        Ok(session.conn.get_input_focus()?.reply()?.focus)
+    }
+
+    /// A mapped top-level window at (x, y), as a client would create it
+    fn create_window(session: &Session, x: i16, y: i16, width: u16, height: u16) -> crate::Window {
+        create_child(session, session.root, x, y, width, height)
+    }
+
+    fn create_child(session: &Session, parent: XWindow, x: i16, y: i16, width: u16, height: u16) -> crate::Window {
+        let window = session.conn.generate_id().unwrap();
+        session
+            .conn
+            .create_window(0, window, parent, x, y, width, height, 0, WindowClass::INPUT_OUTPUT, 0, &Default::default())
+            .unwrap();
+        session.conn.map_window(window).unwrap();
+        session.conn.sync().unwrap();
+        crate::Window::from(window)
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn calls_reuse_the_connection() {
+        let server = Xvfb::start(95);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let client = Session::connect(None).unwrap();
+        let window = create_window(&client, 0, 0, 100, 100);
+
+        let backend = X11Backend::default();
+        let session = backend.session().unwrap();
+        for _ in 0..3 {
+            backend.get_window_info(window).unwrap();
+        }
+        assert!(Arc::ptr_eq(&session, &backend.session().unwrap()), "a call opened a new connection");
+    }
+
+    /// How many requests `op` sends, from the sequence numbers of requests
+    /// sent before and after it
+    fn requests_sent(session: &Session, op: impl Fn(&Session)) -> u64 {
+        let before = session.conn.get_input_focus().unwrap().sequence_number();
+        op(session);
+        let after = session.conn.get_input_focus().unwrap().sequence_number();
+        after - before - 1
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn atoms_are_interned_once_per_session() {
+        let server = Xvfb::start(96);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let client = Session::connect(None).unwrap();
+        let window = create_window(&client, 0, 0, 100, 100);
+        let interned = requests_sent(&Session::connect(None).unwrap(), |session| {
+            session.atoms().unwrap();
+        });
+
+        let session = Session::connect(None).unwrap();
+        let calls = |session: &Session| {
+            get_active_window(session).unwrap();
+            get_window_title(session, window).unwrap();
+            get_window_pid(session, window).unwrap();
+            window_has_state(session, window, WindowState::Maximized).unwrap();
+        };
+        let first = requests_sent(&session, calls);
+        let again = requests_sent(&session, calls);
+        assert_eq!(requests_sent(&session, calls), again);
+        // Only the first calls intern, all atoms in one batch
+        assert_eq!(first, again + interned);
+    }
+
+    fn set_cardinals(session: &Session, window: XWindow, property: Atom, kind: impl Into<Atom>, values: &[u32]) {
+        session.conn.change_property32(PropMode::REPLACE, window, property, kind, values).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn pid_search_skips_windows_destroyed_since_listing() {
+        let server = Xvfb::start(97);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let session = Session::connect(None).unwrap();
+        let atoms = session.atoms().unwrap();
+        let live = create_window(&session, 0, 0, 100, 100);
+        let gone = create_window(&session, 0, 0, 100, 100);
+        for window in [live, gone] {
+            set_cardinals(&session, window.raw(), atoms._NET_WM_PID, AtomEnum::CARDINAL, &[4242]);
+        }
+        // A _NET_CLIENT_LIST that still names a destroyed window, as a WM's
+        // does between the DestroyNotify and its update of the property
+        set_cardinals(&session, session.root, atoms._NET_CLIENT_LIST, AtomEnum::WINDOW, &[gone.raw(), live.raw()]);
+        session.conn.destroy_window(gone.raw()).unwrap();
+        session.conn.sync().unwrap();
+
+        assert_eq!(find_windows_by_pids(&session, &[4242]).unwrap(), [(4242, live)]);
+        assert_eq!(find_windows_by_pid(&session, 4242).unwrap(), [live]);
+        assert_eq!(find_window_by_pid(&session, 4242).unwrap(), Some(live));
+        assert!(pid_has_window(&session, 4242).unwrap());
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn window_count_without_a_window_manager_walks_the_tree() {
+        let server = Xvfb::start(91);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let session = Session::connect(None).unwrap();
+        create_window(&session, 0, 0, 100, 100);
+        create_window(&session, 200, 0, 100, 100);
+
+        // No window manager, so no _NET_CLIENT_LIST to read the count from
+        assert_eq!(window_count(&session).unwrap(), 2);
+        assert_eq!(window_count(&session).unwrap(), list_windows(&session).unwrap().len());
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn window_count_includes_desktop_windows() {
+        let server = Xvfb::start(90);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let session = Session::connect(None).unwrap();
+        let atoms = session.atoms().unwrap();
+        let desktop = create_window(&session, 0, 0, 1280, 800);
+        let editor = create_window(&session, 100, 100, 400, 300);
+        let desktop_type = [atoms._NET_WM_WINDOW_TYPE_DESKTOP];
+        set_cardinals(&session, desktop.raw(), atoms._NET_WM_WINDOW_TYPE, AtomEnum::ATOM, &desktop_type);
+        set_cardinals(&session, session.root, atoms._NET_CLIENT_LIST, AtomEnum::WINDOW, &[desktop.raw(), editor.raw()]);
+        session.conn.sync().unwrap();
+
+        assert_eq!(list_windows(&session).unwrap(), [editor]);
+        assert_eq!(window_count(&session).unwrap(), 2);
+    }
+
+    /// A client at (5, 20) inside a frame at (100, 50), the way a reparenting
+    /// window manager decorates it
+    fn create_reparented_window(session: &Session) -> crate::Window {
+        let frame = create_window(session, 100, 50, 420, 340);
+        create_child(session, frame.raw(), 5, 20, 400, 300)
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn geometry_of_a_reparented_window_is_in_root_coordinates() {
+        let server = Xvfb::start(98);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let session = Session::connect(None).unwrap();
+        let client = create_reparented_window(&session);
+        let expected = WindowInfo { pos: (105, 70), size: (400, 300) };
+
+        assert_eq!(get_window_info(&session, client).unwrap(), expected);
+        assert_eq!(get_window_info_with_retry(&session, client, 2).unwrap(), expected);
+        assert_eq!(get_window_details(&session, client).unwrap().info, expected);
+        let infos = get_window_infos(&session, &[client, get_root_window(&session).unwrap()]).unwrap();
+        assert_eq!(infos[0].as_ref().unwrap(), &expected);
+        assert_eq!(infos[1].as_ref().unwrap(), &WindowInfo { pos: (0, 0), size: (1280, 800) });
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn relative_bounds_of_a_reparented_window() {
+        let server = Xvfb::start(99);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let session = Session::connect(None).unwrap();
+        let client = create_reparented_window(&session);
+        let reference = create_window(&session, 40, 30, 50, 50);
+        let backend = X11Backend::default();
+        assert_eq!(
+            crate::geometry::relative_bounds_in(&backend, client, reference).unwrap(),
+            WindowInfo { pos: (65, 40), size: (400, 300) }
+        );
+        assert_eq!(
+            crate::geometry::relative_bounds_in(&backend, reference, client).unwrap(),
+            WindowInfo { pos: (-65, -40), size: (50, 50) }
+        );
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn shared_window_system_serves_eight_threads() {
+        let server = Xvfb::start(94);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let client = Session::connect(None).unwrap();
+        let window = create_window(&client, 10, 20, 300, 200);
+        let system = Arc::new(crate::WindowSystem::with_backend(BackendKind::X11).unwrap());
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let system = Arc::clone(&system);
+                std::thread::spawn(move || {
+                    for _ in 0..500 {
+                        let info = system.get_window_info(window).unwrap();
+                        assert_eq!((info.pos, info.size), ((10, 20), (300, 200)));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn per_thread_backend_reconnects_once_after_server_restart() {
+        let server = Xvfb::start(93);
+        let _display = DisplayEnv::set(Some(&server.display()));
+        let backend = X11Backend::per_thread();
+        let attempts = Cell::new(0);
+        let counted = |session: &Session| {
+            attempts.set(attempts.get() + 1);
+            round_trip(session)
+        };
+        backend.with_x11(counted).unwrap();
+        assert_eq!(attempts.replace(0), 1);
+
+        drop(server);
+        let server = Xvfb::start(93);
+        // The cached connection fails, the retry runs on a new one
+        backend.with_x11(counted).unwrap();
+        assert_eq!(attempts.replace(0), 2);
+        backend.with_x11(counted).unwrap();
+        assert_eq!(attempts.replace(0), 1);
+
+        // With no server to reconnect to, the one reconnect fails and the error surfaces
+        drop(server);
+        assert!(matches!(backend.with_x11(counted), Err(crate::Error::NoDisplay { .. })));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    /// Every call on `backend` fails with `NoDisplay` instead of panicking
+    fn assert_no_display(backend: &X11Backend, display: Option<&str>) {
+        let window = crate::Window::from(1);
+        let results = [
+            backend.list_windows().map(|_| ()),
+            backend.window_count().map(|_| ()),
+            backend.get_window_info(window).map(|_| ()),
+            backend.get_window_details(window).map(|_| ()),
+            backend.get_active_window().map(|_| ()),
+            backend.find_window_by_pid(std::process::id()).map(|_| ()),
+            backend.pid_has_window(std::process::id()).map(|_| ()),
+            backend.get_window_title(window).map(|_| ()),
+            backend.move_window(window, 0, 0),
+            backend.focus_window(window),
+            backend.list_monitors().map(|_| ()),
+            backend.get_virtual_screen().map(|_| ()),
+        ];
+        for result in results {
+            match result {
+                Err(crate::Error::NoDisplay { display: reported }) => assert_eq!(reported.as_deref(), display),
+                other => panic!("expected NoDisplay, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn unset_display_is_no_display() {
+        let _display = DisplayEnv::set(None);
+
+        assert!(matches!(Session::connect(None), Err(crate::Error::NoDisplay { display: None })));
+        assert_no_display(&X11Backend::with_request_timeout(None), None);
+    }
+
+    #[test]
+    fn display_nothing_answers_on_is_no_display() {
+        let _display = DisplayEnv::set(Some(":987"));
+
+        assert!(matches!(Session::connect(None), Err(crate::Error::NoDisplay { display: Some(_) })));
+        assert_no_display(&X11Backend::with_request_timeout(None), Some(":987"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Window manipulation through the Accessibility (AX) API.
+//!
+//! Quartz only describes windows; moving or resizing another application's
+//! window means finding its `AXUIElement` and setting `AXPosition`/`AXSize`.
+//! Every call needs the Accessibility permission (System Settings > Privacy
+//! & Security > Accessibility), reported as `PermissionDenied` when missing.
+
+use super::{
+    cf_string, kCFBooleanFalse, kCFBooleanTrue, CFArrayGetCount, CFArrayGetValueAtIndex, CFRetain,
+    CFStringCreateWithCString, CFStringRef, CFTypeRef, Owned, WindowListEntry, K_CF_STRING_ENCODING_UTF8,
+};
+use crate::{Permission, PermissionDenied, WindowInfo};
+use std::{error::Error, ffi::c_void};
+
+/// `kAXErrorSuccess`
+const AX_SUCCESS: i32 = 0;
+/// `kAXErrorAPIDisabled`: the process isn't trusted for Accessibility.
+const AX_API_DISABLED: i32 = -25211;
+
+const K_AX_VALUE_CG_POINT_TYPE: u32 = 1;
+const K_AX_VALUE_CG_SIZE_TYPE: u32 = 2;
+
+/// Frames from Quartz and AX are both in points but rounded differently.
+const FRAME_TOLERANCE: i32 = 1;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+unsafe extern "C" {
+    fn AXIsProcessTrusted() -> u8;
+    fn AXIsProcessTrustedWithOptions(options: CFTypeRef) -> u8;
+    static kAXTrustedCheckOptionPrompt: CFStringRef;
+    fn AXUIElementCreateApplication(pid: i32) -> CFTypeRef;
+    fn AXUIElementCopyAttributeValue(element: CFTypeRef, attribute: CFStringRef, value: *mut CFTypeRef) -> i32;
+    fn AXUIElementSetAttributeValue(element: CFTypeRef, attribute: CFStringRef, value: CFTypeRef) -> i32;
+    fn AXUIElementPerformAction(element: CFTypeRef, action: CFStringRef) -> i32;
+    fn AXValueCreate(value_type: u32, value: *const c_void) -> CFTypeRef;
+    fn AXValueGetValue(value: CFTypeRef, value_type: u32, out: *mut c_void) -> u8;
+    /// Private, but stable since 10.5 and the only way to map an AX window to its CGWindowID.
+    fn _AXUIElementGetWindow(element: CFTypeRef, window: *mut u32) -> i32;
+}
+
+/// Opaque `CFDictionaryKeyCallBacks`/`CFDictionaryValueCallBacks`; only their addresses are used.
+#[repr(C)]
+struct CFDictionaryCallBacks {
+    _private: [u8; 0],
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+unsafe extern "C" {
+    fn CFDictionaryCreate(
+        allocator: CFTypeRef,
+        keys: *const CFTypeRef,
+        values: *const CFTypeRef,
+        count: isize,
+        key_callbacks: *const CFDictionaryCallBacks,
+        value_callbacks: *const CFDictionaryCallBacks,
+    ) -> CFTypeRef;
+    static kCFTypeDictionaryKeyCallBacks: CFDictionaryCallBacks;
+    static kCFTypeDictionaryValueCallBacks: CFDictionaryCallBacks;
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CGPoint {
+    x: f64,
+    y: f64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CGSize {
+    width: f64,
+    height: f64,
+}
+
+/// What is known about one AX window when matching it to a Quartz window.
+struct Candidate {
+    /// From `_AXUIElementGetWindow`, when it succeeds.
+    id: Option<u32>,
+    title: Option<String>,
+    frame: Option<WindowInfo>,
+}
+
+/// Pick the candidate that is `target`: an exact window id match if AX
+/// reports ids, otherwise the window whose frame matches and whose title does
+/// too when both sides know it. Same-titled windows are told apart by frame;
+/// windows identical in both are indistinguishable, so the first one wins.
+fn match_window(target: &WindowListEntry, candidates: &[Candidate]) -> Option<usize> {
+    if let Some(index) = candidates.iter().position(|candidate| candidate.id == Some(target.window.raw())) {
+        return Some(index);
+    }
+    let bounds = target.bounds?;
+    candidates.iter().position(|candidate| {
+        let frame_matches = candidate.frame.is_some_and(|frame| {
+            (frame.pos.0 - bounds.pos.0).abs() <= FRAME_TOLERANCE
+                && (frame.pos.1 - bounds.pos.1).abs() <= FRAME_TOLERANCE
+                && (frame.size.0 as i32 - bounds.size.0 as i32).abs() <= FRAME_TOLERANCE
+                && (frame.size.1 as i32 - bounds.size.1 as i32).abs() <= FRAME_TOLERANCE
+        });
+        let title_matches = match (&candidate.title, &target.name) {
+            (Some(title), Some(name)) => title == name,
+            _ => true,
+        };
+        // A window that reported a different id is certainly another window
+        frame_matches && title_matches && candidate.id.is_none()
+    })
+}
+
+fn attribute_name(name: &std::ffi::CStr) -> crate::Result<Owned> {
+    Owned::new(unsafe { CFStringCreateWithCString(std::ptr::null(), name.as_ptr(), K_CF_STRING_ENCODING_UTF8) })
+        .ok_or_else(|| "Failed to create a CFString".into())
+}
+
+fn check(error: i32, operation: &'static str) -> crate::Result<()> {
+    match error {
+        AX_SUCCESS => Ok(()),
+        AX_API_DISABLED => Err(PermissionDenied::needs(operation, Permission::Accessibility).into()),
+        error => Err(format!("{operation} failed with AXError {error}").into()),
+    }
+}
+
+fn copy_attribute(element: CFTypeRef, name: &std::ffi::CStr) -> Option<Owned> {
+    let name = attribute_name(name).ok()?;
+    let mut value = std::ptr::null();
+    if unsafe { AXUIElementCopyAttributeValue(element, name.0, &mut value) } != AX_SUCCESS {
+        return None;
+    }
+    Owned::new(value)
+}
+
+fn frame(element: CFTypeRef) -> Option<WindowInfo> {
+    let position = copy_attribute(element, c"AXPosition")?;
+    let size = copy_attribute(element, c"AXSize")?;
+    let mut point = CGPoint::default();
+    let mut extent = CGSize::default();
+    unsafe {
+        if AXValueGetValue(position.0, K_AX_VALUE_CG_POINT_TYPE, (&mut point as *mut CGPoint).cast()) == 0
+            || AXValueGetValue(size.0, K_AX_VALUE_CG_SIZE_TYPE, (&mut extent as *mut CGSize).cast()) == 0
+        {
+            return None;
+        }
+    }
+    Some(WindowInfo {
+        pos: (point.x.round() as i32, point.y.round() as i32),
+        size: (extent.width.round() as u32, extent.height.round() as u32),
+    })
+}
+
+fn ensure_trusted(operation: &'static str) -> crate::Result<()> {
+    if !is_trusted(false) {
+        return Err(PermissionDenied::needs(operation, Permission::Accessibility).into());
+    }
+    Ok(())
+}
+
+/// Whether this process has Accessibility permission. With `prompt`, an
+/// untrusted process also shows the system dialog pointing the user to the
+/// Accessibility settings (once per process launch, as macOS decides).
+pub(super) fn is_trusted(prompt: bool) -> bool {
+    if !prompt {
+        return unsafe { AXIsProcessTrusted() } != 0;
+    }
+    unsafe {
+        let keys = [kAXTrustedCheckOptionPrompt];
+        let values = [kCFBooleanTrue];
+        let Some(options) = Owned::new(CFDictionaryCreate(
+            std::ptr::null(),
+            keys.as_ptr(),
+            values.as_ptr(),
+            1,
+            &raw const kCFTypeDictionaryKeyCallBacks,
+            &raw const kCFTypeDictionaryValueCallBacks,
+        )) else {
+            return AXIsProcessTrusted() != 0;
+        };
+        AXIsProcessTrustedWithOptions(options.0) != 0
+    }
+}
+
+/// The AX element for a Quartz window.
+fn window_element(target: &WindowListEntry, operation: &'static str) -> crate::Result<Owned> {
+    ensure_trusted(operation)?;
+    let application = Owned::new(unsafe { AXUIElementCreateApplication(target.pid as i32) })
+        .ok_or("AXUIElementCreateApplication failed")?;
+    let name = attribute_name(c"AXWindows")?;
+    let mut windows = std::ptr::null();
+    check(unsafe { AXUIElementCopyAttributeValue(application.0, name.0, &mut windows) }, operation)?;
+    let windows = Owned::new(windows).ok_or("The application reported no AX windows")?;
+
+    let elements: Vec<CFTypeRef> = (0..unsafe { CFArrayGetCount(windows.0) })
+        .map(|index| unsafe { CFArrayGetValueAtIndex(windows.0, index) })
+        .collect();
+    let candidates: Vec<Candidate> = elements
+        .iter()
+        .map(|&element| {
+            let mut id = 0u32;
+            let id = (unsafe { _AXUIElementGetWindow(element, &mut id) } == AX_SUCCESS && id != 0).then_some(id);
+            Candidate {
+                id,
+                title: copy_attribute(element, c"AXTitle").and_then(|title| unsafe { cf_string(title.0) }),
+                frame: frame(element),
+            }
+        })
+        .collect();
+    let index = match_window(target, &candidates)
+        .ok_or_else(|| format!("No accessibility element matches window {}", target.window))?;
+    // Keep the element alive past the array that owns it
+    Owned::new(unsafe { CFRetain(elements[index]) }).ok_or_else(|| "CFRetain failed".into())
+}
+
+fn set_position(element: &Owned, x: i32, y: i32, operation: &'static str) -> crate::Result<()> {
+    let point = CGPoint { x: x as f64, y: y as f64 };
+    let value = Owned::new(unsafe { AXValueCreate(K_AX_VALUE_CG_POINT_TYPE, (&point as *const CGPoint).cast()) })
+        .ok_or("AXValueCreate failed")?;
+    let name = attribute_name(c"AXPosition")?;
+    check(unsafe { AXUIElementSetAttributeValue(element.0, name.0, value.0) }, operation)
+}
+
+fn set_size(element: &Owned, width: u32, height: u32, operation: &'static str) -> crate::Result<()> {
+    let size = CGSize { width: width as f64, height: height as f64 };
+    let value = Owned::new(unsafe { AXValueCreate(K_AX_VALUE_CG_SIZE_TYPE, (&size as *const CGSize).cast()) })
+        .ok_or("AXValueCreate failed")?;
+    let name = attribute_name(c"AXSize")?;
+    check(unsafe { AXUIElementSetAttributeValue(element.0, name.0, value.0) }, operation)
+}
+
+pub(super) fn move_window(target: &WindowListEntry, x: i32, y: i32) -> crate::Result<()> {
+    const OPERATION: &str = "move_window";
+    set_position(&window_element(target, OPERATION)?, x, y, OPERATION)
+}
+
+pub(super) fn resize_window(target: &WindowListEntry, width: u32, height: u32) -> crate::Result<()> {
+    const OPERATION: &str = "resize_window";
+    set_size(&window_element(target, OPERATION)?, width, height, OPERATION)
+}
+
+pub(super) fn set_window_rect(target: &WindowListEntry, rect: WindowInfo) -> crate::Result<()> {
+    const OPERATION: &str = "set_window_rect";
+    let element = window_element(target, OPERATION)?;
+    set_position(&element, rect.pos.0, rect.pos.1, OPERATION)?;
+    set_size(&element, rect.size.0, rect.size.1, OPERATION)
+}
+
+/// Set `AXMinimized`; minimizing animates the window into the Dock, restoring brings it back.
+pub(super) fn set_minimized(
+    target: &WindowListEntry,
+    minimized: bool,
+    operation: &'static str,
+) -> crate::Result<()> {
+    let element = window_element(target, operation)?;
+    let name = attribute_name(c"AXMinimized")?;
+    let value = unsafe { if minimized { kCFBooleanTrue } else { kCFBooleanFalse } };
+    check(unsafe { AXUIElementSetAttributeValue(element.0, name.0, value) }, operation)
+}
+
+/// Press the window's close button, as a user click would; the app may still
+/// ask to save or refuse.
+pub(super) fn close_window(target: &WindowListEntry) -> crate::Result<()> {
+    const OPERATION: &str = "close_window";
+    let element = window_element(target, OPERATION)?;
+    let button = copy_attribute(element.0, c"AXCloseButton")
+        .ok_or_else(|| crate::Error::PropertyMissing { window: target.window, property: "close button" })?;
+    let action = attribute_name(c"AXPress")?;
+    check(unsafe { AXUIElementPerformAction(button.0, action.0) }, OPERATION)
+}
+
+/// Press the window's zoom (green) button, which toggles between the app's
+/// "standard" size and the user's size; apps decide what zoomed means.
+pub(super) fn zoom_window(target: &WindowListEntry) -> crate::Result<()> {
+    const OPERATION: &str = "toggle_maximize";
+    let element = window_element(target, OPERATION)?;
+    let button = copy_attribute(element.0, c"AXZoomButton")
+        .ok_or_else(|| crate::Error::PropertyMissing { window: target.window, property: "zoom button" })?;
+    let action = attribute_name(c"AXPress")?;
+    check(unsafe { AXUIElementPerformAction(button.0, action.0) }, OPERATION)
+}
+
+/// `AXTitle` of the window; `None` if the window has no title.
+pub(super) fn window_title(target: &WindowListEntry) -> crate::Result<Option<String>> {
+    let element = window_element(target, "get_window_title")?;
+    Ok(copy_attribute(element.0, c"AXTitle").and_then(|title| unsafe { cf_string(title.0) }))
+}
+
+/// The app's `AXMainWindow`, when this process has Accessibility permission.
+pub(super) fn main_window(pid: u32) -> Option<crate::Window> {
+    ensure_trusted("find_window_by_pid").ok()?;
+    let application = Owned::new(unsafe { AXUIElementCreateApplication(pid as i32) })?;
+    let main_window = copy_attribute(application.0, c"AXMainWindow")?;
+    let mut id = 0u32;
+    if unsafe { _AXUIElementGetWindow(main_window.0, &mut id) } != AX_SUCCESS || id == 0 {
+        return None;
+    }
+    Some(crate::Window::from(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32, name: Option<&str>, x: i32, y: i32, width: u32, height: u32) -> WindowListEntry {
+        WindowListEntry {
+            window: crate::Window::from(id),
+            pid: 100,
+            layer: 0,
+            onscreen: true,
+            bounds: Some(WindowInfo { pos: (x, y), size: (width, height) }),
+            name: name.map(String::from),
+        }
+    }
+
+    fn candidate(id: Option<u32>, title: Option<&str>, x: i32, y: i32, width: u32, height: u32) -> Candidate {
+        Candidate { id, title: title.map(String::from), frame: Some(WindowInfo { pos: (x, y), size: (width, height) }) }
+    }
+
+    /// Three Terminal windows all titled "bash", as AX reports them without window ids
+    fn same_titled() -> Vec<Candidate> {
+        vec![
+            candidate(None, Some("bash"), 0, 25, 800, 600),
+            candidate(None, Some("bash"), 40, 65, 800, 600),
+            candidate(None, Some("bash"), 900, 25, 640, 480),
+        ]
+    }
+
+    #[test]
+    fn same_titled_windows_are_told_apart_by_frame() {
+        let candidates = same_titled();
+
+        assert_eq!(match_window(&entry(11, Some("bash"), 0, 25, 800, 600), &candidates), Some(0));
+        assert_eq!(match_window(&entry(12, Some("bash"), 40, 65, 800, 600), &candidates), Some(1));
+        assert_eq!(match_window(&entry(13, Some("bash"), 900, 25, 640, 480), &candidates), Some(2));
+        assert_eq!(match_window(&entry(14, Some("bash"), 300, 300, 800, 600), &candidates), None);
+    }
+
+    #[test]
+    fn frames_match_within_one_point() {
+        let candidates = same_titled();
+
+        assert_eq!(match_window(&entry(12, Some("bash"), 41, 64, 801, 599), &candidates), Some(1));
+        assert_eq!(match_window(&entry(12, Some("bash"), 42, 65, 800, 600), &candidates), None);
+        assert_eq!(match_window(&entry(12, Some("bash"), 40, 65, 800, 602), &candidates), None);
+    }
+
+    #[test]
+    fn window_id_match_wins_over_frame_and_title() {
+        let candidates = vec![
+            candidate(None, Some("bash"), 0, 25, 800, 600),
+            candidate(Some(11), Some("vim"), 500, 500, 100, 100),
+        ];
+
+        assert_eq!(match_window(&entry(11, Some("bash"), 0, 25, 800, 600), &candidates), Some(1));
+    }
+
+    #[test]
+    fn candidate_reporting_another_id_is_never_matched_by_frame() {
+        let candidates = vec![
+            candidate(Some(99), Some("bash"), 0, 25, 800, 600),
+            candidate(None, Some("bash"), 0, 25, 800, 600),
+        ];
+
+        assert_eq!(match_window(&entry(11, Some("bash"), 0, 25, 800, 600), &candidates), Some(1));
+        assert_eq!(match_window(&entry(11, Some("bash"), 0, 25, 800, 600), &candidates[..1]), None);
+    }
+
+    #[test]
+    fn title_only_counts_when_both_sides_know_it() {
+        let candidates = vec![
+            candidate(None, Some("bash"), 0, 25, 800, 600),
+            candidate(None, Some("zsh"), 0, 25, 800, 600),
+            candidate(None, None, 40, 65, 800, 600),
+        ];
+
+        assert_eq!(match_window(&entry(11, Some("zsh"), 0, 25, 800, 600), &candidates), Some(1));
+        // Without Screen Recording permission Quartz reports no name
+        assert_eq!(match_window(&entry(11, None, 0, 25, 800, 600), &candidates), Some(0));
+        assert_eq!(match_window(&entry(12, Some("bash"), 40, 65, 800, 600), &candidates), Some(2));
+    }
+
+    #[test]
+    fn identical_windows_match_the_first() {
+        let candidates = vec![
+            candidate(None, Some("bash"), 0, 25, 800, 600),
+            candidate(None, Some("bash"), 0, 25, 800, 600),
+        ];
+
+        assert_eq!(match_window(&entry(11, Some("bash"), 0, 25, 800, 600), &candidates), Some(0));
+    }
+
+    #[test]
+    fn window_without_bounds_only_matches_by_id() {
+        let target = WindowListEntry { bounds: None, ..entry(11, Some("bash"), 0, 25, 800, 600) };
+
+        assert_eq!(match_window(&target, &same_titled()), None);
+        assert_eq!(match_window(&target, &[candidate(Some(11), None, 0, 0, 1, 1)]), Some(0));
+    }
+}
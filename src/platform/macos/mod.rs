@@ -0,0 +1,1037 @@
+//! macOS support built on Quartz Window Services (`CGWindowListCopyWindowInfo`).
+//!
+//! Uses the `kCGWindowNumber` (a `CGWindowID`) as the crate's `Window` and
+//! `kCGWindowOwnerPID` for PID lookups. CoreGraphics and CoreFoundation are
+//! called directly through their C APIs, so no extra dependencies are needed.
+//! The frontmost application comes from `NSWorkspace` through the Objective-C
+//! runtime. Moving, resizing, minimizing and closing go through the
+//! Accessibility API (see `ax`).
+
+use crate::{BackendKind, Unsupported, WindowInfo, WindowState};
+use std::ffi::c_void;
+
+mod ax;
+
+const QUARTZ: &str = "quartz";
+
+type CFIndex = isize;
+type CFTypeRef = *const c_void;
+type CFArrayRef = *const c_void;
+type CFDictionaryRef = *const c_void;
+type CFStringRef = *const c_void;
+
+const K_CF_NUMBER_SINT64_TYPE: CFIndex = 4;
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+const K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY: u32 = 1 << 0;
+const K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW: u32 = 1 << 3;
+const K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS: u32 = 1 << 4;
+const K_CG_NULL_WINDOW_ID: u32 = 0;
+
+/// `PROC_PIDT_SHORTBSDINFO` flavor of `proc_pidinfo`.
+const PROC_PIDT_SHORTBSDINFO: i32 = 13;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+unsafe extern "C" {
+    fn CFArrayGetCount(array: CFArrayRef) -> CFIndex;
+    fn CFArrayGetValueAtIndex(array: CFArrayRef, index: CFIndex) -> *const c_void;
+    fn CFDictionaryGetValue(dict: CFDictionaryRef, key: *const c_void) -> *const c_void;
+    fn CFNumberGetValue(number: CFTypeRef, number_type: CFIndex, value: *mut c_void) -> u8;
+    fn CFBooleanGetValue(boolean: CFTypeRef) -> u8;
+    fn CFStringCreateWithCString(alloc: CFTypeRef, c_str: *const std::ffi::c_char, encoding: u32) -> CFStringRef;
+    fn CFStringGetLength(string: CFStringRef) -> CFIndex;
+    fn CFStringGetMaximumSizeForEncoding(length: CFIndex, encoding: u32) -> CFIndex;
+    fn CFStringGetCString(string: CFStringRef, buffer: *mut std::ffi::c_char, size: CFIndex, encoding: u32) -> u8;
+    fn CFRetain(cf: CFTypeRef) -> CFTypeRef;
+    static kCFBooleanTrue: CFTypeRef;
+    static kCFBooleanFalse: CFTypeRef;
+    fn CFRelease(cf: CFTypeRef);
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+unsafe extern "C" {
+    fn CGWindowListCopyWindowInfo(option: u32, relative_to_window: u32) -> CFArrayRef;
+    fn CGRectMakeWithDictionaryRepresentation(dict: CFDictionaryRef, rect: *mut CGRect) -> u8;
+    fn CGGetActiveDisplayList(max_displays: u32, displays: *mut u32, display_count: *mut u32) -> i32;
+    fn CGDisplayBounds(display: u32) -> CGRect;
+    fn CGDisplayCopyDisplayMode(display: u32) -> CFTypeRef;
+    fn CGDisplayModeGetWidth(mode: CFTypeRef) -> usize;
+    fn CGDisplayModeGetPixelWidth(mode: CFTypeRef) -> usize;
+    fn CGDisplayModeRelease(mode: CFTypeRef);
+    static kCGWindowNumber: CFStringRef;
+    static kCGWindowBounds: CFStringRef;
+    static kCGWindowOwnerPID: CFStringRef;
+    static kCGWindowLayer: CFStringRef;
+    static kCGWindowIsOnscreen: CFStringRef;
+    static kCGWindowName: CFStringRef;
+}
+
+// Loads the NSWorkspace class
+#[link(name = "AppKit", kind = "framework")]
+unsafe extern "C" {}
+
+#[link(name = "objc")]
+unsafe extern "C" {
+    fn objc_getClass(name: *const std::ffi::c_char) -> *mut c_void;
+    fn sel_registerName(name: *const std::ffi::c_char) -> *mut c_void;
+    fn objc_msgSend();
+    fn objc_autoreleasePoolPush() -> *mut c_void;
+    fn objc_autoreleasePoolPop(pool: *mut c_void);
+}
+
+unsafe extern "C" {
+    fn getuid() -> u32;
+    fn proc_pidinfo(pid: i32, flavor: i32, arg: u64, buffer: *mut c_void, buffer_size: i32) -> i32;
+    fn proc_name(pid: i32, buffer: *mut c_void, buffer_size: u32) -> i32;
+}
+
+/// `CGRect` (CGFloat is `f64` on every 64-bit target).
+#[repr(C)]
+#[derive(Default)]
+struct CGRect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// `struct proc_bsdshortinfo` from `<sys/proc_info.h>`; only `uid` is read.
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct ProcBsdShortInfo {
+    pid: u32,
+    ppid: u32,
+    pgid: u32,
+    status: u32,
+    comm: [u8; 16],
+    flags: u32,
+    uid: u32,
+    gid: u32,
+    ruid: u32,
+    rgid: u32,
+    svuid: u32,
+    svgid: u32,
+    rfu: u32,
+}
+
+/// Which windows `list_windows_with` reports. By default only normal
+/// application windows (layer 0) of the current user that are on screen.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowListOptions {
+    /// Include windows that aren't on screen (minimized, hidden apps, other Spaces).
+    pub include_offscreen: bool,
+    /// Include windows owned by other users' processes (e.g. the window server's own).
+    pub include_other_users: bool,
+    /// Layers to report besides layer 0, e.g. 3 for floating palettes, 20 for
+    /// the Dock, 24 for the menu bar or 25 for status items.
+    pub include_layers: Vec<i64>,
+    /// Report every layer, including the menu bar, Dock, Notification Center and overlays.
+    pub all_layers: bool,
+    /// Include desktop elements, the wallpaper and Finder's desktop icons,
+    /// which sit on the layers below 0.
+    pub include_desktop: bool,
+}
+
+impl WindowListOptions {
+    fn includes_layer(&self, layer: i64) -> bool {
+        self.all_layers
+            || layer == 0
+            || self.include_layers.contains(&layer)
+            || (self.include_desktop && layer < 0)
+    }
+}
+
+/// Which of the permissions the macOS backend depends on this process has.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PermissionStatus {
+    /// Needed to move, resize, minimize, close or zoom other apps' windows.
+    pub accessibility: bool,
+    /// Needed for other apps' window titles from Quartz.
+    pub screen_recording: bool,
+}
+
+/// A `CGWindowListCopyWindowInfo` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WindowListEntry {
+    pub window: crate::Window,
+    pub pid: u32,
+    /// 0 for normal application windows; menus, the Dock and overlays use higher layers.
+    pub layer: i64,
+    pub onscreen: bool,
+    pub bounds: Option<WindowInfo>,
+    /// `kCGWindowName`; only reported for other apps' windows with Screen Recording permission.
+    pub name: Option<String>,
+}
+
+/// Owns a CFArray returned by a Copy function.
+struct WindowList(CFArrayRef);
+
+impl WindowList {
+    fn copy(option: u32, relative_to_window: u32) -> crate::Result<Self> {
+        let array = unsafe { CGWindowListCopyWindowInfo(option, relative_to_window) };
+        if array.is_null() {
+            return Err("CGWindowListCopyWindowInfo failed".into());
+        }
+        Ok(Self(array))
+    }
+
+    fn entries(&self) -> Vec<WindowListEntry> {
+        let count = unsafe { CFArrayGetCount(self.0) };
+        (0..count)
+            .filter_map(|index| unsafe {
+                let dict = CFArrayGetValueAtIndex(self.0, index);
+                Some(WindowListEntry {
+                    window: crate::Window::from(u32::try_from(number(dict, kCGWindowNumber)?).ok()?),
+                    pid: u32::try_from(number(dict, kCGWindowOwnerPID)?).ok()?,
+                    layer: number(dict, kCGWindowLayer).unwrap_or(0),
+                    onscreen: boolean(dict, kCGWindowIsOnscreen).unwrap_or(false),
+                    bounds: bounds(dict),
+                    name: string(dict, kCGWindowName),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Drop for WindowList {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0) };
+    }
+}
+
+unsafe fn number(dict: CFDictionaryRef, key: CFStringRef) -> Option<i64> {
+    let value = unsafe { CFDictionaryGetValue(dict, key) };
+    if value.is_null() {
+        return None;
+    }
+    let mut out = 0i64;
+    let ok = unsafe { CFNumberGetValue(value, K_CF_NUMBER_SINT64_TYPE, &mut out as *mut i64 as *mut c_void) };
+    (ok != 0).then_some(out)
+}
+
+unsafe fn boolean(dict: CFDictionaryRef, key: CFStringRef) -> Option<bool> {
+    let value = unsafe { CFDictionaryGetValue(dict, key) };
+    if value.is_null() {
+        return None;
+    }
+    Some(unsafe { CFBooleanGetValue(value) } != 0)
+}
+
+unsafe fn string(dict: CFDictionaryRef, key: CFStringRef) -> Option<String> {
+    let value = unsafe { CFDictionaryGetValue(dict, key) };
+    if value.is_null() {
+        return None;
+    }
+    unsafe { cf_string(value) }
+}
+
+unsafe fn cf_string(string: CFStringRef) -> Option<String> {
+    let length = unsafe { CFStringGetLength(string) };
+    let size = unsafe { CFStringGetMaximumSizeForEncoding(length, K_CF_STRING_ENCODING_UTF8) } + 1;
+    let mut buffer = vec![0u8; usize::try_from(size).ok()?];
+    if unsafe { CFStringGetCString(string, buffer.as_mut_ptr().cast(), size, K_CF_STRING_ENCODING_UTF8) } == 0 {
+        return None;
+    }
+    let len = buffer.iter().position(|&byte| byte == 0).unwrap_or(buffer.len());
+    buffer.truncate(len);
+    String::from_utf8(buffer).ok()
+}
+
+/// Releases a CoreFoundation object obtained from a Create or Copy function.
+struct Owned(CFTypeRef);
+
+impl Owned {
+    fn new(object: CFTypeRef) -> Option<Self> {
+        (!object.is_null()).then_some(Self(object))
+    }
+}
+
+impl Drop for Owned {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0) };
+    }
+}
+
+/// Send a message taking no arguments, e.g. `[NSWorkspace sharedWorkspace]`.
+unsafe fn send<R>(receiver: *mut c_void, selector: &std::ffi::CStr) -> R {
+    let send: unsafe extern "C" fn(*mut c_void, *mut c_void) -> R =
+        unsafe { std::mem::transmute(objc_msgSend as unsafe extern "C" fn()) };
+    unsafe { send(receiver, sel_registerName(selector.as_ptr())) }
+}
+
+/// `NSWorkspace.sharedWorkspace.frontmostApplication.processIdentifier`
+fn frontmost_pid() -> Option<u32> {
+    unsafe {
+        let pool = objc_autoreleasePoolPush();
+        let workspace: *mut c_void = send(objc_getClass(c"NSWorkspace".as_ptr()), c"sharedWorkspace");
+        let application: *mut c_void = if workspace.is_null() {
+            std::ptr::null_mut()
+        } else {
+            send(workspace, c"frontmostApplication")
+        };
+        let pid = if application.is_null() {
+            None
+        } else {
+            u32::try_from(send::<i32>(application, c"processIdentifier")).ok()
+        };
+        objc_autoreleasePoolPop(pool);
+        pid
+    }
+}
+
+/// `kCGWindowBounds`, which Quartz already reports in global display coordinates
+/// with the origin at the top-left of the main display and y growing downwards,
+/// the same convention as X11 and Win32.
+unsafe fn bounds(dict: CFDictionaryRef) -> Option<WindowInfo> {
+    let value = unsafe { CFDictionaryGetValue(dict, kCGWindowBounds) };
+    if value.is_null() {
+        return None;
+    }
+    let mut rect = CGRect::default();
+    if unsafe { CGRectMakeWithDictionaryRepresentation(value, &mut rect) } == 0 {
+        return None;
+    }
+    Some(rect_info(&rect))
+}
+
+/// A `CGRect` in global points rounded to whole points. Quartz and
+/// `CGDisplayBounds` already use the top-left origin, so nothing is flipped.
+fn rect_info(rect: &CGRect) -> WindowInfo {
+    WindowInfo {
+        pos: (rect.x.round() as i32, rect.y.round() as i32),
+        size: (rect.width.round() as u32, rect.height.round() as u32),
+    }
+}
+
+/// The list entry for one window, or `None` if it no longer exists.
+fn find_entry(window: crate::Window) -> crate::Result<Option<WindowListEntry>> {
+    Ok(WindowList::copy(K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW, window.raw())?
+        .entries()
+        .into_iter()
+        .find(|entry| entry.window == window))
+}
+
+/// The owning user of a process, or `None` if it exited or can't be inspected.
+/// Whether a process with this pid is running (and not a zombie)
+pub fn process_exists(pid: u32) -> crate::Result<bool> {
+    const SZOMB: u32 = 5;
+    if pid == 0 {
+        return Ok(false);
+    }
+    let mut info = ProcBsdShortInfo::default();
+    let size = std::mem::size_of::<ProcBsdShortInfo>() as i32;
+    let written = unsafe {
+        proc_pidinfo(pid as i32, PROC_PIDT_SHORTBSDINFO, 0, &mut info as *mut _ as *mut c_void, size)
+    };
+    Ok(written == size && info.status != SZOMB)
+}
+
+/// The name of a running process (its executable's, cut to 32 bytes by the
+/// kernel). `None` if the process isn't running or can't be inspected.
+pub fn get_process_name(pid: u32) -> crate::Result<Option<String>> {
+    if pid == 0 {
+        return Ok(None);
+    }
+    let mut buffer = [0u8; 64];
+    let length = unsafe { proc_name(pid as i32, buffer.as_mut_ptr() as *mut c_void, buffer.len() as u32) };
+    if length <= 0 {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&buffer[..length as usize]).into_owned()))
+}
+
+fn process_uid(pid: u32) -> Option<u32> {
+    let mut info = ProcBsdShortInfo::default();
+    let size = std::mem::size_of::<ProcBsdShortInfo>() as i32;
+    let written = unsafe {
+        proc_pidinfo(pid as i32, PROC_PIDT_SHORTBSDINFO, 0, &mut info as *mut _ as *mut c_void, size)
+    };
+    (written == size).then_some(info.uid)
+}
+
+/// List windows and their details according to `options`, front to back;
+/// desktop elements (wallpaper, desktop icons) are left out unless
+/// `include_desktop` is set.
+pub fn list_window_entries(options: WindowListOptions) -> crate::Result<Vec<WindowListEntry>> {
+    let mut option = if options.include_desktop { 0 } else { K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS };
+    if !options.include_offscreen {
+        option |= K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY;
+    }
+    let uid = unsafe { getuid() };
+    Ok(WindowList::copy(option, K_CG_NULL_WINDOW_ID)?
+        .entries()
+        .into_iter()
+        .filter(|entry| options.includes_layer(entry.layer))
+        .filter(|entry| options.include_other_users || process_uid(entry.pid) == Some(uid))
+        .collect())
+}
+
+/// List windows according to `options`, front to back
+pub fn list_windows_with(options: WindowListOptions) -> crate::Result<Vec<crate::Window>> {
+    Ok(list_window_entries(options)?.into_iter().map(|entry| entry.window).collect())
+}
+
+/// Details of one window on any layer, or `None` if it no longer exists
+pub fn get_window_entry(window: crate::Window) -> crate::Result<Option<WindowListEntry>> {
+    find_entry(window)
+}
+
+/// The frontmost window `list_windows` reports whose frame contains the point,
+/// so the menu bar, Dock and overlays never hide the app window underneath
+pub fn get_window_at_point(x: i32, y: i32) -> crate::Result<Option<crate::Window>> {
+    let contains = |bounds: WindowInfo| {
+        (bounds.pos.0..bounds.pos.0 + bounds.size.0 as i32).contains(&x)
+            && (bounds.pos.1..bounds.pos.1 + bounds.size.1 as i32).contains(&y)
+    };
+    Ok(list_window_entries(WindowListOptions::default())?
+        .into_iter()
+        .find(|entry| entry.bounds.is_some_and(contains))
+        .map(|entry| entry.window))
+}
+
+/// Get the frame of a window in top-left-origin global coordinates (points, not pixels)
+pub fn get_window_info(window: crate::Window) -> crate::Result<WindowInfo> {
+    find_entry(window)?
+        .ok_or_else(|| crate::Error::WindowGone(window))?
+        .bounds
+        .ok_or_else(|| format!("Quartz reported no bounds for window {window}").into())
+}
+
+/// Get the frame of a window in pixels of the display holding most of it,
+/// which is the display whose backing scale the window server renders it at
+pub fn get_window_bounds(window: crate::Window) -> crate::Result<crate::PhysicalWindowInfo> {
+    Ok(crate::geometry::to_physical(get_window_info(window)?, &list_monitors()?))
+}
+
+/// The display holding most of a window, `None` if it is off-screen
+pub fn get_window_monitor(window: crate::Window) -> crate::Result<Option<crate::MonitorInfo>> {
+    Ok(crate::geometry::monitor_of(&get_window_info(window)?, &list_monitors()?).cloned())
+}
+
+/// Every display a window overlaps, the one holding most of it first
+pub fn get_window_monitors(window: crate::Window) -> crate::Result<Vec<crate::MonitorInfo>> {
+    Ok(crate::geometry::monitors_of(&get_window_info(window)?, &list_monitors()?))
+}
+
+/// A display's backing scale: pixel width over point width of its current
+/// mode (2.0 on Retina displays, also for scaled "looks like" resolutions).
+fn backing_scale(display: u32) -> f64 {
+    let mode = unsafe { CGDisplayCopyDisplayMode(display) };
+    if mode.is_null() {
+        return 1.0;
+    }
+    let (points, pixels) = unsafe { (CGDisplayModeGetWidth(mode), CGDisplayModeGetPixelWidth(mode)) };
+    unsafe { CGDisplayModeRelease(mode) };
+    mode_scale(points, pixels)
+}
+
+/// Pixels per point of a display mode, 1 if the mode reports no width
+fn mode_scale(points: usize, pixels: usize) -> f64 {
+    if points == 0 || pixels == 0 {
+        return 1.0;
+    }
+    pixels as f64 / points as f64
+}
+
+/// Every active display, main display first, with bounds in global points.
+/// Displays are named after their CGDirectDisplayID, which stays the same
+/// for a given display across reconnects.
+pub fn list_monitors() -> crate::Result<Vec<crate::MonitorInfo>> {
+    const MAX_DISPLAYS: usize = 32;
+    let mut displays = [0u32; MAX_DISPLAYS];
+    let mut count = 0u32;
+    let error = unsafe { CGGetActiveDisplayList(MAX_DISPLAYS as u32, displays.as_mut_ptr(), &mut count) };
+    if error != 0 {
+        return Err(format!("CGGetActiveDisplayList failed with CGError {error}").into());
+    }
+    Ok(displays[..count as usize]
+        .iter()
+        .map(|&display| {
+            let rect = unsafe { CGDisplayBounds(display) };
+            crate::MonitorInfo {
+                name: format!("Display {display}"),
+                bounds: rect_info(&rect),
+                scale_factor: backing_scale(display),
+            }
+        })
+        .collect())
+}
+
+/// On-screen normal-layer windows of the current user, front to back
+pub fn list_windows() -> crate::Result<Vec<crate::Window>> {
+    list_windows_with(WindowListOptions::default())
+}
+
+/// `list_windows` plus the wallpaper and desktop icon windows; Quartz has no root window
+pub fn list_windows_including_desktop() -> crate::Result<Vec<crate::Window>> {
+    list_windows_with(WindowListOptions { include_desktop: true, ..Default::default() })
+}
+
+/// Whether the window server has a window with this id, on screen or not
+pub fn window_exists(window: crate::Window) -> crate::Result<bool> {
+    Ok(find_entry(window)?.is_some())
+}
+
+/// Number of windows `list_windows` would return
+pub fn window_count() -> crate::Result<usize> {
+    Ok(list_windows()?.len())
+}
+
+/// The process's on-screen normal-layer windows, front to back
+fn entries_for_pid(target_pid: u32) -> crate::Result<Vec<WindowListEntry>> {
+    let option = K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS | K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY;
+    let options = WindowListOptions::default();
+    Ok(WindowList::copy(option, K_CG_NULL_WINDOW_ID)?
+        .entries()
+        .into_iter()
+        .filter(|entry| entry.pid == target_pid && options.includes_layer(entry.layer))
+        .collect())
+}
+
+pub fn find_windows_by_pid(target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+    Ok(entries_for_pid(target_pid)?.into_iter().map(|entry| entry.window).collect())
+}
+
+fn area(entry: &WindowListEntry) -> u64 {
+    entry.bounds.map_or(0, |bounds| bounds.size.0 as u64 * bounds.size.1 as u64)
+}
+
+/// A normal-level window on the current Space with a title
+fn looks_main(entry: &WindowListEntry) -> bool {
+    entry.layer == 0 && entry.onscreen && entry.name.as_deref().is_some_and(|name| !name.is_empty())
+}
+
+/// The process's main window: the largest titled normal window on the current
+/// Space. Titles need Screen Recording permission, so without them the app's
+/// `AXMainWindow` (Accessibility permission) is used, then the largest normal
+/// window. Menu-bar extras and other higher-layer windows are never picked.
+pub fn find_window_by_pid(target_pid: u32) -> crate::Result<Option<crate::Window>> {
+    Ok(pick_main_window(&entries_for_pid(target_pid)?, || ax::main_window(target_pid)))
+}
+
+/// `find_window_by_pid`'s choice among a process's entries (front to back),
+/// with `ax_main` only asked when no entry looks like a main window.
+fn pick_main_window(
+    entries: &[WindowListEntry],
+    ax_main: impl FnOnce() -> Option<crate::Window>,
+) -> Option<crate::Window> {
+    // Entries are front to back; reversing makes max_by_key keep the frontmost on ties
+    let titled = entries.iter().rev().filter(|entry| looks_main(entry));
+    if let Some(entry) = titled.max_by_key(|entry| area(entry)) {
+        return Some(entry.window);
+    }
+    let on_screen = |window: &crate::Window| entries.iter().any(|entry| entry.window == *window);
+    if let Some(window) = ax_main().filter(on_screen) {
+        return Some(window);
+    }
+    let normal = entries.iter().rev().filter(|entry| entry.layer == 0);
+    normal.max_by_key(|entry| area(entry)).map(|entry| entry.window)
+}
+
+/// Whether a window is at the normal level, on the current Space and titled
+pub fn is_main_window(window: crate::Window) -> crate::Result<bool> {
+    Ok(find_entry(window)?.is_some_and(|entry| looks_main(&entry)))
+}
+
+/// The pid of the frontmost application, which may own no windows (e.g. a
+/// menu-bar-only app)
+pub fn get_active_window_pid() -> crate::Result<Option<u32>> {
+    Ok(frontmost_pid())
+}
+
+pub fn get_window_pid(window: crate::Window) -> crate::Result<Option<u32>> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    Ok(Some(entry.pid))
+}
+
+/// `kCGWindowName`, which other apps' windows only report with Screen Recording
+/// permission; otherwise the AX title, which needs Accessibility permission.
+/// With neither permission this fails with `PermissionDenied` rather than
+/// returning an empty title.
+pub fn get_window_title(window: crate::Window) -> crate::Result<Option<String>> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    if let Some(name) = &entry.name {
+        return Ok(Some(name.clone()));
+    }
+    ax::window_title(&entry)
+}
+
+/// The frontmost application's frontmost normal (layer 0) window, or `None`
+/// if it has no window on screen
+pub fn get_active_window() -> crate::Result<Option<crate::Window>> {
+    let Some(pid) = frontmost_pid() else {
+        return Ok(None);
+    };
+    let option = K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS | K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY;
+    Ok(WindowList::copy(option, K_CG_NULL_WINDOW_ID)?
+        .entries()
+        .into_iter()
+        .find(|entry| entry.pid == pid && entry.layer == 0)
+        .map(|entry| entry.window))
+}
+
+/// Only `Closed` and `Visible` can be answered from the window list so far
+pub fn window_has_state(window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    let entry = find_entry(window)?;
+    match state {
+        WindowState::Closed => Ok(entry.is_none()),
+        WindowState::Visible => Ok(entry.is_some_and(|entry| entry.onscreen)),
+        _ if entry.is_none() => Ok(false),
+        _ => Err(Unsupported::new("window_has_state", QUARTZ).into()),
+    }
+}
+
+/// Move a window through the Accessibility API; needs Accessibility permission
+pub fn move_window(window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::move_window(&entry, x, y)
+}
+
+/// Resize a window through the Accessibility API; needs Accessibility permission
+pub fn resize_window(window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::resize_window(&entry, width, height)
+}
+
+/// Move and resize a window in one call; needs Accessibility permission
+pub fn set_window_rect(
+    window: crate::Window,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::set_window_rect(&entry, WindowInfo { pos: (x, y), size: (width, height) })
+}
+
+/// Minimize a window into the Dock; needs Accessibility permission
+pub fn minimize_window(window: crate::Window) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::set_minimized(&entry, true, "minimize_window")
+}
+
+/// Hide a single window by minimizing it. Unlike Cmd-H (`NSRunningApplication.hide`),
+/// which hides every window of the app, this leaves the app's other windows alone,
+/// matching what `hide_window` does on the other platforms. The window still
+/// shows in the Dock. Needs Accessibility permission.
+pub fn hide_window(window: crate::Window) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::set_minimized(&entry, true, "hide_window")
+}
+
+/// Bring a minimized (or hidden) window back; needs Accessibility permission
+pub fn restore_window(window: crate::Window) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::set_minimized(&entry, false, "restore_window")
+}
+
+/// Press the window's close button; needs Accessibility permission
+pub fn close_window(window: crate::Window) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::close_window(&entry)
+}
+
+/// Zoom or unzoom a window with its green button (macOS has no separate
+/// maximized state); needs Accessibility permission
+pub fn toggle_maximize(window: crate::Window) -> crate::Result<()> {
+    let entry = find_entry(window)?.ok_or_else(|| crate::Error::WindowGone(window))?;
+    ax::zoom_window(&entry)
+}
+
+pub fn subscribe_window_events() -> crate::Result<crate::WindowEvents> {
+    Err(Unsupported::new("subscribe_window_events", QUARTZ).into())
+}
+
+pub fn detect_backend() -> BackendKind {
+    BackendKind::Quartz
+}
+
+/// What the Quartz backend can do: moving and resizing (given the
+/// Accessibility permission) but no workspaces, stacking or events
+pub fn capabilities() -> crate::Capabilities {
+    crate::Capabilities {
+        backend: BackendKind::Quartz,
+        is_wayland: false,
+        has_compositor: true,
+        supports_ewmh_state: false,
+        can_move_windows: true,
+        can_move_to_desktop: false,
+        can_set_always_on_top: false,
+        can_watch_events: false,
+        can_set_opacity: false,
+        supports_input_simulation: false,
+    }
+}
+
+/// Always true: the window server is part of macOS, not something that may be absent
+pub fn is_display_available() -> bool {
+    true
+}
+
+/// Whether Quartz reveals other processes' window titles, which it only does
+/// with Screen Recording permission. Inconclusive (reported as not granted)
+/// when no other app has a titled normal window on screen.
+fn screen_recording_granted() -> bool {
+    let own_pid = std::process::id();
+    list_window_entries(WindowListOptions::default())
+        .is_ok_and(|entries| entries.iter().any(|entry| entry.pid != own_pid && entry.name.is_some()))
+}
+
+/// Check the Accessibility and Screen Recording permissions without prompting
+pub fn macos_permissions() -> PermissionStatus {
+    PermissionStatus {
+        accessibility: ax::is_trusted(false),
+        screen_recording: screen_recording_granted(),
+    }
+}
+
+/// Like `macos_permissions`, but shows the system Accessibility prompt if the
+/// permission is missing. The user has to grant it in System Settings; the
+/// result reflects the state before they do.
+pub fn request_macos_permissions() -> PermissionStatus {
+    PermissionStatus {
+        accessibility: ax::is_trusted(true),
+        screen_recording: screen_recording_granted(),
+    }
+}
+
+/// The platform's window system. There is no connection to hold here, so
+/// every instance is equivalent to the shared one behind the free functions.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct WindowSystem;
+
+impl WindowSystem {
+    pub fn new() -> crate::Result<Self> {
+        Ok(WindowSystem)
+    }
+
+    /// Calls here never wait on a server's reply, so there is nothing to time
+    /// out; accepted so the same setup code builds on every platform.
+    pub fn with_request_timeout(self, _timeout: std::time::Duration) -> Self {
+        self
+    }
+}
+
+impl crate::WindowingBackend for WindowSystem {
+    fn kind(&self) -> BackendKind {
+        detect_backend()
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("list_windows", backend = self.kind().name());
+        list_windows()
+    }
+
+    fn list_windows_including_desktop(&self) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("list_windows_including_desktop", backend = self.kind().name());
+        list_windows_including_desktop()
+    }
+
+    fn window_count(&self) -> crate::Result<usize> {
+        trace_span!("window_count", backend = self.kind().name());
+        window_count()
+    }
+
+    fn window_exists(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("window_exists", backend = self.kind().name(), %window);
+        window_exists(window)
+    }
+
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        trace_span!("get_window_info", backend = self.kind().name(), %window);
+        get_window_info(window)
+    }
+
+    fn get_window_bounds(
+        &self,
+        window: crate::Window,
+    ) -> crate::Result<crate::PhysicalWindowInfo> {
+        trace_span!("get_window_bounds", backend = self.kind().name(), %window);
+        get_window_bounds(window)
+    }
+
+    fn get_window_monitor(
+        &self,
+        window: crate::Window,
+    ) -> crate::Result<Option<crate::MonitorInfo>> {
+        trace_span!("get_window_monitor", backend = self.kind().name(), %window);
+        get_window_monitor(window)
+    }
+
+    fn get_window_monitors(&self, window: crate::Window) -> crate::Result<Vec<crate::MonitorInfo>> {
+        trace_span!("get_window_monitors", backend = self.kind().name(), %window);
+        get_window_monitors(window)
+    }
+
+    fn list_monitors(&self) -> crate::Result<Vec<crate::MonitorInfo>> {
+        trace_span!("list_monitors", backend = self.kind().name());
+        list_monitors()
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        trace_span!("find_window_by_pid", backend = self.kind().name(), pid = target_pid);
+        find_window_by_pid(target_pid)
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("find_windows_by_pid", backend = self.kind().name(), pid = target_pid);
+        find_windows_by_pid(target_pid)
+    }
+
+    fn is_main_window(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_main_window", backend = self.kind().name(), %window);
+        is_main_window(window)
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        trace_span!("get_active_window", backend = self.kind().name());
+        get_active_window()
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        trace_span!("get_active_window_pid", backend = self.kind().name());
+        get_active_window_pid()
+    }
+
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        trace_span!("get_window_pid", backend = self.kind().name(), %window);
+        get_window_pid(window)
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        trace_span!("get_window_title", backend = self.kind().name(), %window);
+        get_window_title(window)
+    }
+
+    fn hide_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("hide_window", backend = self.kind().name(), %window);
+        hide_window(window)
+    }
+
+    fn show_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("show_window", backend = self.kind().name(), %window);
+        restore_window(window)
+    }
+
+    fn minimize_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("minimize_window", backend = self.kind().name(), %window);
+        minimize_window(window)
+    }
+
+    fn toggle_maximize(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("toggle_maximize", backend = self.kind().name(), %window);
+        toggle_maximize(window)
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("close_window", backend = self.kind().name(), %window);
+        close_window(window)
+    }
+
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        trace_span!("move_window", backend = self.kind().name(), %window);
+        move_window(window, x, y)
+    }
+
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        trace_span!("resize_window", backend = self.kind().name(), %window);
+        resize_window(window, width, height)
+    }
+
+    fn set_window_rect(
+        &self,
+        window: crate::Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> crate::Result<()> {
+        trace_span!("set_window_rect", backend = self.kind().name(), %window);
+        set_window_rect(window, x, y, width, height)
+    }
+
+    fn subscribe_window_events(&self) -> crate::Result<crate::WindowEvents> {
+        trace_span!("subscribe_window_events", backend = self.kind().name());
+        subscribe_window_events()
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        trace_span!("window_has_state", backend = self.kind().name(), %window);
+        window_has_state(window, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> CGRect {
+        CGRect { x, y, width, height }
+    }
+
+    #[test]
+    fn quartz_rects_keep_the_top_left_origin() {
+        // A window 100pt right of and 50pt below the main display's top-left corner
+        assert_eq!(rect_info(&rect(100.0, 50.0, 800.0, 600.0)), WindowInfo { pos: (100, 50), size: (800, 600) });
+        // A display arranged left of and above the main one
+        assert_eq!(
+            rect_info(&rect(-1440.0, -900.0, 1440.0, 900.0)),
+            WindowInfo { pos: (-1440, -900), size: (1440, 900) }
+        );
+    }
+
+    #[test]
+    fn quartz_rects_round_to_whole_points() {
+        assert_eq!(rect_info(&rect(10.4, 20.6, 300.5, 199.49)), WindowInfo { pos: (10, 21), size: (301, 199) });
+        assert_eq!(rect_info(&rect(-10.6, -0.4, 0.0, 0.0)), WindowInfo { pos: (-11, 0), size: (0, 0) });
+    }
+
+    fn entry(id: u32, layer: i64, name: Option<&str>, width: u32, height: u32) -> WindowListEntry {
+        WindowListEntry {
+            window: crate::Window::from(id),
+            pid: 100,
+            layer,
+            onscreen: true,
+            bounds: Some(WindowInfo { pos: (0, 25), size: (width, height) }),
+            name: name.map(String::from),
+        }
+    }
+
+    fn no_ax_main() -> Option<crate::Window> {
+        None
+    }
+
+    #[test]
+    fn main_window_is_the_largest_titled_normal_window() {
+        // Safari: a downloads popover and a tab preview in front of the browser window
+        let entries = [
+            entry(1, 0, None, 360, 420),
+            entry(2, 0, Some(""), 280, 200),
+            entry(3, 0, Some("Apple"), 1280, 800),
+            entry(4, 0, Some("Favorites"), 600, 400),
+        ];
+
+        assert_eq!(pick_main_window(&entries, no_ax_main), Some(crate::Window::from(3)));
+        assert!(!looks_main(&entries[0]) && !looks_main(&entries[1]) && looks_main(&entries[2]));
+    }
+
+    #[test]
+    fn higher_layers_are_never_the_main_window() {
+        // A floating palette and a menu-bar extra larger than the document window
+        let entries = [
+            entry(1, 3, Some("Colors"), 2000, 2000),
+            entry(2, 25, Some("Item-0"), 3000, 3000),
+            entry(3, 0, Some("Untitled"), 800, 600),
+        ];
+
+        assert_eq!(pick_main_window(&entries, no_ax_main), Some(crate::Window::from(3)));
+        assert_eq!(pick_main_window(&entries[..2], no_ax_main), None);
+    }
+
+    #[test]
+    fn off_screen_windows_do_not_look_main() {
+        let hidden = WindowListEntry { onscreen: false, ..entry(1, 0, Some("Downloads"), 1600, 1000) };
+
+        assert!(!looks_main(&hidden));
+        let entries = [hidden, entry(2, 0, Some("Documents"), 800, 600)];
+        assert_eq!(pick_main_window(&entries, no_ax_main), Some(crate::Window::from(2)));
+    }
+
+    #[test]
+    fn equally_large_windows_pick_the_frontmost() {
+        // Terminal: two default-sized windows
+        let entries = [entry(7, 0, Some("bash — 80×24"), 570, 400), entry(5, 0, Some("bash — 80×24"), 570, 400)];
+
+        assert_eq!(pick_main_window(&entries, no_ax_main), Some(crate::Window::from(7)));
+    }
+
+    #[test]
+    fn untitled_windows_fall_back_to_ax_main_window() {
+        // Without Screen Recording permission Quartz reports no titles
+        let entries = [entry(1, 0, None, 1200, 800), entry(2, 0, None, 600, 400)];
+
+        assert_eq!(pick_main_window(&entries, || Some(crate::Window::from(2))), Some(crate::Window::from(2)));
+        // An AXMainWindow on another Space is not one of the entries
+        assert_eq!(pick_main_window(&entries, || Some(crate::Window::from(9))), Some(crate::Window::from(1)));
+        assert_eq!(pick_main_window(&entries, no_ax_main), Some(crate::Window::from(1)));
+        assert_eq!(pick_main_window(&[], no_ax_main), None);
+    }
+
+    #[test]
+    fn ax_main_window_is_only_asked_without_a_titled_window() {
+        let entries = [entry(1, 0, Some("Finder"), 900, 500)];
+
+        let picked = pick_main_window(&entries, || panic!("AXMainWindow asked although a titled window exists"));
+        assert_eq!(picked, Some(crate::Window::from(1)));
+    }
+
+    #[test]
+    fn area_is_zero_without_bounds() {
+        assert_eq!(area(&entry(1, 0, None, 640, 480)), 640 * 480);
+        assert_eq!(area(&WindowListEntry { bounds: None, ..entry(1, 0, None, 640, 480) }), 0);
+    }
+
+    #[test]
+    fn backing_scale_is_pixels_per_point() {
+        // A Retina MacBook Air at its default "looks like 1440x900"
+        assert_eq!(mode_scale(1440, 2880), 2.0);
+        assert_eq!(mode_scale(1920, 1920), 1.0);
+        assert_eq!(mode_scale(0, 2880), 1.0);
+        assert_eq!(mode_scale(1440, 0), 1.0);
+    }
+
+    #[test]
+    fn physical_bounds_on_a_single_retina_display_double() {
+        let display = crate::MonitorInfo {
+            name: "Display 1".into(),
+            bounds: WindowInfo { pos: (0, 0), size: (1440, 900) },
+            scale_factor: mode_scale(1440, 2880),
+        };
+        let frame = WindowInfo { pos: (100, 38), size: (800, 600) };
+
+        let physical = crate::geometry::to_physical(frame, &[display]);
+        assert_eq!(physical, crate::PhysicalWindowInfo { pos: (200, 76), size: (1600, 1200) });
+    }
+
+    #[test]
+    fn physical_bounds_match_every_display_scale() {
+        for monitor in list_monitors().unwrap() {
+            let frame = WindowInfo { pos: monitor.bounds.pos, size: (100, 50) };
+            let physical = crate::geometry::to_physical(frame, std::slice::from_ref(&monitor));
+            let scaled = |points: f64| (points * monitor.scale_factor).round() as u32;
+            assert_eq!(physical.size, (scaled(100.0), scaled(50.0)), "{monitor:?}");
+            assert!(monitor.scale_factor >= 1.0, "{monitor:?}");
+        }
+    }
+
+    #[test]
+    fn missing_window_is_gone() {
+        // Window ids count up from 1 and are never reused within a login session
+        let window = crate::Window::from(u32::MAX - 1);
+
+        assert!(matches!(get_window_info(window), Err(crate::Error::WindowGone(gone)) if gone == window));
+        assert!(!window_exists(window).unwrap());
+    }
+
+    #[test]
+    fn main_display_is_at_the_origin() {
+        let monitors = list_monitors().unwrap();
+
+        assert_eq!(monitors.first().map(|main| main.bounds.pos), Some((0, 0)));
+    }
+
+    #[test]
+    fn window_info_matches_the_listed_bounds() {
+        let options = WindowListOptions { include_other_users: true, all_layers: true, ..Default::default() };
+        for entry in list_window_entries(options).unwrap().into_iter().filter(|entry| entry.bounds.is_some()) {
+            match get_window_info(entry.window) {
+                Ok(info) => assert_eq!(Some(info), entry.bounds, "{entry:?}"),
+                // Closed between the two calls
+                Err(crate::Error::WindowGone(_)) => {}
+                Err(error) => panic!("{entry:?}: {error}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(all(
+    target_os = "linux",
+    not(any(
+        feature = "x11",
+        feature = "wayland-wlr",
+        feature = "hyprland",
+        feature = "sway-ipc",
+        feature = "gnome-shell",
+        feature = "kwin"
+    ))
+))]
+compile_error!(
+    "no Linux backend enabled: turn on at least one of the `x11`, `wayland-wlr`, `hyprland`, `sway-ipc`, \
+     `gnome-shell` or `kwin` features"
+);
+
+#[cfg(all(target_os = "windows", not(feature = "win32")))]
+compile_error!("the Windows backend is disabled: turn on the `win32` feature");
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
+#[cfg(target_os = "windows")]
+mod win32;
+#[cfg(target_os = "windows")]
+pub use win32::*;
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+pub use macos::*;
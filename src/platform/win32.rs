@@ -0,0 +1,1420 @@
+use windows::{
+    core::BOOL, Win32::{
+        Foundation::{CloseHandle, COLORREF, ERROR_ACCESS_DENIED, ERROR_INVALID_PARAMETER, FALSE, HWND, STILL_ACTIVE, LPARAM, POINT, RECT, TRUE, WPARAM},
+        Graphics::Gdi::{
+            DeleteObject, EnumDisplayMonitors, GetDC, GetDIBits, GetMonitorInfoW, GetObjectW, MonitorFromPoint, MonitorFromWindow, ReleaseDC, MONITORINFO, MONITORINFOEXW, MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTONULL, MONITOR_DEFAULTTOPRIMARY, BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP, HDC, HMONITOR
+        },
+        System::Threading::{GetExitCodeProcess, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION},
+        UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK},
+        UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+        UI::Input::KeyboardAndMouse::IsWindowEnabled,
+        UI::WindowsAndMessaging::{
+            DispatchMessageW, EnumChildWindows, EnumWindows, GetAncestor, GetClassNameW, GetCursorPos, GetDesktopWindow, GetForegroundWindow, GetIconInfo, GetMessageW, GetShellWindow, GetSystemMetrics, GetWindowLongW, GetWindowPlacement, GetWindowRect, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindow, IsWindowVisible, IsZoomed, LogicalToPhysicalPointForPerMonitorDPI, PostMessageW, PostQuitMessage, SendMessageW, SetForegroundWindow, SetLayeredWindowAttributes, SetWindowLongW, SetWindowPlacement, SetWindowPos, ShowWindow, GCLP_HICON, GCLP_HICONSM, GWL_EXSTYLE, HICON, ICONINFO, ICON_BIG, LWA_ALPHA, ICON_SMALL, ICON_SMALL2, MSG, OBJID_WINDOW, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, SW_HIDE, SW_MAXIMIZE, SW_MINIMIZE, SW_RESTORE, SW_SHOWMAXIMIZED, SW_SHOWNA, SW_SHOWMINIMIZED, SW_SHOWNORMAL, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SWP_NOZORDER, EVENT_SYSTEM_FOREGROUND, HWND_BOTTOM, HWND_NOTOPMOST, HWND_TOPMOST, WINEVENT_OUTOFCONTEXT, WM_CLOSE, WM_GETICON, WS_EX_APPWINDOW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT, WINDOWPLACEMENT, WindowFromPoint, GA_ROOT
+        },
+    }
+};
+#[cfg(target_pointer_width = "64")]
+use windows::Win32::UI::WindowsAndMessaging::GetClassLongPtrW;
+#[cfg(target_pointer_width = "32")]
+use windows::Win32::UI::WindowsAndMessaging::GetClassLongW;
+
+use std::sync::mpsc;
+
+use crate::error::Context as _;
+
+use crate::{BackendKind, FocusRefused, IconData, PhysicalWindowInfo, Unsupported, WindowEvent, WindowEvents, WindowInfo, WindowState, WindowStateFlag};
+
+const WIN32: &str = "win32";
+
+struct EnumWindowsData {
+    process_id: u32,
+    windows: Vec<HWND>,
+}
+
+// Callback function for EnumWindows
+unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let data = unsafe { &mut *(lparam.0 as *mut EnumWindowsData) };
+    let mut window_process_id: u32 = 0;
+
+    // Get the process ID that owns this window
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut window_process_id)) };
+
+    // If it matches our target process ID, add it to the list
+    if window_process_id == data.process_id {
+        data.windows.push(hwnd);
+    }
+
+    TRUE // Continue enumeration
+}
+
+pub fn find_windows_by_pid(process_id: u32) -> crate::Result<Vec<crate::Window>> {
+    let mut data = EnumWindowsData {
+        process_id,
+        windows: Vec::new(),
+    };
+
+    unsafe {
+        EnumWindows(
+            Some(enum_windows_proc),
+            LPARAM(&mut data as *mut _ as isize),
+        )?;
+    }
+
+    Ok(data.windows.into_iter().map(crate::Window::from).collect())
+}
+
+// Callback function for EnumWindows that stops at the process's first visible window
+unsafe extern "system" fn find_visible_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let data = unsafe { &mut *(lparam.0 as *mut EnumWindowsData) };
+    let mut window_process_id: u32 = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut window_process_id)) };
+    if window_process_id == data.process_id && unsafe { IsWindowVisible(hwnd) }.as_bool() {
+        data.windows.push(hwnd);
+        return FALSE;
+    }
+    TRUE
+}
+
+/// Whether the process has a visible top-level window. Hidden ones don't
+/// count: console tools and services often own invisible helper windows.
+pub fn pid_has_window(process_id: u32) -> crate::Result<bool> {
+    let mut data = EnumWindowsData {
+        process_id,
+        windows: Vec::new(),
+    };
+    // Fails when the callback stops the enumeration early, which is the success case here
+    let _ = unsafe { EnumWindows(Some(find_visible_proc), LPARAM(&mut data as *mut _ as isize)) };
+    Ok(!data.windows.is_empty())
+}
+
+pub fn find_window_by_pid(process_id: u32) -> crate::Result<Option<crate::Window>> {
+    let windows = find_windows_by_pid(process_id)?;
+
+    for &window in &windows {
+        if is_main_window(window)? {
+            return Ok(Some(window)); // Return first visible window with title
+        }
+        trace_debug!(%window, "not main: invisible or untitled");
+    }
+
+    // If no main window found, return first window (if any)
+    Ok(windows.first().copied())
+}
+
+/// Whether a window looks like an application's primary window: visible and titled
+pub fn is_main_window(window: crate::Window) -> crate::Result<bool> {
+    let hwnd = HWND::from(window);
+    unsafe { Ok(IsWindowVisible(hwnd) != FALSE && GetWindowTextLengthW(hwnd) > 0) }
+}
+
+/// find_windows_by_pid without message-only and invisible helper windows:
+/// only visible windows with a title
+pub fn find_visible_windows_by_pid(process_id: u32) -> crate::Result<Vec<crate::Window>> {
+    let mut visible = Vec::new();
+    for window in find_windows_by_pid(process_id)? {
+        if is_main_window(window)? {
+            visible.push(window);
+        } else {
+            trace_debug!(%window, "skipped: invisible or untitled");
+        }
+    }
+    Ok(visible)
+}
+
+/// The window's frame in screen coordinates. Minimized windows are parked at
+/// (-32000, -32000), so for those this is the rectangle they restore to:
+/// GetWindowPlacement's rcNormalPosition, moved from workspace to screen
+/// coordinates unless it's a tool window (which already uses screen ones).
+fn frame_rect(hwnd: HWND) -> crate::Result<RECT> {
+    if !unsafe { IsWindow(Some(hwnd)) }.as_bool() {
+        return Err(crate::Error::WindowGone(crate::Window::from(hwnd)));
+    }
+    let mut rect = RECT::default();
+    if !unsafe { IsIconic(hwnd) }.as_bool() {
+        unsafe { GetWindowRect(hwnd, &mut rect) }.context("get_window_info", Some(hwnd.into()), "GetWindowRect")?;
+        return Ok(rect);
+    }
+    let mut placement = WINDOWPLACEMENT {
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetWindowPlacement(hwnd, &mut placement) }.context(
+        "get_window_info",
+        Some(hwnd.into()),
+        "GetWindowPlacement",
+    )?;
+    let (dx, dy) = workspace_offset(hwnd)?;
+    rect = placement.rcNormalPosition;
+    rect.left += dx;
+    rect.right += dx;
+    rect.top += dy;
+    rect.bottom += dy;
+    Ok(rect)
+}
+
+/// Screen position of workspace coordinate (0, 0), in which WINDOWPLACEMENT
+/// rectangles are given: the primary monitor's work area, past a top or left
+/// taskbar. Tool windows use screen coordinates, so for them it is (0, 0).
+fn workspace_offset(hwnd: HWND) -> crate::Result<(i32, i32)> {
+    let ex_style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+    if ex_style & WS_EX_TOOLWINDOW.0 != 0 {
+        return Ok((0, 0));
+    }
+    let primary = unsafe { MonitorFromPoint(POINT::default(), MONITOR_DEFAULTTOPRIMARY) };
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetMonitorInfoW(primary, &mut info) }.ok()?;
+    Ok((info.rcWork.left - info.rcMonitor.left, info.rcWork.top - info.rcMonitor.top))
+}
+
+/// How a window is shown, as stored in its placement
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ShowState {
+    Normal,
+    Minimized,
+    Maximized,
+}
+
+/// A window's show state together with its normal (restored) frame, in the
+/// same screen coordinates as `get_window_info`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WindowPlacement {
+    pub normal: WindowInfo,
+    pub state: ShowState,
+}
+
+/// Read a window's placement (GetWindowPlacement): the frame it has, or will
+/// have, when neither minimized nor maximized, and its current show state
+pub fn get_window_placement(window: crate::Window) -> crate::Result<WindowPlacement> {
+    let hwnd = HWND::from(window);
+    let mut placement = WINDOWPLACEMENT {
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetWindowPlacement(hwnd, &mut placement)? };
+    let (dx, dy) = workspace_offset(hwnd)?;
+    let mut normal = WindowInfo::from(placement.rcNormalPosition);
+    normal.pos = (normal.pos.0 + dx, normal.pos.1 + dy);
+    let state = if unsafe { IsIconic(hwnd) }.as_bool() {
+        ShowState::Minimized
+    } else if unsafe { IsZoomed(hwnd) }.as_bool() {
+        ShowState::Maximized
+    } else {
+        ShowState::Normal
+    };
+    Ok(WindowPlacement { normal, state })
+}
+
+/// Set a window's normal frame and show state in one SetWindowPlacement call,
+/// so a minimized or maximized window gets the frame it will restore to
+pub fn set_window_placement(
+    window: crate::Window,
+    normal: WindowInfo,
+    state: ShowState,
+) -> crate::Result<()> {
+    let hwnd = HWND::from(window);
+    let (dx, dy) = workspace_offset(hwnd)?;
+    let normal = WindowInfo { pos: (normal.pos.0 - dx, normal.pos.1 - dy), ..normal };
+    let placement = WINDOWPLACEMENT {
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+        showCmd: match state {
+            ShowState::Normal => SW_SHOWNORMAL,
+            ShowState::Minimized => SW_SHOWMINIMIZED,
+            ShowState::Maximized => SW_SHOWMAXIMIZED,
+        },
+        rcNormalPosition: normal.into(),
+        ..Default::default()
+    };
+    unsafe { SetWindowPlacement(hwnd, &placement)? };
+    Ok(())
+}
+
+/// Minimized windows report the bounds they will be restored to
+pub fn get_window_info(window: crate::Window) -> crate::Result<WindowInfo> {
+    Ok(frame_rect(window.into())?.into())
+}
+
+/// GetWindowRect converted to physical pixels of the window's monitor; a no-op
+/// for per-monitor DPI aware processes, which already get physical coordinates.
+pub fn get_window_bounds(window: crate::Window) -> crate::Result<PhysicalWindowInfo> {
+    let hwnd = HWND::from(window);
+    let rect = frame_rect(hwnd)?;
+    let mut top_left = POINT { x: rect.left, y: rect.top };
+    let mut bottom_right = POINT { x: rect.right, y: rect.bottom };
+    unsafe {
+        // Fails (leaving the points untouched) when the caller is already DPI aware
+        let _ = LogicalToPhysicalPointForPerMonitorDPI(Some(hwnd), &mut top_left);
+        let _ = LogicalToPhysicalPointForPerMonitorDPI(Some(hwnd), &mut bottom_right);
+    }
+    Ok(PhysicalWindowInfo {
+        pos: (top_left.x, top_left.y),
+        size: ((bottom_right.x - top_left.x) as u32, (bottom_right.y - top_left.y) as u32),
+    })
+}
+
+/// The monitor holding most of a window, named by its GDI device name
+/// (`\\.\DISPLAY1`). The scale factor is the monitor's effective DPI over 96.
+pub fn get_window_monitor(window: crate::Window) -> crate::Result<Option<crate::MonitorInfo>> {
+    let monitor = unsafe { MonitorFromWindow(window.into(), MONITOR_DEFAULTTONULL) };
+    if monitor.is_invalid() {
+        return Ok(None);
+    }
+    monitor_info(monitor).map(Some)
+}
+
+/// The work area (rcWork: the monitor less the taskbar and app bars) of the
+/// monitor holding most of a window
+pub fn get_window_work_area(window: crate::Window) -> crate::Result<Option<WindowInfo>> {
+    let monitor = unsafe { MonitorFromWindow(window.into(), MONITOR_DEFAULTTONULL) };
+    if monitor.is_invalid() {
+        return Ok(None);
+    }
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetMonitorInfoW(monitor, &mut info) }.ok()?;
+    Ok(Some(info.rcWork.into()))
+}
+
+// Callback function for EnumDisplayMonitors that collects every monitor handle
+unsafe extern "system" fn collect_monitors_proc(
+    monitor: HMONITOR,
+    _dc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let monitors = unsafe { &mut *(lparam.0 as *mut Vec<HMONITOR>) };
+    monitors.push(monitor);
+    TRUE
+}
+
+/// Every monitor a window overlaps, the one holding most of it first. Uses the
+/// same frame as `get_window_info`, so a minimized window reports the monitors
+/// it will be restored onto.
+pub fn get_window_monitors(window: crate::Window) -> crate::Result<Vec<crate::MonitorInfo>> {
+    let frame = get_window_info(window)?;
+    let clip = RECT::from(frame);
+    let mut handles: Vec<HMONITOR> = Vec::new();
+    unsafe {
+        EnumDisplayMonitors(
+            None,
+            Some(&clip as *const RECT),
+            Some(collect_monitors_proc),
+            LPARAM(&mut handles as *mut _ as isize),
+        )
+    }
+    .ok()?;
+    let monitors = handles.into_iter().map(monitor_info).collect::<crate::Result<Vec<_>>>()?;
+    Ok(crate::geometry::monitors_of(&frame, &monitors))
+}
+
+/// Every monitor, in EnumDisplayMonitors order
+pub fn list_monitors() -> crate::Result<Vec<crate::MonitorInfo>> {
+    let mut handles: Vec<HMONITOR> = Vec::new();
+    unsafe { EnumDisplayMonitors(None, None, Some(collect_monitors_proc), LPARAM(&mut handles as *mut _ as isize)) }
+        .ok()?;
+    handles.into_iter().map(monitor_info).collect()
+}
+
+/// A monitor's name, bounds and scale factor
+fn monitor_info(monitor: HMONITOR) -> crate::Result<crate::MonitorInfo> {
+    let mut info = MONITORINFOEXW {
+        monitorInfo: MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFOEXW>() as u32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    unsafe { GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO) }.ok()?;
+    let length = info.szDevice.iter().position(|&c| c == 0).unwrap_or(info.szDevice.len());
+    let (mut dpi_x, mut dpi_y) = (0u32, 0u32);
+    let scale_factor = match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+        Ok(()) if dpi_x > 0 => dpi_x as f64 / 96.0,
+        _ => 1.0,
+    };
+    Ok(crate::MonitorInfo {
+        name: String::from_utf16_lossy(&info.szDevice[..length]),
+        bounds: info.monitorInfo.rcMonitor.into(),
+        scale_factor,
+    })
+}
+
+/// GetWindowRect has no transient failures to retry
+pub fn get_window_info_with_retry(window: crate::Window, _retries: u32) -> crate::Result<WindowInfo> {
+    get_window_info(window)
+}
+
+/// `None` while no window is in the foreground (e.g. during a focus change)
+pub fn get_active_window_pid() -> crate::Result<Option<u32>> {
+    let active_window = unsafe { GetForegroundWindow() };
+    if active_window.is_invalid() {
+        return Ok(None);
+    }
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(active_window, Some(&mut pid)) };
+    Ok(Some(pid))
+}
+
+pub fn get_window_pid(window: crate::Window) -> crate::Result<Option<u32>> {
+    let mut pid = 0;
+    // Returns 0 (and leaves pid untouched) for an invalid handle
+    if unsafe { GetWindowThreadProcessId(window.into(), Some(&mut pid)) } == 0 {
+        return Err(crate::Error::WindowGone(window));
+    }
+    Ok(Some(pid))
+}
+
+/// Hide the window (SW_HIDE), which also removes its taskbar button
+pub fn hide_window(window: crate::Window) -> crate::Result<()> {
+    let hwnd = HWND::from(window);
+    if !unsafe { IsWindow(Some(hwnd)) }.as_bool() {
+        return Err(crate::Error::WindowGone(window));
+    }
+    // The return value is the previous visibility, not success
+    let _ = unsafe { ShowWindow(hwnd, SW_HIDE) };
+    Ok(())
+}
+
+/// Show a window hidden by `hide_window` (SW_SHOWNA), without activating it
+pub fn show_window(window: crate::Window) -> crate::Result<()> {
+    let hwnd = HWND::from(window);
+    if !unsafe { IsWindow(Some(hwnd)) }.as_bool() {
+        return Err(crate::Error::WindowGone(window));
+    }
+    // The return value is the previous visibility, not success
+    let _ = unsafe { ShowWindow(hwnd, SW_SHOWNA) };
+    Ok(())
+}
+
+/// Alt-Tab leaves out the same WS_EX_TOOLWINDOW windows the taskbar does, so
+/// this is `set_skip_taskbar`: either one toggles both
+pub fn set_skip_switcher(window: crate::Window, skip: bool) -> crate::Result<()> {
+    set_skip_taskbar(window, skip)
+}
+
+/// Toggle WS_EX_TOOLWINDOW, which keeps a visible window off the taskbar and
+/// out of Alt-Tab. WS_EX_APPWINDOW would force the button back, so skipping
+/// clears it.
+pub fn set_skip_taskbar(window: crate::Window, skip: bool) -> crate::Result<()> {
+    let hwnd = HWND::from(window);
+    if !unsafe { IsWindow(Some(hwnd)) }.as_bool() {
+        return Err(crate::Error::WindowGone(window));
+    }
+    let ex_style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+    let ex_style = if skip {
+        (ex_style | WS_EX_TOOLWINDOW.0) & !WS_EX_APPWINDOW.0
+    } else {
+        ex_style & !WS_EX_TOOLWINDOW.0
+    };
+    unsafe {
+        SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style as i32);
+        // Apply the style change to the frame without moving or activating
+        let flags = SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE | SWP_FRAMECHANGED;
+        SetWindowPos(hwnd, None, 0, 0, 0, 0, flags)?;
+    }
+    Ok(())
+}
+
+/// Toggle WS_EX_TRANSPARENT, which only passes clicks through layered windows.
+/// A window that isn't layered yet is made layered and fully opaque; it stays
+/// layered when click-through is turned off, as the app may rely on it.
+pub fn set_click_through(window: crate::Window, click_through: bool) -> crate::Result<()> {
+    let hwnd = HWND::from(window);
+    if !unsafe { IsWindow(Some(hwnd)) }.as_bool() {
+        return Err(crate::Error::WindowGone(window));
+    }
+    let ex_style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+    unsafe {
+        if click_through {
+            SetWindowLongW(hwnd, GWL_EXSTYLE, (ex_style | WS_EX_LAYERED.0 | WS_EX_TRANSPARENT.0) as i32);
+            // A newly layered window isn't drawn until its attributes are set
+            if ex_style & WS_EX_LAYERED.0 == 0 {
+                SetLayeredWindowAttributes(hwnd, COLORREF(0), 255, LWA_ALPHA)?;
+            }
+        } else {
+            SetWindowLongW(hwnd, GWL_EXSTYLE, (ex_style & !WS_EX_TRANSPARENT.0) as i32);
+        }
+    }
+    Ok(())
+}
+
+// Callback function for EnumWindows that collects every top-level window
+unsafe extern "system" fn collect_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let windows = unsafe { &mut *(lparam.0 as *mut Vec<HWND>) };
+    windows.push(hwnd);
+    TRUE
+}
+
+/// The shell's desktop: Progman (the shell window) and the WorkerW windows
+/// that draw the wallpaper behind the icons
+fn is_desktop_window(hwnd: HWND) -> bool {
+    if hwnd == unsafe { GetShellWindow() } {
+        return true;
+    }
+    let mut buffer = [0u16; 16];
+    let copied = unsafe { GetClassNameW(hwnd, &mut buffer) } as usize;
+    matches!(String::from_utf16_lossy(&buffer[..copied]).as_str(), "Progman" | "WorkerW")
+}
+
+/// Every top-level window but the desktop ones
+pub fn list_windows() -> crate::Result<Vec<crate::Window>> {
+    let mut windows: Vec<HWND> = Vec::new();
+    unsafe {
+        EnumWindows(
+            Some(collect_windows_proc),
+            LPARAM(&mut windows as *mut _ as isize),
+        )?;
+    }
+    Ok(windows
+        .into_iter()
+        .filter(|&hwnd| {
+            let desktop = is_desktop_window(hwnd);
+            if desktop {
+                trace_debug!(window = %crate::Window::from(hwnd), "skipped: the shell's desktop (Progman or WorkerW)");
+            }
+            !desktop
+        })
+        .map(crate::Window::from)
+        .collect())
+}
+
+/// The desktop window (GetDesktopWindow), then every top-level window
+/// including the shell's desktop ones
+pub fn list_windows_including_desktop() -> crate::Result<Vec<crate::Window>> {
+    let mut windows: Vec<HWND> = vec![unsafe { GetDesktopWindow() }];
+    unsafe {
+        EnumWindows(
+            Some(collect_windows_proc),
+            LPARAM(&mut windows as *mut _ as isize),
+        )?;
+    }
+    Ok(windows.into_iter().map(crate::Window::from).collect())
+}
+
+unsafe extern "system" fn count_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let count = unsafe { &mut *(lparam.0 as *mut usize) };
+    if !is_desktop_window(hwnd) {
+        *count += 1;
+    }
+    TRUE
+}
+
+/// Every descendant of `parent` (the controls of a dialog, say), in the order
+/// EnumChildWindows reports them
+pub fn enumerate_child_windows(parent: crate::Window) -> crate::Result<Vec<crate::Window>> {
+    if !unsafe { IsWindow(Some(parent.into())) }.as_bool() {
+        return Err(crate::Error::WindowGone(parent));
+    }
+    let mut windows: Vec<HWND> = Vec::new();
+    // The return value of EnumChildWindows is not used
+    let _ = unsafe {
+        EnumChildWindows(
+            Some(parent.into()),
+            Some(collect_windows_proc),
+            LPARAM(&mut windows as *mut _ as isize),
+        )
+    };
+    Ok(windows.into_iter().map(crate::Window::from).collect())
+}
+
+/// Whether the handle names a window (IsWindow), listed or not. Handles are
+/// reused once windows are destroyed, so a live handle may be a newer window.
+pub fn window_exists(window: crate::Window) -> crate::Result<bool> {
+    Ok(unsafe { IsWindow(Some(window.into())) }.as_bool())
+}
+
+/// Number of windows `list_windows` would return, counted without collecting them
+pub fn window_count() -> crate::Result<usize> {
+    let mut count = 0usize;
+    unsafe { EnumWindows(Some(count_windows_proc), LPARAM(&mut count as *mut _ as isize))? };
+    Ok(count)
+}
+
+pub fn get_active_window() -> crate::Result<Option<crate::Window>> {
+    let active_window = unsafe { GetForegroundWindow() };
+    if active_window.is_invalid() {
+        return Ok(None);
+    }
+    Ok(Some(active_window.into()))
+}
+
+pub fn get_window_title(window: crate::Window) -> crate::Result<Option<String>> {
+    let window = HWND::from(window);
+    let length = unsafe { GetWindowTextLengthW(window) };
+    if length <= 0 {
+        return Ok(None);
+    }
+    let mut buffer = vec![0u16; length as usize + 1];
+    let copied = unsafe { GetWindowTextW(window, &mut buffer) };
+    Ok(Some(String::from_utf16_lossy(&buffer[..copied as usize])))
+}
+
+pub fn get_window_app_id(_window: crate::Window) -> crate::Result<Option<String>> {
+    Err(Unsupported::new("get_window_app_id", WIN32).into())
+}
+
+pub fn find_windows_by_app_id(_app_id: &str) -> crate::Result<Vec<crate::Window>> {
+    Err(Unsupported::new("find_windows_by_app_id", WIN32).into())
+}
+
+/// The window class name of the process's main window. Win32 classes have no
+/// instance part, so the class name is returned for both halves of the pair.
+pub fn get_class_for_pid(process_id: u32) -> crate::Result<Option<(String, String)>> {
+    let Some(window) = find_window_by_pid(process_id)? else {
+        return Ok(None);
+    };
+    let class = get_window_class(window)?;
+    Ok(Some((class.clone(), class)))
+}
+
+/// The window's class name, e.g. `Button` or `Edit` for standard controls
+pub fn get_window_class(window: crate::Window) -> crate::Result<String> {
+    // Class names are limited to 256 characters
+    let mut buffer = [0u16; 256];
+    let copied = unsafe { GetClassNameW(window.into(), &mut buffer) };
+    if copied == 0 {
+        return Err(format!("Failed to read the class name of window {window}").into());
+    }
+    Ok(String::from_utf16_lossy(&buffer[..copied as usize]))
+}
+
+pub fn minimize_window(window: crate::Window) -> crate::Result<()> {
+    // ShowWindow returns the previous visibility, not success
+    let _ = unsafe { ShowWindow(window.into(), SW_MINIMIZE) };
+    Ok(())
+}
+
+pub fn maximize_window(window: crate::Window) -> crate::Result<()> {
+    let _ = unsafe { ShowWindow(window.into(), SW_MAXIMIZE) };
+    Ok(())
+}
+
+/// Maximize a window, or restore it if it is already maximized
+pub fn toggle_maximize(window: crate::Window) -> crate::Result<()> {
+    let hwnd = HWND::from(window);
+    let command = if unsafe { IsZoomed(hwnd) }.as_bool() { SW_RESTORE } else { SW_MAXIMIZE };
+    let _ = unsafe { ShowWindow(hwnd, command) };
+    Ok(())
+}
+
+pub fn close_window(window: crate::Window) -> crate::Result<()> {
+    unsafe { PostMessageW(Some(window.into()), WM_CLOSE, WPARAM(0), LPARAM(0)) }.context(
+        "close_window",
+        Some(window),
+        "PostMessageW(WM_CLOSE)",
+    )?;
+    Ok(())
+}
+
+/// Ask the window for its icon, falling back to the window class icon
+fn find_window_icon(window: HWND) -> Option<HICON> {
+    for kind in [ICON_BIG, ICON_SMALL2, ICON_SMALL] {
+        let icon = unsafe {
+            SendMessageW(window, WM_GETICON, Some(WPARAM(kind as usize)), Some(LPARAM(0)))
+        };
+        if icon.0 != 0 {
+            return Some(HICON(icon.0 as *mut _));
+        }
+    }
+    for index in [GCLP_HICON, GCLP_HICONSM] {
+        #[cfg(target_pointer_width = "64")]
+        let icon = unsafe { GetClassLongPtrW(window, index) };
+        #[cfg(target_pointer_width = "32")]
+        let icon = unsafe { GetClassLongW(window, index) } as usize;
+        if icon != 0 {
+            return Some(HICON(icon as *mut _));
+        }
+    }
+    None
+}
+
+/// Read an HICON's color bitmap as RGBA, deriving alpha from the mask for legacy icons
+fn icon_to_rgba(icon: HICON) -> crate::Result<IconData> {
+    let mut info = ICONINFO::default();
+    unsafe { GetIconInfo(icon, &mut info)? };
+
+    let result = (|| {
+        let mut bitmap = BITMAP::default();
+        let color = if info.hbmColor.is_invalid() { info.hbmMask } else { info.hbmColor };
+        if unsafe {
+            GetObjectW(
+                color.into(),
+                std::mem::size_of::<BITMAP>() as i32,
+                Some(&mut bitmap as *mut _ as *mut _),
+            )
+        } == 0
+        {
+            return Err("Failed to read icon bitmap".into());
+        }
+        let width = bitmap.bmWidth as u32;
+        // Monochrome icons stack the AND and XOR masks vertically
+        let height = if info.hbmColor.is_invalid() { bitmap.bmHeight / 2 } else { bitmap.bmHeight } as u32;
+
+        let read_bits = |bitmap: HBITMAP, rows: u32| -> crate::Result<Vec<u8>> {
+            let mut header = BITMAPINFO::default();
+            header.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+            header.bmiHeader.biWidth = width as i32;
+            header.bmiHeader.biHeight = -(rows as i32); // top-down
+            header.bmiHeader.biPlanes = 1;
+            header.bmiHeader.biBitCount = 32;
+            header.bmiHeader.biCompression = BI_RGB.0;
+            let mut pixels = vec![0u8; (width * rows * 4) as usize];
+            let dc = unsafe { GetDC(None) };
+            let lines = unsafe {
+                GetDIBits(
+                    dc,
+                    bitmap,
+                    0,
+                    rows,
+                    Some(pixels.as_mut_ptr() as *mut _),
+                    &mut header,
+                    DIB_RGB_COLORS,
+                )
+            };
+            unsafe { ReleaseDC(None, dc) };
+            if lines == 0 {
+                return Err("GetDIBits failed for icon bitmap".into());
+            }
+            Ok(pixels)
+        };
+
+        let mut pixels = read_bits(color, height)?;
+        // BGRA -> RGBA
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        if pixels.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+            // No alpha channel: opaque wherever the AND mask is clear
+            let mask = read_bits(info.hbmMask, height)?;
+            for (pixel, mask) in pixels.chunks_exact_mut(4).zip(mask.chunks_exact(4)) {
+                pixel[3] = if mask[0] == 0 { 255 } else { 0 };
+            }
+        }
+
+        Ok(IconData { width, height, rgba: pixels })
+    })();
+
+    unsafe {
+        if !info.hbmColor.is_invalid() {
+            let _ = DeleteObject(info.hbmColor.into());
+        }
+        let _ = DeleteObject(info.hbmMask.into());
+    }
+    result
+}
+
+pub fn get_window_icon(window: crate::Window) -> crate::Result<Option<IconData>> {
+    match find_window_icon(window.into()) {
+        Some(icon) => Ok(Some(icon_to_rgba(icon)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn move_window(window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+    unsafe { SetWindowPos(window.into(), None, x, y, 0, 0, SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE) }
+        .context("move_window", Some(window), "SetWindowPos")
+}
+
+pub fn resize_window(window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+    unsafe {
+        SetWindowPos(window.into(), None, 0, 0, width as i32, height as i32, SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE)
+    }
+    .context("resize_window", Some(window), "SetWindowPos")
+}
+
+pub fn set_window_rect(
+    window: crate::Window,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> crate::Result<()> {
+    unsafe { SetWindowPos(window.into(), None, x, y, width as i32, height as i32, SWP_NOZORDER | SWP_NOACTIVATE) }
+        .context("set_window_rect", Some(window), "SetWindowPos")
+}
+
+/// SetForegroundWindow fails when the foreground lock rules deny the caller
+pub fn focus_window(window: crate::Window) -> crate::Result<()> {
+    if !unsafe { SetForegroundWindow(window.into()) }.as_bool() {
+        return Err(FocusRefused::new(window).into());
+    }
+    Ok(())
+}
+
+/// Disabled windows and WS_EX_NOACTIVATE windows (e.g. on-screen keyboards) don't take focus
+pub fn can_focus(window: crate::Window) -> crate::Result<bool> {
+    let hwnd: HWND = window.into();
+    let ex_style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+    Ok(unsafe { IsWindowEnabled(hwnd) }.as_bool() && ex_style & WS_EX_NOACTIVATE.0 == 0)
+}
+
+/// Windows has no user-time hint; activation is the same as focus_window
+pub fn activate_window(window: crate::Window, _update_user_time: bool) -> crate::Result<()> {
+    focus_window(window)
+}
+
+pub fn set_user_time(_window: crate::Window, _timestamp: u32) -> crate::Result<()> {
+    Err(Unsupported::new("set_user_time", WIN32).into())
+}
+
+// Virtual desktops are only exposed through the undocumented IVirtualDesktopManagerInternal
+pub fn get_window_workspace(_window: crate::Window) -> crate::Result<Option<u32>> {
+    Err(Unsupported::new("get_window_workspace", WIN32).into())
+}
+
+pub fn move_window_to_workspace(_window: crate::Window, _workspace: u32) -> crate::Result<()> {
+    Err(Unsupported::new("move_window_to_workspace", WIN32).into())
+}
+
+pub fn get_current_workspace() -> crate::Result<Option<u32>> {
+    Err(Unsupported::new("get_current_workspace", WIN32).into())
+}
+
+pub fn switch_workspace(_workspace: u32) -> crate::Result<()> {
+    Err(Unsupported::new("switch_workspace", WIN32).into())
+}
+
+thread_local! {
+    /// Where the WinEvent hook of the current thread delivers events.
+    static EVENT_SENDER: std::cell::RefCell<Option<mpsc::Sender<WindowEvent>>> = const { std::cell::RefCell::new(None) };
+}
+
+unsafe extern "system" fn win_event_proc(
+    _hook: HWINEVENTHOOK,
+    event: u32,
+    hwnd: HWND,
+    id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    if event != EVENT_SYSTEM_FOREGROUND || id_object != OBJID_WINDOW.0 {
+        return;
+    }
+    let focused = (!hwnd.is_invalid()).then(|| crate::Window::from(hwnd));
+    let delivered = EVENT_SENDER.with_borrow(|sender| {
+        sender.as_ref().is_some_and(|sender| sender.send(WindowEvent::Focused(focused)).is_ok())
+    });
+    if !delivered {
+        // The subscriber is gone; end the message loop
+        unsafe { PostQuitMessage(0) };
+    }
+}
+
+/// Foreground changes from an out-of-context WinEvent hook (EVENT_SYSTEM_FOREGROUND),
+/// serviced by a dedicated thread's message loop. Only `Focused` events are reported.
+pub fn subscribe_window_events() -> crate::Result<WindowEvents> {
+    let (sender, receiver) = mpsc::channel();
+    let (ready_sender, ready) = mpsc::channel::<Result<(), String>>();
+    std::thread::spawn(move || {
+        EVENT_SENDER.with_borrow_mut(|slot| *slot = Some(sender));
+        let hook = unsafe {
+            SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                None,
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            )
+        };
+        if hook.is_invalid() {
+            let _ = ready_sender.send(Err("SetWinEventHook failed".to_string()));
+            return;
+        }
+        let _ = ready_sender.send(Ok(()));
+        let mut message = MSG::default();
+        while unsafe { GetMessageW(&mut message, None, 0, 0) }.as_bool() {
+            unsafe { DispatchMessageW(&message) };
+        }
+        let _ = unsafe { UnhookWinEvent(hook) };
+    });
+    ready.recv().map_err(|_| "The event thread exited")??;
+    Ok(WindowEvents::new(receiver.into_iter().map(Ok)))
+}
+
+impl From<windows::core::Error> for crate::Error {
+    fn from(error: windows::core::Error) -> Self {
+        crate::Error::Platform(Box::new(error))
+    }
+}
+
+/// Whether a process with this pid is running. One that has exited but whose
+/// process object is still held open by someone counts as gone.
+pub fn process_exists(pid: u32) -> crate::Result<bool> {
+    if pid == 0 {
+        return Ok(false);
+    }
+    let process = match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+        Ok(process) => process,
+        // Protected and other users' processes exist but can't be opened
+        Err(e) if e.code() == ERROR_ACCESS_DENIED.to_hresult() => return Ok(true),
+        Err(e) if e.code() == ERROR_INVALID_PARAMETER.to_hresult() => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    let mut exit_code = 0u32;
+    let queried = unsafe { GetExitCodeProcess(process, &mut exit_code) };
+    let _ = unsafe { CloseHandle(process) };
+    queried?;
+    Ok(exit_code == STILL_ACTIVE.0 as u32)
+}
+
+/// The executable file name of a running process, e.g. `notepad.exe`.
+/// `None` if the process isn't running or can't be opened (protected and
+/// other users' processes).
+pub fn get_process_name(pid: u32) -> crate::Result<Option<String>> {
+    if pid == 0 {
+        return Ok(None);
+    }
+    let process = match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+        Ok(process) => process,
+        Err(e) if e.code() == ERROR_ACCESS_DENIED.to_hresult() || e.code() == ERROR_INVALID_PARAMETER.to_hresult() => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let mut buffer = [0u16; 1024];
+    let mut length = buffer.len() as u32;
+    let queried = unsafe {
+        QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, windows::core::PWSTR(buffer.as_mut_ptr()), &mut length)
+    };
+    let _ = unsafe { CloseHandle(process) };
+    queried?;
+    let path = String::from_utf16_lossy(&buffer[..length as usize]);
+    Ok(Some(path.rsplit('\\').next().unwrap_or(&path).to_owned()))
+}
+
+/// Windows always uses the win32 backend
+pub fn detect_backend() -> BackendKind {
+    BackendKind::Win32
+}
+
+/// What the win32 backend can do: everything but workspaces. Desktop Window
+/// Manager composition can't be turned off since Windows 8.
+pub fn capabilities() -> crate::Capabilities {
+    crate::Capabilities {
+        backend: BackendKind::Win32,
+        is_wayland: false,
+        has_compositor: true,
+        supports_ewmh_state: false,
+        can_move_windows: true,
+        can_move_to_desktop: false,
+        can_set_always_on_top: true,
+        can_watch_events: true,
+        can_set_opacity: false,
+        supports_input_simulation: false,
+    }
+}
+
+/// Always true: the window manager is part of Windows, not a server that may be absent
+pub fn is_display_available() -> bool {
+    true
+}
+
+/// The desktop window; its rectangle covers the primary monitor only (see get_virtual_screen)
+pub fn get_root_window() -> crate::Result<crate::Window> {
+    Ok(unsafe { GetDesktopWindow() }.into())
+}
+
+/// GetCursorPos, then the top-level ancestor (GA_ROOT) of the window
+/// WindowFromPoint finds there. Over the desktop this is the shell's desktop
+/// window, which `list_windows` leaves out.
+pub fn pointer_state() -> crate::Result<(i32, i32, Option<crate::Window>)> {
+    let mut point = POINT::default();
+    unsafe { GetCursorPos(&mut point) }.context("pointer_state", None, "GetCursorPos")?;
+    let hit = unsafe { WindowFromPoint(point) };
+    let window = (!hit.is_invalid()).then(|| unsafe { GetAncestor(hit, GA_ROOT) }).filter(|hwnd| !hwnd.is_invalid());
+    Ok((point.x, point.y, window.map(crate::Window::from)))
+}
+
+pub fn get_virtual_screen() -> crate::Result<WindowInfo> {
+    let (x, y, width, height) = unsafe {
+        (
+            GetSystemMetrics(SM_XVIRTUALSCREEN),
+            GetSystemMetrics(SM_YVIRTUALSCREEN),
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        )
+    };
+    if width == 0 || height == 0 {
+        return Err("GetSystemMetrics failed to report the virtual screen".into());
+    }
+    Ok(WindowInfo {
+        pos: (x, y),
+        size: (width as u32, height as u32),
+    })
+}
+
+pub fn window_has_state(window: crate::Window, state: WindowState) -> crate::Result<bool> {
+    let window = HWND::from(window);
+    unsafe {
+        if !IsWindow(Some(window)).as_bool() {
+            return Ok(state == WindowState::Closed);
+        }
+        Ok(match state {
+            WindowState::Closed => false,
+            WindowState::Visible => IsWindowVisible(window).as_bool() && !IsIconic(window).as_bool(),
+            WindowState::Minimized => IsIconic(window).as_bool(),
+            WindowState::Maximized => IsZoomed(window).as_bool(),
+            WindowState::Fullscreen => {
+                // Borderless fullscreen: the window covers its whole monitor
+                let mut rect = RECT::default();
+                GetWindowRect(window, &mut rect)?;
+                let monitor = MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST);
+                let mut info = MONITORINFO {
+                    cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                    ..Default::default()
+                };
+                GetMonitorInfoW(monitor, &mut info).ok()?;
+                !IsIconic(window).as_bool()
+                    && rect.left <= info.rcMonitor.left
+                    && rect.top <= info.rcMonitor.top
+                    && rect.right >= info.rcMonitor.right
+                    && rect.bottom >= info.rcMonitor.bottom
+            }
+            WindowState::Focused => GetForegroundWindow() == window,
+        })
+    }
+}
+
+/// Win32 has no EWMH state list; report the equivalents of minimized, maximized, fullscreen and focused
+pub fn get_window_states(window: crate::Window) -> crate::Result<Vec<WindowStateFlag>> {
+    let mut states = Vec::new();
+    if window_has_state(window, WindowState::Minimized)? {
+        states.push(WindowStateFlag::Hidden);
+    }
+    if window_has_state(window, WindowState::Maximized)? {
+        states.extend([WindowStateFlag::MaximizedVert, WindowStateFlag::MaximizedHorz]);
+    }
+    if window_has_state(window, WindowState::Fullscreen)? {
+        states.push(WindowStateFlag::Fullscreen);
+    }
+    if window_has_state(window, WindowState::Focused)? {
+        states.push(WindowStateFlag::Focused);
+    }
+    Ok(states)
+}
+
+pub fn set_window_state(_window: crate::Window, _flag: WindowStateFlag, _enabled: bool) -> crate::Result<()> {
+    Err(Unsupported::new("set_window_state", WIN32).into())
+}
+
+// Windows has no per-window "all desktops" flag outside the undocumented
+// virtual desktop COM interfaces, so stickiness is unsupported
+pub fn is_sticky(_window: crate::Window) -> crate::Result<bool> {
+    Err(Unsupported::new("is_sticky", WIN32).into())
+}
+
+pub fn set_sticky(_window: crate::Window, _sticky: bool) -> crate::Result<()> {
+    Err(Unsupported::new("set_sticky", WIN32).into())
+}
+
+pub fn is_always_on_top(window: crate::Window) -> crate::Result<bool> {
+    let ex_style = unsafe { GetWindowLongW(window.into(), GWL_EXSTYLE) } as u32;
+    Ok(ex_style & WS_EX_TOPMOST.0 != 0)
+}
+
+pub fn set_always_on_top(window: crate::Window, on_top: bool) -> crate::Result<()> {
+    let insert_after = if on_top { HWND_TOPMOST } else { HWND_NOTOPMOST };
+    unsafe { SetWindowPos(window.into(), Some(insert_after), 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE) }
+        .context("set_always_on_top", Some(window), "SetWindowPos")
+}
+
+/// Windows kept at the bottom by `set_always_below`. Windows has no lasting
+/// "always below" (HWND_BOTTOM only lasts until the window is next raised),
+/// so the keeper thread puts these back whenever the foreground changes.
+static KEPT_BELOW: std::sync::Mutex<Vec<crate::Window>> = std::sync::Mutex::new(Vec::new());
+
+fn send_to_bottom(window: crate::Window) -> windows::core::Result<()> {
+    unsafe { SetWindowPos(window.into(), Some(HWND_BOTTOM), 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE) }
+}
+
+unsafe extern "system" fn keep_below_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    _hwnd: HWND,
+    _id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    let mut kept = KEPT_BELOW.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    kept.retain(|&window| unsafe { IsWindow(Some(window.into())) }.as_bool());
+    for &window in kept.iter() {
+        let _ = send_to_bottom(window);
+    }
+}
+
+/// Start the thread whose WinEvent hook re-applies HWND_BOTTOM, once per process
+fn start_keeping_below() -> crate::Result<()> {
+    static STARTED: std::sync::OnceLock<Result<(), String>> = std::sync::OnceLock::new();
+    STARTED
+        .get_or_init(|| {
+            let (ready_sender, ready) = mpsc::channel::<Result<(), String>>();
+            std::thread::spawn(move || {
+                let hook = unsafe {
+                    SetWinEventHook(
+                        EVENT_SYSTEM_FOREGROUND,
+                        EVENT_SYSTEM_FOREGROUND,
+                        None,
+                        Some(keep_below_proc),
+                        0,
+                        0,
+                        WINEVENT_OUTOFCONTEXT,
+                    )
+                };
+                if hook.is_invalid() {
+                    let _ = ready_sender.send(Err("SetWinEventHook failed".to_string()));
+                    return;
+                }
+                let _ = ready_sender.send(Ok(()));
+                let mut message = MSG::default();
+                while unsafe { GetMessageW(&mut message, None, 0, 0) }.as_bool() {
+                    unsafe { DispatchMessageW(&message) };
+                }
+            });
+            ready.recv().unwrap_or_else(|_| Err("The keep-below thread exited".to_string()))
+        })
+        .clone()
+        .map_err(Into::into)
+}
+
+/// Whether `set_always_below` is keeping this window at the bottom
+pub fn is_always_below(window: crate::Window) -> crate::Result<bool> {
+    if !unsafe { IsWindow(Some(window.into())) }.as_bool() {
+        return Err(crate::Error::WindowGone(window));
+    }
+    Ok(KEPT_BELOW.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains(&window))
+}
+
+/// Move the window to the bottom of the z-order (dropping always-on-top) and
+/// keep it there: the position is re-applied every time another window is
+/// activated, until `set_always_below(window, false)` or the window closes.
+/// Turning it off leaves the window where it is.
+pub fn set_always_below(window: crate::Window, below: bool) -> crate::Result<()> {
+    if below {
+        send_to_bottom(window).context("set_always_below", Some(window), "SetWindowPos")?;
+        start_keeping_below()?;
+    }
+    let mut kept = KEPT_BELOW.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    kept.retain(|&kept| kept != window);
+    if below {
+        kept.push(window);
+    }
+    Ok(())
+}
+
+/// The platform's window system. There is no connection to hold here, so
+/// every instance is equivalent to the shared one behind the free functions.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct WindowSystem;
+
+impl WindowSystem {
+    pub fn new() -> crate::Result<Self> {
+        Ok(WindowSystem)
+    }
+
+    /// Calls here never wait on a server's reply, so there is nothing to time
+    /// out; accepted so the same setup code builds on every platform.
+    pub fn with_request_timeout(self, _timeout: std::time::Duration) -> Self {
+        self
+    }
+}
+
+impl crate::WindowingBackend for WindowSystem {
+    fn kind(&self) -> BackendKind {
+        detect_backend()
+    }
+
+    fn list_windows(&self) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("list_windows", backend = self.kind().name());
+        list_windows()
+    }
+
+    fn list_windows_including_desktop(&self) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("list_windows_including_desktop", backend = self.kind().name());
+        list_windows_including_desktop()
+    }
+
+    fn window_count(&self) -> crate::Result<usize> {
+        trace_span!("window_count", backend = self.kind().name());
+        window_count()
+    }
+
+    fn window_exists(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("window_exists", backend = self.kind().name(), %window);
+        window_exists(window)
+    }
+
+    fn get_window_info(&self, window: crate::Window) -> crate::Result<WindowInfo> {
+        trace_span!("get_window_info", backend = self.kind().name(), %window);
+        get_window_info(window)
+    }
+
+    fn get_window_info_with_retry(&self, window: crate::Window, retries: u32) -> crate::Result<WindowInfo> {
+        trace_span!("get_window_info_with_retry", backend = self.kind().name(), %window);
+        get_window_info_with_retry(window, retries)
+    }
+
+    fn get_window_bounds(&self, window: crate::Window) -> crate::Result<PhysicalWindowInfo> {
+        trace_span!("get_window_bounds", backend = self.kind().name(), %window);
+        get_window_bounds(window)
+    }
+
+    fn get_window_monitor(
+        &self,
+        window: crate::Window,
+    ) -> crate::Result<Option<crate::MonitorInfo>> {
+        trace_span!("get_window_monitor", backend = self.kind().name(), %window);
+        get_window_monitor(window)
+    }
+
+    fn get_window_monitors(&self, window: crate::Window) -> crate::Result<Vec<crate::MonitorInfo>> {
+        trace_span!("get_window_monitors", backend = self.kind().name(), %window);
+        get_window_monitors(window)
+    }
+
+    fn list_monitors(&self) -> crate::Result<Vec<crate::MonitorInfo>> {
+        trace_span!("list_monitors", backend = self.kind().name());
+        list_monitors()
+    }
+
+    fn get_window_work_area(&self, window: crate::Window) -> crate::Result<Option<WindowInfo>> {
+        trace_span!("get_window_work_area", backend = self.kind().name(), %window);
+        get_window_work_area(window)
+    }
+
+    fn find_window_by_pid(&self, target_pid: u32) -> crate::Result<Option<crate::Window>> {
+        trace_span!("find_window_by_pid", backend = self.kind().name(), pid = target_pid);
+        find_window_by_pid(target_pid)
+    }
+
+    fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("find_windows_by_pid", backend = self.kind().name(), pid = target_pid);
+        find_windows_by_pid(target_pid)
+    }
+
+    fn pid_has_window(&self, target_pid: u32) -> crate::Result<bool> {
+        trace_span!("pid_has_window", backend = self.kind().name(), pid = target_pid);
+        pid_has_window(target_pid)
+    }
+
+    fn find_visible_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("find_visible_windows_by_pid", backend = self.kind().name(), pid = target_pid);
+        find_visible_windows_by_pid(target_pid)
+    }
+
+    fn find_windows_by_app_id(&self, app_id: &str) -> crate::Result<Vec<crate::Window>> {
+        trace_span!("find_windows_by_app_id", backend = self.kind().name(), app_id);
+        find_windows_by_app_id(app_id)
+    }
+
+    fn get_class_for_pid(&self, target_pid: u32) -> crate::Result<Option<(String, String)>> {
+        trace_span!("get_class_for_pid", backend = self.kind().name(), pid = target_pid);
+        get_class_for_pid(target_pid)
+    }
+
+    fn is_main_window(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_main_window", backend = self.kind().name(), %window);
+        is_main_window(window)
+    }
+
+    fn get_active_window(&self) -> crate::Result<Option<crate::Window>> {
+        trace_span!("get_active_window", backend = self.kind().name());
+        get_active_window()
+    }
+
+    fn get_active_window_pid(&self) -> crate::Result<Option<u32>> {
+        trace_span!("get_active_window_pid", backend = self.kind().name());
+        get_active_window_pid()
+    }
+
+    fn get_window_pid(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        trace_span!("get_window_pid", backend = self.kind().name(), %window);
+        get_window_pid(window)
+    }
+
+    fn get_window_title(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        trace_span!("get_window_title", backend = self.kind().name(), %window);
+        get_window_title(window)
+    }
+
+    fn get_window_app_id(&self, window: crate::Window) -> crate::Result<Option<String>> {
+        trace_span!("get_window_app_id", backend = self.kind().name(), %window);
+        get_window_app_id(window)
+    }
+
+    fn get_window_icon(&self, window: crate::Window) -> crate::Result<Option<IconData>> {
+        trace_span!("get_window_icon", backend = self.kind().name(), %window);
+        get_window_icon(window)
+    }
+
+    fn hide_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("hide_window", backend = self.kind().name(), %window);
+        hide_window(window)
+    }
+
+    fn show_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("show_window", backend = self.kind().name(), %window);
+        show_window(window)
+    }
+
+    fn set_skip_switcher(&self, window: crate::Window, skip: bool) -> crate::Result<()> {
+        trace_span!("set_skip_switcher", backend = self.kind().name(), %window);
+        set_skip_switcher(window, skip)
+    }
+
+    fn set_skip_taskbar(&self, window: crate::Window, skip: bool) -> crate::Result<()> {
+        trace_span!("set_skip_taskbar", backend = self.kind().name(), %window);
+        set_skip_taskbar(window, skip)
+    }
+
+    fn minimize_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("minimize_window", backend = self.kind().name(), %window);
+        minimize_window(window)
+    }
+
+    fn maximize_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("maximize_window", backend = self.kind().name(), %window);
+        maximize_window(window)
+    }
+
+    fn toggle_maximize(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("toggle_maximize", backend = self.kind().name(), %window);
+        toggle_maximize(window)
+    }
+
+    fn close_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("close_window", backend = self.kind().name(), %window);
+        close_window(window)
+    }
+
+    fn move_window(&self, window: crate::Window, x: i32, y: i32) -> crate::Result<()> {
+        trace_span!("move_window", backend = self.kind().name(), %window);
+        move_window(window, x, y)
+    }
+
+    fn resize_window(&self, window: crate::Window, width: u32, height: u32) -> crate::Result<()> {
+        trace_span!("resize_window", backend = self.kind().name(), %window);
+        resize_window(window, width, height)
+    }
+
+    fn set_window_rect(&self, window: crate::Window, x: i32, y: i32, width: u32, height: u32) -> crate::Result<()> {
+        trace_span!("set_window_rect", backend = self.kind().name(), %window);
+        set_window_rect(window, x, y, width, height)
+    }
+
+    fn focus_window(&self, window: crate::Window) -> crate::Result<()> {
+        trace_span!("focus_window", backend = self.kind().name(), %window);
+        focus_window(window)
+    }
+
+    fn can_focus(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("can_focus", backend = self.kind().name(), %window);
+        can_focus(window)
+    }
+
+    fn activate_window(&self, window: crate::Window, update_user_time: bool) -> crate::Result<()> {
+        trace_span!("activate_window", backend = self.kind().name(), %window);
+        activate_window(window, update_user_time)
+    }
+
+    fn set_user_time(&self, window: crate::Window, timestamp: u32) -> crate::Result<()> {
+        trace_span!("set_user_time", backend = self.kind().name(), %window);
+        set_user_time(window, timestamp)
+    }
+
+    fn get_window_workspace(&self, window: crate::Window) -> crate::Result<Option<u32>> {
+        trace_span!("get_window_workspace", backend = self.kind().name(), %window);
+        get_window_workspace(window)
+    }
+
+    fn move_window_to_workspace(&self, window: crate::Window, workspace: u32) -> crate::Result<()> {
+        trace_span!("move_window_to_workspace", backend = self.kind().name(), %window, workspace);
+        move_window_to_workspace(window, workspace)
+    }
+
+    fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+        trace_span!("get_current_workspace", backend = self.kind().name());
+        get_current_workspace()
+    }
+
+    fn switch_workspace(&self, workspace: u32) -> crate::Result<()> {
+        trace_span!("switch_workspace", backend = self.kind().name(), workspace);
+        switch_workspace(workspace)
+    }
+
+    fn subscribe_window_events(&self) -> crate::Result<WindowEvents> {
+        trace_span!("subscribe_window_events", backend = self.kind().name());
+        subscribe_window_events()
+    }
+
+    fn get_virtual_screen(&self) -> crate::Result<WindowInfo> {
+        trace_span!("get_virtual_screen", backend = self.kind().name());
+        get_virtual_screen()
+    }
+
+    fn get_root_window(&self) -> crate::Result<crate::Window> {
+        trace_span!("get_root_window", backend = self.kind().name());
+        get_root_window()
+    }
+
+    fn pointer_state(&self) -> crate::Result<(i32, i32, Option<crate::Window>)> {
+        trace_span!("pointer_state", backend = self.kind().name());
+        pointer_state()
+    }
+
+    fn window_has_state(&self, window: crate::Window, state: WindowState) -> crate::Result<bool> {
+        trace_span!("window_has_state", backend = self.kind().name(), %window);
+        window_has_state(window, state)
+    }
+
+    fn get_window_states(&self, window: crate::Window) -> crate::Result<Vec<WindowStateFlag>> {
+        trace_span!("get_window_states", backend = self.kind().name(), %window);
+        get_window_states(window)
+    }
+
+    fn set_window_state(&self, window: crate::Window, flag: WindowStateFlag, enabled: bool) -> crate::Result<()> {
+        trace_span!("set_window_state", backend = self.kind().name(), %window);
+        set_window_state(window, flag, enabled)
+    }
+
+    fn is_sticky(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_sticky", backend = self.kind().name(), %window);
+        is_sticky(window)
+    }
+
+    fn set_sticky(&self, window: crate::Window, sticky: bool) -> crate::Result<()> {
+        trace_span!("set_sticky", backend = self.kind().name(), %window);
+        set_sticky(window, sticky)
+    }
+
+    fn is_always_on_top(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_always_on_top", backend = self.kind().name(), %window);
+        is_always_on_top(window)
+    }
+
+    fn set_always_on_top(&self, window: crate::Window, on_top: bool) -> crate::Result<()> {
+        trace_span!("set_always_on_top", backend = self.kind().name(), %window);
+        set_always_on_top(window, on_top)
+    }
+
+    fn is_always_below(&self, window: crate::Window) -> crate::Result<bool> {
+        trace_span!("is_always_below", backend = self.kind().name(), %window);
+        is_always_below(window)
+    }
+
+    fn set_always_below(&self, window: crate::Window, below: bool) -> crate::Result<()> {
+        trace_span!("set_always_below", backend = self.kind().name(), %window);
+        set_always_below(window, below)
+    }
+
+    fn set_click_through(&self, window: crate::Window, click_through: bool) -> crate::Result<()> {
+        trace_span!("set_click_through", backend = self.kind().name(), %window);
+        set_click_through(window, click_through)
+    }
+}
@@ -0,0 +1,310 @@
+//! Searching windows by several criteria at once.
+
+use crate::{Window, WindowProperties, WindowingBackend};
+
+/// A window search combining criteria, all of which must match:
+/// `WindowQuery::new().pid(pid).title_contains("Inbox").visible(true).first()`.
+///
+/// The window list is read once, narrowed by process or app id where one is
+/// set, and the pid, app id, title and workspace the remaining criteria need
+/// are read for every window in one `get_window_properties` batch before
+/// filtering. A criterion the backend can't check (e.g. `workspace` without
+/// workspaces) fails the search with `Unsupported` rather than matching
+/// nothing, and windows closing during the search are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WindowQuery {
+    pid: Option<u32>,
+    app_id: Option<String>,
+    title: Option<String>,
+    workspace: Option<u32>,
+    visible: Option<bool>,
+}
+
+impl WindowQuery {
+    /// A query matching every window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Windows of this process. A pid of 0 fails the search with `InvalidPid`.
+    pub fn pid(mut self, pid: u32) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    /// Windows with this app id (WM_CLASS on X11), ignoring case.
+    pub fn app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.app_id = Some(app_id.into());
+        self
+    }
+
+    /// Windows whose title contains `title` (case-sensitive).
+    pub fn title_contains(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Windows on this workspace; windows on every workspace match too.
+    pub fn workspace(mut self, workspace: u32) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    /// Windows that pass `is_main_window` (`true`) or fail it (`false`).
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = Some(visible);
+        self
+    }
+
+    /// Every matching window, in the backend's enumeration order.
+    pub fn all(&self) -> crate::Result<Vec<Window>> {
+        self.all_in(crate::default_backend())
+    }
+
+    /// The first matching window.
+    pub fn first(&self) -> crate::Result<Option<Window>> {
+        self.first_in(crate::default_backend())
+    }
+
+    /// `all`, run against `backend` instead of the default one.
+    pub fn all_in<B: WindowingBackend + ?Sized>(&self, backend: &B) -> crate::Result<Vec<Window>> {
+        let candidates = self.candidates(backend)?;
+        let properties = self.properties(backend, &candidates)?;
+        let mut found = Vec::new();
+        for (window, properties) in candidates.into_iter().zip(properties) {
+            if self.matches(backend, window, properties)? {
+                found.push(window);
+            }
+        }
+        Ok(found)
+    }
+
+    /// `first`, run against `backend` instead of the default one.
+    pub fn first_in<B: WindowingBackend + ?Sized>(&self, backend: &B) -> crate::Result<Option<Window>> {
+        let candidates = self.candidates(backend)?;
+        let properties = self.properties(backend, &candidates)?;
+        for (window, properties) in candidates.into_iter().zip(properties) {
+            if self.matches(backend, window, properties)? {
+                return Ok(Some(window));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The windows to check, narrowed by the backend where it can search itself
+    fn candidates<B: WindowingBackend + ?Sized>(&self, backend: &B) -> crate::Result<Vec<Window>> {
+        if let Some(pid) = self.pid {
+            crate::backend::check_pid(pid)?;
+        }
+        if self.workspace.is_some() {
+            // A backend without workspaces can't check the criterion
+            backend.get_current_workspace()?;
+        }
+        match (self.pid, &self.app_id, self.visible) {
+            (Some(pid), _, Some(true)) => backend.find_visible_windows_by_pid(pid),
+            (Some(pid), _, _) => backend.find_windows_by_pid(pid),
+            (None, Some(app_id), _) => backend.find_windows_by_app_id(app_id),
+            (None, None, _) => backend.list_windows(),
+        }
+    }
+
+    /// Each candidate's properties from one `get_window_properties` batch, or
+    /// `None` for each if no remaining criterion needs them
+    fn properties<B: WindowingBackend + ?Sized>(
+        &self,
+        backend: &B,
+        candidates: &[Window],
+    ) -> crate::Result<Vec<Option<crate::WindowResult<WindowProperties>>>> {
+        let needed = (self.pid.is_some() && self.app_id.is_some()) || self.title.is_some() || self.workspace.is_some();
+        if !needed || candidates.is_empty() {
+            return Ok(candidates.iter().map(|_| None).collect());
+        }
+        let properties = backend.get_window_properties(candidates)?;
+        if properties.len() != candidates.len() {
+            return Err("get_window_properties returned the wrong number of results".into());
+        }
+        Ok(properties.into_iter().map(Some).collect())
+    }
+
+    /// Whether `window` meets the criteria `candidates` didn't already apply;
+    /// `false` for a window that has closed
+    fn matches<B: WindowingBackend + ?Sized>(
+        &self,
+        backend: &B,
+        window: Window,
+        properties: Option<crate::WindowResult<WindowProperties>>,
+    ) -> crate::Result<bool> {
+        match self.check(backend, window, properties) {
+            Err(crate::Error::WindowGone(_) | crate::Error::WindowNotFound(_)) => Ok(false),
+            result => result,
+        }
+    }
+
+    fn check<B: WindowingBackend + ?Sized>(
+        &self,
+        backend: &B,
+        window: Window,
+        properties: Option<crate::WindowResult<WindowProperties>>,
+    ) -> crate::Result<bool> {
+        if let Some(properties) = properties.transpose()? {
+            if self.pid.is_some()
+                && let Some(app_id) = &self.app_id
+                && !properties.app_id.is_some_and(|actual| actual.eq_ignore_ascii_case(app_id))
+            {
+                return Ok(false);
+            }
+            if let Some(title) = &self.title
+                && !properties.title.is_some_and(|actual| actual.contains(title.as_str()))
+            {
+                return Ok(false);
+            }
+            if let Some(workspace) = self.workspace
+                && properties.workspace.is_some_and(|actual| actual != workspace)
+            {
+                return Ok(false);
+            }
+        }
+        if let Some(visible) = self.visible
+            && !(self.pid.is_some() && visible)
+            && backend.is_main_window(window)? != visible
+        {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBackend, MockFailure, MockWindow};
+    use crate::WindowStateFlag;
+
+    fn window(id: u64) -> Window {
+        Window::from_id(id).unwrap()
+    }
+
+    /// Two browser windows (one a tooltip kept off the taskbar) and a
+    /// terminal, spread over two workspaces
+    fn desktop() -> MockBackend {
+        MockBackend::new()
+            .with_window(
+                MockWindow::new(window(1))
+                    .with_pid(10)
+                    .with_app_id("Firefox")
+                    .with_title("Inbox - Mail")
+                    .with_workspace(0),
+            )
+            .with_window(
+                MockWindow::new(window(2))
+                    .with_pid(10)
+                    .with_app_id("firefox")
+                    .with_title("Inbox tooltip")
+                    .with_state(WindowStateFlag::SkipTaskbar)
+                    .with_workspace(1),
+            )
+            .with_window(MockWindow::new(window(3)).with_pid(20).with_app_id("kitty").with_title("~").with_workspace(1))
+    }
+
+    #[test]
+    fn an_empty_query_matches_every_window() {
+        assert_eq!(WindowQuery::new().all_in(&desktop()).unwrap(), vec![window(1), window(2), window(3)]);
+    }
+
+    #[test]
+    fn criteria_are_combined() {
+        let backend = desktop();
+
+        let query = WindowQuery::new().pid(10).title_contains("Inbox");
+        assert_eq!(query.all_in(&backend).unwrap(), vec![window(1), window(2)]);
+        assert_eq!(query.clone().visible(true).all_in(&backend).unwrap(), vec![window(1)]);
+        assert_eq!(query.clone().visible(false).all_in(&backend).unwrap(), vec![window(2)]);
+        assert_eq!(query.workspace(1).first_in(&backend).unwrap(), Some(window(2)));
+    }
+
+    #[test]
+    fn app_id_ignores_case() {
+        let backend = desktop();
+
+        assert_eq!(WindowQuery::new().app_id("FIREFOX").all_in(&backend).unwrap(), vec![window(1), window(2)]);
+        let visible = WindowQuery::new().pid(10).app_id("FireFox").visible(true);
+        assert_eq!(visible.all_in(&backend).unwrap(), vec![window(1)]);
+        assert!(WindowQuery::new().pid(20).app_id("firefox").all_in(&backend).unwrap().is_empty());
+    }
+
+    #[test]
+    fn windows_on_every_workspace_match_any_workspace() {
+        let backend = desktop().with_window(MockWindow::new(window(4)).with_title("Panel"));
+
+        assert_eq!(WindowQuery::new().workspace(0).all_in(&backend).unwrap(), vec![window(1), window(4)]);
+    }
+
+    #[test]
+    fn first_is_none_without_a_match() {
+        assert_eq!(WindowQuery::new().title_contains("Calendar").first_in(&desktop()).unwrap(), None);
+    }
+
+    #[test]
+    fn a_zero_pid_fails_the_search() {
+        assert!(matches!(WindowQuery::new().pid(0).all_in(&desktop()), Err(crate::Error::InvalidPid(0))));
+    }
+
+    #[test]
+    fn windows_closing_during_the_search_are_left_out() {
+        let backend = desktop().with_failure("get_window_title", MockFailure::WindowGone);
+
+        assert!(WindowQuery::new().title_contains("Inbox").all_in(&backend).unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_criterion_the_backend_cannot_check_fails_the_search() {
+        let backend = desktop().with_failure("get_current_workspace", MockFailure::Unsupported);
+
+        assert!(matches!(WindowQuery::new().workspace(1).all_in(&backend), Err(crate::Error::Unsupported(_))));
+    }
+
+    /// The mock behind nothing but listing and `get_window_properties`, which
+    /// it counts, so a query reading a property any other way fails
+    struct Batched {
+        mock: MockBackend,
+        batches: std::sync::atomic::AtomicUsize,
+    }
+
+    impl WindowingBackend for Batched {
+        fn kind(&self) -> crate::BackendKind {
+            self.mock.kind()
+        }
+
+        fn list_windows(&self) -> crate::Result<Vec<Window>> {
+            self.mock.list_windows()
+        }
+
+        fn find_windows_by_pid(&self, target_pid: u32) -> crate::Result<Vec<Window>> {
+            self.mock.find_windows_by_pid(target_pid)
+        }
+
+        fn get_current_workspace(&self) -> crate::Result<Option<u32>> {
+            self.mock.get_current_workspace()
+        }
+
+        fn get_window_properties(
+            &self,
+            windows: &[Window],
+        ) -> crate::Result<Vec<crate::WindowResult<WindowProperties>>> {
+            self.batches.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            self.mock.get_window_properties(windows)
+        }
+    }
+
+    #[test]
+    fn properties_are_read_in_one_batch() {
+        let backend = Batched { mock: desktop(), batches: Default::default() };
+
+        let query = WindowQuery::new().pid(10).app_id("firefox").title_contains("Inbox").workspace(1);
+        assert_eq!(query.all_in(&backend).unwrap(), vec![window(2)]);
+        assert_eq!(backend.batches.load(std::sync::atomic::Ordering::Relaxed), 1);
+        // Nothing to read for a pid alone
+        assert_eq!(WindowQuery::new().pid(20).all_in(&backend).unwrap(), vec![window(3)]);
+        assert_eq!(backend.batches.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! Point-in-time captures of every window's geometry, and diffs between them.
+
+use crate::{Window, WindowInfo, WindowingBackend};
+use std::collections::HashMap;
+
+/// One window as seen by `snapshot_desktop`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowSnapshot {
+    pub window: Window,
+    pub info: WindowInfo,
+    /// The owning process, when the backend reports it. Used to tell a reused
+    /// handle apart from the window that previously had it.
+    pub pid: Option<u32>,
+}
+
+/// The active window as one consistent reading, from `active_window_snapshot`:
+/// every field describes `window`, even if focus moved while it was read.
+/// `pid`, `title`, `app_id` and `process_name` are `None` where the window or
+/// backend doesn't report them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActiveWindowSnapshot {
+    pub window: Window,
+    pub pid: Option<u32>,
+    pub title: Option<String>,
+    /// The application id (WM_CLASS on X11); `None` on Windows, which has none.
+    pub app_id: Option<String>,
+    /// The owning process's name from `get_process_name`, e.g. `firefox` or
+    /// `notepad.exe`; what activity logs group by where app ids are missing.
+    pub process_name: Option<String>,
+    pub info: WindowInfo,
+}
+
+/// Every window with known geometry at one point in time, in `list_windows` order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DesktopSnapshot {
+    pub windows: Vec<WindowSnapshot>,
+}
+
+/// A difference between two `DesktopSnapshot`s. A window that both moved and
+/// resized is reported once as `Moved` and once as `Resized`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowChange {
+    Appeared(Window, WindowInfo),
+    Disappeared(Window, WindowInfo),
+    Moved {
+        window: Window,
+        old: WindowInfo,
+        new: WindowInfo,
+    },
+    Resized {
+        window: Window,
+        old: WindowInfo,
+        new: WindowInfo,
+    },
+}
+
+/// Capture the geometry of every listed window. Windows that close while the
+/// snapshot is taken, or whose geometry the backend can't report, are skipped.
+pub fn snapshot_desktop() -> crate::Result<DesktopSnapshot> {
+    snapshot_desktop_in(crate::default_backend())
+}
+
+/// `snapshot_desktop` on `backend`
+pub(crate) fn snapshot_desktop_in(backend: &dyn WindowingBackend) -> crate::Result<DesktopSnapshot> {
+    let mut windows = Vec::new();
+    for window in backend.list_windows()? {
+        let Ok(info) = backend.get_window_info(window) else {
+            continue;
+        };
+        windows.push(WindowSnapshot {
+            window,
+            info,
+            pid: backend.get_window_pid(window).ok().flatten(),
+        });
+    }
+    Ok(DesktopSnapshot { windows })
+}
+
+/// Whether two snapshots with the same handle are the same window: false only
+/// when both know the owning process and it differs.
+fn same_window(old: &WindowSnapshot, new: &WindowSnapshot) -> bool {
+    old.pid.zip(new.pid).is_none_or(|(old_pid, new_pid)| old_pid == new_pid)
+}
+
+/// Compare two snapshots by window handle. A handle whose owning process
+/// changed between the snapshots was reused by a new window, so it is reported
+/// as the old window disappearing and the new one appearing.
+pub fn diff_snapshots(old: &DesktopSnapshot, new: &DesktopSnapshot) -> Vec<WindowChange> {
+    let before: HashMap<Window, &WindowSnapshot> =
+        old.windows.iter().map(|snapshot| (snapshot.window, snapshot)).collect();
+    let after: HashMap<Window, &WindowSnapshot> =
+        new.windows.iter().map(|snapshot| (snapshot.window, snapshot)).collect();
+
+    let mut changes = Vec::new();
+    for snapshot in &old.windows {
+        if !after.get(&snapshot.window).is_some_and(|current| same_window(snapshot, current)) {
+            changes.push(WindowChange::Disappeared(snapshot.window, snapshot.info));
+        }
+    }
+    for snapshot in &new.windows {
+        let window = snapshot.window;
+        let (old, new) = match before.get(&window) {
+            Some(previous) if same_window(previous, snapshot) => (previous.info, snapshot.info),
+            _ => {
+                changes.push(WindowChange::Appeared(window, snapshot.info));
+                continue;
+            }
+        };
+        if old.pos != new.pos {
+            changes.push(WindowChange::Moved { window, old, new });
+        }
+        if old.size != new.size {
+            changes.push(WindowChange::Resized { window, old, new });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBackend, MockFailure, MockWindow};
+
+    fn window(id: u64) -> Window {
+        Window::from_id(id).unwrap()
+    }
+
+    fn frame(x: i32, y: i32, width: u32, height: u32) -> WindowInfo {
+        WindowInfo { pos: (x, y), size: (width, height) }
+    }
+
+    #[test]
+    fn snapshot_reads_every_window_in_list_order() {
+        let backend = MockBackend::new()
+            .with_window(MockWindow::new(window(2)).with_pid(7).with_geometry(10, 20, 300, 200))
+            .with_window(MockWindow::new(window(1)).with_geometry(-50, 0, 100, 100));
+
+        let snapshot = snapshot_desktop_in(&backend).unwrap();
+        assert_eq!(snapshot.windows, [
+            WindowSnapshot { window: window(2), info: frame(10, 20, 300, 200), pid: Some(7) },
+            WindowSnapshot { window: window(1), info: frame(-50, 0, 100, 100), pid: None },
+        ]);
+    }
+
+    #[test]
+    fn snapshot_skips_windows_without_geometry() {
+        let backend = MockBackend::new()
+            .with_window(MockWindow::new(window(1)))
+            .with_failure("get_window_info", MockFailure::WindowGone);
+
+        assert!(snapshot_desktop_in(&backend).unwrap().windows.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_each_kind_of_change() {
+        let backend = MockBackend::new()
+            .with_window(MockWindow::new(window(1)).with_geometry(0, 0, 100, 100))
+            .with_window(MockWindow::new(window(2)).with_geometry(0, 0, 100, 100))
+            .with_window(MockWindow::new(window(3)));
+        let old = snapshot_desktop_in(&backend).unwrap();
+        backend.move_window(window(1), 50, 60).unwrap();
+        backend.set_window_rect(window(2), 5, 5, 200, 100).unwrap();
+        backend.remove_window(window(3));
+        backend.add_window(MockWindow::new(window(4)).with_geometry(1, 2, 3, 4));
+        let new = snapshot_desktop_in(&backend).unwrap();
+
+        assert_eq!(diff_snapshots(&old, &new), [
+            WindowChange::Disappeared(window(3), frame(0, 0, 800, 600)),
+            WindowChange::Moved { window: window(1), old: frame(0, 0, 100, 100), new: frame(50, 60, 100, 100) },
+            WindowChange::Moved { window: window(2), old: frame(0, 0, 100, 100), new: frame(5, 5, 200, 100) },
+            WindowChange::Resized { window: window(2), old: frame(0, 0, 100, 100), new: frame(5, 5, 200, 100) },
+            WindowChange::Appeared(window(4), frame(1, 2, 3, 4)),
+        ]);
+        assert!(diff_snapshots(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn diff_treats_a_reused_handle_as_a_new_window() {
+        let snapshot = |pid| DesktopSnapshot {
+            windows: vec![WindowSnapshot { window: window(1), info: frame(0, 0, 10, 10), pid }],
+        };
+
+        assert_eq!(diff_snapshots(&snapshot(Some(1)), &snapshot(Some(2))), [
+            WindowChange::Disappeared(window(1), frame(0, 0, 10, 10)),
+            WindowChange::Appeared(window(1), frame(0, 0, 10, 10)),
+        ]);
+        // An unknown pid on either side can't tell them apart
+        assert!(diff_snapshots(&snapshot(None), &snapshot(Some(2))).is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+//! Coarse window states and waiting for a window to reach one.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A high-level state a window can be in, as far as automation cares.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WindowState {
+    /// Mapped and not minimized.
+    Visible,
+    Minimized,
+    Maximized,
+    Fullscreen,
+    /// The active (foreground) window.
+    Focused,
+    /// The window no longer exists.
+    Closed,
+}
+
+/// One entry of a window's EWMH `_NET_WM_STATE`, or the closest equivalent
+/// a compositor backend reports.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WindowStateFlag {
+    Modal,
+    Sticky,
+    MaximizedVert,
+    MaximizedHorz,
+    Shaded,
+    SkipTaskbar,
+    SkipPager,
+    /// Minimized (iconified) or otherwise not shown.
+    Hidden,
+    Fullscreen,
+    Above,
+    Below,
+    DemandsAttention,
+    Focused,
+    /// A state the crate doesn't know, by its atom name.
+    Other(String),
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Block until `window` is in `target` state or `timeout` elapses.
+///
+/// Returns `Ok(true)` once the state is reached and `Ok(false)` on timeout.
+/// The state is polled every 50ms through `window_has_state`.
+pub fn wait_for_state(
+    window: crate::Window,
+    target: WindowState,
+    timeout: Duration,
+) -> crate::Result<bool> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if crate::window_has_state(window, target)? {
+            return Ok(true);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(false);
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
@@ -0,0 +1,29 @@
+//! `tracing` instrumentation, compiled in with the `tracing` feature. Without
+//! it the macros expand to nothing: their arguments aren't evaluated and no
+//! `tracing` code is linked. Only usable in statement position.
+
+/// Enter a debug-level span for the rest of the enclosing block, named after
+/// the operation: `trace_span!("list_windows", backend = self.kind().name())`.
+macro_rules! trace_span {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($arg)+).entered();
+    };
+}
+
+/// A debug-level event, e.g. why a window was left out of a result.
+macro_rules! trace_debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)+);
+    };
+}
+
+/// A warning-level event, for a desktop that lacks something the crate relies
+/// on (a missing EWMH property, say) and degrades the results.
+macro_rules! trace_warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)+);
+    };
+}
@@ -0,0 +1,128 @@
+//! Following one application's window across restarts, by identity rather than handle.
+
+use crate::{Window, WindowState};
+
+/// Finds a window by what it is (app id, title, process) instead of by
+/// handle, so it can be found again after its application restarts and the
+/// old handle is gone. Every criterion that is set must match.
+///
+/// `resolve` keeps returning the window found last while it is open and still
+/// matches, and searches again otherwise.
+#[derive(Debug, Clone)]
+pub struct WindowTracker {
+    app_id: Option<String>,
+    title: Option<String>,
+    pid: Option<u32>,
+    current: Option<Window>,
+}
+
+impl WindowTracker {
+    fn new() -> Self {
+        Self { app_id: None, title: None, pid: None, current: None }
+    }
+
+    /// Track the window of the application with this app id (WM_CLASS on X11), ignoring case.
+    pub fn by_app_id(app_id: impl Into<String>) -> Self {
+        Self::new().with_app_id(app_id)
+    }
+
+    /// Track a window whose title contains `title`.
+    pub fn by_title(title: impl Into<String>) -> Self {
+        Self::new().with_title(title)
+    }
+
+    /// Track a window of one process. A restarted application has a new pid,
+    /// so this is mostly useful to narrow `by_app_id` or `by_title`.
+    pub fn by_pid(pid: u32) -> Self {
+        Self::new().with_pid(pid)
+    }
+
+    /// Track `window` by its app id, or by its title where it has none. It is
+    /// the resolved window until it closes.
+    pub fn for_window(window: Window) -> crate::Result<Self> {
+        let mut tracker = match crate::get_window_app_id(window).ok().flatten() {
+            Some(app_id) => Self::by_app_id(app_id),
+            None => match crate::get_window_title(window)? {
+                Some(title) => Self::by_title(title),
+                None => return Err(format!("Window {window} has neither an app id nor a title to track").into()),
+            },
+        };
+        tracker.current = Some(window);
+        Ok(tracker)
+    }
+
+    pub fn with_app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.app_id = Some(app_id.into());
+        self
+    }
+
+    /// Also require the title to contain `title` (case-sensitive).
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_pid(mut self, pid: u32) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    /// The window found by the last `resolve`, without checking it is still open.
+    pub fn current(&self) -> Option<Window> {
+        self.current
+    }
+
+    /// The tracked window: the last one found if it is still open and still
+    /// matches, else a fresh search (preferring windows that pass
+    /// `is_main_window`). `None` while the application has no matching window.
+    pub fn resolve(&mut self) -> crate::Result<Option<Window>> {
+        if let Some(window) = self.current
+            && self.matches(window).unwrap_or(false)
+        {
+            return Ok(Some(window));
+        }
+        let candidates = match (&self.app_id, self.pid) {
+            (Some(app_id), _) => crate::find_windows_by_app_id(app_id)?,
+            (None, Some(pid)) => crate::find_windows_by_pid(pid)?,
+            (None, None) => crate::list_windows()?,
+        };
+        let mut found = None;
+        for window in candidates {
+            // Windows closing mid-search don't match
+            if !self.matches(window).unwrap_or(false) {
+                continue;
+            }
+            if crate::is_main_window(window).unwrap_or(false) {
+                found = Some(window);
+                break;
+            }
+            found.get_or_insert(window);
+        }
+        self.current = found;
+        Ok(found)
+    }
+
+    fn matches(&self, window: Window) -> crate::Result<bool> {
+        if crate::window_has_state(window, WindowState::Closed)? {
+            return Ok(false);
+        }
+        if let Some(pid) = self.pid
+            && crate::get_window_pid(window)? != Some(pid)
+        {
+            return Ok(false);
+        }
+        if let Some(app_id) = &self.app_id {
+            let actual = crate::get_window_app_id(window)?;
+            if !actual.is_some_and(|actual| actual.eq_ignore_ascii_case(app_id)) {
+                return Ok(false);
+            }
+        }
+        if let Some(title) = &self.title {
+            let actual = crate::get_window_title(window)?;
+            if !actual.is_some_and(|actual| actual.contains(title.as_str())) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
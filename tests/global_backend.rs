@@ -0,0 +1,40 @@
+//! The free functions forward to the backend set with `set_global_backend`.
+//! The global backend can only be set once per process, so this file has one
+//! test and the rest of the suite never sets it.
+
+#![cfg(feature = "mock")]
+
+use windowing::{MockBackend, MockWindow, Window, WindowInfo, WindowQuery, WindowStateFlag};
+
+fn window(id: u64) -> Window {
+    Window::from_id(id).unwrap()
+}
+
+#[test]
+fn free_functions_forward_to_the_global_backend() {
+    let mock = MockBackend::new()
+        .with_window(MockWindow::new(window(1)).with_pid(10).with_title("Editor").with_geometry(0, 0, 800, 600))
+        .with_window(MockWindow::new(window(2)).with_pid(20).with_app_id("kitty").with_title("~"))
+        .with_active(window(1));
+    windowing::set_global_backend(Box::new(mock)).unwrap();
+    assert!(windowing::set_global_backend(Box::new(MockBackend::new())).is_err());
+
+    assert_eq!(windowing::default_backend().kind(), windowing::BackendKind::X11);
+    assert_eq!(windowing::list_windows().unwrap(), vec![window(1), window(2)]);
+    assert_eq!(windowing::window_count().unwrap(), 2);
+    assert_eq!(windowing::get_active_window().unwrap(), Some(window(1)));
+    assert_eq!(windowing::find_window_by_pid(20).unwrap(), Some(window(2)));
+    assert_eq!(windowing::get_window_title(window(1)).unwrap().as_deref(), Some("Editor"));
+    assert_eq!(windowing::active_window_snapshot().unwrap().unwrap().pid, Some(10));
+    assert_eq!(WindowQuery::new().app_id("kitty").first().unwrap(), Some(window(2)));
+
+    windowing::move_window(window(1), 100, 50).unwrap();
+    assert_eq!(windowing::get_window_info(window(1)).unwrap(), WindowInfo { pos: (100, 50), size: (800, 600) });
+    windowing::set_window_state(window(1), WindowStateFlag::Above, true).unwrap();
+    assert!(windowing::get_window_states(window(1)).unwrap().contains(&WindowStateFlag::Above));
+    windowing::focus_window(window(2)).unwrap();
+    assert_eq!(windowing::get_active_window_pid().unwrap(), Some(20));
+    windowing::close_window(window(2)).unwrap();
+    assert_eq!(windowing::list_windows().unwrap(), vec![window(1)]);
+    assert!(matches!(windowing::get_window_title(window(2)), Err(windowing::Error::WindowGone(_))));
+}
@@ -0,0 +1,53 @@
+//! Without a display every free function fails with `NoDisplay` rather than
+//! panicking. Detection is cached for the process and the environment is
+//! shared by its threads, so this file has one test, which clears the
+//! session's variables before anything reads them.
+
+#![cfg(all(target_os = "linux", feature = "x11"))]
+
+use windowing::{Error, Window, WindowQuery, WindowSystem};
+
+/// `Ok(())` for a `NoDisplay` failure, the result otherwise
+fn no_display<T: std::fmt::Debug>(result: windowing::Result<T>) -> Result<(), String> {
+    match result {
+        Err(Error::NoDisplay { display: None }) => Ok(()),
+        other => Err(format!("{other:?}")),
+    }
+}
+
+#[test]
+fn free_functions_fail_without_a_display() {
+    for name in ["DISPLAY", "WAYLAND_DISPLAY", "WINDOWING_BACKEND", "HYPRLAND_INSTANCE_SIGNATURE", "SWAYSOCK", "I3SOCK"]
+    {
+        // SAFETY: the only test in this process, run before anything reads the environment
+        unsafe { std::env::remove_var(name) };
+    }
+    let window = Window::from_id(1).unwrap();
+    let pid = std::process::id();
+
+    let results = [
+        ("WindowSystem::new", no_display(WindowSystem::new())),
+        ("list_windows", no_display(windowing::list_windows())),
+        ("window_count", no_display(windowing::window_count())),
+        ("get_window_info", no_display(windowing::get_window_info(window))),
+        ("get_window_details", no_display(windowing::get_window_details(window))),
+        ("get_window_title", no_display(windowing::get_window_title(window))),
+        ("get_active_window", no_display(windowing::get_active_window())),
+        ("find_window_by_pid", no_display(windowing::find_window_by_pid(pid))),
+        ("find_windows_by_pid", no_display(windowing::find_windows_by_pid(pid))),
+        ("find_visible_windows_by_pid", no_display(windowing::find_visible_windows_by_pid(pid))),
+        ("pid_has_window", no_display(windowing::pid_has_window(pid))),
+        ("WindowQuery::first", no_display(WindowQuery::new().title_contains("Inbox").first())),
+        ("move_window", no_display(windowing::move_window(window, 0, 0))),
+        ("focus_window", no_display(windowing::focus_window(window))),
+        ("list_monitors", no_display(windowing::list_monitors())),
+        ("get_virtual_screen", no_display(windowing::get_virtual_screen())),
+        // Another thread opens an X11 connection of its own
+        ("list_windows on a new thread", std::thread::spawn(|| no_display(windowing::list_windows())).join().unwrap()),
+    ];
+    for (name, result) in results {
+        if let Err(other) = result {
+            panic!("{name}: expected NoDisplay, got {other}");
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! The `serde` feature's JSON format, which is part of the API: field and
+//! variant names as in Rust, windows as plain integer ids.
+
+#![cfg(feature = "serde")]
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::json;
+use windowing::{
+    ActiveWindowSnapshot, DesktopSnapshot, IconData, Layout, LayoutEntry, MatchStrategy, MonitorInfo,
+    PhysicalWindowInfo, Window, WindowChange, WindowDetails, WindowEvent, WindowId, WindowInfo, WindowProperties,
+    WindowSnapshot, WindowState, WindowStateFlag,
+};
+
+fn window(id: u64) -> Window {
+    Window::from_id(id).unwrap()
+}
+
+/// Serializes `value`, checks the JSON is `expected`, and reads it back
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug>(value: &T, expected: serde_json::Value) {
+    let text = serde_json::to_string(value).unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&text).unwrap(), expected, "{text}");
+    assert_eq!(&serde_json::from_str::<T>(&text).unwrap(), value);
+}
+
+fn info() -> WindowInfo {
+    WindowInfo { pos: (-10, 20), size: (800, 600) }
+}
+
+#[test]
+fn window_ids_are_plain_integers() {
+    round_trip(&WindowId::from_x11(0x1c0_0007), json!(0x1c0_0007));
+    round_trip(&WindowId::from(u64::MAX), json!(u64::MAX));
+    round_trip(&window(0x1c0_0007), json!(0x1c0_0007));
+    let as_id = serde_json::to_string(&WindowId::from(window(42))).unwrap();
+    assert_eq!(serde_json::to_string(&window(42)).unwrap(), as_id);
+}
+
+#[test]
+fn windows_this_platform_cannot_have_are_rejected() {
+    assert!(serde_json::from_str::<Window>("0").is_err());
+    assert!(serde_json::from_str::<Window>("-1").is_err());
+    assert!(serde_json::from_str::<Window>("\"42\"").is_err());
+    #[cfg(not(target_os = "windows"))]
+    assert!(serde_json::from_str::<Window>(&u64::MAX.to_string()).is_err());
+}
+
+#[test]
+fn geometry() {
+    round_trip(&info(), json!({"pos": [-10, 20], "size": [800, 600]}));
+    let physical = PhysicalWindowInfo { pos: (-20, 40), size: (1600, 1200) };
+    round_trip(&physical, json!({"pos": [-20, 40], "size": [1600, 1200]}));
+    round_trip(
+        &MonitorInfo { name: "eDP-1".into(), bounds: info(), scale_factor: 1.5 },
+        json!({"name": "eDP-1", "bounds": {"pos": [-10, 20], "size": [800, 600]}, "scale_factor": 1.5}),
+    );
+}
+
+#[test]
+fn window_details_and_properties() {
+    let details = WindowDetails {
+        info: info(),
+        title: Some("notes.md - Editor".into()),
+        pid: Some(4160),
+        states: vec![WindowStateFlag::Above, WindowStateFlag::Other("_KDE_NET_WM_STATE_X".into())],
+        on_active_desktop: true,
+    };
+    round_trip(
+        &details,
+        json!({
+            "info": {"pos": [-10, 20], "size": [800, 600]},
+            "title": "notes.md - Editor",
+            "pid": 4160,
+            "states": ["Above", {"Other": "_KDE_NET_WM_STATE_X"}],
+            "on_active_desktop": true,
+        }),
+    );
+    round_trip(
+        &WindowProperties { pid: None, app_id: Some("kitty".into()), title: None, workspace: Some(2) },
+        json!({"pid": null, "app_id": "kitty", "title": null, "workspace": 2}),
+    );
+    let icon = IconData { width: 1, height: 1, rgba: vec![255, 0, 0, 255] };
+    round_trip(&icon, json!({"width": 1, "height": 1, "rgba": [255, 0, 0, 255]}));
+}
+
+#[test]
+fn states() {
+    for (state, name) in [
+        (WindowState::Visible, "Visible"),
+        (WindowState::Minimized, "Minimized"),
+        (WindowState::Maximized, "Maximized"),
+        (WindowState::Fullscreen, "Fullscreen"),
+        (WindowState::Focused, "Focused"),
+        (WindowState::Closed, "Closed"),
+    ] {
+        round_trip(&state, json!(name));
+    }
+    round_trip(&WindowStateFlag::DemandsAttention, json!("DemandsAttention"));
+}
+
+#[test]
+fn events_and_changes() {
+    round_trip(&WindowEvent::Opened(window(7)), json!({"Opened": 7}));
+    round_trip(&WindowEvent::Focused(None), json!({"Focused": null}));
+    round_trip(&WindowEvent::Focused(Some(window(7))), json!({"Focused": 7}));
+    round_trip(&WindowEvent::WorkspaceChanged(window(7), Some(1)), json!({"WorkspaceChanged": [7, 1]}));
+    round_trip(
+        &WindowChange::Moved { window: window(7), old: info(), new: WindowInfo { pos: (0, 0), ..info() } },
+        json!({"Moved": {
+            "window": 7,
+            "old": {"pos": [-10, 20], "size": [800, 600]},
+            "new": {"pos": [0, 0], "size": [800, 600]},
+        }}),
+    );
+}
+
+#[test]
+fn snapshots() {
+    round_trip(
+        &DesktopSnapshot { windows: vec![WindowSnapshot { window: window(7), info: info(), pid: Some(10) }] },
+        json!({"windows": [{"window": 7, "info": {"pos": [-10, 20], "size": [800, 600]}, "pid": 10}]}),
+    );
+    round_trip(
+        &ActiveWindowSnapshot {
+            window: window(7),
+            pid: Some(10),
+            title: Some("~".into()),
+            app_id: Some("kitty".into()),
+            process_name: Some("kitty".into()),
+            info: info(),
+        },
+        json!({
+            "window": 7,
+            "pid": 10,
+            "title": "~",
+            "app_id": "kitty",
+            "process_name": "kitty",
+            "info": {"pos": [-10, 20], "size": [800, 600]},
+        }),
+    );
+}
+
+#[test]
+fn layouts() {
+    let layout = Layout {
+        windows: vec![LayoutEntry {
+            window: window(7),
+            pid: Some(10),
+            app_id: Some("kitty".into()),
+            title: None,
+            info: info(),
+            monitor: Some(MonitorInfo { name: "HDMI-1".into(), bounds: info(), scale_factor: 1.0 }),
+            workspace: None,
+            maximized: false,
+            minimized: true,
+        }],
+    };
+    round_trip(
+        &layout,
+        json!({"windows": [{
+            "window": 7,
+            "pid": 10,
+            "app_id": "kitty",
+            "title": null,
+            "info": {"pos": [-10, 20], "size": [800, 600]},
+            "monitor": {"name": "HDMI-1", "bounds": {"pos": [-10, 20], "size": [800, 600]}, "scale_factor": 1.0},
+            "workspace": null,
+            "maximized": false,
+            "minimized": true,
+        }]}),
+    );
+    round_trip(&MatchStrategy::SameApp, json!("SameApp"));
+}